@@ -0,0 +1,155 @@
+//! Curated per-application baselines of needs, merged with profiled actions so that common daemons get good results
+//! even from short profiling runs
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context as _;
+
+use crate::cl::ExtraBindPort;
+
+/// Presets shipped with shh, by name
+const SHIPPED_PRESETS: [(&str, &str); 3] = [
+    ("nginx", include_str!("../presets/nginx.toml")),
+    ("postgresql", include_str!("../presets/postgresql.toml")),
+    ("redis", include_str!("../presets/redis.toml")),
+];
+
+/// Directory of local or downloaded presets, which take precedence over shipped ones with the same name
+const LOCAL_PRESETS_DIR: &str = "/etc/shh/presets";
+
+/// Preset file content
+///
+/// Stored as TOML, for example:
+/// ```toml
+/// description = "nginx web server"
+/// read_paths = ["/etc/nginx"]
+/// write_paths = ["/var/log/nginx"]
+/// bind_ports = ["tcp:80", "tcp:443"]
+/// syscalls = ["sendfile"]
+/// ```
+#[derive(Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PresetFile {
+    description: String,
+    #[serde(default)]
+    read_paths: Vec<PathBuf>,
+    #[serde(default)]
+    write_paths: Vec<PathBuf>,
+    #[serde(default)]
+    bind_ports: Vec<String>,
+    #[serde(default)]
+    syscalls: Vec<String>,
+}
+
+/// Needs of an application, allowed on top of profiled ones
+#[derive(Debug, Clone)]
+pub(crate) struct AppPreset {
+    /// Preset name or file path, as it was selected
+    pub source: String,
+    pub description: String,
+    pub read_paths: Vec<PathBuf>,
+    pub write_paths: Vec<PathBuf>,
+    pub bind_ports: Vec<ExtraBindPort>,
+    pub syscalls: Vec<String>,
+}
+
+impl AppPreset {
+    /// Load preset from a file path, or by name from local then shipped presets
+    pub(crate) fn load(source: &str) -> anyhow::Result<Self> {
+        if source.contains('/') {
+            // Absolute path, so that it can be passed to profiling wrappers running elsewhere
+            let path = fs::canonicalize(source)
+                .with_context(|| format!("Failed to read preset from {source}"))?;
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read preset from {}", path.display()))?;
+            return Self::parse(&path.to_string_lossy(), &content);
+        }
+        let local_path = Path::new(LOCAL_PRESETS_DIR).join(format!("{source}.toml"));
+        let content = if local_path.is_file() {
+            fs::read_to_string(&local_path)
+                .with_context(|| format!("Failed to read preset from {}", local_path.display()))?
+        } else {
+            SHIPPED_PRESETS
+                .iter()
+                .find(|(n, _)| *n == source)
+                .map(|(_, c)| (*c).to_owned())
+                .ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Unknown preset {source:?}, available presets: {}",
+                        Self::names().join(", ")
+                    )
+                })?
+        };
+        Self::parse(source, &content)
+    }
+
+    fn parse(source: &str, content: &str) -> anyhow::Result<Self> {
+        let file: PresetFile =
+            toml::from_str(content).with_context(|| format!("Failed to parse preset {source}"))?;
+        for path in file.read_paths.iter().chain(&file.write_paths) {
+            anyhow::ensure!(
+                path.is_absolute(),
+                "Path {} of preset {source} is not absolute",
+                path.display()
+            );
+        }
+        Ok(Self {
+            source: source.to_owned(),
+            description: file.description,
+            read_paths: file.read_paths,
+            write_paths: file.write_paths,
+            bind_ports: file
+                .bind_ports
+                .iter()
+                .map(|p| p.parse())
+                .collect::<anyhow::Result<_>>()?,
+            syscalls: file.syscalls,
+        })
+    }
+
+    /// Names of available presets, shipped and local
+    pub(crate) fn names() -> Vec<String> {
+        let mut names: Vec<_> = SHIPPED_PRESETS
+            .iter()
+            .map(|(n, _)| (*n).to_owned())
+            .chain(
+                fs::read_dir(LOCAL_PRESETS_DIR)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(Result::ok)
+                    .filter_map(|e| {
+                        e.file_name()
+                            .to_str()?
+                            .strip_suffix(".toml")
+                            .map(ToOwned::to_owned)
+                    }),
+            )
+            .collect();
+        names.sort_unstable();
+        names.dedup();
+        names
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shipped_presets() {
+        for (name, content) in SHIPPED_PRESETS {
+            let preset = AppPreset::parse(name, content).unwrap();
+            assert!(!preset.description.is_empty());
+        }
+
+        let preset = AppPreset::load("nginx").unwrap();
+        assert!(preset.bind_ports.iter().any(|p| p.port.get() == 443));
+        assert!(AppPreset::load("unknown").is_err());
+        assert!(
+            AppPreset::parse("relative", "description = \"\"\nread_paths = [\"etc\"]").is_err()
+        );
+    }
+}
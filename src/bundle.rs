@@ -0,0 +1,150 @@
+//! Bundles of applied hardening config with their provenance, to transfer vetted hardening between identical hosts
+//! without profiling again
+
+use std::{fs, path::Path, time::SystemTime};
+
+use anyhow::Context as _;
+
+use crate::environment::Environment;
+
+/// Where and when a bundle was exported
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Provenance {
+    /// Version of shh that exported the bundle
+    pub shh_version: String,
+    /// Host the hardening config was applied on
+    pub hostname: String,
+    /// Service unit the hardening config was applied to
+    pub unit: String,
+    /// Export time, in seconds since the Unix epoch
+    pub exported: u64,
+    /// Environment of the host the hardening config was applied on
+    pub environment: Environment,
+}
+
+/// Hardening config applied to a service, stored as TOML
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct HardeningBundle {
+    pub provenance: Provenance,
+    /// Hardening config fragment
+    pub fragment: String,
+    /// Helper unit running setup commands with their own hardening options, if they were profiled separately
+    pub exec_start_pre_unit: Option<String>,
+}
+
+impl HardeningBundle {
+    pub(crate) fn new(
+        unit: String,
+        environment: Environment,
+        fragment: String,
+        exec_start_pre_unit: Option<String>,
+    ) -> anyhow::Result<Self> {
+        Ok(Self {
+            provenance: Provenance {
+                shh_version: env!("CARGO_PKG_VERSION").to_owned(),
+                hostname: nix::unistd::gethostname()?.to_string_lossy().into_owned(),
+                unit,
+                exported: SystemTime::now()
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs(),
+                environment,
+            },
+            fragment,
+            exec_start_pre_unit,
+        })
+    }
+
+    pub(crate) fn read(path: &Path) -> anyhow::Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read hardening bundle from {}", path.display()))?;
+        toml::from_str(&content)
+            .with_context(|| format!("Failed to parse hardening bundle from {}", path.display()))
+    }
+
+    pub(crate) fn write(&self, path: &Path) -> anyhow::Result<()> {
+        fs::write(path, toml::to_string(self)?)
+            .with_context(|| format!("Failed to write hardening bundle to {}", path.display()))
+    }
+
+    /// Get warnings about differences between the environment the bundle was exported from and the local one,
+    /// which may make the hardening config break the service or be less effective
+    pub(crate) fn compatibility_warnings(&self, local: &Environment) -> Vec<String> {
+        let origin = &self.provenance.environment;
+        let mut warnings = Vec::new();
+        if origin.systemd_version != local.systemd_version {
+            warnings.push(format!(
+                "Hardening config was built for systemd {}, but local systemd is {}",
+                origin
+                    .systemd_version
+                    .as_ref()
+                    .map_or_else(|| "(none)".to_owned(), ToString::to_string),
+                local
+                    .systemd_version
+                    .as_ref()
+                    .map_or_else(|| "(none)".to_owned(), ToString::to_string),
+            ));
+        }
+        if origin.kernel_version != local.kernel_version {
+            warnings.push(format!(
+                "Hardening config was built for Linux kernel {}, but local kernel is {}",
+                origin.kernel_version, local.kernel_version
+            ));
+        }
+        for (name, value) in &origin.sysctls {
+            if local.sysctls.get(name) != Some(value) {
+                warnings.push(format!(
+                    "Hardening config was built with sysctl {name}={value}, but local value is {}",
+                    local.sysctls.get(name).map_or("(unset)", String::as_str)
+                ));
+            }
+        }
+        warnings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::systemd::{KernelVersion, SystemdVersion};
+
+    #[test]
+    fn test_bundle() {
+        let environment = || Environment {
+            systemd_version: Some(SystemdVersion::new(254, 1)),
+            kernel_version: KernelVersion::new(6, 4, 12),
+            strace_version: None,
+            sysctls: BTreeMap::from([("kernel.yama.ptrace_scope".to_owned(), "1".to_owned())]),
+        };
+        let bundle = HardeningBundle::new(
+            "foo.service".to_owned(),
+            environment(),
+            "[Service]\nProtectSystem=strict\n".to_owned(),
+            None,
+        )
+        .unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("foo.toml");
+        bundle.write(&path).unwrap();
+        assert_eq!(HardeningBundle::read(&path).unwrap(), bundle);
+
+        assert!(bundle.compatibility_warnings(&environment()).is_empty());
+        let other = Environment {
+            systemd_version: Some(SystemdVersion::new(252, 0)),
+            sysctls: BTreeMap::new(),
+            ..environment()
+        };
+        assert_eq!(
+            bundle.compatibility_warnings(&other),
+            vec![
+                "Hardening config was built for systemd 254.1, but local systemd is 252.0",
+                "Hardening config was built with sysctl kernel.yama.ptrace_scope=1, but local value is (unset)",
+            ]
+        );
+    }
+}
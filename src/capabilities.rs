@@ -0,0 +1,236 @@
+//! Tracing of the kernel capability checks with a kprobe, for exact capability needs instead of inferring them
+//! from syscalls
+
+use std::{
+    collections::BTreeSet,
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, Write as _},
+    path::{Path, PathBuf},
+    process,
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use anyhow::Context as _;
+
+use crate::summarize::ProgramAction;
+
+/// Capability names, by number
+pub(crate) const CAPABILITIES: [&str; 41] = [
+    "CAP_CHOWN",
+    "CAP_DAC_OVERRIDE",
+    "CAP_DAC_READ_SEARCH",
+    "CAP_FOWNER",
+    "CAP_FSETID",
+    "CAP_KILL",
+    "CAP_SETGID",
+    "CAP_SETUID",
+    "CAP_SETPCAP",
+    "CAP_LINUX_IMMUTABLE",
+    "CAP_NET_BIND_SERVICE",
+    "CAP_NET_BROADCAST",
+    "CAP_NET_ADMIN",
+    "CAP_NET_RAW",
+    "CAP_IPC_LOCK",
+    "CAP_IPC_OWNER",
+    "CAP_SYS_MODULE",
+    "CAP_SYS_RAWIO",
+    "CAP_SYS_CHROOT",
+    "CAP_SYS_PTRACE",
+    "CAP_SYS_PACCT",
+    "CAP_SYS_ADMIN",
+    "CAP_SYS_BOOT",
+    "CAP_SYS_NICE",
+    "CAP_SYS_RESOURCE",
+    "CAP_SYS_TIME",
+    "CAP_SYS_TTY_CONFIG",
+    "CAP_MKNOD",
+    "CAP_LEASE",
+    "CAP_AUDIT_WRITE",
+    "CAP_AUDIT_CONTROL",
+    "CAP_SETFCAP",
+    "CAP_MAC_OVERRIDE",
+    "CAP_MAC_ADMIN",
+    "CAP_SYSLOG",
+    "CAP_WAKE_ALARM",
+    "CAP_BLOCK_SUSPEND",
+    "CAP_AUDIT_READ",
+    "CAP_PERFMON",
+    "CAP_BPF",
+    "CAP_CHECKPOINT_RESTORE",
+];
+
+/// Possible tracefs mount points
+const TRACEFS_DIRS: [&str; 2] = ["/sys/kernel/tracing", "/sys/kernel/debug/tracing"];
+
+/// Group of our kprobe events
+const KPROBE_GROUP: &str = "shh";
+
+/// Written to the trace buffer to stop reading it
+const STOP_MARKER: &str = "shh-capability-tracer-stop";
+
+/// Capabilities checked so far
+type CheckedCapabilities = Arc<Mutex<BTreeSet<&'static str>>>;
+
+/// Kprobe on `cap_capable`, with events of the current process and its future children recorded in a dedicated
+/// tracing instance, to not interfere with other tracing users
+pub(crate) struct CapabilityTracer {
+    instance_dir: PathBuf,
+    kprobe_events_path: PathBuf,
+    event: String,
+    checked: CheckedCapabilities,
+    reader: Option<thread::JoinHandle<anyhow::Result<()>>>,
+}
+
+impl CapabilityTracer {
+    /// Start tracing capability checks, must be called before the profiled program is started
+    // TODO APPROXIMATION checks done by the profiling process itself are recorded too, so a few capabilities it
+    // needs may be kept
+    pub(crate) fn start() -> anyhow::Result<Self> {
+        let tracefs_dir = TRACEFS_DIRS
+            .iter()
+            .map(Path::new)
+            .find(|d| d.join("kprobe_events").is_file())
+            .ok_or_else(|| anyhow::anyhow!("No tracefs with kprobe support found"))?;
+        let pid = process::id();
+        let event = format!("capable_{pid}");
+        let kprobe_events_path = tracefs_dir.join("kprobe_events");
+        // cap_capable(cred, targ_ns, cap, opts)
+        append(
+            &kprobe_events_path,
+            &format!("p:{KPROBE_GROUP}/{event} cap_capable cap=$arg3:s32"),
+        )?;
+        // From here, cleanup is done on drop
+        let mut tracer = Self {
+            instance_dir: tracefs_dir
+                .join("instances")
+                .join(format!("{}_{pid}", env!("CARGO_PKG_NAME"))),
+            kprobe_events_path,
+            event,
+            checked: CheckedCapabilities::default(),
+            reader: None,
+        };
+        fs::create_dir(&tracer.instance_dir)
+            .with_context(|| format!("Failed to create {}", tracer.instance_dir.display()))?;
+        fs::write(tracer.instance_dir.join("set_event_pid"), pid.to_string())?;
+        fs::write(tracer.instance_dir.join("options/event-fork"), "1")?;
+        fs::write(tracer.event_dir().join("enable"), "1")?;
+        let pipe = File::open(tracer.instance_dir.join("trace_pipe"))?;
+        let checked = Arc::clone(&tracer.checked);
+        tracer.reader = Some(thread::spawn(move || {
+            read_checks(BufReader::new(pipe), &checked)
+        }));
+        log::info!("Tracing capability checks");
+        Ok(tracer)
+    }
+
+    fn event_dir(&self) -> PathBuf {
+        self.instance_dir
+            .join("events")
+            .join(KPROBE_GROUP)
+            .join(&self.event)
+    }
+
+    /// Get actions for the capabilities checked so far
+    pub(crate) fn actions(&self) -> Vec<ProgramAction> {
+        #[expect(clippy::unwrap_used)]
+        self.checked
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|c| ProgramAction::Capability((*c).to_owned()))
+            .collect()
+    }
+
+    /// Stop tracing, once the profiled program has exited, and get actions for all checked capabilities
+    pub(crate) fn finish(mut self) -> anyhow::Result<Vec<ProgramAction>> {
+        // Events of exited processes are all in the buffer before the marker
+        append(&self.instance_dir.join("trace_marker"), STOP_MARKER)?;
+        if let Some(reader) = self.reader.take() {
+            reader
+                .join()
+                .map_err(|_| anyhow::anyhow!("Capability trace reader thread panicked"))??;
+        }
+        Ok(self.actions())
+    }
+}
+
+impl Drop for CapabilityTracer {
+    fn drop(&mut self) {
+        // The kprobe can only be removed once it is not enabled in any instance
+        let _ = fs::write(self.event_dir().join("enable"), "0");
+        if let Err(err) = fs::remove_dir(&self.instance_dir) {
+            log::warn!(
+                "Failed to remove tracing instance {}: {err}",
+                self.instance_dir.display()
+            );
+        }
+        if let Err(err) = append(
+            &self.kprobe_events_path,
+            &format!("-:{KPROBE_GROUP}/{}", self.event),
+        ) {
+            log::warn!(
+                "Failed to remove kprobe {KPROBE_GROUP}/{}: {err}",
+                self.event
+            );
+        }
+    }
+}
+
+/// Append a line to a tracefs control file
+fn append(path: &Path, line: &str) -> anyhow::Result<()> {
+    let mut file = OpenOptions::new()
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    writeln!(file, "{line}")
+        .with_context(|| format!("Failed to write {line:?} to {}", path.display()))
+}
+
+/// Read trace events until the stop marker
+fn read_checks<R: BufRead>(reader: R, checked: &CheckedCapabilities) -> anyhow::Result<()> {
+    for line in reader.lines() {
+        let line = line?;
+        if line.ends_with(STOP_MARKER) {
+            break;
+        }
+        if let Some(cap) = parse_check(&line) {
+            #[expect(clippy::unwrap_used)]
+            checked.lock().unwrap().insert(cap);
+        }
+    }
+    Ok(())
+}
+
+/// Parse capability from a trace event line, ie.
+/// `nginx-1234 [001] ..... 123.456789: capable_42: (cap_capable+0x0/0x80) cap=10`
+fn parse_check(line: &str) -> Option<&'static str> {
+    let cap: usize = line.rsplit_once(" cap=")?.1.trim().parse().ok()?;
+    CAPABILITIES.get(cap).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn test_read_checks() {
+        let trace = format!(
+            "           nginx-1234    [001] ..... 123.456789: capable_42: (cap_capable+0x0/0x80) cap=10
+           nginx-1234    [001] ..... 123.456790: capable_42: (cap_capable+0x0/0x80) cap=21
+           nginx-1235    [002] ..... 123.456791: capable_42: (cap_capable+0x0/0x80) cap=10
+           nginx-1235    [002] ..... 123.456792: capable_42: (cap_capable+0x0/0x80) cap=99
+             shh-42      [000] ..... 123.456793: tracing_mark_write: {STOP_MARKER}
+           nginx-1236    [002] ..... 123.456794: capable_42: (cap_capable+0x0/0x80) cap=12
+"
+        );
+        let checked = CheckedCapabilities::default();
+        read_checks(Cursor::new(trace), &checked).unwrap();
+        assert_eq!(
+            checked.lock().unwrap().iter().copied().collect::<Vec<_>>(),
+            vec!["CAP_NET_BIND_SERVICE", "CAP_SYS_ADMIN"]
+        );
+    }
+}
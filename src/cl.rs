@@ -1,8 +1,24 @@
 //! Command line interface
 
-use std::path::PathBuf;
+use std::{
+    collections::BTreeSet,
+    env, fmt,
+    io::IsTerminal,
+    num::NonZeroU16,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
 
-use clap::Parser;
+use clap::{Parser, ValueEnum as _};
+
+use crate::{
+    app_preset::AppPreset,
+    summarize::{
+        CountableSetSpecifier, NetworkActivity, NetworkActivityKind, ProgramAction, SetSpecifier,
+    },
+    systemd::{SocketFamily, SocketProtocol, SystemdVersion, SYSCALL_CLASSES},
+};
 
 /// Command line arguments
 #[derive(Parser, Debug)]
@@ -10,6 +26,49 @@ use clap::Parser;
 pub(crate) struct Args {
     #[command(subcommand)]
     pub action: Action,
+    /// When to use colors in terminal output
+    #[arg(long, global = true, value_enum, default_value_t)]
+    pub color: ColorMode,
+    /// Format of errors written to stderr, the exit code tells the error category
+    #[arg(long, global = true, value_enum, default_value_t)]
+    pub error_format: ErrorFormat,
+}
+
+/// When to use colors in terminal output
+#[derive(Debug, Clone, Copy, Default, clap::ValueEnum, strum::Display)]
+#[strum(serialize_all = "snake_case")]
+pub(crate) enum ColorMode {
+    /// Use colors if output is a terminal, and the `NO_COLOR` environment variable is not set
+    #[default]
+    Auto,
+    /// Always use colors
+    Always,
+    /// Never use colors
+    Never,
+}
+
+/// Format of errors written to stderr
+#[derive(Debug, Clone, Copy, Default, clap::ValueEnum, strum::Display)]
+#[strum(serialize_all = "snake_case")]
+pub(crate) enum ErrorFormat {
+    /// Human readable message, with its causes
+    #[default]
+    Text,
+    /// Single line JSON object, with the error category, exit code, message and causes
+    Json,
+}
+
+impl ColorMode {
+    /// Whether colors should be used for a given output stream
+    pub(crate) fn enabled<S: IsTerminal>(self, stream: &S) -> bool {
+        match self {
+            Self::Auto => {
+                env::var_os("NO_COLOR").is_none_or(|v| v.is_empty()) && stream.is_terminal()
+            }
+            Self::Always => true,
+            Self::Never => false,
+        }
+    }
 }
 
 /// How hard we should harden
@@ -24,7 +83,165 @@ pub(crate) enum HardeningMode {
     Aggressive,
 }
 
-#[derive(Debug, clap::Parser)]
+/// Distribution package source tree layout
+#[derive(Debug, Clone, Copy, Eq, PartialEq, clap::ValueEnum, strum::Display)]
+#[strum(serialize_all = "snake_case")]
+pub(crate) enum PackagingLayout {
+    /// Files in the `debian` directory, to install with `debian/PACKAGE.install`
+    Debian,
+    /// Files in the `SOURCES` directory, to install from the spec file
+    Rpm,
+}
+
+/// What happens when a program calls a syscall denied by `SystemCallFilter`
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, clap::ValueEnum, strum::Display)]
+#[strum(serialize_all = "snake_case")]
+pub(crate) enum SyscallDenyAction {
+    /// Fail the call with EPERM error
+    #[default]
+    Eperm,
+    /// Fail the call with ENOSYS error, as if the syscall was not implemented
+    Enosys,
+    /// Kill the program with SIGSYS signal
+    Kill,
+}
+
+/// How capability needs are found
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, clap::ValueEnum, strum::Display)]
+#[strum(serialize_all = "snake_case")]
+pub(crate) enum CapabilitySource {
+    /// Infer capabilities from syscalls, only those we can reliably infer are removed
+    #[default]
+    Syscalls,
+    /// Trace kernel capability checks with a kprobe during profiling (requires root), all never checked
+    /// capabilities are removed
+    Kprobe,
+}
+
+/// Kind of system the service runs on, leaving out option families it does not support or that get in its way
+#[derive(Debug, Clone, Copy, Eq, PartialEq, clap::ValueEnum, strum::Display)]
+#[strum(serialize_all = "snake_case")]
+pub(crate) enum HardeningPreset {
+    /// Dedicated server, all options are considered
+    Server,
+    /// Desktop system, services interact with user sessions, so home directories, temporary directories (ie. X11
+    /// sockets) and the network namespace (ie. abstract sockets) are not isolated
+    Desktop,
+    /// Service in a container (ie. systemd-nspawn), options relying on BPF programs attached to the service cgroup
+    /// are left out, since the container manager may not delegate them
+    Container,
+    /// Embedded system, options relying on kernel features often disabled on small kernels (cgroup BPF, `/proc`
+    /// mount options) are left out
+    Embedded,
+}
+
+/// Syscall deny action override for a syscall group
+#[derive(Debug, Clone)]
+pub(crate) struct SyscallGroupDenyAction {
+    pub group: String,
+    pub action: SyscallDenyAction,
+}
+
+impl FromStr for SyscallGroupDenyAction {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (group, action) = s
+            .split_once('=')
+            .ok_or_else(|| anyhow::anyhow!("Expected GROUP=ACTION, got {s:?}"))?;
+        let group = group.trim_start_matches('@');
+        anyhow::ensure!(
+            SYSCALL_CLASSES.contains_key(group),
+            "Unknown syscall group {group:?}"
+        );
+        let action = SyscallDenyAction::from_str(action, true).map_err(anyhow::Error::msg)?;
+        Ok(Self {
+            group: group.to_owned(),
+            action,
+        })
+    }
+}
+
+impl fmt::Display for SyscallGroupDenyAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.group, self.action)
+    }
+}
+
+/// Port to allow binding to, ie. 'tcp:8080', or '8080' for both TCP and UDP
+#[derive(Debug, Clone)]
+pub(crate) struct ExtraBindPort {
+    /// Protocol, or `None` for both TCP and UDP
+    pub proto: Option<SocketProtocol>,
+    pub port: NonZeroU16,
+}
+
+impl FromStr for ExtraBindPort {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (proto, port) = match s.split_once(':') {
+            Some(("tcp", port)) => (Some(SocketProtocol::Tcp), port),
+            Some(("udp", port)) => (Some(SocketProtocol::Udp), port),
+            Some((proto, _)) => anyhow::bail!("Unknown protocol {proto:?}, expected tcp or udp"),
+            None => (None, s),
+        };
+        let port = port
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid port {port:?}"))?;
+        Ok(Self { proto, port })
+    }
+}
+
+impl fmt::Display for ExtraBindPort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(proto) = &self.proto {
+            write!(f, "{proto}:")?;
+        }
+        write!(f, "{}", self.port)
+    }
+}
+
+/// Command run after a hardening config is applied, to record the change
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub(crate) enum PostApplyHook {
+    /// Commit all changes in /etc with `etckeeper commit`
+    Etckeeper,
+    /// Commit written files in the git repository containing them
+    Git,
+    /// Shell command, getting the change details in environment variables
+    Command(String),
+}
+
+impl FromStr for PostApplyHook {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "etckeeper" => Self::Etckeeper,
+            "git" => Self::Git,
+            cmd => Self::Command(cmd.to_owned()),
+        })
+    }
+}
+
+#[derive(Debug, Clone, clap::Parser)]
+pub(crate) struct HookOptions {
+    /// Run after applying a hardening config, to record the change: 'etckeeper' or 'git' to commit it with a message
+    /// containing the unit, exposure change and shh version, or a shell command getting them in `SHH_UNIT`,
+    /// `SHH_PATHS`, `SHH_EXPOSURE`, `SHH_EXPOSURE_DELTA`, `SHH_VERSION` and `SHH_COMMIT_MESSAGE` environment variables
+    #[arg(long, value_name = "HOOK", env = "SHH_POST_APPLY_HOOK", default_value = None)]
+    pub post_apply_hook: Option<PostApplyHook>,
+}
+
+/// Parse an absolute path
+fn parse_absolute_path(s: &str) -> anyhow::Result<PathBuf> {
+    let path = PathBuf::from(s);
+    anyhow::ensure!(path.is_absolute(), "Path {s:?} is not absolute");
+    Ok(path)
+}
+
+#[derive(Debug, Clone, clap::Parser)]
 pub(crate) struct HardeningOptions {
     /// How hard we should harden
     #[arg(short, long, default_value_t, value_enum)]
@@ -32,6 +249,48 @@ pub(crate) struct HardeningOptions {
     /// Enable advanced network firewalling
     #[arg(short = 'f', long, default_value_t)]
     pub network_firewalling: bool,
+    /// What happens when the program calls a denied syscall
+    #[arg(long, default_value_t, value_enum)]
+    pub syscall_deny_action: SyscallDenyAction,
+    /// Override the denied syscall action for a syscall group, ie. 'GROUP=ACTION', can be repeated
+    #[arg(long = "syscall-group-deny-action", value_name = "GROUP=ACTION")]
+    pub syscall_group_deny_actions: Vec<SyscallGroupDenyAction>,
+    /// Also generate resource limits (`LimitNOFILE`, `TasksMax`) from observed peak usage, with a safety margin
+    #[arg(long, default_value_t)]
+    pub resource_limits: bool,
+    /// Only generate options and syntaxes supported by this systemd version (ie. '250'), instead of the local one,
+    /// for services deployed on other systems
+    #[arg(long, value_name = "VERSION")]
+    pub target_systemd_version: Option<SystemdVersion>,
+    /// Also allow reading this path, for needs not seen during profiling (ie. a monthly job), can be repeated
+    #[arg(long = "extra-read-path", value_name = "PATH", value_parser = parse_absolute_path)]
+    pub extra_read_paths: Vec<PathBuf>,
+    /// Also allow writing this path, for needs not seen during profiling (ie. log rotation), can be repeated
+    #[arg(long = "extra-write-path", value_name = "PATH", value_parser = parse_absolute_path)]
+    pub extra_write_paths: Vec<PathBuf>,
+    /// Also allow binding to this port, ie. 'tcp:8080', or '8080' for both TCP and UDP, can be repeated
+    #[arg(long = "extra-bind-port", value_name = "[PROTO:]PORT")]
+    pub extra_bind_ports: Vec<ExtraBindPort>,
+    /// Also allow this syscall, for needs not seen during profiling, can be repeated
+    #[arg(long = "extra-syscall", value_name = "SYSCALL")]
+    pub extra_syscalls: Vec<String>,
+    /// Also allow the needs of a curated application preset (ie. 'nginx', or a preset file path), for good results
+    /// from short profiling runs of common daemons, can be repeated
+    #[arg(long = "app-preset", value_name = "NAME|FILE", value_parser = AppPreset::load)]
+    pub app_presets: Vec<AppPreset>,
+    /// Service manages cgroups or runs containers (ie. `Delegate=yes`), do not isolate mounts, namespaces and cgroups
+    #[arg(long, default_value_t)]
+    pub container_manager: bool,
+    /// How to find the capabilities needed, to build `CapabilityBoundingSet`
+    #[arg(long, default_value_t, value_enum)]
+    pub capability_source: CapabilitySource,
+    /// Kind of system the service runs on, to leave out option families it does not support
+    #[arg(long, value_enum, default_value = None)]
+    pub preset: Option<HardeningPreset>,
+    /// Leave out paths accessed fewer than this number of times (ie. '2' for paths accessed only once), likely
+    /// incidental probing during a long profiling run, for tighter options, left out paths are listed for review
+    #[arg(long, value_name = "N", default_value = None)]
+    pub min_path_accesses: Option<usize>,
 }
 
 impl HardeningOptions {
@@ -41,6 +300,19 @@ impl HardeningOptions {
         Self {
             mode: HardeningMode::Safe,
             network_firewalling: false,
+            syscall_deny_action: SyscallDenyAction::default(),
+            syscall_group_deny_actions: vec![],
+            resource_limits: false,
+            target_systemd_version: None,
+            extra_read_paths: vec![],
+            extra_write_paths: vec![],
+            extra_bind_ports: vec![],
+            extra_syscalls: vec![],
+            app_presets: vec![],
+            container_manager: false,
+            capability_source: CapabilitySource::default(),
+            preset: None,
+            min_path_accesses: None,
         }
     }
 
@@ -49,18 +321,346 @@ impl HardeningOptions {
         Self {
             mode: HardeningMode::Aggressive,
             network_firewalling: true,
+            syscall_deny_action: SyscallDenyAction::default(),
+            syscall_group_deny_actions: vec![],
+            resource_limits: true,
+            target_systemd_version: None,
+            extra_read_paths: vec![],
+            extra_write_paths: vec![],
+            extra_bind_ports: vec![],
+            extra_syscalls: vec![],
+            app_presets: vec![],
+            container_manager: false,
+            capability_source: CapabilitySource::default(),
+            preset: None,
+            min_path_accesses: None,
+        }
+    }
+
+    /// Get action to take when a syscall of the given group is denied
+    pub(crate) fn syscall_deny_action(&self, group: &str) -> SyscallDenyAction {
+        self.syscall_group_deny_actions
+            .iter()
+            .rev()
+            .find(|o| o.group == group)
+            .map_or(self.syscall_deny_action, |o| o.action)
+    }
+
+    /// Build command line arguments for the same options
+    pub(crate) fn to_args(&self) -> Vec<String> {
+        let mut args = vec!["-m".to_owned(), self.mode.to_string()];
+        if self.network_firewalling {
+            args.push("-f".to_owned());
+        }
+        args.extend([
+            "--syscall-deny-action".to_owned(),
+            self.syscall_deny_action.to_string(),
+        ]);
+        for deny_action in &self.syscall_group_deny_actions {
+            args.extend([
+                "--syscall-group-deny-action".to_owned(),
+                deny_action.to_string(),
+            ]);
+        }
+        if self.resource_limits {
+            args.push("--resource-limits".to_owned());
+        }
+        if let Some(version) = &self.target_systemd_version {
+            args.extend(["--target-systemd-version".to_owned(), version.to_string()]);
+        }
+        for path in &self.extra_read_paths {
+            args.extend([
+                "--extra-read-path".to_owned(),
+                path.to_string_lossy().into_owned(),
+            ]);
+        }
+        for path in &self.extra_write_paths {
+            args.extend([
+                "--extra-write-path".to_owned(),
+                path.to_string_lossy().into_owned(),
+            ]);
+        }
+        for port in &self.extra_bind_ports {
+            args.extend(["--extra-bind-port".to_owned(), port.to_string()]);
+        }
+        for syscall in &self.extra_syscalls {
+            args.extend(["--extra-syscall".to_owned(), syscall.clone()]);
+        }
+        for preset in &self.app_presets {
+            args.extend(["--app-preset".to_owned(), preset.source.clone()]);
+        }
+        if self.container_manager {
+            args.push("--container-manager".to_owned());
+        }
+        if self.capability_source != CapabilitySource::default() {
+            args.extend([
+                "--capability-source".to_owned(),
+                self.capability_source.to_string(),
+            ]);
+        }
+        if let Some(preset) = self.preset {
+            args.extend(["--preset".to_owned(), preset.to_string()]);
+        }
+        if let Some(min_path_accesses) = self.min_path_accesses {
+            args.extend([
+                "--min-path-accesses".to_owned(),
+                min_path_accesses.to_string(),
+            ]);
+        }
+        args
+    }
+
+    /// Actions to allow on top of profiled ones, for needs known but not seen during profiling
+    pub(crate) fn extra_actions(&self) -> Vec<ProgramAction> {
+        let mut actions: Vec<_> = self
+            .extra_read_paths
+            .iter()
+            .chain(self.app_presets.iter().flat_map(|p| &p.read_paths))
+            .cloned()
+            .map(ProgramAction::Read)
+            .chain(
+                self.extra_write_paths
+                    .iter()
+                    .chain(self.app_presets.iter().flat_map(|p| &p.write_paths))
+                    .cloned()
+                    .map(ProgramAction::Write),
+            )
+            .collect();
+        let mut syscalls: BTreeSet<String> = self
+            .extra_syscalls
+            .iter()
+            .chain(self.app_presets.iter().flat_map(|p| &p.syscalls))
+            .cloned()
+            .collect();
+        for bind_port in self
+            .extra_bind_ports
+            .iter()
+            .chain(self.app_presets.iter().flat_map(|p| &p.bind_ports))
+        {
+            actions.push(ProgramAction::NetworkActivity(NetworkActivity {
+                af: SetSpecifier::Some(vec![SocketFamily::Ipv4, SocketFamily::Ipv6]),
+                proto: bind_port.proto.clone().map_or_else(
+                    || SetSpecifier::Some(vec![SocketProtocol::Tcp, SocketProtocol::Udp]),
+                    SetSpecifier::One,
+                ),
+                kind: SetSpecifier::One(NetworkActivityKind::Bind),
+                local_port: CountableSetSpecifier::One(bind_port.port.into()),
+                remote_addr: None,
+            }));
+            // Syscalls needed to bind, and accept connections for TCP
+            syscalls.extend(
+                ["socket", "bind", "listen", "accept", "accept4"]
+                    .into_iter()
+                    .map(ToOwned::to_owned),
+            );
+        }
+        if !syscalls.is_empty() {
+            actions.push(ProgramAction::Syscalls(syscalls));
+        }
+        actions
+    }
+}
+
+/// How likely an option is to break the program in situations not seen during profiling
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, clap::ValueEnum, strum::Display)]
+#[strum(serialize_all = "snake_case")]
+pub(crate) enum RiskLevel {
+    /// Very unlikely to break anything
+    Low,
+    /// May break rarely used features
+    Medium,
+    /// May break the program under conditions or loads not seen during profiling
+    High,
+}
+
+impl RiskLevel {
+    /// Next higher level
+    pub(crate) fn raised(self) -> Self {
+        match self {
+            Self::Low => Self::Medium,
+            Self::Medium | Self::High => Self::High,
+        }
+    }
+}
+
+/// How to trace the profiled program syscalls
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, clap::ValueEnum, strum::Display)]
+#[strum(serialize_all = "snake_case")]
+pub(crate) enum Tracer {
+    /// Run strace and parse its output
+    #[default]
+    Strace,
+    /// Use the built-in ptrace tracer, which does not need strace to be installed
+    #[cfg(target_arch = "x86_64")]
+    Ptrace,
+}
+
+#[derive(Debug, clap::Parser)]
+pub(crate) struct RunIdentityOptions {
+    /// Run the command as this user (name or UID), with its groups unless a group is also set.
+    /// Use the same user as the service to profile, to get options matching its real behavior.
+    #[arg(short, long, default_value = None)]
+    pub user: Option<String>,
+    /// Run the command with this group (name or GID) as only group
+    #[arg(short, long, requires = "user", default_value = None)]
+    pub group: Option<String>,
+}
+
+#[derive(Debug, clap::Parser)]
+pub(crate) struct TransientUnitOptions {
+    /// Set a property on the transient service unit, ie. 'NAME=VALUE', can be repeated
+    #[arg(long = "unit-property", requires = "unit", value_name = "NAME=VALUE")]
+    pub properties: Vec<String>,
+    /// Run the transient unit in the user service manager, to profile desktop applications and harden them as user
+    /// services (options not supported by user services are left out)
+    #[arg(
+        id = "user_unit",
+        long = "user-unit",
+        requires = "unit",
+        conflicts_with = "user",
+        default_value_t = false
+    )]
+    pub user: bool,
+    /// Run the command in a transient scope instead of a service, to keep the environment of the calling session
+    /// (ie. for graphical applications)
+    #[arg(long, requires = "unit", default_value_t = false)]
+    pub scope: bool,
+}
+
+#[derive(Debug, Clone, clap::Parser)]
+pub(crate) struct StraceLogOptions {
+    /// Log strace output to this file (for services, each profiled command logs to this path suffixed with the unit
+    /// name and command index).
+    /// Only use for debugging: this will slow down processing, and may generate a huge file.
+    #[arg(short = 'l', long = "strace-log-path", default_value = None)]
+    pub path: Option<PathBuf>,
+    /// Rotate strace log file when its size exceeds this value in MiB
+    #[arg(long = "strace-log-max-size", requires = "path", default_value = None)]
+    pub max_size_mb: Option<u64>,
+    /// Number of rotated strace log files to keep
+    #[arg(
+        long = "strace-log-keep",
+        requires = "max_size_mb",
+        default_value_t = 3
+    )]
+    pub keep: usize,
+    /// Compress rotated strace log files with gzip
+    #[arg(
+        long = "strace-log-compress",
+        requires = "max_size_mb",
+        default_value_t = false
+    )]
+    pub compress: bool,
+    /// Redact obvious secrets (HTTP authorization headers, private keys) from strings in the strace log,
+    /// before sharing it
+    #[arg(long = "strace-log-redact", requires = "path", default_value_t = false)]
+    pub redact: bool,
+}
+
+impl StraceLogOptions {
+    /// Build command line arguments for the same options, logging to another path
+    pub(crate) fn to_args(&self, path: &Path) -> Vec<String> {
+        let mut args = vec![
+            "--strace-log-path".to_owned(),
+            path.to_string_lossy().into_owned(),
+        ];
+        if let Some(max_size_mb) = self.max_size_mb {
+            args.extend([
+                "--strace-log-max-size".to_owned(),
+                max_size_mb.to_string(),
+                "--strace-log-keep".to_owned(),
+                self.keep.to_string(),
+            ]);
+            if self.compress {
+                args.push("--strace-log-compress".to_owned());
+            }
         }
+        if self.redact {
+            args.push("--strace-log-redact".to_owned());
+        }
+        args
+    }
+}
+
+#[derive(Debug, Clone, clap::Parser)]
+pub(crate) struct StraceOptions {
+    /// Path of the strace binary to use, ie. a static build for hosts where the distribution one is too old
+    #[arg(
+        id = "strace_path",
+        long = "strace-path",
+        value_name = "PATH",
+        env = "SHH_STRACE_PATH",
+        default_value = None
+    )]
+    pub path: Option<PathBuf>,
+}
+
+impl StraceOptions {
+    pub(crate) fn binary(&self) -> &Path {
+        self.path.as_deref().unwrap_or(Path::new("strace"))
     }
 
-    pub(crate) fn to_cmdline(&self) -> String {
-        format!(
-            "-m {}{}",
-            self.mode,
-            if self.network_firewalling { " -n" } else { "" }
-        )
+    pub(crate) fn to_args(&self) -> Vec<String> {
+        self.path
+            .iter()
+            .flat_map(|p| ["--strace-path".to_owned(), p.to_string_lossy().into_owned()])
+            .collect()
     }
 }
 
+#[derive(Debug, Clone, clap::Parser)]
+pub(crate) struct SamplingOptions {
+    /// Only summarize identical syscalls once per interval in milliseconds, to reduce profiling overhead for syscall
+    /// heavy programs like databases or proxies
+    #[arg(long = "sampling-interval-ms", value_name = "MS", default_value = None)]
+    pub interval_ms: Option<u64>,
+}
+
+impl SamplingOptions {
+    pub(crate) fn interval(&self) -> Option<Duration> {
+        self.interval_ms.map(Duration::from_millis)
+    }
+
+    pub(crate) fn to_args(&self) -> Vec<String> {
+        self.interval_ms
+            .iter()
+            .flat_map(|i| ["--sampling-interval-ms".to_owned(), i.to_string()])
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, clap::Parser)]
+pub(crate) struct ReportOptions {
+    /// Also write a self-contained HTML report with exposure estimation, options justifications, and observed
+    /// behavior, to share with people who do not run shh
+    #[arg(long, value_name = "FILE", default_value = None)]
+    pub report_html: Option<PathBuf>,
+    /// Also write findings (options that could not be applied, risky behavior...) in SARIF format, for code scanning
+    /// dashboards
+    #[arg(long, value_name = "FILE", default_value = None)]
+    pub report_sarif: Option<PathBuf>,
+    /// Also write options grouped by category, with a summary of each (option count, values left out or allowed,
+    /// highest breakage risk), in JSON format
+    #[arg(long, value_name = "FILE", default_value = None)]
+    pub report_json: Option<PathBuf>,
+    /// TOML file with option weight overrides for the exposure estimation, and options that must be set
+    #[arg(long, value_name = "FILE", default_value = None)]
+    pub exposure_model: Option<PathBuf>,
+    /// Only output options with at least this breakage risk
+    #[arg(long, value_enum, default_value = None)]
+    pub min_risk: Option<RiskLevel>,
+    /// Only output options with at most this breakage risk, to apply the safest ones first
+    #[arg(long, value_enum, default_value = None)]
+    pub max_risk: Option<RiskLevel>,
+    /// Precede each option with comments explaining why its value was chosen, or what observed behavior bounded it
+    #[arg(long, default_value_t = false)]
+    pub annotate: bool,
+    /// Check if the program could run with an effectively read only root filesystem (`ProtectSystem=strict`, empty
+    /// `/etc` and `/var`, and only explicitly bound paths), and log the options to do so or what prevents it
+    #[arg(long, default_value_t = false)]
+    pub read_only_root: bool,
+}
+
 #[derive(Debug, clap::Subcommand)]
 pub(crate) enum Action {
     /// Run a program to profile its behavior
@@ -73,10 +673,47 @@ pub(crate) enum Action {
         /// Generate profile data file to be merged with others instead of generating systemd options directly
         #[arg(short, long, default_value = None)]
         profile_data_path: Option<PathBuf>,
-        /// Log strace output to this file.
-        /// Only use for debugging: this will slow down processing, and may generate a huge file.
-        #[arg(short = 'l', long, default_value = None)]
-        strace_log_path: Option<PathBuf>,
+        #[command(flatten)]
+        identity_opts: RunIdentityOptions,
+        /// How to trace the command syscalls
+        #[arg(long, default_value_t, value_enum)]
+        tracer: Tracer,
+        #[command(flatten)]
+        strace_opts: StraceOptions,
+        #[command(flatten)]
+        sampling_opts: SamplingOptions,
+        #[command(flatten)]
+        strace_log_opts: StraceLogOptions,
+        /// Run the command in a transient systemd service unit, to profile it in the same conditions as a real service
+        #[arg(long, default_value_t = false, conflicts_with = "path")]
+        unit: bool,
+        #[command(flatten)]
+        unit_opts: TransientUnitOptions,
+        /// Output a bubblewrap command line instead of systemd options, for hosts without systemd
+        /// (automatically enabled if systemd is not detected)
+        #[arg(long, default_value_t = false, conflicts_with = "unit")]
+        standalone: bool,
+        /// In standalone mode, also write an OCI seccomp profile (as used by container runtimes) to this file
+        #[arg(long, value_name = "FILE", requires = "standalone", default_value = None)]
+        seccomp_profile: Option<PathBuf>,
+        /// Print newly discovered actions (paths, network activity, syscall groups) as the program runs,
+        /// to see when profiling coverage plateaus
+        #[arg(long, default_value_t = false, conflicts_with = "unit")]
+        watch: bool,
+        /// Print when each permission (path, network activity, syscall group...) was first needed, to tell startup
+        /// needs that could be moved to setup commands (`ExecStartPre=`) from steady state ones
+        #[arg(long, default_value_t = false)]
+        timeline: bool,
+        /// PID file written by the program when it daemonizes: stop and reload signals sent to shh are forwarded to
+        /// the process it names, as when shh wraps the main process of a forking service
+        #[arg(long, value_name = "PATH", default_value = None)]
+        pid_file: Option<PathBuf>,
+        /// Generate options for the environment (versions...) exported in this file by `env export`,
+        /// instead of the local one
+        #[arg(long, value_name = "FILE", default_value = None)]
+        env_file: Option<PathBuf>,
+        #[command(flatten)]
+        report_opts: ReportOptions,
     },
     /// Merge profile data from previous runs to generate systemd options
     MergeProfileData {
@@ -85,40 +722,286 @@ pub(crate) enum Action {
         /// Profile data paths
         #[arg(num_args = 1.., required = true)]
         paths: Vec<PathBuf>,
+        /// Profile data path of a setup command (`ExecStartPre`) to generate separate options for, can be repeated
+        #[arg(long = "exec-start-pre-profile-data", value_name = "PATH")]
+        exec_start_pre_paths: Vec<PathBuf>,
+        /// Root directory of the profiled service (`RootDirectory=`), to translate the paths it sees to host paths
+        /// where options need them
+        #[arg(long, value_name = "DIR", default_value = None)]
+        root_directory: Option<PathBuf>,
+        /// Move profile data into this directory, to accumulate it across service activations,
+        /// and generate options from all activations so far
+        #[arg(long, value_name = "DIR", default_value = None)]
+        accumulate_dir: Option<PathBuf>,
+        /// Number of service activations to profile, when accumulating profile data
+        #[arg(long, value_name = "N", requires = "accumulate_dir", default_value = None)]
+        activations: Option<usize>,
+        /// Profiled service unit, whose hardening fragment report findings are located in
+        #[arg(long, value_name = "UNIT", default_value = None)]
+        unit: Option<String>,
+        /// Also output a smoke test script exercising key observed actions, to be written by `finish-profile`
+        #[arg(long, default_value_t = false)]
+        smoke_test: bool,
+        /// Also output a summary of profiled actions, for `finish-profile` to warn about poor profiling coverage
+        #[arg(long, default_value_t = false)]
+        coverage: bool,
+        /// Also output when each permission was first needed, at the earliest in the profiled runs, to be shown by
+        /// `finish-profile --timeline`
+        #[arg(long, default_value_t = false)]
+        timeline: bool,
+        /// Generate options for the environment (versions...) exported in this file by `env export`,
+        /// instead of the local one
+        #[arg(long, value_name = "FILE", default_value = None)]
+        env_file: Option<PathBuf>,
+        #[command(flatten)]
+        report_opts: ReportOptions,
+    },
+    /// Fetch profile data from remote hosts through SSH, and merge it to generate systemd options
+    Collect {
+        /// File with one SSH destination per line ('host' or 'user@host'), '#' starts a comment
+        #[arg(long)]
+        hosts: PathBuf,
+        #[command(flatten)]
+        hardening_opts: HardeningOptions,
+        /// Profile data paths on remote hosts, may contain glob patterns
+        #[arg(num_args = 1.., required = true)]
+        remote_paths: Vec<PathBuf>,
+        #[command(flatten)]
+        report_opts: ReportOptions,
+    },
+    /// Print statistics of a profile data file (top accessed directories, syscalls by group, network activity...),
+    /// to check profiling coverage before generating options
+    ProfileStats {
+        /// Profile data path
+        path: PathBuf,
+        /// Number of top accessed directories to print
+        #[arg(long, default_value_t = 10)]
+        top: usize,
+        /// Instead, print for each syscall group the fraction of its syscalls used, and whether `SystemCallFilter=`
+        /// denies it, with used syscalls allowed as exceptions
+        #[arg(long)]
+        syscall_groups: bool,
+    },
+    /// Check a strace log (as written with `--strace-log-path`) in strict mode, and report lines that can not be
+    /// parsed with their line number and surrounding lines, for bug reports.
+    /// Rotated log files are checked too, oldest first.
+    CheckLog {
+        /// Strace log path
+        path: PathBuf,
     },
     /// Act on a systemd service unit
     #[clap(subcommand)]
     Service(ServiceAction),
+    /// Remove everything written by shh: profiling and hardening config fragments of all services (which are
+    /// restarted), leftover profile data, and exposure history
+    Purge,
+    /// Manage snapshots of the local environment options are generated for
+    #[clap(subcommand)]
+    Env(EnvAction),
     /// Dump markdown formatted list of supported systemd options
     ListSystemdOptions,
+    /// List curated application presets usable with `--app-preset`
+    ListAppPresets,
+    /// Generate shell completion script, including completion of service unit names
+    GenShellCompletion {
+        /// Shell to generate the completion script for
+        #[arg(value_enum)]
+        shell: clap_complete::Shell,
+    },
+    /// List service unit names starting with a prefix, for completion scripts
+    #[command(name = "__complete-units", hide = true)]
+    CompleteUnits {
+        /// Unit name prefix
+        #[arg(default_value = "")]
+        prefix: String,
+    },
 }
 
 #[derive(Debug, clap::Subcommand)]
+pub(crate) enum EnvAction {
+    /// Write local systemd, kernel and strace versions and relevant sysctl settings, to generate options for this
+    /// machine elsewhere with `--env-file`
+    Export {
+        /// Output file, standard output if not set
+        #[arg(short, long, default_value = None)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, Clone, clap::Subcommand)]
+#[expect(clippy::large_enum_variant)]
 pub(crate) enum ServiceAction {
     /// Add fragment config to service to profile its behavior
     StartProfile {
-        /// Service unit name
+        /// Service unit name, or glob pattern matching loaded units (ie. `'php-fpm*'`)
         service: String,
         #[command(flatten)]
         hardening_opts: HardeningOptions,
+        /// Profile setup commands (`ExecStartPre`) separately from the main process, and harden them in a helper unit,
+        /// so that privileged setup steps do not loosen the main process sandbox
+        #[arg(long, default_value_t = false)]
+        split_exec_start_pre: bool,
+        #[command(flatten)]
+        strace_opts: StraceOptions,
+        #[command(flatten)]
+        sampling_opts: SamplingOptions,
+        #[command(flatten)]
+        strace_log_opts: StraceLogOptions,
+        /// For services activated by a timer, profile this number of activations, accumulating profile data from
+        /// each one, before profiling can be finished
+        #[arg(long, value_name = "N", default_value = None)]
+        activations: Option<usize>,
+        /// Precede each option in the hardening config with comments explaining why its value was chosen
+        #[arg(long, default_value_t = false)]
+        annotate: bool,
+        /// Related service unit to profile in the same window (ie. a worker or helper unit, a template profiling all
+        /// its instances), to recognize data exchanged with it, profiling of all units is finished together
+        #[arg(long = "with", value_name = "UNIT")]
+        related_units: Vec<String>,
         /// Disable immediate service restart
         #[arg(short, long, default_value_t = false)]
         no_restart: bool,
     },
     /// Get profiling result and remove fragment config from service
     FinishProfile {
-        /// Service unit name
+        /// Service unit name, or glob pattern matching loaded units (ie. `'php-fpm*'`)
         service: String,
         /// Automatically apply hardening config
         #[arg(short, long, default_value_t = false)]
         apply: bool,
+        /// Finish profiling even if fewer service activations than requested were profiled
+        #[arg(short, long, default_value_t = false)]
+        force: bool,
+        /// Only log syscalls the syscall filter would deny (`SystemCallLog=`), instead of denying them,
+        /// until `enforce` is run
+        #[arg(long, default_value_t = false, requires = "apply")]
+        complain: bool,
+        /// After applying, score the hardened unit with `systemd-analyze security` too, and list the settings it
+        /// flags that shh does not manage
+        #[arg(long, default_value_t = false, requires = "apply")]
+        cross_check: bool,
+        /// Write a shell script exercising key observed actions (path reads and writes, port binds) to this file,
+        /// to check the hardened service can still perform them after future option edits
+        #[arg(long, value_name = "FILE", default_value = None)]
+        smoke_test: Option<PathBuf>,
+        /// Print when each permission was first needed while profiling, to tell startup needs that could be moved to
+        /// setup commands (`ExecStartPre=`) from steady state ones
+        #[arg(long, default_value_t = false)]
+        timeline: bool,
+        /// Only output and apply options with at least this breakage risk
+        #[arg(long, value_enum, default_value = None)]
+        min_risk: Option<RiskLevel>,
+        /// Only output and apply options with at most this breakage risk, to apply the safest ones first
+        #[arg(long, value_enum, default_value = None)]
+        max_risk: Option<RiskLevel>,
+        #[command(flatten)]
+        hook_opts: HookOptions,
         /// Disable immediate service restart
         #[arg(short, long, default_value_t = false)]
         no_restart: bool,
     },
+    /// Show syscalls that would have been denied since hardening config was applied in complain mode
+    ComplainReport {
+        /// Service unit name, or glob pattern matching loaded units (ie. `'php-fpm*'`)
+        service: String,
+    },
+    /// Enforce the syscall filter of hardening config applied in complain mode
+    Enforce {
+        /// Service unit name, or glob pattern matching loaded units (ie. `'php-fpm*'`)
+        service: String,
+        /// Enforce even if syscalls that would have been denied were logged
+        #[arg(short, long, default_value_t = false)]
+        force: bool,
+        #[command(flatten)]
+        hook_opts: HookOptions,
+        /// Disable immediate service restart
+        #[arg(short, long, default_value_t = false)]
+        no_restart: bool,
+    },
+    /// Get intermediate profiling result without stopping the service
+    SnapshotProfile {
+        /// Service unit name, or glob pattern matching loaded units (ie. `'php-fpm*'`)
+        service: String,
+        #[command(flatten)]
+        report_opts: ReportOptions,
+    },
+    /// Pause profiling, to run the service without profiling overhead for a while, keeping profile data so far
+    PauseProfile {
+        /// Service unit name, or glob pattern matching loaded units (ie. `'php-fpm*'`)
+        service: String,
+        /// Disable immediate service restart
+        #[arg(short, long, default_value_t = false)]
+        no_restart: bool,
+    },
+    /// Resume profiling paused with `pause-profile`
+    ResumeProfile {
+        /// Service unit name, or glob pattern matching loaded units (ie. `'php-fpm*'`)
+        service: String,
+        /// Disable immediate service restart
+        #[arg(short, long, default_value_t = false)]
+        no_restart: bool,
+    },
+    /// Show profiling state and progress
+    ProfileStatus {
+        /// Service unit name, or glob pattern matching loaded units (ie. `'php-fpm*'`)
+        service: String,
+    },
     /// Remove profiling and/or hardening config fragments, and restart service to restore its initial state
     Reset {
-        /// Service unit name
+        /// Service unit name, or glob pattern matching loaded units (ie. `'php-fpm*'`)
+        #[arg(required_unless_present = "all")]
+        service: Option<String>,
+        /// Reset all services with profiling or hardening config fragments, and remove leftover profile data
+        #[arg(long, default_value_t = false, conflicts_with = "service")]
+        all: bool,
+    },
+    /// Write applied hardening config with its provenance (host, environment, time) to a bundle file, to apply it on
+    /// identical hosts with `import` without profiling again
+    Export {
+        /// Service unit name, or glob pattern matching loaded units (ie. `'php-fpm*'`)
+        service: String,
+        /// Bundle file, or package source tree directory with `--packaging-output`
+        path: PathBuf,
+        /// Write the hardening config as a drop-in in a package source tree instead, with stable content, to ship
+        /// it in a distribution package
+        #[arg(long, value_name = "LAYOUT", default_value = None)]
+        packaging_output: Option<PackagingLayout>,
+    },
+    /// Apply hardening config from a bundle file written by `export`
+    Import {
+        /// Service unit name, or glob pattern matching loaded units (ie. `'php-fpm*'`)
         service: String,
+        /// Bundle file
+        path: PathBuf,
+        #[command(flatten)]
+        hook_opts: HookOptions,
+        /// Disable immediate service restart
+        #[arg(short, long, default_value_t = false)]
+        no_restart: bool,
     },
+    /// Show exposure score history of hardening configs applied to the service
+    History {
+        /// Service unit name, or glob pattern matching loaded units (ie. `'php-fpm*'`)
+        service: String,
+    },
+}
+
+impl ServiceAction {
+    /// Service unit name the action applies to, possibly with wildcards
+    pub(crate) fn service_mut(&mut self) -> Option<&mut String> {
+        match self {
+            Self::StartProfile { service, .. }
+            | Self::FinishProfile { service, .. }
+            | Self::ComplainReport { service }
+            | Self::Enforce { service, .. }
+            | Self::SnapshotProfile { service, .. }
+            | Self::PauseProfile { service, .. }
+            | Self::ResumeProfile { service, .. }
+            | Self::ProfileStatus { service }
+            | Self::Export { service, .. }
+            | Self::Import { service, .. }
+            | Self::History { service } => Some(service),
+            Self::Reset { service, .. } => service.as_mut(),
+        }
+    }
 }
@@ -0,0 +1,123 @@
+//! Profile data collection from remote hosts
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use anyhow::Context as _;
+
+/// Read SSH destinations from a hosts file
+pub(crate) fn read_hosts(path: &Path) -> anyhow::Result<Vec<String>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read hosts file {}", path.display()))?;
+    let hosts = parse_hosts(&content);
+    anyhow::ensure!(!hosts.is_empty(), "No hosts in {path:?}");
+    Ok(hosts)
+}
+
+/// Parse hosts file content: one destination per line, empty lines and lines starting with '#' are ignored
+fn parse_hosts(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(ToOwned::to_owned)
+        .collect()
+}
+
+/// Copy profile data files from remote hosts into a local directory, and return their local paths.
+/// Hosts that can not be reached or have no profile data are skipped with a warning.
+pub(crate) fn fetch(
+    hosts: &[String],
+    remote_paths: &[PathBuf],
+    dir: &Path,
+) -> anyhow::Result<Vec<PathBuf>> {
+    fetch_with(|| Command::new("scp"), hosts, remote_paths, dir)
+}
+
+/// Fetch profile data with the scp command built by `scp`
+fn fetch_with(
+    scp: impl Fn() -> Command,
+    hosts: &[String],
+    remote_paths: &[PathBuf],
+    dir: &Path,
+) -> anyhow::Result<Vec<PathBuf>> {
+    let mut local_paths = Vec::new();
+    for (host_idx, host) in hosts.iter().enumerate() {
+        let host_local_paths_count = local_paths.len();
+        for (path_idx, remote_path) in remote_paths.iter().enumerate() {
+            // Each remote path gets its own directory to avoid name collisions
+            let local_dir = dir.join(host_idx.to_string()).join(path_idx.to_string());
+            fs::create_dir_all(&local_dir)?;
+            log::info!("Fetching {host}:{remote_path:?}...");
+            let status = scp()
+                .args(["-q", "-o", "BatchMode=yes", "--"])
+                .arg(format!("{host}:{}", remote_path.to_string_lossy()))
+                .arg(&local_dir)
+                .stdin(Stdio::null())
+                .status()
+                .context("Failed to run scp")?;
+            if !status.success() {
+                log::warn!("Failed to fetch {remote_path:?} from host {host:?}");
+                continue;
+            }
+            for entry in fs::read_dir(&local_dir)? {
+                local_paths.push(entry?.path());
+            }
+        }
+        if local_paths.len() == host_local_paths_count {
+            log::warn!("No profile data found on host {host:?}");
+        }
+    }
+    anyhow::ensure!(
+        !local_paths.is_empty(),
+        "No profile data could be fetched from any host"
+    );
+    Ok(local_paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hosts() {
+        assert_eq!(
+            parse_hosts("# web servers\nweb1\n  root@web2.example.com \n\n#web3\n"),
+            vec!["web1", "root@web2.example.com"]
+        );
+    }
+
+    #[test]
+    fn test_fetch_skips_failed_hosts() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        // Fake scp, only the "up" host is reachable
+        let script_path = temp_dir.path().join("scp.sh");
+        fs::write(
+            &script_path,
+            "case \"$5\" in up:*) touch \"$6/data\" ;; *) exit 1 ;; esac\n",
+        )
+        .unwrap();
+        let scp = || {
+            let mut cmd = Command::new("sh");
+            cmd.arg(&script_path);
+            cmd
+        };
+        let remote_paths = vec![PathBuf::from("/run/shh-profile-data_*/*")];
+        let dir = temp_dir.path().join("fetched");
+
+        assert_eq!(
+            fetch_with(
+                scp,
+                &["down".to_owned(), "up".to_owned()],
+                &remote_paths,
+                &dir
+            )
+            .unwrap(),
+            vec![dir.join("1/0/data")]
+        );
+        assert!(fetch_with(scp, &["down".to_owned()], &remote_paths, &dir).is_err());
+    }
+}
@@ -0,0 +1,123 @@
+//! Shell completion
+
+use std::{fmt::Write as _, io};
+
+use clap::CommandFactory as _;
+use clap_complete::Shell;
+
+use crate::cl;
+
+/// Name of the hidden subcommand listing service unit names
+const COMPLETE_UNITS_SUBCOMMAND: &str = "__complete-units";
+
+/// Get names of the service subcommands taking a unit name as argument
+fn unit_subcommands(cmd: &clap::Command) -> Vec<String> {
+    cmd.find_subcommand("service")
+        .into_iter()
+        .flat_map(clap::Command::get_subcommands)
+        .filter(|s| s.get_positionals().any(|a| a.get_id() == "service"))
+        .map(|s| s.get_name().to_owned())
+        .collect()
+}
+
+/// Shell code to complete unit names by calling the hidden subcommand, if supported for this shell
+fn unit_completion(shell: Shell, bin_name: &str, subcommands: &[String]) -> Option<String> {
+    let mut script = String::new();
+    match shell {
+        Shell::Bash => {
+            let _ = writeln!(
+                script,
+                r#"
+_{bin_name}_units() {{
+    local cur="${{COMP_WORDS[COMP_CWORD]}}"
+    if [[ ${{COMP_CWORD}} -eq 3 && "${{COMP_WORDS[1]}}" == "service" && "${{cur}}" != -* ]]; then
+        case "${{COMP_WORDS[2]}}" in
+            {subcommands})
+                mapfile -t COMPREPLY < <({bin_name} {COMPLETE_UNITS_SUBCOMMAND} "${{cur}}" 2>/dev/null)
+                return 0
+                ;;
+        esac
+    fi
+    _{bin_name} "$@"
+}}
+complete -F _{bin_name}_units -o bashdefault -o default {bin_name}"#,
+                subcommands = subcommands.join("|")
+            );
+        }
+        Shell::Fish => {
+            let _ = writeln!(
+                script,
+                r#"
+complete -c {bin_name} -n "__fish_seen_subcommand_from service; and __fish_seen_subcommand_from {subcommands}" -f -a "({bin_name} {COMPLETE_UNITS_SUBCOMMAND} (commandline -ct) 2>/dev/null)""#,
+                subcommands = subcommands.join(" ")
+            );
+        }
+        Shell::Zsh => {
+            let _ = writeln!(
+                script,
+                r#"
+_{bin_name}_units() {{
+    local -a units
+    units=(${{(f)"$({bin_name} {COMPLETE_UNITS_SUBCOMMAND} "$PREFIX" 2>/dev/null)"}})
+    compadd -a units
+}}"#
+            );
+        }
+        _ => return None,
+    }
+    Some(script)
+}
+
+/// Write completion script for a shell
+pub(crate) fn write_script<W: io::Write>(shell: Shell, out: &mut W) -> anyhow::Result<()> {
+    let mut cmd = cl::Args::command();
+    let bin_name = cmd.get_name().to_owned();
+    let mut buf = Vec::new();
+    clap_complete::generate(shell, &mut cmd, &bin_name, &mut buf);
+    let mut script = String::from_utf8(buf)?;
+
+    let subcommands = unit_subcommands(&cmd);
+    if let Some(unit_script) = unit_completion(shell, &bin_name, &subcommands) {
+        if shell == Shell::Zsh {
+            // Use the unit completion function for unit arguments instead of the default file completion,
+            // and define it at the start of the script, because it is autoloaded as a function body
+            script = script.replace(
+                ":service -- Service unit name:_default'",
+                &format!(":service -- Service unit name:_{bin_name}_units'"),
+            );
+            let (first_line, rest) = script.split_once('\n').unwrap_or((&script, ""));
+            script = format!("{first_line}\n{unit_script}{rest}");
+        } else {
+            script.push_str(&unit_script);
+        }
+    }
+
+    out.write_all(script.as_bytes())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unit_subcommands() {
+        assert_eq!(
+            unit_subcommands(&cl::Args::command()),
+            vec![
+                "start-profile",
+                "finish-profile",
+                "complain-report",
+                "enforce",
+                "snapshot-profile",
+                "pause-profile",
+                "resume-profile",
+                "profile-status",
+                "reset",
+                "export",
+                "import",
+                "history"
+            ]
+        );
+    }
+}
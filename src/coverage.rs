@@ -0,0 +1,143 @@
+//! Profiling coverage heuristics, to warn before applying options derived from an unrepresentative run
+
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::summarize::ProgramAction;
+
+/// Below this runtime, a service has likely not reached its steady state
+const MIN_RUNTIME: Duration = Duration::from_secs(30);
+
+/// Below this number of distinct syscalls, the program has likely done little more than starting
+const MIN_SYSCALL_COUNT: usize = 40;
+
+/// Command prefixes of `ExecXxx=` directives
+/// See <https://www.freedesktop.org/software/systemd/man/systemd.service.html#Command%20lines>
+const EXEC_PREFIXES: [char; 6] = ['-', '@', ':', '+', '!', '|'];
+
+/// Summary of profiled actions, to judge profiling coverage
+#[derive(Debug, Default, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+pub(crate) struct Coverage {
+    /// Number of distinct syscalls
+    pub syscall_count: usize,
+    /// Whether any socket activity was seen
+    pub network: bool,
+    /// Executables that were run
+    pub executables: BTreeSet<PathBuf>,
+}
+
+impl Coverage {
+    pub(crate) fn new(actions: &[ProgramAction]) -> Self {
+        let mut syscalls = BTreeSet::new();
+        let mut coverage = Self::default();
+        for action in actions {
+            match action {
+                ProgramAction::Syscalls(names) => syscalls.extend(names),
+                ProgramAction::NetworkActivity(_) => coverage.network = true,
+                ProgramAction::Exec(path) | ProgramAction::SetuidExec(path) => {
+                    coverage.executables.insert(path.clone());
+                }
+                _ => {}
+            }
+        }
+        coverage.syscall_count = syscalls.len();
+        coverage
+    }
+
+    /// Whether the executable of a command line was run, matched by file name as paths may be resolved differently
+    fn ran(&self, cmd: &str) -> bool {
+        let Some(exe) = cmd
+            .trim_start_matches(EXEC_PREFIXES)
+            .split_whitespace()
+            .next()
+        else {
+            return true;
+        };
+        let exe_name = Path::new(exe).file_name();
+        self.executables.iter().any(|e| e.file_name() == exe_name)
+    }
+}
+
+/// What is known of the profiled service, outside of its profiled actions
+#[derive(Debug)]
+pub(crate) struct ServiceContext {
+    /// Runtime of the last activation
+    pub runtime: Option<Duration>,
+    /// Socket units activating the service
+    pub socket_units: Vec<String>,
+    /// Profiled setup command lines
+    pub exec_start_pre_cmds: Vec<String>,
+}
+
+/// Get warnings about profiling coverage looking poor
+pub(crate) fn warnings(coverage: &Coverage, context: &ServiceContext) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if let Some(runtime) = context.runtime.filter(|r| *r < MIN_RUNTIME) {
+        warnings.push(format!(
+            "Service only ran for {}s during its last profiled activation, it may not have reached its steady state",
+            runtime.as_secs()
+        ));
+    }
+    if coverage.syscall_count < MIN_SYSCALL_COUNT {
+        warnings.push(format!(
+            "Only {} distinct syscalls were seen, the service may have done little more than starting",
+            coverage.syscall_count
+        ));
+    }
+    if !coverage.network && !context.socket_units.is_empty() {
+        warnings.push(format!(
+            "Service is activated by {}, but no socket activity was seen, it may not have served any request",
+            context.socket_units.join(", ")
+        ));
+    }
+    for cmd in context
+        .exec_start_pre_cmds
+        .iter()
+        .filter(|c| !coverage.ran(c))
+    {
+        warnings.push(format!("Setup command {cmd:?} was never run"));
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_warnings() {
+        let actions: Vec<_> = vec![
+            ProgramAction::Exec("/usr/bin/foo".into()),
+            ProgramAction::Exec("/usr/lib/foo/setup".into()),
+            ProgramAction::Syscalls((0..50).map(|i| format!("syscall{i}")).collect()),
+        ];
+        let coverage = Coverage::new(&actions);
+        assert_eq!(coverage.syscall_count, 50);
+        assert!(!coverage.network);
+
+        let long_run = ServiceContext {
+            runtime: Some(Duration::from_hours(1)),
+            socket_units: vec![],
+            exec_start_pre_cmds: vec!["-/usr/lib/foo/setup --init".to_owned()],
+        };
+        assert!(warnings(&coverage, &long_run).is_empty());
+
+        let short_run = ServiceContext {
+            runtime: Some(Duration::from_secs(5)),
+            socket_units: vec!["foo.socket".to_owned()],
+            exec_start_pre_cmds: vec!["@/usr/bin/bar bar".to_owned()],
+        };
+        assert_eq!(
+            warnings(&Coverage::default(), &short_run),
+            vec![
+                "Service only ran for 5s during its last profiled activation, it may not have reached its steady state",
+                "Only 0 distinct syscalls were seen, the service may have done little more than starting",
+                "Service is activated by foo.socket, but no socket activity was seen, it may not have served any request",
+                "Setup command \"@/usr/bin/bar bar\" was never run",
+            ]
+        );
+    }
+}
@@ -0,0 +1,105 @@
+//! Snapshot of the host environment option generation depends on
+
+use std::{collections::BTreeMap, fs, path::Path};
+
+use anyhow::Context as _;
+
+use crate::{
+    strace::StraceVersion,
+    systemd::{KernelVersion, SystemdVersion},
+};
+
+/// Kernel settings that change what a sandboxed program can do, recorded for reference
+const SYSCTLS: [&str; 5] = [
+    "kernel.unprivileged_bpf_disabled",
+    "kernel.unprivileged_userns_clone",
+    "kernel.yama.ptrace_scope",
+    "net.ipv6.conf.all.disable_ipv6",
+    "user.max_user_namespaces",
+];
+
+/// Host environment, either detected locally or loaded from a file exported on another machine
+///
+/// Stored as TOML, for example:
+/// ```toml
+/// systemd_version = "254.1"
+/// kernel_version = "6.4.12"
+/// strace_version = "6.5"
+///
+/// [sysctls]
+/// "kernel.yama.ptrace_scope" = "1"
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Environment {
+    /// Systemd version, if systemd is running
+    pub systemd_version: Option<SystemdVersion>,
+    pub kernel_version: KernelVersion,
+    /// Strace version, if installed
+    pub strace_version: Option<StraceVersion>,
+    /// Values of relevant sysctl settings, by name
+    #[serde(default)]
+    pub sysctls: BTreeMap<String, String>,
+}
+
+impl Environment {
+    /// Detect local environment
+    pub(crate) fn local_system() -> anyhow::Result<Self> {
+        let kernel_version = KernelVersion::local_system()?;
+        let systemd_version = SystemdVersion::local_system()
+            .inspect_err(|err| log::info!("No systemd detected ({err})"))
+            .ok();
+        let strace_version = StraceVersion::local_system(Path::new("strace"))
+            .inspect_err(|err| log::debug!("No strace detected ({err})"))
+            .ok();
+        let sysctls = SYSCTLS
+            .iter()
+            .filter_map(|name| {
+                let path = Path::new("/proc/sys").join(name.replace('.', "/"));
+                // Some settings are only readable by root, or do not exist on all kernels
+                let value = fs::read_to_string(path)
+                    .inspect_err(|err| log::debug!("Skipping sysctl {name}: {err}"))
+                    .ok()?;
+                Some(((*name).to_owned(), value.trim().to_owned()))
+            })
+            .collect();
+        Ok(Self {
+            systemd_version,
+            kernel_version,
+            strace_version,
+            sysctls,
+        })
+    }
+
+    pub(crate) fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read environment from {}", path.display()))?;
+        toml::from_str(&content)
+            .with_context(|| format!("Failed to parse environment from {}", path.display()))
+    }
+
+    pub(crate) fn to_toml(&self) -> anyhow::Result<String> {
+        Ok(toml::to_string(self)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toml_roundtrip() {
+        let env = Environment {
+            systemd_version: Some(SystemdVersion::new(254, 1)),
+            kernel_version: KernelVersion::new(6, 4, 12),
+            strace_version: None,
+            sysctls: BTreeMap::from([("kernel.yama.ptrace_scope".to_owned(), "1".to_owned())]),
+        };
+        let content = env.to_toml().unwrap();
+        assert_eq!(
+            content,
+            "systemd_version = \"254.1\"\nkernel_version = \"6.4.12\"\n\n[sysctls]\n\"kernel.yama.ptrace_scope\" = \"1\"\n"
+        );
+        assert_eq!(toml::from_str::<Environment>(&content).unwrap(), env);
+    }
+}
@@ -0,0 +1,149 @@
+//! Error categories, mapped to exit codes so that wrappers can tell misconfiguration from transient failures
+
+use std::{fmt, io::Write as _, process::ExitCode};
+
+use crate::cl::ErrorFormat;
+
+/// Error category, attached as context to errors with `.context(ErrorKind::X)`
+#[derive(Debug, Clone, Copy, Eq, PartialEq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ErrorKind {
+    /// The system or host setup does not allow running the command (ie. missing strace or systemd)
+    Environment,
+    /// Unexpected input data (ie. strace output, profile data, bundle or environment files)
+    Parsing,
+    /// Interaction with the service manager failed
+    Systemd,
+    /// An operation did not complete in time, it may succeed when retried
+    Timeout,
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Environment => write!(f, "Unsupported environment"),
+            Self::Parsing => write!(f, "Failed to parse input data"),
+            Self::Systemd => write!(f, "Systemd interaction failed"),
+            Self::Timeout => write!(f, "Operation timed out"),
+        }
+    }
+}
+
+impl std::error::Error for ErrorKind {}
+
+/// Exit code for errors without a category, 2 is used by clap for invalid command lines
+const GENERIC_EXIT_CODE: u8 = 1;
+
+impl ErrorKind {
+    /// Get the category of an error, from its context or from the type of its causes
+    pub(crate) fn of(err: &anyhow::Error) -> Option<Self> {
+        if let Some(kind) = err.downcast_ref::<Self>() {
+            return Some(*kind);
+        }
+        err.chain().find_map(|cause| {
+            if cause.is::<zbus::Error>() || cause.is::<zbus::fdo::Error>() {
+                Some(Self::Systemd)
+            } else if cause.is::<serde_json::Error>()
+                || cause.is::<toml::de::Error>()
+                || cause.is::<bincode::Error>()
+            {
+                Some(Self::Parsing)
+            } else {
+                None
+            }
+        })
+    }
+
+    pub(crate) fn exit_code(self) -> u8 {
+        match self {
+            Self::Environment => 3,
+            Self::Parsing => 4,
+            Self::Systemd => 5,
+            Self::Timeout => 6,
+        }
+    }
+}
+
+/// Error, as written in JSON format
+#[derive(Debug, serde::Serialize)]
+struct JsonError {
+    kind: Option<ErrorKind>,
+    exit_code: u8,
+    message: String,
+    causes: Vec<String>,
+}
+
+impl JsonError {
+    fn new(err: &anyhow::Error) -> Self {
+        let kind = ErrorKind::of(err);
+        let mut messages = err.chain().map(ToString::to_string);
+        Self {
+            kind,
+            exit_code: kind.map_or(GENERIC_EXIT_CODE, ErrorKind::exit_code),
+            #[expect(clippy::unwrap_used)]
+            message: messages.next().unwrap(),
+            causes: messages.collect(),
+        }
+    }
+}
+
+/// Write error to stderr in the requested format, and get the process exit code for it
+pub(crate) fn report(err: &anyhow::Error, format: ErrorFormat) -> ExitCode {
+    let json_err = JsonError::new(err);
+    match format {
+        ErrorFormat::Text => eprintln!("Error: {err:?}"),
+        ErrorFormat::Json => {
+            let mut stderr = std::io::stderr().lock();
+            let _ = serde_json::to_writer(&mut stderr, &json_err);
+            let _ = writeln!(stderr);
+        }
+    }
+    ExitCode::from(json_err.exit_code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_kind() {
+        assert_eq!(
+            ErrorKind::of(
+                &anyhow::anyhow!("Unable to get strace version").context(ErrorKind::Environment)
+            ),
+            Some(ErrorKind::Environment)
+        );
+
+        // Outermost category wins
+        assert_eq!(
+            ErrorKind::of(
+                &anyhow::anyhow!("Job start for foo.service failed")
+                    .context(ErrorKind::Timeout)
+                    .context(ErrorKind::Systemd)
+            ),
+            Some(ErrorKind::Systemd)
+        );
+
+        // Category from cause type
+        assert_eq!(
+            ErrorKind::of(
+                &anyhow::Error::new(serde_json::from_str::<u32>("{").unwrap_err())
+                    .context("Failed to load foo.json")
+            ),
+            Some(ErrorKind::Parsing)
+        );
+
+        assert_eq!(
+            ErrorKind::of(&anyhow::anyhow!("Something went wrong")),
+            None
+        );
+
+        let err = anyhow::anyhow!("No profile data found")
+            .context(ErrorKind::Parsing)
+            .context("Failed to merge profile data");
+        assert_eq!(
+            serde_json::to_string(&JsonError::new(&err)).unwrap(),
+            r#"{"kind":"parsing","exit_code":4,"message":"Failed to merge profile data","causes":["Failed to parse input data","No profile data found"]}"#
+        );
+    }
+}
@@ -0,0 +1,212 @@
+//! Exposure score history of services, to follow hardening progress and catch regressions
+
+use std::{
+    fmt::Write as _,
+    fs::{self, OpenOptions},
+    io::Write as _,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use anyhow::Context as _;
+
+/// Directory of history files, one per unit
+const HISTORY_DIR: &str = "/var/lib/shh/history";
+
+/// Change to the service hardening that was recorded
+#[derive(
+    Debug, Clone, Copy, Eq, PartialEq, serde::Serialize, serde::Deserialize, strum::Display,
+)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub(crate) enum Event {
+    /// Hardening config built from profiling was applied
+    Hardened,
+    /// Hardening config was removed
+    Reset,
+}
+
+/// Exposure of a service at some point in time
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub(crate) struct Entry {
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    pub event: Event,
+    pub exposure: f64,
+    /// Number of hardening options set
+    pub option_count: usize,
+}
+
+impl Entry {
+    pub(crate) fn now(event: Event, exposure: f64, option_count: usize) -> Self {
+        Self {
+            timestamp: SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            event,
+            exposure,
+            option_count,
+        }
+    }
+}
+
+fn history_path(dir: &Path, unit: &str) -> PathBuf {
+    dir.join(format!("{unit}.jsonl"))
+}
+
+/// Append entry to the history of a unit
+pub(crate) fn record(unit: &str, entry: &Entry) -> anyhow::Result<()> {
+    record_in(Path::new(HISTORY_DIR), unit, entry)
+}
+
+fn record_in(dir: &Path, unit: &str, entry: &Entry) -> anyhow::Result<()> {
+    fs::create_dir_all(dir)?;
+    let path = history_path(dir, unit);
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    writeln!(file, "{}", serde_json::to_string(entry)?)?;
+    Ok(())
+}
+
+/// Load history of a unit, oldest entry first
+pub(crate) fn load(unit: &str) -> anyhow::Result<Vec<Entry>> {
+    load_from(Path::new(HISTORY_DIR), unit)
+}
+
+fn load_from(dir: &Path, unit: &str) -> anyhow::Result<Vec<Entry>> {
+    let path = history_path(dir, unit);
+    if !path.is_file() {
+        return Ok(Vec::new());
+    }
+    fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?
+        .lines()
+        .map(|l| serde_json::from_str(l).with_context(|| format!("Invalid history line {l:?}")))
+        .collect()
+}
+
+/// Remove history of all units
+pub(crate) fn purge() -> anyhow::Result<()> {
+    let dir = Path::new(HISTORY_DIR);
+    if dir.is_dir() {
+        fs::remove_dir_all(dir)?;
+        log::info!("{} removed", dir.display());
+    }
+    // Only remove our state directory if nothing else is left in it
+    if let Some(state_dir) = dir.parent() {
+        let _ = fs::remove_dir(state_dir);
+    }
+    Ok(())
+}
+
+/// Exposure increase of a new hardening compared to the previous one, if any
+pub(crate) fn regression(entries: &[Entry], exposure: f64) -> Option<f64> {
+    entries
+        .iter()
+        .rev()
+        .find(|e| e.event == Event::Hardened)
+        .map(|e| exposure - e.exposure)
+        .filter(|d| *d > 0.0)
+}
+
+/// Format a Unix timestamp as an UTC date and time
+/// See <https://howardhinnant.github.io/date_algorithms.html#civil_from_days>
+fn format_timestamp(timestamp: u64) -> String {
+    let days = timestamp / 86400;
+    let secs = timestamp % 86400;
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02} UTC",
+        secs / 3600,
+        (secs / 60) % 60
+    )
+}
+
+/// Format history as a table, with the exposure change of each entry
+pub(crate) fn format(entries: &[Entry]) -> String {
+    let mut history = String::new();
+    let mut prev_exposure = None;
+    for entry in entries {
+        let change =
+            prev_exposure.map_or_else(String::new, |p| format!(" ({:+.1})", entry.exposure - p));
+        let _ = writeln!(
+            history,
+            "{}  {:<8}  exposure {:.1}{change}, {} option(s)",
+            format_timestamp(entry.timestamp),
+            entry.event.to_string(),
+            entry.exposure,
+            entry.option_count
+        );
+        prev_exposure = Some(entry.exposure);
+    }
+    if let (Some(first), Some(last)) = (entries.first(), entries.last()) {
+        let _ = writeln!(
+            history,
+            "Trend: exposure {:.1} -> {:.1} over {} change(s)",
+            first.exposure,
+            last.exposure,
+            entries.len()
+        );
+    }
+    history
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(load_from(dir.path(), "foo.service").unwrap().is_empty());
+
+        let entries = [
+            Entry {
+                timestamp: 1_700_000_000,
+                event: Event::Hardened,
+                exposure: 3.0,
+                option_count: 20,
+            },
+            Entry {
+                timestamp: 1_700_086_400,
+                event: Event::Reset,
+                exposure: 10.0,
+                option_count: 0,
+            },
+            Entry {
+                timestamp: 1_709_251_199,
+                event: Event::Hardened,
+                exposure: 2.5,
+                option_count: 22,
+            },
+        ];
+        for entry in &entries {
+            record_in(dir.path(), "foo.service", entry).unwrap();
+        }
+        let loaded = load_from(dir.path(), "foo.service").unwrap();
+        assert_eq!(loaded, entries);
+
+        assert_eq!(regression(&loaded, 2.0), None);
+        assert_eq!(regression(&loaded, 3.0), Some(0.5));
+        assert_eq!(
+            format(&loaded),
+            "2023-11-14 22:13 UTC  hardened  exposure 3.0, 20 option(s)
+2023-11-15 22:13 UTC  reset     exposure 10.0 (+7.0), 0 option(s)
+2024-02-29 23:59 UTC  hardened  exposure 2.5 (-7.5), 22 option(s)
+Trend: exposure 3.0 -> 2.5 over 3 change(s)
+"
+        );
+    }
+}
@@ -0,0 +1,155 @@
+//! Hook run after a hardening config is applied, to record the change in version control
+
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::cl::PostApplyHook;
+
+/// Hardening config change that was applied to a service
+pub(crate) struct AppliedChange<'a> {
+    /// Service unit name
+    pub unit: &'a str,
+    /// What was done, ie. "harden" or "enforce syscall filter of"
+    pub action: &'a str,
+    /// Written hardening fragment and helper unit files
+    pub paths: &'a [PathBuf],
+    /// Estimated exposure after the change
+    pub exposure: f64,
+    /// Exposure change since the previous hardening config (or none)
+    pub exposure_delta: f64,
+}
+
+impl AppliedChange<'_> {
+    fn commit_message(&self) -> String {
+        format!(
+            "{}: {} {} (exposure {:.1}, {:+.1})\n\nApplied by {} {}",
+            env!("CARGO_PKG_NAME"),
+            self.action,
+            self.unit,
+            self.exposure,
+            self.exposure_delta,
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION")
+        )
+    }
+}
+
+/// Build commands to run for a hook
+fn commands(hook: &PostApplyHook, change: &AppliedChange) -> Vec<Command> {
+    let message = change.commit_message();
+    match hook {
+        PostApplyHook::Etckeeper => {
+            let mut cmd = Command::new("etckeeper");
+            cmd.args(["commit", &message]);
+            vec![cmd]
+        }
+        PostApplyHook::Git => {
+            // Fragments may not be in the same repository, ie. if only /etc/systemd is versioned
+            change
+                .paths
+                .iter()
+                .flat_map(|path| {
+                    let dir = path.parent().unwrap_or(Path::new("/"));
+                    let mut add_cmd = Command::new("git");
+                    add_cmd.arg("-C").arg(dir).arg("add").arg("--").arg(path);
+                    let mut commit_cmd = Command::new("git");
+                    commit_cmd
+                        .arg("-C")
+                        .arg(dir)
+                        .args(["commit", "-m", &message, "--"])
+                        .arg(path);
+                    [add_cmd, commit_cmd]
+                })
+                .collect()
+        }
+        PostApplyHook::Command(shell_cmd) => {
+            let mut cmd = Command::new("sh");
+            cmd.args(["-c", shell_cmd])
+                .env("SHH_UNIT", change.unit)
+                .env(
+                    "SHH_PATHS",
+                    change
+                        .paths
+                        .iter()
+                        .map(|p| p.to_string_lossy())
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                )
+                .env("SHH_EXPOSURE", format!("{:.1}", change.exposure))
+                .env(
+                    "SHH_EXPOSURE_DELTA",
+                    format!("{:+.1}", change.exposure_delta),
+                )
+                .env("SHH_VERSION", env!("CARGO_PKG_VERSION"))
+                .env("SHH_COMMIT_MESSAGE", &message);
+            vec![cmd]
+        }
+    }
+}
+
+/// Run hook for an applied change
+pub(crate) fn run(hook: &PostApplyHook, change: &AppliedChange) -> anyhow::Result<()> {
+    for mut cmd in commands(hook, change) {
+        log::info!("Running post apply hook {cmd:?}");
+        let status = cmd.status()?;
+        anyhow::ensure!(status.success(), "Post apply hook {cmd:?} failed: {status}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_commands() {
+        let paths = vec![PathBuf::from(
+            "/etc/systemd/system/foo.service.d/zz_shh-harden.conf",
+        )];
+        let change = AppliedChange {
+            unit: "foo.service",
+            action: "harden",
+            paths: &paths,
+            exposure: 2.5,
+            exposure_delta: -7.5,
+        };
+        let message = format!(
+            "shh: harden foo.service (exposure 2.5, -7.5)\n\nApplied by shh {}",
+            env!("CARGO_PKG_VERSION")
+        );
+        assert_eq!(change.commit_message(), message);
+
+        let git_cmds = commands(&PostApplyHook::Git, &change);
+        assert_eq!(
+            git_cmds
+                .iter()
+                .map(|c| c.get_args().collect::<Vec<_>>())
+                .collect::<Vec<_>>(),
+            vec![
+                vec![
+                    "-C",
+                    "/etc/systemd/system/foo.service.d",
+                    "add",
+                    "--",
+                    "/etc/systemd/system/foo.service.d/zz_shh-harden.conf"
+                ],
+                vec![
+                    "-C",
+                    "/etc/systemd/system/foo.service.d",
+                    "commit",
+                    "-m",
+                    &message,
+                    "--",
+                    "/etc/systemd/system/foo.service.d/zz_shh-harden.conf"
+                ],
+            ]
+        );
+
+        let shell_cmds = commands(&PostApplyHook::Command("true".to_owned()), &change);
+        assert!(shell_cmds[0]
+            .get_envs()
+            .any(|(k, v)| k == "SHH_EXPOSURE_DELTA" && v == Some("-7.5".as_ref())));
+    }
+}
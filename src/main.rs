@@ -3,23 +3,62 @@
 #![cfg_attr(all(feature = "nightly", test), feature(test))]
 
 use std::{
+    collections::BTreeSet,
     fs::{self, File},
+    io, iter,
+    os::unix::fs::PermissionsExt as _,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
     thread,
+    time::{Duration, Instant, SystemTime},
 };
 
 use anyhow::Context;
 use clap::Parser;
+use itertools::Itertools as _;
 
+mod app_preset;
+mod bundle;
+mod capabilities;
 mod cl;
+mod collect;
+mod complete;
+mod coverage;
+mod environment;
+mod error;
+mod history;
+mod hook;
+mod overhead;
+mod packaging;
+mod path_tree;
+mod profile;
+#[cfg(target_arch = "x86_64")]
+mod ptrace;
+mod report;
+mod smoke_test;
+mod standalone;
+mod stats;
 mod strace;
 mod summarize;
 mod systemd;
+mod timeline;
+mod watch;
 
 fn sd_options(
     sd_version: &systemd::SystemdVersion,
     kernel_version: &systemd::KernelVersion,
     hardening_opts: &cl::HardeningOptions,
 ) -> Vec<systemd::OptionDescription> {
+    let sd_version = if let Some(target_version) = &hardening_opts.target_systemd_version {
+        log::info!("Generating options for systemd {target_version}");
+        target_version
+    } else {
+        sd_version
+    };
     let sd_opts = systemd::build_options(sd_version, kernel_version, hardening_opts);
     log::info!(
         "Enabled support for systemd options: {}",
@@ -32,7 +71,564 @@ fn sd_options(
     sd_opts
 }
 
-fn main() -> anyhow::Result<()> {
+/// How long to wait for profiling wrappers to write their snapshot
+const SNAPSHOT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Path of the intermediate profile data, for a given final profile data path
+fn profile_snapshot_path(profile_data_path: &Path) -> PathBuf {
+    let mut path = profile_data_path.as_os_str().to_owned();
+    path.push(".snapshot");
+    path.into()
+}
+
+/// Get actions gathered so far by a profiled service, whether its profiling wrappers are finished or still running
+fn profiling_snapshot(
+    service: &systemd::Service,
+    paths: &[PathBuf],
+) -> anyhow::Result<Vec<summarize::ProgramAction>> {
+    Ok(profiling_snapshot_data(service, paths)?
+        .into_iter()
+        .flat_map(|d| d.actions)
+        .collect())
+}
+
+/// Get profile data files gathered so far by a profiled service, whether its profiling wrappers are finished or still
+/// running
+fn profiling_snapshot_data(
+    service: &systemd::Service,
+    paths: &[PathBuf],
+) -> anyhow::Result<Vec<profile::ProfileData>> {
+    // Ask running profiling wrapper to write a snapshot
+    let snapshot_paths: Vec<_> = paths.iter().map(|p| profile_snapshot_path(p)).collect();
+    for snapshot_path in &snapshot_paths {
+        let _ = fs::remove_file(snapshot_path);
+    }
+    // Services activated by a timer are not running most of the time
+    if service.is_active()? {
+        service.signal_main_process(nix::sys::signal::Signal::SIGUSR1)?;
+
+        // The snapshot is only written when the profiled program makes its next syscall
+        let deadline = Instant::now() + SNAPSHOT_TIMEOUT;
+        while !snapshot_paths.iter().any(|p| p.is_file()) {
+            if Instant::now() > deadline {
+                log::warn!("Timeout waiting for profiling snapshot, service may be idle");
+                break;
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+    }
+
+    // Load profile data from finished and running profiling wrappers
+    let mut profiles = Vec::new();
+    for (path, snapshot_path) in paths.iter().zip(&snapshot_paths) {
+        let data_path = if path.is_file() {
+            path
+        } else if snapshot_path.is_file() {
+            snapshot_path
+        } else {
+            log::debug!("No profile data yet for {path:?}");
+            continue;
+        };
+        profiles.push(profile::ProfileData::read(data_path)?);
+        if data_path == snapshot_path {
+            fs::remove_file(snapshot_path)?;
+        }
+    }
+    Ok(profiles)
+}
+
+/// Check strace is installed, recent enough, and supports the arguments we need
+fn check_strace(strace_bin: &Path) -> anyhow::Result<()> {
+    let strace_version = strace::StraceVersion::local_system(strace_bin).context(
+        "Unable to get strace version, install strace or set its path with --strace-path",
+    )?;
+    log::info!(
+        "Detected strace version {strace_version} for {}",
+        strace_bin.display()
+    );
+    if strace_version < strace::StraceVersion::new(6, 4) {
+        log::warn!("Strace version >=6.4 is strongly recommended, if you experience strace output parsing errors, please consider upgrading, or use a more recent build (ie. static) with --strace-path");
+    }
+    strace::Strace::probe(strace_bin).context(
+        "Strace is too old or built without needed features, use a more recent build (ie. static) with --strace-path",
+    )
+}
+
+/// Profile command by tracing its syscalls, and summarize its actions
+#[expect(clippy::too_many_arguments)]
+fn profile_command(
+    command: &[String],
+    identity_opts: &cl::RunIdentityOptions,
+    tracer: cl::Tracer,
+    strace_opts: &cl::StraceOptions,
+    sampling_opts: &cl::SamplingOptions,
+    strace_log_opts: cl::StraceLogOptions,
+    profile_data_path: Option<&Path>,
+    sd_opts: &[systemd::OptionDescription],
+    watch: bool,
+    capability_source: cl::CapabilitySource,
+    pid_file: Option<&Path>,
+    color: bool,
+) -> anyhow::Result<profile::ProfileData> {
+    // Capability checks of the program are only recorded if tracing starts before it
+    let capability_tracer = (capability_source == cl::CapabilitySource::Kprobe)
+        .then(capabilities::CapabilityTracer::start)
+        .transpose()
+        .context("Failed to start capability tracing")?;
+
+    // Start tracing
+    let cmd = command.iter().map(|a| &**a).collect::<Vec<&str>>();
+    let mut _strace = None;
+    let syscalls: Box<dyn Iterator<Item = anyhow::Result<strace::Syscall>>> = match tracer {
+        cl::Tracer::Strace => {
+            check_strace(strace_opts.binary()).context(error::ErrorKind::Environment)?;
+            let st =
+                strace::Strace::run(strace_opts.binary(), &cmd, identity_opts, strace_log_opts)?;
+            let logs = st.log_lines()?;
+            // Keep strace process alive until we are done reading its output
+            _strace = Some(st);
+            Box::new(logs)
+        }
+        #[cfg(target_arch = "x86_64")]
+        cl::Tracer::Ptrace => {
+            if strace_log_opts.path.is_some() {
+                log::warn!("Strace log options are ignored with the ptrace tracer");
+            }
+            Box::new(ptrace::Tracer::run(&cmd, identity_opts)?)
+        }
+    };
+    let metadata = profile::ProfileMetadata::local(command)?;
+
+    // Start signal handling thread
+    let stop_signals = [
+        signal_hook::consts::signal::SIGINT,
+        signal_hook::consts::signal::SIGQUIT,
+        signal_hook::consts::signal::SIGTERM,
+    ];
+    if let Some(pid_file) = pid_file {
+        // Stop and reload signals are sent by systemd (or by ExecStop=/ExecReload= commands using $MAINPID) to the
+        // main process, which is us, and not the daemonized program
+        let mut forwarded_signals =
+            signal_hook::iterator::Signals::new(stop_signals.into_iter().chain([
+                signal_hook::consts::signal::SIGHUP,
+                signal_hook::consts::signal::SIGUSR2,
+            ]))?;
+        let pid_file = pid_file.to_owned();
+        thread::spawn(move || {
+            for sig in forwarded_signals.forever() {
+                if let Err(err) = forward_signal(&pid_file, sig) {
+                    log::warn!("Failed to forward signal {sig:?}: {err:#}");
+                }
+            }
+        });
+    } else {
+        let mut signals = signal_hook::iterator::Signals::new(stop_signals)?;
+        thread::spawn(move || {
+            for sig in signals.forever() {
+                // The traced processes already get the signal, so the iterator will stop naturally
+                log::info!("Got signal {sig:?}, ignoring");
+            }
+        });
+    }
+
+    // Intermediate results can be requested at any time
+    let snapshot_requested = Arc::new(AtomicBool::new(false));
+    signal_hook::flag::register(
+        signal_hook::consts::signal::SIGUSR1,
+        Arc::clone(&snapshot_requested),
+    )?;
+
+    // Summarize actions
+    let tracing_start = Instant::now();
+    let start_time = SystemTime::now();
+    let mut summarizer = sampling_opts
+        .interval()
+        .map_or_else(summarize::Summarizer::default, |interval| {
+            summarize::Summarizer::with_sampling(interval)
+        });
+    let mut watcher = watch.then(watch::Watcher::new);
+    for syscall in syscalls {
+        summarizer.add_syscall(&syscall?)?;
+        if let Some(watcher) = watcher.as_mut() {
+            watcher.print_discovered(&summarizer);
+        }
+        if snapshot_requested.swap(false, Ordering::Relaxed) {
+            let mut actions = summarizer.actions();
+            if let Some(capability_tracer) = capability_tracer.as_ref() {
+                actions.extend(capability_tracer.actions());
+            }
+            actions.extend(systemd::env_temp_dir());
+            if let Some(profile_data_path) = profile_data_path {
+                // Write to a temporary file first, so that readers never see partial data
+                let snapshot_path = profile_snapshot_path(profile_data_path);
+                log::info!("Writing profile data snapshot into {snapshot_path:?}...");
+                #[expect(clippy::unwrap_used)]
+                let mut file =
+                    tempfile::NamedTempFile::new_in(profile_data_path.parent().unwrap())?;
+                let profile_data = profile::ProfileData {
+                    metadata: metadata.clone(),
+                    actions,
+                    start_time,
+                    end_time: SystemTime::now(),
+                    syscall_count: summarizer.syscall_total(),
+                    timeline: timeline::entries(&summarizer),
+                };
+                profile_data.write_to(&mut file)?;
+                // Written first, so that it is there once the snapshot is
+                overhead::Overhead {
+                    syscall_count: summarizer.syscall_total(),
+                    duration: tracing_start.elapsed(),
+                }
+                .write(&overhead::Overhead::path(profile_data_path))?;
+                file.persist(snapshot_path)?;
+            } else {
+                let resolved_opts = systemd::resolve(sd_opts, &actions);
+                systemd::report_options(resolved_opts, &systemd::OptionComments::new(), color);
+            }
+        }
+    }
+    let timeline = timeline::entries(&summarizer);
+    let end_time = SystemTime::now();
+    let syscall_count = summarizer.syscall_total();
+    let overhead = overhead::Overhead {
+        syscall_count,
+        duration: tracing_start.elapsed(),
+    };
+    log::info!("Profiling overhead: {overhead}");
+    if let Some(profile_data_path) = profile_data_path {
+        overhead.write(&overhead::Overhead::path(profile_data_path))?;
+    }
+    let mut actions = summarizer.finish();
+    if let Some(capability_tracer) = capability_tracer {
+        actions.extend(capability_tracer.finish()?);
+    }
+    // The program inherits our environment
+    actions.extend(systemd::env_temp_dir());
+    Ok(profile::ProfileData {
+        metadata,
+        actions,
+        start_time,
+        end_time,
+        syscall_count,
+        timeline,
+    })
+}
+
+/// Forward signal to the process whose PID is in a PID file
+fn forward_signal(pid_file: &Path, sig: i32) -> anyhow::Result<()> {
+    let pid: i32 = fs::read_to_string(pid_file)
+        .with_context(|| format!("Failed to read {}", pid_file.display()))?
+        .trim()
+        .parse()
+        .with_context(|| format!("Invalid PID in {}", pid_file.display()))?;
+    let signal = nix::sys::signal::Signal::try_from(sig)?;
+    log::info!("Forwarding signal {signal} to process {pid}");
+    nix::sys::signal::kill(nix::unistd::Pid::from_raw(pid), signal)?;
+    Ok(())
+}
+
+/// Resolve options from actions, for a service optionally running in a root directory
+fn resolve_options(
+    sd_opts: &[systemd::OptionDescription],
+    actions: &[summarize::ProgramAction],
+    root_directory: Option<&Path>,
+) -> Vec<systemd::OptionWithValue> {
+    let mut resolved_opts = systemd::resolve(sd_opts, actions);
+    if let Some(root_directory) = root_directory {
+        systemd::translate_root_directory(&mut resolved_opts, root_directory);
+    }
+    resolved_opts
+}
+
+/// Resolve options from actions, report them, and write optional reports
+fn resolve_and_report(
+    sd_opts: &[systemd::OptionDescription],
+    actions: &[summarize::ProgramAction],
+    hardening_opts: &cl::HardeningOptions,
+    report_opts: &cl::ReportOptions,
+    root_directory: Option<&Path>,
+    service: Option<&systemd::Service>,
+    color: bool,
+) -> anyhow::Result<()> {
+    let mut profiled_actions = actions.to_vec();
+    let dropped_paths = hardening_opts
+        .min_path_accesses
+        .map(|n| profile::drop_rare_path_actions(&mut profiled_actions, n))
+        .unwrap_or_default();
+    if !dropped_paths.is_empty() {
+        log::warn!(
+            "{} path(s) accessed too rarely were left out, check the program does not need them:",
+            dropped_paths.len()
+        );
+        for path in &dropped_paths {
+            log::warn!("  {}", path.display());
+        }
+    }
+
+    // Extra allowances are resolved like profiled actions, but are not part of profile data
+    let mut actions = [
+        profiled_actions.as_slice(),
+        &hardening_opts.extra_actions(),
+        &systemd::plugin_actions(actions),
+    ]
+    .concat();
+    let temp_dir_opts = systemd::temp_dir_options(&mut actions);
+    for opt in &temp_dir_opts {
+        log::info!("Temporary files outside of /tmp are allowed with {opt}");
+    }
+    let actions = &actions;
+    let plugins = systemd::loaded_plugins(actions);
+    if !plugins.is_empty() {
+        log::info!(
+            "Loaded NSS/PAM plugins, whose known needs are kept allowed: {}",
+            plugins.iter().join(", ")
+        );
+    }
+    for advice in systemd::bus_advice(actions)
+        .into_iter()
+        .chain(systemd::scheduling_advice(actions))
+    {
+        log::info!("{advice}");
+    }
+    match systemd::dedicated_user(actions) {
+        Ok(()) => log::info!(
+            "The program does not change credentials or need root privileges, if it runs as root, it could run as a dedicated user with DynamicUser=true or User=, as long as file permissions allow it"
+        ),
+        Err(blockers) => {
+            log::info!("Running the program as a dedicated user with User= needs changes:");
+            for blocker in blockers {
+                log::info!("  {blocker}");
+            }
+        }
+    }
+
+    // Resolve
+    let mut resolved_opts = resolve_options(sd_opts, actions, root_directory);
+    if report_opts.min_risk.is_some() || report_opts.max_risk.is_some() {
+        let risks = systemd::option_risks(&resolved_opts, &systemd::justify(sd_opts, actions));
+        let mut risks = risks.into_iter();
+        resolved_opts.retain(|o| {
+            #[expect(clippy::unwrap_used)] // one risk per option
+            let risk = risks.next().unwrap();
+            let keep = report_opts.min_risk.is_none_or(|r| risk >= r)
+                && report_opts.max_risk.is_none_or(|r| risk <= r);
+            if !keep {
+                log::info!("Leaving out option {} with {risk} breakage risk", o.name);
+            }
+            keep
+        });
+    }
+    resolved_opts.extend(temp_dir_opts);
+    let exposure_model = report_opts
+        .exposure_model
+        .as_deref()
+        .map(systemd::ExposureModel::load)
+        .transpose()?
+        .unwrap_or_default();
+    for option in exposure_model.missing_mandatory(&resolved_opts) {
+        log::warn!("Mandatory option {option} could not be set");
+    }
+
+    // Report
+    if report_opts.report_html.is_some()
+        || report_opts.report_sarif.is_some()
+        || report_opts.report_json.is_some()
+    {
+        let report = report::Report::new(
+            sd_opts,
+            actions,
+            &resolved_opts,
+            &exposure_model,
+            dropped_paths,
+            service.map(systemd::Service::hardening_fragment_path),
+        );
+        if let Some(report_path) = report_opts.report_html.as_deref() {
+            report.write_html(report_path)?;
+        }
+        if let Some(report_path) = report_opts.report_sarif.as_deref() {
+            report.write_sarif(report_path)?;
+        }
+        if let Some(report_path) = report_opts.report_json.as_deref() {
+            report.write_json(report_path)?;
+        }
+    }
+    if report_opts.read_only_root {
+        match systemd::read_only_root(actions) {
+            Ok(opts) => log::info!(
+                "An effectively read only root filesystem can be used with: {}",
+                opts.join(" ")
+            ),
+            Err(blockers) => {
+                log::warn!("An effectively read only root filesystem can not be used:");
+                for blocker in blockers {
+                    log::warn!("  {blocker}");
+                }
+            }
+        }
+    }
+    let comments = if report_opts.annotate {
+        systemd::annotate(&resolved_opts, &systemd::justify(sd_opts, actions))
+    } else {
+        systemd::OptionComments::new()
+    };
+    systemd::report_options(resolved_opts, &comments, color);
+
+    Ok(())
+}
+
+/// Remove profiling and hardening fragments of services, and restart them
+fn reset_services(services: &[systemd::Service]) -> anyhow::Result<()> {
+    let Some(first_service) = services.first() else {
+        log::info!("No service has profiling or hardening config");
+        return Ok(());
+    };
+    for service in services {
+        let _ = service.remove_profile_fragment();
+        if service.remove_hardening_fragment().is_ok() {
+            let entry = history::Entry::now(history::Event::Reset, systemd::MAX_EXPOSURE, 0);
+            if let Err(err) = history::record(&service.unit_name(), &entry) {
+                log::warn!("Failed to record exposure history: {err:#}");
+            }
+        }
+    }
+    first_service.reload_unit_config()?;
+    // Restart as many units as possible, a failing one must not leave others running with removed config
+    let mut failed_units = Vec::new();
+    for service in services {
+        let instances = match service.instances() {
+            Ok(instances) => instances,
+            Err(err) => {
+                log::error!(
+                    "Failed to get instances of {}: {err:#}",
+                    service.unit_name()
+                );
+                failed_units.push(service.unit_name());
+                continue;
+            }
+        };
+        for instance in instances {
+            if let Err(err) = instance.action(systemd::UnitAction::TryRestart, false) {
+                log::error!("Failed to restart {}: {err:#}", instance.unit_name());
+                failed_units.push(instance.unit_name());
+            }
+        }
+    }
+    if !failed_units.is_empty() {
+        return Err(
+            anyhow::anyhow!("Failed to restart {}", failed_units.join(", "))
+                .context(error::ErrorKind::Systemd),
+        );
+    }
+    Ok(())
+}
+
+/// Record exposure of a service hardening config in its history, warning if it increased since the previous one
+fn record_hardening(
+    service: &systemd::Service,
+    resolved_opts: &[systemd::OptionWithValue],
+) -> (f64, f64) {
+    let exposure = systemd::ExposureModel::default().exposure(resolved_opts);
+    let unit = service.unit_name();
+    let mut prev_exposure = systemd::MAX_EXPOSURE;
+    match history::load(&unit) {
+        Ok(entries) => {
+            if let Some(increase) = history::regression(&entries, exposure) {
+                log::warn!("Exposure increased by {increase:.1} since the previous hardening config, the service may need more permissions than before (ie. after a package update)");
+            }
+            if let Some(last) = entries.last() {
+                prev_exposure = last.exposure;
+            }
+        }
+        Err(err) => log::warn!("Failed to load exposure history: {err:#}"),
+    }
+    let entry = history::Entry::now(history::Event::Hardened, exposure, resolved_opts.len());
+    if let Err(err) = history::record(&unit, &entry) {
+        log::warn!("Failed to record exposure history: {err:#}");
+    }
+    (exposure, exposure - prev_exposure)
+}
+
+/// Compare the exposure of an hardened unit with the one `systemd-analyze security` gives, and log the settings it
+/// flags that shh does not manage, which are left to be set manually
+fn cross_check_exposure(service: &systemd::Service, exposure: f64) {
+    match systemd::analyze_security(&service.unit_name()) {
+        Ok(analysis) => {
+            log::info!(
+                "Exposure level: {exposure:.1} estimated by shh, {:.1} according to systemd-analyze security",
+                analysis.exposure
+            );
+            let unmanaged = analysis.unmanaged();
+            if !unmanaged.is_empty() {
+                log::info!(
+                    "Settings flagged by systemd-analyze security that shh does not manage: {}",
+                    unmanaged.join(", ")
+                );
+            }
+        }
+        Err(err) => log::warn!("Failed to cross-check with systemd-analyze security: {err:#}"),
+    }
+}
+
+/// Parse options set in a hardening fragment
+fn fragment_options(fragment: &str) -> Vec<systemd::OptionWithValue> {
+    fragment
+        .lines()
+        .filter(|l| !l.starts_with('#'))
+        .filter_map(|l| l.parse().ok())
+        .collect()
+}
+
+/// Run post apply hook if set, the hardening config being already applied, failures are only logged
+fn run_post_apply_hook(
+    hook_opts: &cl::HookOptions,
+    service: &systemd::Service,
+    action: &str,
+    (exposure, exposure_delta): (f64, f64),
+) {
+    let Some(hook) = &hook_opts.post_apply_hook else {
+        return;
+    };
+    let change = hook::AppliedChange {
+        unit: &service.unit_name(),
+        action,
+        paths: &service.hardening_paths(),
+        exposure,
+        exposure_delta,
+    };
+    if let Err(err) = hook::run(hook, &change) {
+        log::warn!("{err:#}");
+    }
+}
+
+fn main() -> ExitCode {
+    // Parse cl args
+    let args = cl::Args::parse();
+
+    let error_format = args.error_format;
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => error::report(&err, error_format),
+    }
+}
+
+/// Setup logging and environment, and run actions from command line
+fn run(args: cl::Args) -> anyhow::Result<()> {
+    let color = args.color.enabled(&io::stdout());
+
+    // Completion actions run in the user shell, so they must stay quiet and not depend on the system setup
+    match &args.action {
+        cl::Action::GenShellCompletion { shell } => {
+            return complete::write_script(*shell, &mut io::stdout());
+        }
+        cl::Action::CompleteUnits { prefix } => {
+            for name in systemd::Service::list_names(prefix)? {
+                println!("{name}");
+            }
+            return Ok(());
+        }
+        _ => {}
+    }
+
     // Init logger
     simple_logger::SimpleLogger::new()
         .with_level(if cfg!(debug_assertions) {
@@ -40,147 +636,894 @@ fn main() -> anyhow::Result<()> {
         } else {
             log::LevelFilter::Info
         })
+        .with_colors(args.color.enabled(&io::stderr()))
         .env()
         .init()
         .context("Failed to setup logger")?;
 
-    // Get versions
-    let sd_version = systemd::SystemdVersion::local_system()?;
-    let kernel_version = systemd::KernelVersion::local_system()?;
-    let strace_version = strace::StraceVersion::local_system()?;
-    log::info!("Detected versions: Systemd {sd_version}, Linux kernel {kernel_version}, strace {strace_version}");
-    if strace_version < strace::StraceVersion::new(6, 4) {
-        log::warn!("Strace version >=6.4 is strongly recommended, if you experience strace output parsing errors, please consider upgrading");
+    // Get environment
+    let env_file = match &args.action {
+        cl::Action::Run { env_file, .. } | cl::Action::MergeProfileData { env_file, .. } => {
+            env_file.as_deref()
+        }
+        _ => None,
+    };
+    let env = if let Some(env_file) = env_file {
+        log::info!("Using environment from {env_file:?}");
+        environment::Environment::load(env_file).context(error::ErrorKind::Environment)?
+    } else {
+        environment::Environment::local_system().context(error::ErrorKind::Environment)?
+    };
+    if let Some(sd_version) = &env.systemd_version {
+        log::info!(
+            "Versions: Systemd {sd_version}, Linux kernel {}",
+            env.kernel_version
+        );
+    } else {
+        log::info!("Versions: Linux kernel {}, no systemd", env.kernel_version);
     }
 
-    // Parse cl args
-    let args = cl::Args::parse();
+    // Handle CL args, service actions being run for each unit matching a unit name pattern
+    let actions = match args.action {
+        cl::Action::Service(action) => expand_unit_pattern(action)?
+            .into_iter()
+            .map(cl::Action::Service)
+            .collect(),
+        action => vec![action],
+    };
+    for action in actions {
+        run_action(action, &env, color)?;
+    }
+
+    Ok(())
+}
+
+/// Get service actions for each service matching the unit name pattern of an action, if it has one
+fn expand_unit_pattern(mut action: cl::ServiceAction) -> anyhow::Result<Vec<cl::ServiceAction>> {
+    let Some(service) = action.service_mut() else {
+        return Ok(vec![action]);
+    };
+    let names = systemd::Service::expand_name(service)?;
+    if names.len() > 1 {
+        // A bundle file holds the config of a single service
+        anyhow::ensure!(
+            !matches!(
+                action,
+                cl::ServiceAction::Export {
+                    packaging_output: None,
+                    ..
+                } | cl::ServiceAction::Import { .. }
+            ),
+            "Several services match, but a bundle file is for a single service: {}",
+            names.join(", ")
+        );
+        log::info!("Matching services: {}", names.join(", "));
+    }
+    Ok(names
+        .into_iter()
+        .map(|name| {
+            let mut action = action.clone();
+            if let Some(action_service) = action.service_mut() {
+                *action_service = name;
+            }
+            action
+        })
+        .collect())
+}
 
-    // Handle CL args
-    match args.action {
+/// Run action from command line
+#[expect(clippy::too_many_lines)]
+fn run_action(
+    action: cl::Action,
+    env: &environment::Environment,
+    color: bool,
+) -> anyhow::Result<()> {
+    let kernel_version = &env.kernel_version;
+    let sd_version = env.systemd_version.as_ref();
+    let require_systemd = || {
+        sd_version
+            .ok_or_else(|| anyhow::anyhow!("Systemd is required for this command"))
+            .context(error::ErrorKind::Environment)
+    };
+
+    match action {
         cl::Action::Run {
             command,
-            hardening_opts,
+            mut hardening_opts,
             profile_data_path,
-            strace_log_path,
+            identity_opts,
+            tracer,
+            strace_opts,
+            sampling_opts,
+            strace_log_opts,
+            unit,
+            unit_opts,
+            standalone,
+            seccomp_profile,
+            watch,
+            timeline,
+            pid_file,
+            report_opts,
+            ..
         } => {
-            // Build supported systemd options
-            let sd_opts = sd_options(&sd_version, &kernel_version, &hardening_opts);
-
-            // Run strace
-            let cmd = command.iter().map(|a| &**a).collect::<Vec<&str>>();
-            let st = strace::Strace::run(&cmd, strace_log_path)?;
-
-            // Start signal handling thread
-            let mut signals = signal_hook::iterator::Signals::new([
-                signal_hook::consts::signal::SIGINT,
-                signal_hook::consts::signal::SIGQUIT,
-                signal_hook::consts::signal::SIGTERM,
-            ])?;
-            thread::spawn(move || {
-                for sig in signals.forever() {
-                    // The strace, and its watched child processes already get the signal, so the iterator will stop naturally
-                    log::info!("Got signal {sig:?}, ignoring");
-                }
-            });
-
-            // Summarize actions
-            let logs = st.log_lines()?;
-            let actions = summarize::summarize(logs)?;
-            log::debug!("{actions:?}");
+            let standalone = standalone || sd_version.is_none();
+            if standalone && unit {
+                return Err(
+                    anyhow::anyhow!("Running in a transient unit requires systemd")
+                        .context(error::ErrorKind::Environment),
+                );
+            }
+            // Transient units are run by the service manager, with its privileges
+            let unprivileged = !unit && !nix::unistd::geteuid().is_root();
+            if unprivileged && (hardening_opts.capability_source == cl::CapabilitySource::Kprobe) {
+                log::warn!("Tracing capability checks needs root, inferring capabilities from syscalls instead");
+                hardening_opts.capability_source = cl::CapabilitySource::Syscalls;
+            }
+
+            // Build supported systemd options, for standalone mode their effects are used to build other artifacts,
+            // so assume all are supported
+            let latest_sd_version = systemd::SystemdVersion::latest();
+            let run_sd_version = if standalone {
+                &latest_sd_version
+            } else {
+                require_systemd()?
+            };
+            let mut sd_opts = sd_options(run_sd_version, kernel_version, &hardening_opts);
+            if unit_opts.user {
+                sd_opts.retain(|o| !systemd::SYSTEM_SERVICE_ONLY_OPTIONS.contains(&o.name));
+            }
+
+            let profile_data = if unit {
+                systemd::profile_transient_unit(
+                    &command,
+                    &hardening_opts,
+                    &identity_opts,
+                    tracer,
+                    &strace_opts,
+                    &sampling_opts,
+                    &unit_opts,
+                )?
+            } else {
+                profile_command(
+                    &command,
+                    &identity_opts,
+                    tracer,
+                    &strace_opts,
+                    &sampling_opts,
+                    strace_log_opts,
+                    profile_data_path.as_deref(),
+                    &sd_opts,
+                    watch,
+                    hardening_opts.capability_source,
+                    pid_file.as_deref(),
+                    color,
+                )?
+            };
+            log::debug!("{:?}", profile_data.actions);
+            if timeline {
+                print!("{}", timeline::format(&profile_data.timeline));
+            }
+            if unprivileged {
+                let limits = systemd::unprivileged_profiling_limits(&sd_opts);
+                if !limits.is_empty() {
+                    log::warn!("Profiled without root privileges, these options could not be fully evaluated:");
+                    for limit in limits {
+                        log::warn!("  {limit}");
+                    }
+                }
+            }
 
             if let Some(profile_data_path) = profile_data_path {
                 // Dump profile data
                 log::info!("Writing profile data into {profile_data_path:?}...");
                 let file = File::create(profile_data_path)?;
-                bincode::serialize_into(file, &actions)?;
-            } else {
-                // Resolve
+                profile_data.write_to(file)?;
+            } else if standalone {
+                let actions = [
+                    hardening_opts.extra_actions(),
+                    systemd::plugin_actions(&profile_data.actions),
+                    profile_data.actions,
+                ]
+                .concat();
                 let resolved_opts = systemd::resolve(&sd_opts, &actions);
-
-                // Report
-                systemd::report_options(resolved_opts);
+                standalone::report(&command, &resolved_opts, seccomp_profile.as_deref())?;
+            } else {
+                resolve_and_report(
+                    &sd_opts,
+                    &profile_data.actions,
+                    &hardening_opts,
+                    &report_opts,
+                    None,
+                    None,
+                    color,
+                )?;
             }
         }
         cl::Action::MergeProfileData {
             hardening_opts,
             paths,
+            exec_start_pre_paths,
+            root_directory,
+            accumulate_dir,
+            activations,
+            unit,
+            smoke_test,
+            coverage,
+            timeline,
+            report_opts,
+            ..
         } => {
             // Build supported systemd options
-            let sd_opts = sd_options(&sd_version, &kernel_version, &hardening_opts);
+            let sd_opts = sd_options(require_systemd()?, kernel_version, &hardening_opts);
+
+            // Profile data from previous activations is merged too
+            let (paths, exec_start_pre_paths) = if let Some(accumulate_dir) = &accumulate_dir {
+                let accumulated =
+                    profile::accumulate(accumulate_dir, &paths, &exec_start_pre_paths)?;
+                log::info!(
+                    "Profiled activations: {}{}",
+                    profile::activation_count(accumulate_dir)?,
+                    activations.map(|n| format!("/{n}")).unwrap_or_default()
+                );
+                accumulated
+            } else {
+                (paths, exec_start_pre_paths)
+            };
 
             // Load and merge profile data
-            let mut actions: Vec<summarize::ProgramAction> = Vec::new();
-            for path in &paths {
-                let file = File::open(path)?;
-                let mut profile_actions: Vec<summarize::ProgramAction> =
-                    bincode::deserialize_from(file)?;
-                actions.append(&mut profile_actions);
-            }
+            let actions = profile::load_and_merge(&paths)?;
             log::debug!("{actions:?}");
 
-            // Resolve
-            let resolved_opts = systemd::resolve(&sd_opts, &actions);
+            resolve_and_report(
+                &sd_opts,
+                &actions,
+                &hardening_opts,
+                &report_opts,
+                root_directory.as_deref(),
+                unit.as_deref().map(systemd::Service::new).as_ref(),
+                color,
+            )?;
+
+            if smoke_test {
+                systemd::report_smoke_test(&smoke_test::script(&actions));
+            }
+
+            if timeline {
+                systemd::report_timeline(&timeline::format(&profile::load_and_merge_timelines(
+                    &paths,
+                )?));
+            }
 
-            // Report
-            systemd::report_options(resolved_opts);
+            // Setup commands profiled separately get their own options
+            let exec_start_pre_actions = profile::load_and_merge(&exec_start_pre_paths)?;
+            if !exec_start_pre_paths.is_empty() {
+                log::debug!("{exec_start_pre_actions:?}");
+                systemd::report_exec_start_pre_options(
+                    resolve_options(&sd_opts, &exec_start_pre_actions, root_directory.as_deref()),
+                    &systemd::OptionComments::new(),
+                    color,
+                );
+            }
 
-            // Remove profile data files
-            for path in paths {
-                fs::remove_file(path)?;
+            if coverage {
+                systemd::report_coverage(&coverage::Coverage::new(
+                    &[actions, exec_start_pre_actions].concat(),
+                ))?;
             }
+
+            // Remove profile data files, accumulated ones are removed when profiling is finished
+            if accumulate_dir.is_none() {
+                for path in paths.into_iter().chain(exec_start_pre_paths) {
+                    fs::remove_file(path)?;
+                }
+            }
+        }
+        cl::Action::Collect {
+            hosts,
+            hardening_opts,
+            remote_paths,
+            report_opts,
+        } => {
+            // Build supported systemd options
+            let sd_opts = sd_options(require_systemd()?, kernel_version, &hardening_opts);
+
+            // Fetch profile data
+            let hosts = collect::read_hosts(&hosts)?;
+            let tmp_dir = tempfile::tempdir()?;
+            let paths = collect::fetch(&hosts, &remote_paths, tmp_dir.path())?;
+            anyhow::ensure!(!paths.is_empty(), "No profile data found on any host");
+
+            // Load and merge profile data
+            let actions = profile::load_and_merge(&paths)?;
+            log::debug!("{actions:?}");
+
+            resolve_and_report(
+                &sd_opts,
+                &actions,
+                &hardening_opts,
+                &report_opts,
+                None,
+                None,
+                color,
+            )?;
         }
         cl::Action::Service(cl::ServiceAction::StartProfile {
             service,
             hardening_opts,
+            split_exec_start_pre,
+            strace_opts,
+            sampling_opts,
+            strace_log_opts,
+            activations,
+            annotate,
+            related_units,
             no_restart,
         }) => {
             let service = systemd::Service::new(&service);
-            service.add_profile_fragment(&hardening_opts)?;
+            let related_units: Vec<_> = related_units
+                .iter()
+                .map(|u| systemd::Service::new(u))
+                .collect();
+            for unit in iter::once(&service).chain(&related_units) {
+                unit.add_profile_fragment(
+                    &hardening_opts,
+                    split_exec_start_pre,
+                    &strace_opts,
+                    &sampling_opts,
+                    &strace_log_opts,
+                    activations,
+                    annotate,
+                )?;
+                if let Err(err) = unit.record_baseline_startup_time() {
+                    log::warn!("Failed to record service startup time: {err:#}");
+                }
+            }
+            if !related_units.is_empty() {
+                service.set_related_units(&related_units)?;
+            }
             if no_restart {
                 log::warn!("Profiling config will only be applied when systemd config is reloaded, and service restarted");
             } else {
                 service.reload_unit_config()?;
-                service.action("restart", false)?;
+                // Restart all units last, so that they are profiled in the same window
+                for unit in iter::once(&service).chain(&related_units) {
+                    for instance in unit.instances()? {
+                        instance.action(systemd::UnitAction::Restart, false)?;
+                    }
+                }
             }
         }
         cl::Action::Service(cl::ServiceAction::FinishProfile {
             service,
             apply,
+            force,
+            complain,
+            cross_check,
+            mut smoke_test,
+            timeline,
+            min_risk,
+            max_risk,
+            hook_opts,
             no_restart,
         }) => {
+            if complain {
+                anyhow::ensure!(
+                    *require_systemd()? >= systemd::SystemdVersion::new(247, 0),
+                    "Complain mode requires systemd >= 247"
+                );
+            }
             let service = systemd::Service::new(&service);
-            service.action("stop", true)?;
-            service.remove_profile_fragment()?;
-            let resolved_opts = service.profiling_result()?;
-            log::info!(
-                "Resolved systemd options: {}",
-                resolved_opts
-                    .iter()
-                    .map(|o| format!("{o}"))
-                    .collect::<Vec<_>>()
-                    .join(", ")
+            let related_units = service.related_units()?;
+            let units: Vec<_> = iter::once(&service).chain(&related_units).collect();
+            let mut units_merge_args = Vec::with_capacity(units.len());
+            for unit in &units {
+                let merge_args = unit.profiling_merge_args()?;
+                if let (Some(accumulate_dir), Some(activations)) =
+                    (&merge_args.accumulate_dir, merge_args.activations)
+                {
+                    let count = profile::activation_count(accumulate_dir)?;
+                    if count < activations {
+                        anyhow::ensure!(
+                            force,
+                            "Only {count}/{activations} service activations were profiled, wait for more or use --force"
+                        );
+                        log::warn!("Only {count}/{activations} service activations were profiled");
+                    }
+                }
+                units_merge_args.push(merge_args);
+            }
+            // Data exchanged between units can only be seen while they all still run
+            if !related_units.is_empty() {
+                let mut unit_actions = Vec::new();
+                for (unit, merge_args) in units.iter().zip(&units_merge_args) {
+                    for instance in unit.instances()? {
+                        let actions = profiling_snapshot(&instance, &merge_args.paths)?;
+                        unit_actions.push((instance.unit_name(), actions));
+                    }
+                }
+                for warning in systemd::related_unit_warnings(&unit_actions) {
+                    log::warn!("{warning}");
+                }
+            }
+            for (unit, merge_args) in units.into_iter().zip(units_merge_args) {
+                if !related_units.is_empty() {
+                    log::info!("Finishing profiling of {}", unit.unit_name());
+                }
+                let split_exec_start_pre = !merge_args.exec_start_pre_paths.is_empty();
+                // Options are resolved by the merge command when the service stops
+                if min_risk.is_some() || max_risk.is_some() {
+                    unit.set_profiling_risk_bounds(min_risk, max_risk)?;
+                    unit.reload_unit_config()?;
+                }
+                for instance in unit.instances()? {
+                    instance.action(systemd::UnitAction::Stop, true)?;
+                }
+                // Profiling results may be from older versions without coverage output
+                match unit.profiling_coverage_warnings() {
+                    Ok(warnings) => {
+                        for warning in warnings {
+                            log::warn!("{warning}");
+                        }
+                    }
+                    Err(err) => log::debug!("Unable to check profiling coverage: {err:#}"),
+                }
+                unit.remove_profile_fragment()?;
+                if let Some(accumulate_dir) = &merge_args.accumulate_dir {
+                    if accumulate_dir.is_dir() {
+                        fs::remove_dir_all(accumulate_dir)?;
+                    }
+                }
+                let (resolved_opts, comments) = unit.profiling_result()?;
+                if timeline {
+                    // Profiling results may be from older versions without timeline output
+                    match unit.profiling_timeline() {
+                        Ok(unit_timeline) => print!("{unit_timeline}"),
+                        Err(err) => log::warn!("Unable to get profiling timeline: {err:#}"),
+                    }
+                }
+                // The smoke test is only written for the main service
+                if let Some(smoke_test_path) = smoke_test.take() {
+                    fs::write(&smoke_test_path, unit.smoke_test_script()?).with_context(|| {
+                        format!(
+                            "Failed to write smoke test to {}",
+                            smoke_test_path.display()
+                        )
+                    })?;
+                    fs::set_permissions(&smoke_test_path, fs::Permissions::from_mode(0o755))?;
+                    log::info!("Smoke test written to {}", smoke_test_path.display());
+                }
+                log::info!(
+                    "Resolved systemd options: {}",
+                    resolved_opts
+                        .iter()
+                        .map(|o| format!("{o}"))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+                let exec_start_pre_opts = if split_exec_start_pre {
+                    let opts = unit.exec_start_pre_profiling_result()?;
+                    log::info!(
+                        "Resolved systemd options for setup commands: {}",
+                        opts.iter()
+                            .map(|o| format!("{o}"))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    );
+                    Some(opts)
+                } else {
+                    None
+                };
+                let mut applied_exposure = None;
+                if apply && !resolved_opts.is_empty() {
+                    let exposure = record_hardening(unit, &resolved_opts);
+                    applied_exposure = Some(exposure.0);
+                    unit.add_hardening_fragment(
+                        resolved_opts,
+                        &comments,
+                        exec_start_pre_opts,
+                        complain,
+                    )?;
+                    run_post_apply_hook(&hook_opts, unit, "harden", exposure);
+                    if complain {
+                        log::info!("Syscall filter applied in complain mode, check syscalls it would deny with 'shh service complain-report', and enforce it with 'shh service enforce'");
+                    }
+                }
+                unit.reload_unit_config()?;
+                if let Some(exposure) = applied_exposure.filter(|_| cross_check) {
+                    cross_check_exposure(unit, exposure);
+                }
+                if !no_restart {
+                    for instance in unit.instances()? {
+                        instance.action(systemd::UnitAction::Start, false)?;
+                    }
+                }
+            }
+        }
+        cl::Action::Service(cl::ServiceAction::ComplainReport { service }) => {
+            let service = systemd::Service::new(&service);
+            let since = service
+                .complain_mode_start_time()?
+                .ok_or_else(|| anyhow::anyhow!("Hardening config is not in complain mode"))?;
+            println!(
+                "Complain mode active for: {}",
+                stats::format_duration(since.elapsed().unwrap_or_default())
+            );
+            let logged = service.complain_mode_logged_syscalls(since)?;
+            if logged.is_empty() {
+                println!("No syscall would have been denied");
+            }
+            for (syscall, count) in logged.iter().dedup_with_count().map(|(c, s)| (s, c)) {
+                println!("{syscall} ({count} times)");
+            }
+        }
+        cl::Action::Service(cl::ServiceAction::Enforce {
+            service,
+            force,
+            hook_opts,
+            no_restart,
+        }) => {
+            let service = systemd::Service::new(&service);
+            let since = service
+                .complain_mode_start_time()?
+                .ok_or_else(|| anyhow::anyhow!("Hardening config is not in complain mode"))?;
+            let logged = service.complain_mode_logged_syscalls(since)?;
+            if !logged.is_empty() {
+                anyhow::ensure!(
+                    force,
+                    "{} syscall(s) would have been denied since complain mode started, check them with 'shh service complain-report', or use --force",
+                    logged.len()
+                );
+                log::warn!(
+                    "{} syscall(s) would have been denied since complain mode started",
+                    logged.len()
+                );
+            }
+            service.enforce_hardening_fragment()?;
+            let (fragment, _) = service.hardening_fragment()?;
+            let exposure = systemd::ExposureModel::default().exposure(&fragment_options(&fragment));
+            // Exposure estimation already counts the syscall filter in complain mode
+            run_post_apply_hook(
+                &hook_opts,
+                &service,
+                "enforce syscall filter of",
+                (exposure, 0.0),
             );
-            if apply && !resolved_opts.is_empty() {
-                service.add_hardening_fragment(resolved_opts)?;
+            service.reload_unit_config()?;
+            if !no_restart {
+                service.action(systemd::UnitAction::TryRestart, false)?;
+            }
+        }
+        cl::Action::Service(cl::ServiceAction::SnapshotProfile {
+            service,
+            report_opts,
+        }) => {
+            let service = systemd::Service::new(&service);
+            let merge_args = service.profiling_merge_args()?;
+            let sd_opts = sd_options(
+                require_systemd()?,
+                kernel_version,
+                &merge_args.hardening_opts,
+            );
+
+            // Include profile data from previous activations
+            let (accumulated_paths, accumulated_exec_start_pre_paths) = merge_args
+                .accumulate_dir
+                .as_deref()
+                .map(profile::accumulated_paths)
+                .transpose()?
+                .unwrap_or_default();
+
+            let mut actions = profiling_snapshot(&service, &merge_args.paths)?;
+            actions.extend(profile::load_and_merge(&accumulated_paths)?);
+            anyhow::ensure!(!actions.is_empty(), "No profile data available yet");
+            log::debug!("{actions:?}");
+
+            let root_directory = merge_args.root_directory.as_deref();
+            resolve_and_report(
+                &sd_opts,
+                &actions,
+                &merge_args.hardening_opts,
+                &report_opts,
+                root_directory,
+                Some(&service),
+                color,
+            )?;
+
+            if !merge_args.exec_start_pre_paths.is_empty() {
+                // Setup commands have exited before the service started, so their profile data is already written
+                let exec_start_pre_paths: Vec<_> = merge_args
+                    .exec_start_pre_paths
+                    .iter()
+                    .filter(|p| p.is_file())
+                    .chain(&accumulated_exec_start_pre_paths)
+                    .cloned()
+                    .collect();
+                let exec_start_pre_actions = profile::load_and_merge(&exec_start_pre_paths)?;
+                systemd::report_exec_start_pre_options(
+                    resolve_options(&sd_opts, &exec_start_pre_actions, root_directory),
+                    &systemd::OptionComments::new(),
+                    color,
+                );
             }
+        }
+        cl::Action::Service(cl::ServiceAction::PauseProfile {
+            service,
+            no_restart,
+        }) => {
+            let service = systemd::Service::new(&service);
+            service.pause_profile()?;
             service.reload_unit_config()?;
             if !no_restart {
-                service.action("start", false)?;
+                service.action(systemd::UnitAction::Start, false)?;
+            }
+        }
+        cl::Action::Service(cl::ServiceAction::ResumeProfile {
+            service,
+            no_restart,
+        }) => {
+            let service = systemd::Service::new(&service);
+            service.resume_profile()?;
+            service.reload_unit_config()?;
+            if !no_restart {
+                service.action(systemd::UnitAction::TryRestart, false)?;
+            }
+        }
+        cl::Action::Service(cl::ServiceAction::ProfileStatus { service }) => {
+            let service = systemd::Service::new(&service);
+            if service.is_profile_paused() {
+                println!(
+                    "Profiling active: no (paused, resume it with 'shh service resume-profile')"
+                );
+                return Ok(());
+            }
+            if !service.is_profiled() {
+                println!("Profiling active: no");
+                return Ok(());
+            }
+            let merge_args = service.profiling_merge_args()?;
+            if let (Some(accumulate_dir), Some(activations)) =
+                (&merge_args.accumulate_dir, merge_args.activations)
+            {
+                println!(
+                    "Profiled activations: {}/{activations}",
+                    profile::activation_count(accumulate_dir)?
+                );
             }
+            if !service.is_active()? {
+                println!("Profiling active: no (profiling is set up, but service is not running)");
+                return Ok(());
+            }
+            println!("Profiling active: yes");
+
+            let paths: Vec<_> = merge_args
+                .exec_start_pre_paths
+                .into_iter()
+                .chain(merge_args.paths)
+                .collect();
+            let profiles = profiling_snapshot_data(&service, &paths)?;
+            // Profiling wrappers of setup commands and of the main process run one after the other
+            if let (Some(start_time), Some(end_time)) = (
+                profiles.iter().map(|p| p.start_time).min(),
+                profiles.iter().map(|p| p.end_time).max(),
+            ) {
+                println!(
+                    "Profiling duration: {}",
+                    stats::format_duration(end_time.duration_since(start_time).unwrap_or_default())
+                );
+            }
+            let syscall_count: u64 = profiles.iter().map(|p| p.syscall_count).sum();
+            let actions: Vec<_> = profiles.into_iter().flat_map(|p| p.actions).collect();
+            let distinct_syscalls: BTreeSet<_> = actions
+                .iter()
+                .filter_map(|a| match a {
+                    summarize::ProgramAction::Syscalls(syscalls) => Some(syscalls),
+                    _ => None,
+                })
+                .flatten()
+                .collect();
+            println!(
+                "Syscalls: {syscall_count} total, {} distinct",
+                distinct_syscalls.len()
+            );
+            let overheads: Vec<_> = paths
+                .iter()
+                .map(|p| overhead::Overhead::path(p))
+                .filter(|p| p.is_file())
+                .map(|p| overhead::Overhead::read(&p))
+                .collect::<anyhow::Result<_>>()?;
+            if !overheads.is_empty() {
+                println!(
+                    "Syscall rate: {:.0}/s, estimated tracing cost: {:.1}% of a CPU",
+                    overhead::total_syscall_rate(&overheads),
+                    overhead::total_tracing_cost(&overheads) * 100.0
+                );
+            }
+            if let Some((baseline, profiled)) = service.startup_times()? {
+                println!(
+                    "Startup time: {}",
+                    overhead::startup_comparison(baseline, profiled)
+                );
+            }
+            println!(
+                "Actions: {}",
+                actions
+                    .iter()
+                    .filter(|a| !matches!(a, summarize::ProgramAction::Syscalls(_)))
+                    .count()
+            );
+
+            if !actions.is_empty() {
+                let sd_opts = sd_options(
+                    require_systemd()?,
+                    kernel_version,
+                    &merge_args.hardening_opts,
+                );
+                let resolved_opts = systemd::resolve(&sd_opts, &actions);
+                println!("Options resolvable so far:");
+                for opt in resolved_opts {
+                    println!("{opt}");
+                }
+            }
+        }
+        cl::Action::Service(cl::ServiceAction::Reset { service, all }) => {
+            if all {
+                reset_services(&systemd::Service::list_managed()?)?;
+                systemd::Service::remove_profile_data_dirs()?;
+            } else if let Some(service) = service {
+                reset_services(&[systemd::Service::new(&service)])?;
+            }
+        }
+        cl::Action::Service(cl::ServiceAction::Export {
+            service,
+            path,
+            packaging_output: Some(layout),
+        }) => {
+            let service = systemd::Service::new(&service);
+            anyhow::ensure!(
+                service.complain_mode_start_time()?.is_none(),
+                "Hardening config of {} is in complain mode, enforce it before packaging it",
+                service.unit_name()
+            );
+            let (fragment, exec_start_pre_unit) = service.hardening_fragment()?;
+            let exec_start_pre_unit_path = service.exec_start_pre_unit_path();
+            let exec_start_pre_unit_name = exec_start_pre_unit_path
+                .file_name()
+                .map(|n| n.to_string_lossy())
+                .unwrap_or_default();
+            let files = packaging::packaging_files(
+                layout,
+                &service.drop_in_dir_name(),
+                &fragment,
+                exec_start_pre_unit
+                    .as_deref()
+                    .map(|u| (exec_start_pre_unit_name.as_ref(), u)),
+            );
+            packaging::write(&path, &files)?;
+            log::info!("{}", packaging::install_instructions(layout, &files));
+        }
+        cl::Action::Service(cl::ServiceAction::Export {
+            service,
+            path,
+            packaging_output: None,
+        }) => {
+            let service = systemd::Service::new(&service);
+            let (fragment, exec_start_pre_unit) = service.hardening_fragment()?;
+            let bundle = bundle::HardeningBundle::new(
+                service.unit_name(),
+                env.clone(),
+                fragment,
+                exec_start_pre_unit,
+            )?;
+            bundle.write(&path)?;
+            log::info!("Hardening config written to {}", path.display());
         }
-        cl::Action::Service(cl::ServiceAction::Reset { service }) => {
+        cl::Action::Service(cl::ServiceAction::Import {
+            service,
+            path,
+            hook_opts,
+            no_restart,
+        }) => {
             let service = systemd::Service::new(&service);
-            let _ = service.remove_profile_fragment();
-            let _ = service.remove_hardening_fragment();
+            let bundle = bundle::HardeningBundle::read(&path)?;
+            if bundle.provenance.unit != service.unit_name() {
+                // The hardening fragment starts the helper unit by name
+                anyhow::ensure!(
+                    bundle.exec_start_pre_unit.is_none(),
+                    "Hardening config was applied to {}, and its setup commands helper unit can not be used for {}",
+                    bundle.provenance.unit,
+                    service.unit_name()
+                );
+                log::warn!(
+                    "Hardening config was applied to {}, not {}",
+                    bundle.provenance.unit,
+                    service.unit_name()
+                );
+            }
+            log::info!(
+                "Importing hardening config exported from {} by shh {}",
+                bundle.provenance.hostname,
+                bundle.provenance.shh_version
+            );
+            for warning in bundle.compatibility_warnings(env) {
+                log::warn!("{warning}");
+            }
+            service.import_hardening_fragment(
+                &bundle.fragment,
+                bundle.exec_start_pre_unit.as_deref(),
+            )?;
+            let exposure = record_hardening(&service, &fragment_options(&bundle.fragment));
+            run_post_apply_hook(&hook_opts, &service, "import hardening config of", exposure);
             service.reload_unit_config()?;
-            service.action("try-restart", false)?;
+            if !no_restart {
+                service.action(systemd::UnitAction::TryRestart, false)?;
+            }
+        }
+        cl::Action::Service(cl::ServiceAction::History { service }) => {
+            let service = systemd::Service::new(&service);
+            let entries = history::load(&service.unit_name())?;
+            if entries.is_empty() {
+                println!("No hardening config was applied to {}", service.unit_name());
+            } else {
+                print!("{}", history::format(&entries));
+            }
+        }
+        cl::Action::Purge => {
+            reset_services(&systemd::Service::list_managed()?)?;
+            systemd::Service::remove_profile_data_dirs()?;
+            history::purge()?;
+        }
+        cl::Action::Env(cl::EnvAction::Export { output }) => {
+            let content = env.to_toml()?;
+            if let Some(output) = output {
+                fs::write(&output, content)?;
+                log::info!("Environment written to {output:?}");
+            } else {
+                print!("{content}");
+            }
+        }
+        cl::Action::ProfileStats {
+            path,
+            top,
+            syscall_groups,
+        } => {
+            let profile_data = profile::ProfileData::read(&path)?;
+            if syscall_groups {
+                stats::write_syscall_groups(&profile_data, &mut io::stdout())?;
+            } else {
+                stats::write(&profile_data, top, &mut io::stdout())?;
+            }
+        }
+        cl::Action::CheckLog { path } => {
+            let mut issue_count = 0;
+            for segment in strace::log_segments(&path) {
+                let mut segment =
+                    segment.with_context(|| format!("Failed to open {}", path.display()))?;
+                let mut log = String::new();
+                segment
+                    .reader
+                    .read_to_string(&mut log)
+                    .with_context(|| format!("Failed to read {}", segment.path.display()))?;
+                issue_count +=
+                    strace::check_log(&segment.path.to_string_lossy(), &log, &mut io::stdout())?;
+            }
+            if issue_count > 0 {
+                return Err(anyhow::anyhow!("Strace log has unparsable lines")
+                    .context(error::ErrorKind::Parsing));
+            }
+        }
+        cl::Action::ListAppPresets => {
+            for name in app_preset::AppPreset::names() {
+                match app_preset::AppPreset::load(&name) {
+                    Ok(preset) => println!("{name}: {}", preset.description),
+                    Err(err) => log::warn!("{err:#}"),
+                }
+            }
         }
         cl::Action::ListSystemdOptions => {
             println!("# Supported systemd options");
             let mut sd_opts = sd_options(
-                &sd_version,
-                &kernel_version,
+                require_systemd()?,
+                kernel_version,
                 &cl::HardeningOptions::strict(),
             );
             sd_opts.sort_unstable_by_key(|o| o.name);
@@ -201,7 +1544,32 @@ fn main() -> anyhow::Result<()> {
                 }
             }
         }
+        cl::Action::GenShellCompletion { .. } | cl::Action::CompleteUnits { .. } => unreachable!(),
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{os::unix::process::ExitStatusExt as _, process::Command};
+
+    use super::*;
+
+    #[test]
+    fn test_forward_signal() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let pid_file = tmp_dir.path().join("daemon.pid");
+        assert!(forward_signal(&pid_file, signal_hook::consts::signal::SIGHUP).is_err());
+        fs::write(&pid_file, "not a pid\n").unwrap();
+        assert!(forward_signal(&pid_file, signal_hook::consts::signal::SIGHUP).is_err());
+
+        let mut daemon = Command::new("sleep").arg("60").spawn().unwrap();
+        fs::write(&pid_file, format!("{}\n", daemon.id())).unwrap();
+        forward_signal(&pid_file, signal_hook::consts::signal::SIGTERM).unwrap();
+        assert_eq!(
+            daemon.wait().unwrap().signal(),
+            Some(signal_hook::consts::signal::SIGTERM)
+        );
+    }
+}
@@ -0,0 +1,135 @@
+//! Profiling overhead estimation, so that the profiling window of latency sensitive services can be chosen knowingly
+
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::Context as _;
+
+/// Approximate cost of a traced syscall: two tracer stops (entry and exit), and argument decoding
+// TODO APPROXIMATION this depends on the CPU, the kernel, the tracer, and the decoded arguments
+const TRACED_SYSCALL_COST: Duration = Duration::from_micros(20);
+
+/// Syscall activity measured by a profiling wrapper
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub(crate) struct Overhead {
+    /// Number of traced syscalls
+    pub syscall_count: u64,
+    /// Wall clock time since tracing started
+    pub duration: Duration,
+}
+
+impl Overhead {
+    /// Get path of the overhead file written next to profile data
+    pub(crate) fn path(profile_data_path: &Path) -> PathBuf {
+        let mut path = profile_data_path.as_os_str().to_owned();
+        path.push(".overhead");
+        path.into()
+    }
+
+    pub(crate) fn read(path: &Path) -> anyhow::Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse profiling overhead from {}", path.display()))
+    }
+
+    pub(crate) fn write(&self, path: &Path) -> anyhow::Result<()> {
+        fs::write(path, serde_json::to_string(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Traced syscalls per second
+    pub(crate) fn syscall_rate(&self) -> f64 {
+        let secs = self.duration.as_secs_f64();
+        if secs > 0.0 {
+            #[expect(clippy::cast_precision_loss)]
+            let count = self.syscall_count as f64;
+            count / secs
+        } else {
+            0.0
+        }
+    }
+
+    /// Estimated fraction of a CPU spent tracing
+    pub(crate) fn tracing_cost(&self) -> f64 {
+        self.syscall_rate() * TRACED_SYSCALL_COST.as_secs_f64()
+    }
+}
+
+impl fmt::Display for Overhead {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} syscalls traced in {:.1}s ({:.0} syscalls/s), estimated tracing cost {:.1}% of a CPU",
+            self.syscall_count,
+            self.duration.as_secs_f64(),
+            self.syscall_rate(),
+            self.tracing_cost() * 100.0
+        )
+    }
+}
+
+/// Total syscall rate of profiling wrappers
+// TODO APPROXIMATION wrappers of setup commands did not run concurrently with the main one, but their rates are summed
+pub(crate) fn total_syscall_rate(overheads: &[Overhead]) -> f64 {
+    overheads.iter().map(Overhead::syscall_rate).sum()
+}
+
+/// Estimated fraction of a CPU spent tracing by profiling wrappers
+pub(crate) fn total_tracing_cost(overheads: &[Overhead]) -> f64 {
+    overheads.iter().map(Overhead::tracing_cost).sum()
+}
+
+/// Describe the startup time of the profiled service activation, compared to the one before profiling
+pub(crate) fn startup_comparison(baseline: Duration, profiled: Duration) -> String {
+    let slowdown = if baseline.is_zero() {
+        String::new()
+    } else {
+        format!(
+            ", {:+.0}%",
+            (profiled.as_secs_f64() / baseline.as_secs_f64() - 1.0) * 100.0
+        )
+    };
+    format!(
+        "{:.2}s with profiling, {:.2}s without{slowdown}",
+        profiled.as_secs_f64(),
+        baseline.as_secs_f64()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overhead() {
+        let overhead = Overhead {
+            syscall_count: 25_000,
+            duration: Duration::from_secs(10),
+        };
+        assert!((overhead.syscall_rate() - 2500.0).abs() < f64::EPSILON);
+        assert_eq!(
+            overhead.to_string(),
+            "25000 syscalls traced in 10.0s (2500 syscalls/s), estimated tracing cost 5.0% of a CPU"
+        );
+        let other = Overhead {
+            syscall_count: 0,
+            duration: Duration::ZERO,
+        };
+        assert!((total_syscall_rate(&[overhead.clone(), other]) - 2500.0).abs() < f64::EPSILON);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = Overhead::path(&dir.path().join("001"));
+        assert_eq!(path, dir.path().join("001.overhead"));
+        overhead.write(&path).unwrap();
+        assert_eq!(Overhead::read(&path).unwrap(), overhead);
+
+        assert_eq!(
+            startup_comparison(Duration::from_millis(400), Duration::from_secs(1)),
+            "1.00s with profiling, 0.40s without, +150%"
+        );
+    }
+}
@@ -0,0 +1,174 @@
+//! Applied hardening config laid out in distribution package sources, for maintainers shipping it in their packages
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context as _;
+
+use crate::cl::PackagingLayout;
+
+/// Name of the hardening drop-in, once installed in the unit drop-in directory
+const DROP_IN_NAME: &str = "hardening.conf";
+
+/// A file to add to the package sources
+#[derive(Debug, Eq, PartialEq)]
+pub(crate) struct PackagingFile {
+    /// Path relative to the package source tree
+    pub source: PathBuf,
+    /// Installation path, relative to the system unit directory
+    pub install: PathBuf,
+    pub content: String,
+}
+
+/// Make content stable, so that it only changes in version control when options do
+fn stable_content(content: &str) -> String {
+    let lines: Vec<_> = content.lines().map(str::trim_end).collect();
+    format!("{}\n", lines.join("\n").trim_end_matches('\n'))
+}
+
+/// Get files to add to the package sources, for the hardening drop-in and the setup commands helper unit if any
+pub(crate) fn packaging_files(
+    layout: PackagingLayout,
+    drop_in_dir: &str,
+    fragment: &str,
+    exec_start_pre_unit: Option<(&str, &str)>,
+) -> Vec<PackagingFile> {
+    let install = Path::new(drop_in_dir).join(DROP_IN_NAME);
+    let source = match layout {
+        PackagingLayout::Debian => Path::new("debian").join(&install),
+        // Spec file sources are referred to by file name
+        PackagingLayout::Rpm => Path::new("SOURCES").join(format!(
+            "{}-{DROP_IN_NAME}",
+            drop_in_dir.trim_end_matches(".d")
+        )),
+    };
+    let mut files = vec![PackagingFile {
+        source,
+        install,
+        content: stable_content(fragment),
+    }];
+    if let Some((unit_name, unit)) = exec_start_pre_unit {
+        let source_dir = match layout {
+            PackagingLayout::Debian => "debian",
+            PackagingLayout::Rpm => "SOURCES",
+        };
+        files.push(PackagingFile {
+            source: Path::new(source_dir).join(unit_name),
+            install: PathBuf::from(unit_name),
+            content: stable_content(unit),
+        });
+    }
+    files
+}
+
+/// Write files in the package source tree, leaving unchanged ones untouched
+pub(crate) fn write(root: &Path, files: &[PackagingFile]) -> anyhow::Result<()> {
+    for file in files {
+        let path = root.join(&file.source);
+        if fs::read_to_string(&path).is_ok_and(|c| c == file.content) {
+            log::info!("{} is unchanged", path.display());
+            continue;
+        }
+        #[expect(clippy::unwrap_used)]
+        fs::create_dir_all(path.parent().unwrap())?;
+        fs::write(&path, &file.content)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        log::info!("{} written", path.display());
+    }
+    Ok(())
+}
+
+/// Get instructions to install the files in the package
+pub(crate) fn install_instructions(layout: PackagingLayout, files: &[PackagingFile]) -> String {
+    match layout {
+        PackagingLayout::Debian => format!(
+            "Add to debian/PACKAGE.install:\n{}",
+            files
+                .iter()
+                .map(|f| {
+                    #[expect(clippy::unwrap_used)]
+                    let install_dir = f.install.parent().unwrap().join("");
+                    format!(
+                        "{} usr/lib/systemd/system/{}",
+                        f.source.display(),
+                        install_dir.display()
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        ),
+        PackagingLayout::Rpm => format!(
+            "Add the files as sources of the spec file, and install them in %install with:\n{}",
+            files
+                .iter()
+                .map(|f| {
+                    #[expect(clippy::unwrap_used)]
+                    let source_name = f.source.file_name().unwrap().to_string_lossy();
+                    format!(
+                        "install -Dpm 0644 %{{_sourcedir}}/{source_name} %{{buildroot}}%{{_unitdir}}/{}",
+                        f.install.display()
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_packaging_files() {
+        let fragment =
+            "# This file has been autogenerated by shh\n[Service]\nProtectSystem=strict  \n\n\n";
+        let debian_files = packaging_files(
+            PackagingLayout::Debian,
+            "foo@.service.d",
+            fragment,
+            Some(("foo-shh-pre@.service", "[Service]\nType=oneshot\n")),
+        );
+        assert_eq!(
+            debian_files,
+            vec![
+                PackagingFile {
+                    source: "debian/foo@.service.d/hardening.conf".into(),
+                    install: "foo@.service.d/hardening.conf".into(),
+                    content: "# This file has been autogenerated by shh\n[Service]\nProtectSystem=strict\n"
+                        .to_owned(),
+                },
+                PackagingFile {
+                    source: "debian/foo-shh-pre@.service".into(),
+                    install: "foo-shh-pre@.service".into(),
+                    content: "[Service]\nType=oneshot\n".to_owned(),
+                },
+            ]
+        );
+        assert_eq!(
+            install_instructions(PackagingLayout::Debian, &debian_files),
+            "Add to debian/PACKAGE.install:\ndebian/foo@.service.d/hardening.conf usr/lib/systemd/system/foo@.service.d/\ndebian/foo-shh-pre@.service usr/lib/systemd/system/"
+        );
+
+        let rpm_files = packaging_files(PackagingLayout::Rpm, "foo.service.d", fragment, None);
+        assert_eq!(
+            rpm_files[0].source,
+            PathBuf::from("SOURCES/foo.service-hardening.conf")
+        );
+        assert_eq!(
+            install_instructions(PackagingLayout::Rpm, &rpm_files),
+            "Add the files as sources of the spec file, and install them in %install with:\ninstall -Dpm 0644 %{_sourcedir}/foo.service-hardening.conf %{buildroot}%{_unitdir}/foo.service.d/hardening.conf"
+        );
+
+        // Writing again leaves files untouched
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), &rpm_files).unwrap();
+        let path = dir.path().join(&rpm_files[0].source);
+        let modified = fs::metadata(&path).unwrap().modified().unwrap();
+        write(dir.path(), &rpm_files).unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().modified().unwrap(), modified);
+        assert_eq!(fs::read_to_string(&path).unwrap(), rpm_files[0].content);
+    }
+}
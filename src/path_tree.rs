@@ -0,0 +1,188 @@
+//! Prefix tree aggregating path actions by directory
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ffi::{OsStr, OsString},
+    path::{Component, Path, PathBuf},
+};
+
+use crate::summarize::ProgramAction;
+
+/// How a path was accessed
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, strum::Display)]
+#[strum(serialize_all = "snake_case")]
+pub(crate) enum PathAccess {
+    Read,
+    Write,
+    Create,
+    Exec,
+}
+
+impl PathAccess {
+    /// Get path and access kind of an action, if it acts on a path
+    fn of_action(action: &ProgramAction) -> Option<(&Path, Self)> {
+        match action {
+            ProgramAction::Read(path) => Some((path, Self::Read)),
+            ProgramAction::Write(path) => Some((path, Self::Write)),
+            ProgramAction::Create(path) => Some((path, Self::Create)),
+            ProgramAction::Exec(path) | ProgramAction::SetuidExec(path) => Some((path, Self::Exec)),
+            _ => None,
+        }
+    }
+}
+
+/// Tree node for a path component
+#[derive(Debug, Default)]
+pub(crate) struct PathNode {
+    children: BTreeMap<OsString, PathNode>,
+    /// Access kinds of this exact path
+    accesses: BTreeSet<PathAccess>,
+    /// Number of accesses of this path and paths below it, by kind
+    pub counts: BTreeMap<PathAccess, usize>,
+    /// Index of the first action that created this exact path
+    first_create: Option<usize>,
+}
+
+impl PathNode {
+    /// Total number of accesses of this path and paths below it
+    pub(crate) fn total(&self) -> usize {
+        self.counts.values().sum()
+    }
+}
+
+/// Paths accessed by a program, aggregated in a prefix tree, so that lookups do not need to scan all actions,
+/// and decisions can be made at the directory level
+#[derive(Debug, Default)]
+pub(crate) struct PathTree {
+    root: PathNode,
+}
+
+impl PathTree {
+    /// Build tree from path actions, other actions are ignored
+    pub(crate) fn new(actions: &[ProgramAction]) -> Self {
+        let mut tree = Self::default();
+        for (idx, action) in actions.iter().enumerate() {
+            if let Some((path, access)) = PathAccess::of_action(action) {
+                tree.insert(path, access, idx);
+            }
+        }
+        tree
+    }
+
+    fn insert(&mut self, path: &Path, access: PathAccess, idx: usize) {
+        let mut node = &mut self.root;
+        *node.counts.entry(access).or_default() += 1;
+        for component in Self::components(path) {
+            node = node.children.entry(component.to_owned()).or_default();
+            *node.counts.entry(access).or_default() += 1;
+        }
+        node.accesses.insert(access);
+        if access == PathAccess::Create {
+            node.first_create.get_or_insert(idx);
+        }
+    }
+
+    fn components(path: &Path) -> impl Iterator<Item = &OsStr> {
+        path.components().filter_map(|c| match c {
+            Component::Normal(n) => Some(n),
+            _ => None,
+        })
+    }
+
+    /// Get node of a path, if it or paths below it were accessed
+    pub(crate) fn get(&self, path: &Path) -> Option<&PathNode> {
+        Self::components(path).try_fold(&self.root, |node, c| node.children.get(c))
+    }
+
+    /// Whether a path was created by an action before the one at the given index
+    pub(crate) fn created_before(&self, path: &Path, idx: usize) -> bool {
+        self.get(path)
+            .and_then(|n| n.first_create)
+            .is_some_and(|i| i < idx)
+    }
+
+    /// Accessed paths with their access kinds, in path order
+    pub(crate) fn paths(&self) -> Vec<(PathBuf, &BTreeSet<PathAccess>)> {
+        let mut paths = Vec::new();
+        let mut stack = vec![(PathBuf::from("/"), &self.root)];
+        while let Some((path, node)) = stack.pop() {
+            if !node.accesses.is_empty() {
+                paths.push((path.clone(), &node.accesses));
+            }
+            // Reverse so that children are popped in order
+            stack.extend(
+                node.children
+                    .iter()
+                    .rev()
+                    .map(|(name, child)| (path.join(name), child)),
+            );
+        }
+        paths
+    }
+
+    /// Directories at a given depth below the root (or accessed paths above it), with their nodes
+    pub(crate) fn dirs(&self, depth: usize) -> Vec<(PathBuf, &PathNode)> {
+        let mut dirs = Vec::new();
+        let mut stack = vec![(PathBuf::from("/"), &self.root, 0)];
+        while let Some((path, node, node_depth)) = stack.pop() {
+            if node_depth == depth || node.children.is_empty() {
+                dirs.push((path, node));
+            } else {
+                stack.extend(
+                    node.children
+                        .iter()
+                        .rev()
+                        .map(|(name, child)| (path.join(name), child, node_depth + 1)),
+                );
+            }
+        }
+        dirs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_tree() {
+        let actions = vec![
+            ProgramAction::Read("/usr/lib/libc.so".into()),
+            ProgramAction::Create("/var/lib/app/db".into()),
+            ProgramAction::Read("/var/lib/app/db".into()),
+            ProgramAction::Write("/var/lib/app/db".into()),
+            ProgramAction::Tasks(1),
+        ];
+        let tree = PathTree::new(&actions);
+
+        assert!(tree.created_before(Path::new("/var/lib/app/db"), 2));
+        assert!(!tree.created_before(Path::new("/var/lib/app/db"), 1));
+        assert!(!tree.created_before(Path::new("/usr/lib/libc.so"), 4));
+        assert!(tree.get(Path::new("/etc")).is_none());
+        assert_eq!(tree.get(Path::new("/var/lib")).unwrap().total(), 3);
+
+        assert_eq!(
+            tree.paths(),
+            vec![
+                (
+                    PathBuf::from("/usr/lib/libc.so"),
+                    &BTreeSet::from([PathAccess::Read])
+                ),
+                (
+                    PathBuf::from("/var/lib/app/db"),
+                    &BTreeSet::from([PathAccess::Read, PathAccess::Write, PathAccess::Create])
+                ),
+            ]
+        );
+        assert_eq!(
+            tree.dirs(2)
+                .into_iter()
+                .map(|(p, n)| (p, n.total()))
+                .collect::<Vec<_>>(),
+            vec![
+                (PathBuf::from("/usr/lib"), 1),
+                (PathBuf::from("/var/lib"), 3)
+            ]
+        );
+    }
+}
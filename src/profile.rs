@@ -0,0 +1,433 @@
+//! Profile data files
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use anyhow::Context as _;
+
+use crate::{
+    summarize::ProgramAction,
+    timeline::{self, TimelineEntry},
+};
+
+/// Information about how and where profile data was generated
+#[derive(Debug, Clone, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+pub(crate) struct ProfileMetadata {
+    /// Version of shh that generated the profile data
+    pub shh_version: String,
+    /// Host on which the program was profiled
+    pub hostname: String,
+    /// Profiled command line
+    pub command: Vec<String>,
+}
+
+impl ProfileMetadata {
+    /// Build metadata for a command profiled on this host
+    pub(crate) fn local(command: &[String]) -> anyhow::Result<Self> {
+        Ok(Self {
+            shh_version: env!("CARGO_PKG_VERSION").to_owned(),
+            hostname: nix::unistd::gethostname()?.to_string_lossy().into_owned(),
+            command: command.to_vec(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+pub(crate) struct ProfileData {
+    pub metadata: ProfileMetadata,
+    pub actions: Vec<ProgramAction>,
+    /// Time tracing started
+    pub start_time: SystemTime,
+    /// Time tracing ended, or the snapshot was written
+    pub end_time: SystemTime,
+    /// Number of syscalls traced, including the ones sampled out
+    pub syscall_count: u64,
+    /// When each permission was first needed
+    pub timeline: Vec<TimelineEntry>,
+}
+
+impl ProfileData {
+    pub(crate) fn read(path: &Path) -> anyhow::Result<Self> {
+        let file =
+            File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+        let data: Self = bincode::deserialize_from(file)
+            .with_context(|| format!("Failed to load profile data from {}", path.display()))?;
+        Ok(data)
+    }
+
+    pub(crate) fn write_to<W: Write>(&self, writer: W) -> anyhow::Result<()> {
+        bincode::serialize_into(writer, self)?;
+        Ok(())
+    }
+
+    /// Number of distinct syscalls traced
+    pub(crate) fn distinct_syscall_count(&self) -> usize {
+        self.actions
+            .iter()
+            .find_map(|a| match a {
+                ProgramAction::Syscalls(syscalls) => Some(syscalls.len()),
+                _ => None,
+            })
+            .unwrap_or(0)
+    }
+
+    /// Time spent tracing
+    pub(crate) fn duration(&self) -> Duration {
+        self.end_time
+            .duration_since(self.start_time)
+            .unwrap_or_default()
+    }
+}
+
+/// Load profile data files, check they are consistent, and merge their actions
+pub(crate) fn load_and_merge(paths: &[PathBuf]) -> anyhow::Result<Vec<ProgramAction>> {
+    let profiles = paths
+        .iter()
+        .map(|p| ProfileData::read(p))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    validate(paths, &profiles)?;
+    // Only compare runs of the same command, a service can run several different ones
+    let mut runs: BTreeMap<&[String], Vec<&ProfileData>> = BTreeMap::new();
+    for profile in &profiles {
+        runs.entry(&profile.metadata.command)
+            .or_default()
+            .push(profile);
+    }
+    for (command, command_runs) in runs {
+        let unstable = unstable_actions(&command_runs);
+        if !unstable.is_empty() {
+            log::warn!(
+                "{} action(s) of command {:?} were observed in only some of its {} runs, they may depend on timing or rare code paths, consider profiling longer before trusting the result:",
+                unstable.len(),
+                command.join(" "),
+                command_runs.len()
+            );
+            for (action, count) in unstable {
+                log::warn!("  {action} ({count}/{})", command_runs.len());
+            }
+        }
+    }
+    Ok(profiles.into_iter().flat_map(|p| p.actions).collect())
+}
+
+/// Load profile data files, and merge when each permission was first needed in them
+pub(crate) fn load_and_merge_timelines(paths: &[PathBuf]) -> anyhow::Result<Vec<TimelineEntry>> {
+    let profiles = paths
+        .iter()
+        .map(|p| ProfileData::read(p))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let timelines: Vec<_> = profiles.iter().map(|p| p.timeline.as_slice()).collect();
+    Ok(timeline::merge(&timelines))
+}
+
+/// Filename prefix of accumulated profile data from setup commands
+const ACCUMULATED_EXEC_START_PRE_PREFIX: &str = "pre-";
+
+/// Move profile data of a service activation into a new subdirectory of the accumulation directory, and get paths
+/// of all profile data accumulated so far, for the main process and setup commands
+pub(crate) fn accumulate(
+    dir: &Path,
+    paths: &[PathBuf],
+    exec_start_pre_paths: &[PathBuf],
+) -> anyhow::Result<(Vec<PathBuf>, Vec<PathBuf>)> {
+    let activation_dir = dir.join(format!("{:04}", activation_count(dir)? + 1));
+    fs::create_dir_all(&activation_dir)?;
+    let moves = paths.iter().map(|p| (p, "")).chain(
+        exec_start_pre_paths
+            .iter()
+            .map(|p| (p, ACCUMULATED_EXEC_START_PRE_PREFIX)),
+    );
+    for (path, prefix) in moves {
+        let filename = path
+            .file_name()
+            .and_then(|f| f.to_str())
+            .ok_or_else(|| anyhow::anyhow!("Invalid profile data path {path:?}"))?;
+        // Both are in /run, so this never crosses filesystems
+        fs::rename(path, activation_dir.join(format!("{prefix}{filename}")))
+            .with_context(|| format!("Failed to move {}", path.display()))?;
+    }
+    accumulated_paths(dir)
+}
+
+/// Get paths of profile data accumulated in a directory, for the main process and setup commands
+pub(crate) fn accumulated_paths(dir: &Path) -> anyhow::Result<(Vec<PathBuf>, Vec<PathBuf>)> {
+    let mut accumulated_paths = Vec::new();
+    let mut accumulated_exec_start_pre_paths = Vec::new();
+    if !dir.is_dir() {
+        return Ok((accumulated_paths, accumulated_exec_start_pre_paths));
+    }
+    for activation_entry in fs::read_dir(dir)? {
+        for entry in fs::read_dir(activation_entry?.path())? {
+            let path = entry?.path();
+            if path
+                .file_name()
+                .and_then(|f| f.to_str())
+                .is_some_and(|f| f.starts_with(ACCUMULATED_EXEC_START_PRE_PREFIX))
+            {
+                accumulated_exec_start_pre_paths.push(path);
+            } else {
+                accumulated_paths.push(path);
+            }
+        }
+    }
+    accumulated_paths.sort_unstable();
+    accumulated_exec_start_pre_paths.sort_unstable();
+    Ok((accumulated_paths, accumulated_exec_start_pre_paths))
+}
+
+/// Get number of service activations whose profile data was accumulated in a directory
+pub(crate) fn activation_count(dir: &Path) -> anyhow::Result<usize> {
+    if !dir.is_dir() {
+        return Ok(0);
+    }
+    Ok(fs::read_dir(dir)?.count())
+}
+
+/// Check profile data from different runs can be merged together
+fn validate(paths: &[PathBuf], profiles: &[ProfileData]) -> anyhow::Result<()> {
+    let local_version = env!("CARGO_PKG_VERSION");
+    for (path, profile) in paths.iter().zip(profiles) {
+        anyhow::ensure!(
+            profile.metadata.shh_version == local_version,
+            "Profile data {:?} from host {:?} was generated by shh {}, but this is shh {}",
+            path,
+            profile.metadata.hostname,
+            profile.metadata.shh_version,
+            local_version
+        );
+    }
+    if let Some(first) = profiles.first() {
+        for (path, profile) in paths.iter().zip(profiles).skip(1) {
+            if profile.metadata.command != first.metadata.command {
+                log::warn!(
+                    "Profile data {:?} from host {:?} was generated for command {:?}, but {:?} was generated for command {:?}",
+                    path,
+                    profile.metadata.hostname,
+                    profile.metadata.command.join(" "),
+                    paths[0],
+                    first.metadata.command.join(" ")
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Get descriptions of actions observed in some but not all profiles, with the number of profiles they were observed in
+fn unstable_actions(profiles: &[&ProfileData]) -> Vec<(String, usize)> {
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for profile in profiles {
+        let descriptions: BTreeSet<String> = profile
+            .actions
+            .iter()
+            .flat_map(|a| match a {
+                // Compare syscalls individually, the set as a whole almost always differs
+                ProgramAction::Syscalls(syscalls) => {
+                    syscalls.iter().map(|s| format!("syscall {s}")).collect()
+                }
+                // Peak resource usage is expected to vary
+                ProgramAction::OpenFiles(_) | ProgramAction::Tasks(_) => vec![],
+                _ => vec![a.to_string()],
+            })
+            .collect();
+        for description in descriptions {
+            *counts.entry(description).or_default() += 1;
+        }
+    }
+    counts
+        .into_iter()
+        .filter(|(_, count)| *count < profiles.len())
+        .collect()
+}
+
+/// Remove actions on paths accessed fewer than `min_accesses` times, likely incidental probing, and get the removed
+/// paths
+// TODO APPROXIMATION consecutive actions on the same path are counted as a single access, since they usually come
+// from a single syscall, and repeated identical accesses may have been sampled out
+pub(crate) fn drop_rare_path_actions(
+    actions: &mut Vec<ProgramAction>,
+    min_accesses: usize,
+) -> Vec<PathBuf> {
+    let action_path = |action: &ProgramAction| match action {
+        ProgramAction::Read(path) | ProgramAction::Write(path) | ProgramAction::Create(path) => {
+            Some(path.clone())
+        }
+        _ => None,
+    };
+    let mut counts: BTreeMap<PathBuf, usize> = BTreeMap::new();
+    let mut prev_path = None;
+    for action in actions.iter() {
+        let path = action_path(action);
+        if let Some(path) = &path {
+            if prev_path.as_ref() != Some(path) {
+                *counts.entry(path.clone()).or_default() += 1;
+            }
+        }
+        prev_path = path;
+    }
+    let rare: Vec<_> = counts
+        .into_iter()
+        .filter(|(_, count)| *count < min_accesses)
+        .map(|(path, _)| path)
+        .collect();
+    actions.retain(|a| action_path(a).is_none_or(|p| rare.binary_search(&p).is_err()));
+    rare
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_and_merge() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let command = vec!["true".to_owned()];
+        let paths: Vec<_> = (0..2)
+            .map(|i| temp_dir.path().join(format!("{i}")))
+            .collect();
+        for (i, path) in paths.iter().enumerate() {
+            let data = ProfileData {
+                metadata: ProfileMetadata::local(&command).unwrap(),
+                actions: vec![ProgramAction::Read(format!("/file{i}").into())],
+                start_time: SystemTime::UNIX_EPOCH,
+                end_time: SystemTime::UNIX_EPOCH,
+                syscall_count: 0,
+                timeline: vec![
+                    (
+                        Duration::from_secs(2 - i as u64),
+                        "syscall group @mount".to_owned(),
+                    ),
+                    (Duration::from_secs(3), format!("read /file{i}")),
+                ],
+            };
+            data.write_to(File::create(path).unwrap()).unwrap();
+        }
+        assert_eq!(
+            load_and_merge(&paths).unwrap(),
+            vec![
+                ProgramAction::Read("/file0".into()),
+                ProgramAction::Read("/file1".into())
+            ]
+        );
+        assert_eq!(
+            load_and_merge_timelines(&paths).unwrap(),
+            vec![
+                (Duration::from_secs(1), "syscall group @mount".to_owned()),
+                (Duration::from_secs(3), "read /file0".to_owned()),
+                (Duration::from_secs(3), "read /file1".to_owned()),
+            ]
+        );
+
+        let mut data = ProfileData::read(&paths[1]).unwrap();
+        data.metadata.shh_version = "0.0.0".to_owned();
+        data.write_to(File::create(&paths[1]).unwrap()).unwrap();
+        assert!(load_and_merge(&paths).is_err());
+    }
+
+    #[test]
+    fn test_accumulate() {
+        let run_dir = tempfile::tempdir().unwrap();
+        let accumulate_dir = run_dir.path().join("accumulated");
+        let path = run_dir.path().join("002");
+        let exec_start_pre_path = run_dir.path().join("001");
+        assert_eq!(activation_count(&accumulate_dir).unwrap(), 0);
+
+        for _ in 0..2 {
+            fs::write(&path, "").unwrap();
+            fs::write(&exec_start_pre_path, "").unwrap();
+            accumulate(
+                &accumulate_dir,
+                std::slice::from_ref(&path),
+                std::slice::from_ref(&exec_start_pre_path),
+            )
+            .unwrap();
+        }
+        fs::write(&path, "").unwrap();
+        assert_eq!(
+            accumulate(&accumulate_dir, std::slice::from_ref(&path), &[]).unwrap(),
+            (
+                vec![
+                    accumulate_dir.join("0001/002"),
+                    accumulate_dir.join("0002/002"),
+                    accumulate_dir.join("0003/002"),
+                ],
+                vec![
+                    accumulate_dir.join("0001/pre-001"),
+                    accumulate_dir.join("0002/pre-001"),
+                ]
+            )
+        );
+        assert_eq!(activation_count(&accumulate_dir).unwrap(), 3);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_unstable_actions() {
+        let metadata = ProfileMetadata::local(&["true".to_owned()]).unwrap();
+        let profiles: Vec<_> = [
+            vec![
+                ProgramAction::Read("/a".into()),
+                ProgramAction::Read("/b".into()),
+                ProgramAction::Syscalls(["read".to_owned(), "poll".to_owned()].into()),
+            ],
+            vec![
+                ProgramAction::Read("/a".into()),
+                ProgramAction::Syscalls(["read".to_owned()].into()),
+            ],
+            vec![
+                ProgramAction::Read("/a".into()),
+                ProgramAction::Read("/b".into()),
+                ProgramAction::Syscalls(["read".to_owned()].into()),
+            ],
+        ]
+        .into_iter()
+        .map(|actions| ProfileData {
+            metadata: metadata.clone(),
+            actions,
+            start_time: SystemTime::UNIX_EPOCH,
+            end_time: SystemTime::UNIX_EPOCH,
+            syscall_count: 0,
+            timeline: vec![],
+        })
+        .collect();
+
+        let profiles: Vec<_> = profiles.iter().collect();
+        assert_eq!(
+            unstable_actions(&profiles),
+            vec![("read /b".to_owned(), 2), ("syscall poll".to_owned(), 1)]
+        );
+        assert!(unstable_actions(&profiles[..1]).is_empty());
+    }
+
+    #[test]
+    fn test_drop_rare_path_actions() {
+        let mut actions = vec![
+            ProgramAction::Read("/etc/foo.conf".into()),
+            ProgramAction::Create("/var/lib/foo/probe".into()),
+            ProgramAction::Write("/var/lib/foo/probe".into()),
+            ProgramAction::Read("/var/lib/foo/db".into()),
+            ProgramAction::Exec("/usr/bin/foo".into()),
+            ProgramAction::Read("/etc/foo.conf".into()),
+            ProgramAction::Write("/var/lib/foo/db".into()),
+        ];
+        assert_eq!(
+            drop_rare_path_actions(&mut actions, 2),
+            vec![PathBuf::from("/var/lib/foo/probe")]
+        );
+        assert_eq!(
+            actions,
+            vec![
+                ProgramAction::Read("/etc/foo.conf".into()),
+                ProgramAction::Read("/var/lib/foo/db".into()),
+                ProgramAction::Exec("/usr/bin/foo".into()),
+                ProgramAction::Read("/etc/foo.conf".into()),
+                ProgramAction::Write("/var/lib/foo/db".into()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,1255 @@
+//! Syscall argument decoding, into the same expressions as the strace output parser
+
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io,
+    net::{Ipv4Addr, Ipv6Addr},
+    os::unix::{ffi::OsStrExt as _, fs::FileExt as _},
+    sync::LazyLock,
+};
+
+use nix::unistd::Pid;
+
+use crate::{
+    capabilities::CAPABILITIES,
+    strace::{BufferExpression, BufferType, Expression, IntegerExpression, IntegerExpressionValue},
+};
+
+/// How to decode a syscall argument
+#[derive(Debug, Clone, Copy)]
+pub(super) enum ArgType {
+    /// Plain integer
+    Int,
+    /// File descriptor
+    Fd,
+    /// Directory file descriptor for *at syscalls, or `AT_FDCWD`
+    DirFd,
+    /// Null terminated path
+    Path,
+    /// `open` flags
+    OpenFlags,
+    /// `openat2` `open_how` structure
+    OpenHow,
+    /// Memory protection flags
+    Prot,
+    /// `mknod` file type and permissions
+    MknodMode,
+    /// Socket address, with its length in another argument
+    SockAddr { len_idx: usize },
+    /// Socket address, with a pointer to its length in another argument
+    SockAddrOut { len_ptr_idx: usize },
+    /// `msghdr` structure, of which only the socket address is decoded
+    MsgHdr,
+    /// Socket address family
+    SocketFamily,
+    /// Socket type and flags
+    SocketType,
+    /// Scheduler policy
+    SchedPolicy,
+    /// `sched_attr` structure, of which only the policy and nice value are decoded
+    SchedAttr,
+    /// Clock identifier
+    ClockId,
+    /// `renameat2` flags
+    RenameFlags,
+    /// `epoll_ctl` operation
+    EpollCtlOp,
+    /// `epoll_event` structure
+    EpollEvent,
+    /// `fcntl` command
+    FcntlCmd,
+    /// `fcntl` argument, of which only `flock` structures of lock commands are decoded
+    FcntlArg,
+    /// `flock` operation
+    FlockOp,
+    /// `cap_user_header_t` structure
+    CapHeader,
+    /// `cap_user_data_t` structures, for the header version in the first argument
+    CapData,
+    /// `clone`, `unshare` and `setns` flags, of which only namespace flags are decoded
+    CloneFlags,
+    /// `clone_args` structure, of which only the flags are decoded
+    CloneArgs,
+    /// Key or keyring serial number, possibly a special keyring id
+    KeySerial,
+    /// `keyctl` operation
+    KeyctlOp,
+}
+
+/// Argument types of the syscalls we need to decode, other syscalls are only counted
+pub(super) static SYSCALL_ARGS: LazyLock<HashMap<&'static str, &'static [ArgType]>> =
+    LazyLock::new(|| {
+        HashMap::from([
+            (
+                "bind",
+                [ArgType::Fd, ArgType::SockAddr { len_idx: 2 }, ArgType::Int].as_slice(),
+            ),
+            (
+                "accept",
+                &[
+                    ArgType::Fd,
+                    ArgType::SockAddrOut { len_ptr_idx: 2 },
+                    ArgType::Int,
+                ],
+            ),
+            (
+                "accept4",
+                &[
+                    ArgType::Fd,
+                    ArgType::SockAddrOut { len_ptr_idx: 2 },
+                    ArgType::Int,
+                    ArgType::Int,
+                ],
+            ),
+            ("access", &[ArgType::Path, ArgType::Int]),
+            (
+                "add_key",
+                &[
+                    ArgType::Path,
+                    ArgType::Path,
+                    ArgType::Int,
+                    ArgType::Int,
+                    ArgType::KeySerial,
+                ],
+            ),
+            ("capget", &[ArgType::CapHeader, ArgType::CapData]),
+            ("capset", &[ArgType::CapHeader, ArgType::CapData]),
+            ("chdir", &[ArgType::Path]),
+            ("chown", &[ArgType::Path, ArgType::Int, ArgType::Int]),
+            (
+                "connect",
+                &[ArgType::Fd, ArgType::SockAddr { len_idx: 2 }, ArgType::Int],
+            ),
+            (
+                "epoll_ctl",
+                &[
+                    ArgType::Fd,
+                    ArgType::EpollCtlOp,
+                    ArgType::Fd,
+                    ArgType::EpollEvent,
+                ],
+            ),
+            ("execve", &[ArgType::Path]),
+            ("execveat", &[ArgType::DirFd, ArgType::Path]),
+            (
+                "copy_file_range",
+                &[
+                    ArgType::Fd,
+                    ArgType::Int,
+                    ArgType::Fd,
+                    ArgType::Int,
+                    ArgType::Int,
+                    ArgType::Int,
+                ],
+            ),
+            ("faccessat", &[ArgType::DirFd, ArgType::Path, ArgType::Int]),
+            (
+                "faccessat2",
+                &[ArgType::DirFd, ArgType::Path, ArgType::Int, ArgType::Int],
+            ),
+            ("fchdir", &[ArgType::Fd]),
+            (
+                "clone",
+                &[
+                    ArgType::CloneFlags,
+                    ArgType::Int,
+                    ArgType::Int,
+                    ArgType::Int,
+                    ArgType::Int,
+                ],
+            ),
+            ("clone3", &[ArgType::CloneArgs, ArgType::Int]),
+            ("fchown", &[ArgType::Fd, ArgType::Int, ArgType::Int]),
+            (
+                "fcntl",
+                &[ArgType::Fd, ArgType::FcntlCmd, ArgType::FcntlArg],
+            ),
+            ("flock", &[ArgType::Fd, ArgType::FlockOp]),
+            (
+                "fchownat",
+                &[
+                    ArgType::DirFd,
+                    ArgType::Path,
+                    ArgType::Int,
+                    ArgType::Int,
+                    ArgType::Int,
+                ],
+            ),
+            (
+                "fsconfig",
+                &[
+                    ArgType::Fd,
+                    ArgType::Int,
+                    ArgType::Path,
+                    ArgType::Path,
+                    ArgType::Int,
+                ],
+            ),
+            ("fspick", &[ArgType::DirFd, ArgType::Path, ArgType::Int]),
+            ("fstat", &[ArgType::Fd, ArgType::Int]),
+            ("getdents", &[ArgType::Fd, ArgType::Int, ArgType::Int]),
+            ("getdents64", &[ArgType::Fd, ArgType::Int, ArgType::Int]),
+            ("io_uring_setup", &[ArgType::Int, ArgType::Int]),
+            ("ioctl", &[ArgType::Fd, ArgType::Int, ArgType::Int]),
+            // Only the first key argument is decoded, others depend on the operation
+            (
+                "keyctl",
+                &[
+                    ArgType::KeyctlOp,
+                    ArgType::KeySerial,
+                    ArgType::Int,
+                    ArgType::Int,
+                    ArgType::Int,
+                ],
+            ),
+            ("lchown", &[ArgType::Path, ArgType::Int, ArgType::Int]),
+            ("listen", &[ArgType::Fd, ArgType::Int]),
+            ("lstat", &[ArgType::Path, ArgType::Int]),
+            ("mknod", &[ArgType::Path, ArgType::MknodMode, ArgType::Int]),
+            (
+                "mknodat",
+                &[
+                    ArgType::DirFd,
+                    ArgType::Path,
+                    ArgType::MknodMode,
+                    ArgType::Int,
+                ],
+            ),
+            (
+                "mmap",
+                &[
+                    ArgType::Int,
+                    ArgType::Int,
+                    ArgType::Prot,
+                    ArgType::Int,
+                    ArgType::Fd,
+                    ArgType::Int,
+                ],
+            ),
+            (
+                "mount_setattr",
+                &[
+                    ArgType::DirFd,
+                    ArgType::Path,
+                    ArgType::Int,
+                    ArgType::Int,
+                    ArgType::Int,
+                ],
+            ),
+            (
+                "move_mount",
+                &[
+                    ArgType::DirFd,
+                    ArgType::Path,
+                    ArgType::DirFd,
+                    ArgType::Path,
+                    ArgType::Int,
+                ],
+            ),
+            ("mprotect", &[ArgType::Int, ArgType::Int, ArgType::Prot]),
+            (
+                "newfstatat",
+                &[ArgType::DirFd, ArgType::Path, ArgType::Int, ArgType::Int],
+            ),
+            (
+                "name_to_handle_at",
+                &[
+                    ArgType::DirFd,
+                    ArgType::Path,
+                    ArgType::Int,
+                    ArgType::Int,
+                    ArgType::Int,
+                ],
+            ),
+            ("open", &[ArgType::Path, ArgType::OpenFlags, ArgType::Int]),
+            ("open_tree", &[ArgType::DirFd, ArgType::Path, ArgType::Int]),
+            (
+                "open_by_handle_at",
+                &[ArgType::Fd, ArgType::Int, ArgType::OpenFlags],
+            ),
+            (
+                "openat",
+                &[
+                    ArgType::DirFd,
+                    ArgType::Path,
+                    ArgType::OpenFlags,
+                    ArgType::Int,
+                ],
+            ),
+            (
+                "openat2",
+                &[
+                    ArgType::DirFd,
+                    ArgType::Path,
+                    ArgType::OpenHow,
+                    ArgType::Int,
+                ],
+            ),
+            (
+                "pkey_mprotect",
+                &[ArgType::Int, ArgType::Int, ArgType::Prot, ArgType::Int],
+            ),
+            (
+                "recvfrom",
+                &[
+                    ArgType::Fd,
+                    ArgType::Int,
+                    ArgType::Int,
+                    ArgType::Int,
+                    ArgType::SockAddrOut { len_ptr_idx: 5 },
+                    ArgType::Int,
+                ],
+            ),
+            ("recvmsg", &[ArgType::Fd, ArgType::MsgHdr, ArgType::Int]),
+            ("rename", &[ArgType::Path, ArgType::Path]),
+            (
+                "renameat",
+                &[ArgType::DirFd, ArgType::Path, ArgType::DirFd, ArgType::Path],
+            ),
+            (
+                "renameat2",
+                &[
+                    ArgType::DirFd,
+                    ArgType::Path,
+                    ArgType::DirFd,
+                    ArgType::Path,
+                    ArgType::RenameFlags,
+                ],
+            ),
+            (
+                "sched_setscheduler",
+                &[ArgType::Int, ArgType::SchedPolicy, ArgType::Int],
+            ),
+            (
+                "sched_setattr",
+                &[ArgType::Int, ArgType::SchedAttr, ArgType::Int],
+            ),
+            (
+                "request_key",
+                &[
+                    ArgType::Path,
+                    ArgType::Path,
+                    ArgType::Int,
+                    ArgType::KeySerial,
+                ],
+            ),
+            ("sendmsg", &[ArgType::Fd, ArgType::MsgHdr, ArgType::Int]),
+            (
+                "sendto",
+                &[
+                    ArgType::Fd,
+                    ArgType::Int,
+                    ArgType::Int,
+                    ArgType::Int,
+                    ArgType::SockAddr { len_idx: 5 },
+                    ArgType::Int,
+                ],
+            ),
+            (
+                "sendfile",
+                &[ArgType::Fd, ArgType::Fd, ArgType::Int, ArgType::Int],
+            ),
+            ("setfsgid", &[ArgType::Int]),
+            ("setfsuid", &[ArgType::Int]),
+            ("setgid", &[ArgType::Int]),
+            ("setgroups", &[ArgType::Int, ArgType::Int]),
+            ("setns", &[ArgType::Fd, ArgType::CloneFlags]),
+            ("setpriority", &[ArgType::Int, ArgType::Int, ArgType::Int]),
+            ("setregid", &[ArgType::Int, ArgType::Int]),
+            ("setresgid", &[ArgType::Int, ArgType::Int, ArgType::Int]),
+            ("setresuid", &[ArgType::Int, ArgType::Int, ArgType::Int]),
+            ("setreuid", &[ArgType::Int, ArgType::Int]),
+            ("setuid", &[ArgType::Int]),
+            ("shmat", &[ArgType::Int, ArgType::Int, ArgType::Int]),
+            (
+                "socket",
+                &[ArgType::SocketFamily, ArgType::SocketType, ArgType::Int],
+            ),
+            (
+                "socketpair",
+                &[
+                    ArgType::SocketFamily,
+                    ArgType::SocketType,
+                    ArgType::Int,
+                    ArgType::Int,
+                ],
+            ),
+            (
+                "splice",
+                &[
+                    ArgType::Fd,
+                    ArgType::Int,
+                    ArgType::Fd,
+                    ArgType::Int,
+                    ArgType::Int,
+                    ArgType::Int,
+                ],
+            ),
+            ("stat", &[ArgType::Path, ArgType::Int]),
+            (
+                "statx",
+                &[
+                    ArgType::DirFd,
+                    ArgType::Path,
+                    ArgType::Int,
+                    ArgType::Int,
+                    ArgType::Int,
+                ],
+            ),
+            (
+                "tee",
+                &[ArgType::Fd, ArgType::Fd, ArgType::Int, ArgType::Int],
+            ),
+            (
+                "timer_create",
+                &[ArgType::ClockId, ArgType::Int, ArgType::Int],
+            ),
+            ("unshare", &[ArgType::CloneFlags]),
+        ])
+    });
+
+const AT_FDCWD: i32 = -100;
+
+/// Maximum length of paths we read from the traced process memory
+const PATH_MAX: usize = 4096;
+
+const OPEN_FLAGS: [(u64, &str); 17] = [
+    (0o20_200_000, "O_TMPFILE"),
+    (0o4_010_000, "O_SYNC"),
+    (0o100, "O_CREAT"),
+    (0o200, "O_EXCL"),
+    (0o400, "O_NOCTTY"),
+    (0o1000, "O_TRUNC"),
+    (0o2000, "O_APPEND"),
+    (0o4000, "O_NONBLOCK"),
+    (0o10_000, "O_DSYNC"),
+    (0o20_000, "O_ASYNC"),
+    (0o40_000, "O_DIRECT"),
+    (0o100_000, "O_LARGEFILE"),
+    (0o200_000, "O_DIRECTORY"),
+    (0o400_000, "O_NOFOLLOW"),
+    (0o1_000_000, "O_NOATIME"),
+    (0o2_000_000, "O_CLOEXEC"),
+    (0o10_000_000, "O_PATH"),
+];
+
+const RESOLVE_FLAGS: [(u64, &str); 6] = [
+    (0x01, "RESOLVE_NO_XDEV"),
+    (0x02, "RESOLVE_NO_MAGICLINKS"),
+    (0x04, "RESOLVE_NO_SYMLINKS"),
+    (0x08, "RESOLVE_BENEATH"),
+    (0x10, "RESOLVE_IN_ROOT"),
+    (0x20, "RESOLVE_CACHED"),
+];
+
+const OPEN_ACCESS_MODES: [(u64, &str); 3] = [(0, "O_RDONLY"), (1, "O_WRONLY"), (2, "O_RDWR")];
+
+const PROT_FLAGS: [(u64, &str); 6] = [
+    (0x1, "PROT_READ"),
+    (0x2, "PROT_WRITE"),
+    (0x4, "PROT_EXEC"),
+    (0x8, "PROT_SEM"),
+    (0x0100_0000, "PROT_GROWSDOWN"),
+    (0x0200_0000, "PROT_GROWSUP"),
+];
+
+const FILE_TYPES: [(u64, &str); 7] = [
+    (0o140_000, "S_IFSOCK"),
+    (0o120_000, "S_IFLNK"),
+    (0o100_000, "S_IFREG"),
+    (0o60_000, "S_IFBLK"),
+    (0o40_000, "S_IFDIR"),
+    (0o20_000, "S_IFCHR"),
+    (0o10_000, "S_IFIFO"),
+];
+
+const SOCKET_FAMILIES: [(u64, &str); 14] = [
+    (0, "AF_UNSPEC"),
+    (1, "AF_UNIX"),
+    (2, "AF_INET"),
+    (4, "AF_IPX"),
+    (5, "AF_APPLETALK"),
+    (10, "AF_INET6"),
+    (15, "AF_KEY"),
+    (16, "AF_NETLINK"),
+    (17, "AF_PACKET"),
+    (29, "AF_CAN"),
+    (31, "AF_BLUETOOTH"),
+    (38, "AF_ALG"),
+    (40, "AF_VSOCK"),
+    (44, "AF_XDP"),
+];
+
+const SOCKET_TYPES: [(u64, &str); 7] = [
+    (1, "SOCK_STREAM"),
+    (2, "SOCK_DGRAM"),
+    (3, "SOCK_RAW"),
+    (4, "SOCK_RDM"),
+    (5, "SOCK_SEQPACKET"),
+    (6, "SOCK_DCCP"),
+    (10, "SOCK_PACKET"),
+];
+
+const SOCKET_FLAGS: [(u64, &str); 2] = [(0o4000, "SOCK_NONBLOCK"), (0o2_000_000, "SOCK_CLOEXEC")];
+
+const SCHED_POLICIES: [(u64, &str); 6] = [
+    (0, "SCHED_OTHER"),
+    (1, "SCHED_FIFO"),
+    (2, "SCHED_RR"),
+    (3, "SCHED_BATCH"),
+    (5, "SCHED_IDLE"),
+    (6, "SCHED_DEADLINE"),
+];
+
+const SCHED_RESET_ON_FORK: u64 = 0x4000_0000;
+
+/// Lock commands only, other commands are not decoded
+const FCNTL_LOCK_CMDS: [(u64, &str); 4] = [
+    (6, "F_SETLK"),
+    (7, "F_SETLKW"),
+    (37, "F_OFD_SETLK"),
+    (38, "F_OFD_SETLKW"),
+];
+
+const FLOCK_TYPES: [(u64, &str); 3] = [(0, "F_RDLCK"), (1, "F_WRLCK"), (2, "F_UNLCK")];
+
+const FLOCK_OPS: [(u64, &str); 4] = [
+    (1, "LOCK_SH"),
+    (2, "LOCK_EX"),
+    (4, "LOCK_NB"),
+    (8, "LOCK_UN"),
+];
+
+const CAP_VERSIONS: [(u64, &str); 3] = [
+    (0x1998_0330, "_LINUX_CAPABILITY_VERSION_1"),
+    (0x2007_1026, "_LINUX_CAPABILITY_VERSION_2"),
+    (0x2008_0522, "_LINUX_CAPABILITY_VERSION_3"),
+];
+
+/// Namespace flags only, other clone flags are not decoded
+const CLONE_NEW_FLAGS: [(u64, &str); 7] = [
+    (0x0002_0000, "CLONE_NEWNS"),
+    (0x0200_0000, "CLONE_NEWCGROUP"),
+    (0x0400_0000, "CLONE_NEWUTS"),
+    (0x0800_0000, "CLONE_NEWIPC"),
+    (0x1000_0000, "CLONE_NEWUSER"),
+    (0x2000_0000, "CLONE_NEWPID"),
+    (0x4000_0000, "CLONE_NEWNET"),
+];
+
+/// Special keyring ids, which are negative
+const KEY_SPECS: [(i32, &str); 8] = [
+    (-1, "KEY_SPEC_THREAD_KEYRING"),
+    (-2, "KEY_SPEC_PROCESS_KEYRING"),
+    (-3, "KEY_SPEC_SESSION_KEYRING"),
+    (-4, "KEY_SPEC_USER_KEYRING"),
+    (-5, "KEY_SPEC_USER_SESSION_KEYRING"),
+    (-6, "KEY_SPEC_GROUP_KEYRING"),
+    (-7, "KEY_SPEC_REQKEY_AUTH_KEY"),
+    (-8, "KEY_SPEC_REQUESTOR_KEYRING"),
+];
+
+const KEYCTL_OPS: [(u64, &str); 20] = [
+    (0, "KEYCTL_GET_KEYRING_ID"),
+    (1, "KEYCTL_JOIN_SESSION_KEYRING"),
+    (2, "KEYCTL_UPDATE"),
+    (3, "KEYCTL_REVOKE"),
+    (4, "KEYCTL_CHOWN"),
+    (5, "KEYCTL_SETPERM"),
+    (6, "KEYCTL_DESCRIBE"),
+    (7, "KEYCTL_CLEAR"),
+    (8, "KEYCTL_LINK"),
+    (9, "KEYCTL_UNLINK"),
+    (10, "KEYCTL_SEARCH"),
+    (11, "KEYCTL_READ"),
+    (12, "KEYCTL_INSTANTIATE"),
+    (13, "KEYCTL_NEGATE"),
+    (14, "KEYCTL_SET_REQKEY_KEYRING"),
+    (15, "KEYCTL_SET_TIMEOUT"),
+    (16, "KEYCTL_ASSUME_AUTHORITY"),
+    (17, "KEYCTL_GET_SECURITY"),
+    (18, "KEYCTL_SESSION_TO_PARENT"),
+    (21, "KEYCTL_INVALIDATE"),
+];
+
+const CLOCK_IDS: [(u64, &str); 11] = [
+    (0, "CLOCK_REALTIME"),
+    (1, "CLOCK_MONOTONIC"),
+    (2, "CLOCK_PROCESS_CPUTIME_ID"),
+    (3, "CLOCK_THREAD_CPUTIME_ID"),
+    (4, "CLOCK_MONOTONIC_RAW"),
+    (5, "CLOCK_REALTIME_COARSE"),
+    (6, "CLOCK_MONOTONIC_COARSE"),
+    (7, "CLOCK_BOOTTIME"),
+    (8, "CLOCK_REALTIME_ALARM"),
+    (9, "CLOCK_BOOTTIME_ALARM"),
+    (11, "CLOCK_TAI"),
+];
+
+const RENAME_FLAGS: [(u64, &str); 3] = [
+    (1, "RENAME_NOREPLACE"),
+    (2, "RENAME_EXCHANGE"),
+    (4, "RENAME_WHITEOUT"),
+];
+
+const EPOLL_CTL_OPS: [(u64, &str); 3] = [
+    (1, "EPOLL_CTL_ADD"),
+    (2, "EPOLL_CTL_DEL"),
+    (3, "EPOLL_CTL_MOD"),
+];
+
+const EPOLL_EVENTS: [(u64, &str); 15] = [
+    (0x1, "EPOLLIN"),
+    (0x2, "EPOLLPRI"),
+    (0x4, "EPOLLOUT"),
+    (0x8, "EPOLLERR"),
+    (0x10, "EPOLLHUP"),
+    (0x40, "EPOLLRDNORM"),
+    (0x80, "EPOLLRDBAND"),
+    (0x100, "EPOLLWRNORM"),
+    (0x200, "EPOLLWRBAND"),
+    (0x400, "EPOLLMSG"),
+    (0x2000, "EPOLLRDHUP"),
+    (1 << 28, "EPOLLEXCLUSIVE"),
+    (1 << 29, "EPOLLWAKEUP"),
+    (1 << 30, "EPOLLONESHOT"),
+    (1 << 31, "EPOLLET"),
+];
+
+/// Decode syscall arguments from their raw register values
+pub(super) fn decode_args(pid: Pid, arg_types: &[ArgType], raw_args: &[u64; 6]) -> Vec<Expression> {
+    arg_types
+        .iter()
+        .zip(raw_args)
+        .map(|(arg_type, raw)| decode_arg(pid, *arg_type, *raw, raw_args))
+        .collect()
+}
+
+#[expect(clippy::cast_possible_truncation)]
+fn decode_arg(pid: Pid, arg_type: ArgType, raw: u64, raw_args: &[u64; 6]) -> Expression {
+    match arg_type {
+        ArgType::Int => integer(literal(raw)),
+        ArgType::Fd => fd(pid, raw as i32),
+        ArgType::DirFd => {
+            if raw as i32 == AT_FDCWD {
+                Expression::Integer(IntegerExpression {
+                    value: IntegerExpressionValue::NamedConst("AT_FDCWD".to_owned()),
+                    metadata: link_target(pid, "cwd"),
+                })
+            } else {
+                fd(pid, raw as i32)
+            }
+        }
+        ArgType::Path => match read_path(pid, raw) {
+            Ok(path) => Expression::Buffer(BufferExpression {
+                value: path,
+                type_: BufferType::Unknown,
+            }),
+            Err(_) => integer(literal(raw)),
+        },
+        ArgType::OpenFlags => integer(open_flags(raw)),
+        // struct open_how { u64 flags; u64 mode; u64 resolve; }
+        ArgType::OpenHow => match read_bytes(pid, raw, 24) {
+            Ok(how) => {
+                #[expect(clippy::unwrap_used)]
+                let [how_flags, how_mode, how_resolve] =
+                    [0, 8, 16].map(|o| u64::from_ne_bytes(how[o..o + 8].try_into().unwrap()));
+                Expression::Struct(HashMap::from([
+                    ("flags".to_owned(), integer(open_flags(how_flags))),
+                    ("mode".to_owned(), integer(literal(how_mode))),
+                    (
+                        "resolve".to_owned(),
+                        integer(flags(how_resolve, &RESOLVE_FLAGS)),
+                    ),
+                ]))
+            }
+            Err(_) => integer(literal(raw)),
+        },
+        ArgType::Prot => integer(if raw == 0 {
+            IntegerExpressionValue::NamedConst("PROT_NONE".to_owned())
+        } else {
+            flags(raw, &PROT_FLAGS)
+        }),
+        ArgType::MknodMode => {
+            let file_type = raw & 0o170_000;
+            let mut vs = Vec::new();
+            if file_type != 0 {
+                vs.push(named(file_type, &FILE_TYPES));
+            }
+            vs.push(literal(raw & 0o7777));
+            integer(IntegerExpressionValue::BinaryOr(vs))
+        }
+        ArgType::SockAddr { len_idx } => sockaddr(pid, raw, raw_args[len_idx]),
+        ArgType::SockAddrOut { len_ptr_idx } => match read_bytes(pid, raw_args[len_ptr_idx], 4) {
+            Ok(len) if raw != 0 => {
+                #[expect(clippy::unwrap_used)]
+                let len = u32::from_ne_bytes(len.try_into().unwrap());
+                sockaddr(pid, raw, len.into())
+            }
+            _ => integer(literal(raw)),
+        },
+        // struct msghdr { void *msg_name; socklen_t msg_namelen; ... }
+        ArgType::MsgHdr => match read_bytes(pid, raw, 12) {
+            Ok(hdr) => {
+                #[expect(clippy::unwrap_used)]
+                let name = u64::from_ne_bytes(hdr[..8].try_into().unwrap());
+                #[expect(clippy::unwrap_used)]
+                let name_len = u32::from_ne_bytes(hdr[8..].try_into().unwrap());
+                Expression::Struct(HashMap::from([
+                    ("msg_name".to_owned(), sockaddr(pid, name, name_len.into())),
+                    ("msg_namelen".to_owned(), integer(literal(name_len.into()))),
+                ]))
+            }
+            Err(_) => integer(literal(raw)),
+        },
+        ArgType::SocketFamily => integer(named(raw, &SOCKET_FAMILIES)),
+        ArgType::SocketType => {
+            let mut vs = vec![named(raw & 0xf, &SOCKET_TYPES)];
+            match flags(raw & !0xf, &SOCKET_FLAGS) {
+                IntegerExpressionValue::Literal(0) => {}
+                IntegerExpressionValue::BinaryOr(mut fs) => vs.append(&mut fs),
+                f => vs.push(f),
+            }
+            integer(if vs.len() == 1 {
+                vs.remove(0)
+            } else {
+                IntegerExpressionValue::BinaryOr(vs)
+            })
+        }
+        ArgType::SchedPolicy => {
+            let policy = named(raw & !SCHED_RESET_ON_FORK, &SCHED_POLICIES);
+            integer(if raw & SCHED_RESET_ON_FORK == 0 {
+                policy
+            } else {
+                IntegerExpressionValue::BinaryOr(vec![
+                    policy,
+                    IntegerExpressionValue::NamedConst("SCHED_RESET_ON_FORK".to_owned()),
+                ])
+            })
+        }
+        // struct sched_attr { u32 size; u32 sched_policy; u64 sched_flags; s32 sched_nice; ... }
+        ArgType::SchedAttr => match read_bytes(pid, raw, 20) {
+            Ok(attr) => {
+                #[expect(clippy::unwrap_used)]
+                let policy = u32::from_ne_bytes(attr[4..8].try_into().unwrap());
+                #[expect(clippy::unwrap_used)]
+                let nice = i32::from_ne_bytes(attr[16..20].try_into().unwrap());
+                Expression::Struct(HashMap::from([
+                    (
+                        "sched_policy".to_owned(),
+                        integer(named(policy.into(), &SCHED_POLICIES)),
+                    ),
+                    (
+                        "sched_nice".to_owned(),
+                        integer(IntegerExpressionValue::Literal(nice.into())),
+                    ),
+                ]))
+            }
+            Err(_) => integer(literal(raw)),
+        },
+        ArgType::ClockId => integer(named(raw, &CLOCK_IDS)),
+        ArgType::RenameFlags => integer(flags(raw, &RENAME_FLAGS)),
+        ArgType::EpollCtlOp => integer(named(raw, &EPOLL_CTL_OPS)),
+        ArgType::EpollEvent => match read_bytes(pid, raw, 4) {
+            Ok(events) => {
+                #[expect(clippy::unwrap_used)]
+                let events = u32::from_ne_bytes(events.try_into().unwrap());
+                Expression::Struct(HashMap::from([(
+                    "events".to_owned(),
+                    integer(flags(events.into(), &EPOLL_EVENTS)),
+                )]))
+            }
+            Err(_) => integer(literal(raw)),
+        },
+        ArgType::FcntlCmd => integer(named(raw, &FCNTL_LOCK_CMDS)),
+        // struct flock { short l_type; short l_whence; ... }
+        ArgType::FcntlArg => {
+            let lock = FCNTL_LOCK_CMDS
+                .iter()
+                .any(|(c, _)| *c == raw_args[1])
+                .then(|| read_bytes(pid, raw, 2).ok())
+                .flatten();
+            match lock {
+                Some(lock) => {
+                    #[expect(clippy::unwrap_used)]
+                    let l_type = u16::from_ne_bytes(lock.try_into().unwrap());
+                    Expression::Struct(HashMap::from([(
+                        "l_type".to_owned(),
+                        integer(named(l_type.into(), &FLOCK_TYPES)),
+                    )]))
+                }
+                None => integer(literal(raw)),
+            }
+        }
+        ArgType::FlockOp => integer(flags(raw, &FLOCK_OPS)),
+        // struct __user_cap_header_struct { u32 version; int pid; }
+        ArgType::CapHeader => match read_bytes(pid, raw, 8) {
+            Ok(header) => {
+                #[expect(clippy::unwrap_used)]
+                let version = u32::from_ne_bytes(header[0..4].try_into().unwrap());
+                #[expect(clippy::unwrap_used)]
+                let target = i32::from_ne_bytes(header[4..8].try_into().unwrap());
+                Expression::Struct(HashMap::from([
+                    (
+                        "version".to_owned(),
+                        integer(named(version.into(), &CAP_VERSIONS)),
+                    ),
+                    (
+                        "pid".to_owned(),
+                        integer(IntegerExpressionValue::Literal(target.into())),
+                    ),
+                ]))
+            }
+            Err(_) => integer(literal(raw)),
+        },
+        // struct __user_cap_data_struct { u32 effective; u32 permitted; u32 inheritable; }, one for version 1,
+        // two for the 64 bit sets of later versions
+        ArgType::CapData => {
+            let v1 = read_bytes(pid, raw_args[0], 4).is_ok_and(|v| {
+                v.try_into()
+                    .is_ok_and(|v| u64::from(u32::from_ne_bytes(v)) == CAP_VERSIONS[0].0)
+            });
+            let count = if v1 { 1 } else { 2 };
+            match read_bytes(pid, raw, 12 * count) {
+                Ok(data) => {
+                    let set = |offset: usize| {
+                        (0..count)
+                            .map(|i| {
+                                let start = i * 12 + offset;
+                                #[expect(clippy::unwrap_used)]
+                                let bits =
+                                    u32::from_ne_bytes(data[start..start + 4].try_into().unwrap());
+                                u64::from(bits) << (32 * i)
+                            })
+                            .fold(0, |acc, bits| acc | bits)
+                    };
+                    Expression::Struct(HashMap::from([
+                        ("effective".to_owned(), integer(capabilities(set(0)))),
+                        ("permitted".to_owned(), integer(capabilities(set(4)))),
+                        ("inheritable".to_owned(), integer(capabilities(set(8)))),
+                    ]))
+                }
+                Err(_) => integer(literal(raw)),
+            }
+        }
+        ArgType::CloneFlags => integer(flags(raw, &CLONE_NEW_FLAGS)),
+        #[expect(clippy::cast_possible_truncation)]
+        ArgType::KeySerial => integer(
+            KEY_SPECS
+                .iter()
+                .find(|(id, _)| *id == raw as i32)
+                .map_or_else(
+                    || literal(raw),
+                    |(_, n)| IntegerExpressionValue::NamedConst((*n).to_owned()),
+                ),
+        ),
+        ArgType::KeyctlOp => integer(named(raw, &KEYCTL_OPS)),
+        // struct clone_args { u64 flags; ... }
+        ArgType::CloneArgs => match read_bytes(pid, raw, 8) {
+            Ok(clone_flags) => {
+                #[expect(clippy::unwrap_used)]
+                let clone_flags = u64::from_ne_bytes(clone_flags.try_into().unwrap());
+                Expression::Struct(HashMap::from([(
+                    "flags".to_owned(),
+                    integer(flags(clone_flags, &CLONE_NEW_FLAGS)),
+                )]))
+            }
+            Err(_) => integer(literal(raw)),
+        },
+    }
+}
+
+/// Decode `open` flags, with the access mode first like strace does
+fn open_flags(raw: u64) -> IntegerExpressionValue {
+    let access_mode = named(raw & 0o3, &OPEN_ACCESS_MODES);
+    match flags(raw & !0o3, &OPEN_FLAGS) {
+        IntegerExpressionValue::Literal(0) => access_mode,
+        IntegerExpressionValue::BinaryOr(mut vs) => {
+            vs.insert(0, access_mode);
+            IntegerExpressionValue::BinaryOr(vs)
+        }
+        v => IntegerExpressionValue::BinaryOr(vec![access_mode, v]),
+    }
+}
+
+fn integer(value: IntegerExpressionValue) -> Expression {
+    Expression::Integer(IntegerExpression {
+        value,
+        metadata: None,
+    })
+}
+
+/// Raw register value as a signed literal, like strace prints most integers
+#[expect(clippy::cast_possible_wrap)]
+fn literal(raw: u64) -> IntegerExpressionValue {
+    IntegerExpressionValue::Literal((raw as i64).into())
+}
+
+/// Name a value from a set of exclusive values
+fn named(value: u64, names: &[(u64, &str)]) -> IntegerExpressionValue {
+    names.iter().find(|(v, _)| *v == value).map_or_else(
+        || literal(value),
+        |(_, n)| IntegerExpressionValue::NamedConst((*n).to_owned()),
+    )
+}
+
+/// Decompose a value into named bit flags, with a literal for unknown remaining bits
+fn flags(value: u64, names: &[(u64, &str)]) -> IntegerExpressionValue {
+    let mut remaining = value;
+    let mut vs: Vec<_> = names
+        .iter()
+        .filter(|(v, _)| {
+            let set = (*v != 0) && (remaining & v == *v);
+            if set {
+                remaining &= !v;
+            }
+            set
+        })
+        .map(|(_, n)| IntegerExpressionValue::NamedConst((*n).to_owned()))
+        .collect();
+    if remaining != 0 {
+        vs.push(literal(remaining));
+    }
+    match vs.len() {
+        0 => IntegerExpressionValue::Literal(0),
+        1 => vs.remove(0),
+        _ => IntegerExpressionValue::BinaryOr(vs),
+    }
+}
+
+/// Decode a capability set as bit shifts, like strace does, ie. `1<<CAP_CHOWN|1<<CAP_KILL`
+fn capabilities(set: u64) -> IntegerExpressionValue {
+    let mut vs: Vec<_> = (0..u64::BITS)
+        .filter(|bit| set & (1 << bit) != 0)
+        .map(|bit| IntegerExpressionValue::LeftBitShift {
+            bits: Box::new(IntegerExpressionValue::Literal(1)),
+            shift: Box::new(
+                CAPABILITIES
+                    .get(bit as usize)
+                    .map_or(IntegerExpressionValue::Literal(bit.into()), |c| {
+                        IntegerExpressionValue::NamedConst((*c).to_owned())
+                    }),
+            ),
+        })
+        .collect();
+    match vs.len() {
+        0 => IntegerExpressionValue::Literal(0),
+        1 => vs.remove(0),
+        _ => IntegerExpressionValue::BinaryOr(vs),
+    }
+}
+
+/// File descriptor, with its path like strace `--decode-fds=path` does
+fn fd(pid: Pid, fd: i32) -> Expression {
+    Expression::Integer(IntegerExpression {
+        value: IntegerExpressionValue::Literal(fd.into()),
+        metadata: link_target(pid, &format!("fd/{fd}")),
+    })
+}
+
+/// Read symbolic link in the procfs directory of a process
+fn link_target(pid: Pid, name: &str) -> Option<Vec<u8>> {
+    fs::read_link(format!("/proc/{pid}/{name}"))
+        .ok()
+        .map(|p| p.as_os_str().as_bytes().to_vec())
+}
+
+/// Decode a socket address structure, with the members the strace parser would produce
+fn sockaddr(pid: Pid, addr: u64, len: u64) -> Expression {
+    if addr == 0 {
+        return integer(literal(addr));
+    }
+    #[expect(clippy::cast_possible_truncation)]
+    let Ok(bytes) = read_bytes(pid, addr, (len as usize).min(128)) else {
+        return integer(literal(addr));
+    };
+    if bytes.len() < 2 {
+        return integer(literal(addr));
+    }
+    let family = u16::from_ne_bytes([bytes[0], bytes[1]]);
+    let mut members = HashMap::from([(
+        "sa_family".to_owned(),
+        integer(named(family.into(), &SOCKET_FAMILIES)),
+    )]);
+    let port = |sa: &[u8]| {
+        sa.get(2..4).map(|p| Expression::Macro {
+            name: "htons".to_owned(),
+            args: vec![integer(IntegerExpressionValue::Literal(
+                u16::from_be_bytes([p[0], p[1]]).into(),
+            ))],
+        })
+    };
+    match family {
+        1 => {
+            let sun_path = &bytes[2..];
+            let (value, type_) = if let Some(abstract_path) = sun_path.strip_prefix(&[0]) {
+                (abstract_path.to_vec(), BufferType::AbstractPath)
+            } else {
+                let end = sun_path
+                    .iter()
+                    .position(|b| *b == 0)
+                    .unwrap_or(sun_path.len());
+                (sun_path[..end].to_vec(), BufferType::Unknown)
+            };
+            members.insert(
+                "sun_path".to_owned(),
+                Expression::Buffer(BufferExpression { value, type_ }),
+            );
+        }
+        2 => {
+            if let Some(port) = port(&bytes) {
+                members.insert("sin_port".to_owned(), port);
+            }
+            if let Some(ip_bytes) = bytes.get(4..8) {
+                #[expect(clippy::unwrap_used)]
+                let ip = Ipv4Addr::from(<[u8; 4]>::try_from(ip_bytes).unwrap());
+                members.insert(
+                    "sin_addr".to_owned(),
+                    Expression::Macro {
+                        name: "inet_addr".to_owned(),
+                        args: vec![Expression::Buffer(BufferExpression {
+                            value: ip.to_string().into_bytes(),
+                            type_: BufferType::Unknown,
+                        })],
+                    },
+                );
+            }
+        }
+        10 => {
+            if let Some(port) = port(&bytes) {
+                members.insert("sin6_port".to_owned(), port);
+            }
+            if let Some(ip_bytes) = bytes.get(8..24) {
+                #[expect(clippy::unwrap_used)]
+                let ip = Ipv6Addr::from(<[u8; 16]>::try_from(ip_bytes).unwrap());
+                members.insert(
+                    "sin6_addr".to_owned(),
+                    Expression::Macro {
+                        name: "inet_pton".to_owned(),
+                        args: vec![
+                            integer(IntegerExpressionValue::NamedConst("AF_INET6".to_owned())),
+                            Expression::Buffer(BufferExpression {
+                                value: ip.to_string().into_bytes(),
+                                type_: BufferType::Unknown,
+                            }),
+                            Expression::DestinationAddress("sin6_addr".to_owned()),
+                        ],
+                    },
+                );
+            }
+        }
+        _ => {}
+    }
+    Expression::Struct(members)
+}
+
+/// Read bytes from the memory of a traced process
+fn read_bytes(pid: Pid, addr: u64, len: usize) -> io::Result<Vec<u8>> {
+    let mem = File::open(format!("/proc/{pid}/mem"))?;
+    let mut buf = vec![0; len];
+    mem.read_exact_at(&mut buf, addr)?;
+    Ok(buf)
+}
+
+/// Read a null terminated path from the memory of a traced process
+fn read_path(pid: Pid, addr: u64) -> io::Result<Vec<u8>> {
+    if addr == 0 {
+        return Err(io::ErrorKind::InvalidInput.into());
+    }
+    let mem = File::open(format!("/proc/{pid}/mem"))?;
+    let mut path = Vec::new();
+    let mut buf = [0; 256];
+    while path.len() < PATH_MAX {
+        // Reads may stop early at the end of a mapping
+        let count = mem.read_at(&mut buf, addr + path.len() as u64)?;
+        if count == 0 {
+            break;
+        }
+        if let Some(end) = buf[..count].iter().position(|b| *b == 0) {
+            path.extend_from_slice(&buf[..end]);
+            return Ok(path);
+        }
+        path.extend_from_slice(&buf[..count]);
+    }
+    Err(io::ErrorKind::InvalidData.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flags() {
+        assert_eq!(
+            flags(0o2_000_101, &OPEN_FLAGS),
+            IntegerExpressionValue::BinaryOr(vec![
+                IntegerExpressionValue::NamedConst("O_CREAT".to_owned()),
+                IntegerExpressionValue::NamedConst("O_CLOEXEC".to_owned()),
+                IntegerExpressionValue::Literal(1),
+            ])
+        );
+        assert_eq!(
+            flags(0o20_200_000, &OPEN_FLAGS),
+            IntegerExpressionValue::NamedConst("O_TMPFILE".to_owned())
+        );
+        assert_eq!(flags(0, &OPEN_FLAGS), IntegerExpressionValue::Literal(0));
+
+        let pid = Pid::this();
+        assert_eq!(
+            decode_arg(pid, ArgType::OpenFlags, 0o2_000_101, &[0; 6]),
+            integer(IntegerExpressionValue::BinaryOr(vec![
+                IntegerExpressionValue::NamedConst("O_WRONLY".to_owned()),
+                IntegerExpressionValue::NamedConst("O_CREAT".to_owned()),
+                IntegerExpressionValue::NamedConst("O_CLOEXEC".to_owned()),
+            ]))
+        );
+        let how: [u64; 3] = [0o2_000_000, 0, 0x18];
+        assert_eq!(
+            decode_arg(pid, ArgType::OpenHow, how.as_ptr() as u64, &[0; 6]),
+            Expression::Struct(HashMap::from([
+                (
+                    "flags".to_owned(),
+                    integer(IntegerExpressionValue::BinaryOr(vec![
+                        IntegerExpressionValue::NamedConst("O_RDONLY".to_owned()),
+                        IntegerExpressionValue::NamedConst("O_CLOEXEC".to_owned()),
+                    ]))
+                ),
+                (
+                    "mode".to_owned(),
+                    integer(IntegerExpressionValue::Literal(0))
+                ),
+                (
+                    "resolve".to_owned(),
+                    integer(IntegerExpressionValue::BinaryOr(vec![
+                        IntegerExpressionValue::NamedConst("RESOLVE_BENEATH".to_owned()),
+                        IntegerExpressionValue::NamedConst("RESOLVE_IN_ROOT".to_owned()),
+                    ]))
+                ),
+            ]))
+        );
+        assert_eq!(
+            decode_arg(pid, ArgType::SocketType, 0o2_000_001, &[0; 6]),
+            integer(IntegerExpressionValue::BinaryOr(vec![
+                IntegerExpressionValue::NamedConst("SOCK_STREAM".to_owned()),
+                IntegerExpressionValue::NamedConst("SOCK_CLOEXEC".to_owned()),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_capabilities() {
+        let shift = |cap: IntegerExpressionValue| IntegerExpressionValue::LeftBitShift {
+            bits: Box::new(IntegerExpressionValue::Literal(1)),
+            shift: Box::new(cap),
+        };
+        assert_eq!(capabilities(0), IntegerExpressionValue::Literal(0));
+        assert_eq!(
+            capabilities((1 << 18) | (1 << 39) | (1 << 63)),
+            IntegerExpressionValue::BinaryOr(vec![
+                shift(IntegerExpressionValue::NamedConst(
+                    "CAP_SYS_CHROOT".to_owned()
+                )),
+                shift(IntegerExpressionValue::NamedConst("CAP_BPF".to_owned())),
+                shift(IntegerExpressionValue::Literal(63)),
+            ])
+        );
+
+        let pid = Pid::this();
+        let header: [u32; 2] = [0x2008_0522, 0];
+        // Low and high halves of the effective, permitted and inheritable sets
+        let data: [u32; 6] = [0, 1 << 10, 0, 0, 1 << 7, 0];
+        let raw_args = [header.as_ptr() as u64, data.as_ptr() as u64, 0, 0, 0, 0];
+        assert_eq!(
+            decode_arg(pid, ArgType::CapHeader, raw_args[0], &raw_args),
+            Expression::Struct(HashMap::from([
+                (
+                    "version".to_owned(),
+                    integer(IntegerExpressionValue::NamedConst(
+                        "_LINUX_CAPABILITY_VERSION_3".to_owned()
+                    ))
+                ),
+                (
+                    "pid".to_owned(),
+                    integer(IntegerExpressionValue::Literal(0))
+                ),
+            ]))
+        );
+        assert_eq!(
+            decode_arg(pid, ArgType::CapData, raw_args[1], &raw_args),
+            Expression::Struct(HashMap::from([
+                (
+                    "effective".to_owned(),
+                    integer(IntegerExpressionValue::Literal(0))
+                ),
+                (
+                    "permitted".to_owned(),
+                    integer(IntegerExpressionValue::BinaryOr(vec![
+                        shift(IntegerExpressionValue::NamedConst(
+                            "CAP_NET_BIND_SERVICE".to_owned()
+                        )),
+                        shift(IntegerExpressionValue::NamedConst("CAP_BPF".to_owned())),
+                    ]))
+                ),
+                (
+                    "inheritable".to_owned(),
+                    integer(IntegerExpressionValue::Literal(0))
+                ),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_read_memory() {
+        let pid = Pid::this();
+        let path = c"/some/path";
+        assert_eq!(read_path(pid, path.as_ptr() as u64).unwrap(), b"/some/path");
+
+        let mut sockaddr_in = [0_u8; 16];
+        sockaddr_in[..2].copy_from_slice(&2_u16.to_ne_bytes());
+        sockaddr_in[2..4].copy_from_slice(&8080_u16.to_be_bytes());
+        sockaddr_in[4..8].copy_from_slice(&[127, 0, 0, 1]);
+        assert_eq!(
+            decode_arg(
+                pid,
+                ArgType::SockAddr { len_idx: 2 },
+                sockaddr_in.as_ptr() as u64,
+                &[0, 0, 16, 0, 0, 0]
+            ),
+            Expression::Struct(HashMap::from([
+                (
+                    "sa_family".to_owned(),
+                    integer(IntegerExpressionValue::NamedConst("AF_INET".to_owned()))
+                ),
+                (
+                    "sin_port".to_owned(),
+                    Expression::Macro {
+                        name: "htons".to_owned(),
+                        args: vec![integer(IntegerExpressionValue::Literal(8080))]
+                    }
+                ),
+                (
+                    "sin_addr".to_owned(),
+                    Expression::Macro {
+                        name: "inet_addr".to_owned(),
+                        args: vec![Expression::Buffer(BufferExpression {
+                            value: b"127.0.0.1".to_vec(),
+                            type_: BufferType::Unknown,
+                        })]
+                    }
+                ),
+            ]))
+        );
+
+        let mut msghdr = [0_u8; 56];
+        msghdr[..8].copy_from_slice(&(sockaddr_in.as_ptr() as u64).to_ne_bytes());
+        msghdr[8..12].copy_from_slice(&16_u32.to_ne_bytes());
+        assert!(matches!(
+            decode_arg(pid, ArgType::MsgHdr, msghdr.as_ptr() as u64, &[0; 6]),
+            Expression::Struct(members) if matches!(
+                members.get("msg_name"),
+                Some(Expression::Struct(name)) if name.contains_key("sin_port")
+            )
+        ));
+    }
+}
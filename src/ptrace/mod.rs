@@ -0,0 +1,489 @@
+//! Native ptrace based tracer, producing the same syscalls as the strace output parser
+
+use std::{
+    collections::HashMap,
+    io,
+    ops::RangeInclusive,
+    os::unix::{ffi::OsStrExt as _, process::CommandExt as _},
+    process::{Command, Stdio},
+    time::Instant,
+};
+
+use anyhow::Context as _;
+use nix::{
+    errno::Errno,
+    libc,
+    sys::{
+        ptrace,
+        signal::{self, Signal},
+        wait::{waitpid, WaitPidFlag, WaitStatus},
+    },
+    unistd::{Gid, Pid, Uid},
+};
+
+use crate::{
+    cl::RunIdentityOptions,
+    strace::{BufferExpression, BufferType, Expression, Syscall},
+    summarize::{FD_CREATE_SYSCALLS, TASK_CREATE_SYSCALLS},
+};
+
+mod decode;
+mod syscalls;
+
+pub(crate) use syscalls::name as syscall_name;
+
+/// Return values in this range are errors (`-errno`)
+const ERRNO_RANGE: RangeInclusive<i64> = -4095..=-1;
+
+/// `AUDIT_ARCH_X86_64` from linux/audit.h, architecture of native syscalls in seccomp data
+const AUDIT_ARCH_X86_64: u32 = 0xc000_003e;
+
+/// Build a classic BPF instruction
+#[expect(clippy::cast_possible_truncation)]
+const fn bpf_stmt(code: u32, jt: u8, jf: u8, k: u32) -> libc::sock_filter {
+    libc::sock_filter {
+        code: code as u16,
+        jt,
+        jf,
+        k,
+    }
+}
+
+/// Seccomp filter stopping the tracee on every syscall entry, so that syscalls whose result we do not need only cause
+/// a single stop instead of a syscall entry and exit stop.
+/// Exec syscalls are allowed, because they run before the tracer can enable seccomp stops, they are reported from
+/// exec events instead.
+#[expect(clippy::cast_possible_truncation)]
+const SECCOMP_FILTER: [libc::sock_filter; 7] = [
+    bpf_stmt(
+        libc::BPF_LD | libc::BPF_W | libc::BPF_ABS,
+        0,
+        0,
+        std::mem::offset_of!(libc::seccomp_data, arch) as u32,
+    ),
+    bpf_stmt(
+        libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K,
+        0,
+        3,
+        AUDIT_ARCH_X86_64,
+    ),
+    bpf_stmt(
+        libc::BPF_LD | libc::BPF_W | libc::BPF_ABS,
+        0,
+        0,
+        std::mem::offset_of!(libc::seccomp_data, nr) as u32,
+    ),
+    bpf_stmt(
+        libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K,
+        2,
+        0,
+        libc::SYS_execve as u32,
+    ),
+    bpf_stmt(
+        libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K,
+        1,
+        0,
+        libc::SYS_execveat as u32,
+    ),
+    bpf_stmt(libc::BPF_RET | libc::BPF_K, 0, 0, libc::SECCOMP_RET_TRACE),
+    bpf_stmt(libc::BPF_RET | libc::BPF_K, 0, 0, libc::SECCOMP_RET_ALLOW),
+];
+
+/// What happened after waiting for the tracees
+enum TraceEvent {
+    /// A syscall completed
+    Syscall(Syscall),
+    /// Some other tracing event, or a syscall that did not complete yet
+    Other,
+    /// All tracees exited
+    Finished,
+}
+
+/// A syscall that was entered but did not return yet
+struct PendingSyscall {
+    name: &'static str,
+}
+
+pub(crate) struct Tracer {
+    /// Initial traced process
+    root_pid: Pid,
+    /// Traced threads and processes, with their current syscall if they are inside one
+    tracees: HashMap<Pid, Option<PendingSyscall>>,
+    /// Syscall reported before any tracing stop, for the command execution
+    initial_exec: Option<Syscall>,
+    /// Time of the previous syscall, for relative timestamps
+    prev_ts: Instant,
+}
+
+impl Tracer {
+    pub(crate) fn run(
+        command: &[&str],
+        identity_opts: &RunIdentityOptions,
+    ) -> anyhow::Result<Self> {
+        let identity = Self::identity(identity_opts)?;
+
+        let mut cmd = Command::new(command[0]);
+        cmd.args(&command[1..]).stdin(Stdio::null());
+        // SAFETY: the closure only calls async signal safe functions, and does not allocate
+        unsafe {
+            cmd.pre_exec(move || {
+                if let Some((uid, gid, groups)) = identity.as_ref() {
+                    nix::unistd::setgroups(groups).map_err(io::Error::from)?;
+                    nix::unistd::setgid(*gid).map_err(io::Error::from)?;
+                    nix::unistd::setuid(*uid).map_err(io::Error::from)?;
+                }
+                ptrace::traceme().map_err(io::Error::from)?;
+                Self::install_seccomp_filter()
+            });
+        }
+        let child = cmd
+            .spawn()
+            .with_context(|| format!("Failed to start {:?}", command[0]))?;
+        #[expect(clippy::cast_possible_wrap)]
+        let root_pid = Pid::from_raw(child.id() as i32);
+
+        // The child stops after its execve call
+        match waitpid(root_pid, None)? {
+            WaitStatus::Stopped(_, Signal::SIGTRAP) => {}
+            status => anyhow::bail!("Unexpected traced process state {status:?}"),
+        }
+        ptrace::setoptions(
+            root_pid,
+            ptrace::Options::PTRACE_O_TRACESYSGOOD
+                | ptrace::Options::PTRACE_O_TRACECLONE
+                | ptrace::Options::PTRACE_O_TRACEFORK
+                | ptrace::Options::PTRACE_O_TRACEVFORK
+                | ptrace::Options::PTRACE_O_TRACEEXEC
+                | ptrace::Options::PTRACE_O_TRACESECCOMP
+                | ptrace::Options::PTRACE_O_EXITKILL,
+        )?;
+        let initial_exec = Self::exec_syscall(root_pid, 0.0)?;
+        ptrace::cont(root_pid, None)?;
+
+        Ok(Self {
+            root_pid,
+            tracees: HashMap::from([(root_pid, None)]),
+            initial_exec: Some(initial_exec),
+            prev_ts: Instant::now(),
+        })
+    }
+
+    /// Install the seccomp filter in the process about to run the command
+    fn install_seccomp_filter() -> io::Result<()> {
+        let mut filter = SECCOMP_FILTER;
+        let prog = libc::sock_fprog {
+            #[expect(clippy::cast_possible_truncation)]
+            len: filter.len() as libc::c_ushort,
+            filter: filter.as_mut_ptr(),
+        };
+        // Without CAP_SYS_ADMIN, a seccomp filter can only be installed if privileges can not be gained on exec, which
+        // is already the case for a process traced by an unprivileged tracer
+        // TODO APPROXIMATION when running the command as another user, setuid executables it runs do not gain
+        // privileges
+        if !nix::unistd::geteuid().is_root() {
+            // SAFETY: prctl with integer arguments only
+            if unsafe { libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) } != 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        // SAFETY: the filter program outlives the call, the kernel copies it
+        if unsafe {
+            libc::prctl(
+                libc::PR_SET_SECCOMP,
+                libc::SECCOMP_MODE_FILTER,
+                &raw const prog,
+            )
+        } != 0
+        {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// Build the exec syscall of a process that just replaced its memory, since exec syscalls are not stopped on.
+    /// Only the executable path is known at this point.
+    fn exec_syscall(pid: Pid, rel_ts: f64) -> anyhow::Result<Syscall> {
+        let exe = std::fs::read_link(format!("/proc/{pid}/exe"))?;
+        Ok(Syscall {
+            pid: Self::syscall_pid(pid),
+            rel_ts,
+            name: "execve".to_owned(),
+            args: vec![Expression::Buffer(BufferExpression {
+                value: exe.as_os_str().as_bytes().to_vec(),
+                type_: BufferType::Unknown,
+            })],
+            ret_val: 0,
+        })
+    }
+
+    /// Resolve user, group, and supplementary groups to run the command with
+    fn identity(
+        identity_opts: &RunIdentityOptions,
+    ) -> anyhow::Result<Option<(Uid, Gid, Vec<Gid>)>> {
+        let Some(user) = identity_opts.user.as_ref() else {
+            return Ok(None);
+        };
+        anyhow::ensure!(
+            nix::unistd::geteuid().is_root(),
+            "Running the command as another user requires root"
+        );
+        let user = match user.parse() {
+            Ok(uid) => nix::unistd::User::from_uid(Uid::from_raw(uid))?,
+            Err(_) => nix::unistd::User::from_name(user)?,
+        }
+        .ok_or_else(|| anyhow::anyhow!("Unknown user {user:?}"))?;
+        if let Some(group) = identity_opts.group.as_ref() {
+            let gid = match group.parse() {
+                Ok(gid) => Gid::from_raw(gid),
+                Err(_) => {
+                    nix::unistd::Group::from_name(group)?
+                        .ok_or_else(|| anyhow::anyhow!("Unknown group {group:?}"))?
+                        .gid
+                }
+            };
+            Ok(Some((user.uid, gid, vec![gid])))
+        } else {
+            let name = std::ffi::CString::new(user.name)?;
+            let groups = nix::unistd::getgrouplist(&name, user.gid)?;
+            Ok(Some((user.uid, user.gid, groups)))
+        }
+    }
+
+    #[expect(clippy::cast_sign_loss)]
+    fn syscall_pid(pid: Pid) -> u32 {
+        pid.as_raw() as u32
+    }
+
+    fn decode_args(pid: Pid, name: &str, raw_args: &[u64; 6]) -> Vec<Expression> {
+        decode::SYSCALL_ARGS
+            .get(name)
+            .map(|arg_types| decode::decode_args(pid, arg_types, raw_args))
+            .unwrap_or_default()
+    }
+
+    /// Whether we need the exit of a syscall, for its output arguments or return value
+    fn needs_exit(name: &str) -> bool {
+        decode::SYSCALL_ARGS.contains_key(name)
+            || FD_CREATE_SYSCALLS.contains(&name)
+            || TASK_CREATE_SYSCALLS.contains(&name)
+    }
+
+    /// Let a stopped tracee run until its next syscall entry, or until the exit of its current syscall if we need it
+    fn resume(&self, pid: Pid, sig: Option<Signal>) -> anyhow::Result<()> {
+        let res = if self.tracees.get(&pid).is_some_and(Option::is_some) {
+            ptrace::syscall(pid, sig)
+        } else {
+            ptrace::cont(pid, sig)
+        };
+        match res {
+            // Tracee may have been killed in the meantime
+            Ok(()) | Err(Errno::ESRCH) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Get registers of a stopped tracee, if it still exists
+    fn regs(pid: Pid) -> anyhow::Result<Option<libc::user_regs_struct>> {
+        match ptrace::getregs(pid) {
+            Ok(regs) => Ok(Some(regs)),
+            Err(Errno::ESRCH) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Time since the previous syscall
+    fn rel_ts(&mut self) -> f64 {
+        let now = Instant::now();
+        let rel_ts = now.duration_since(self.prev_ts).as_secs_f64();
+        self.prev_ts = now;
+        rel_ts
+    }
+
+    /// Handle a seccomp stop on syscall entry, and return the syscall if we do not need its exit
+    fn seccomp_stop(&mut self, pid: Pid) -> anyhow::Result<Option<Syscall>> {
+        let Some(regs) = Self::regs(pid)? else {
+            return Ok(None);
+        };
+        // TODO APPROXIMATION 32-bit syscalls from compat mode processes are named from their 64-bit numbers
+        let name = syscalls::name(regs.orig_rax).unwrap_or("unknown");
+        if Self::needs_exit(name) {
+            self.tracees.insert(pid, Some(PendingSyscall { name }));
+            return Ok(None);
+        }
+        // TODO APPROXIMATION syscalls that are only counted are reported before they run, so unlike with strace
+        // --successful-only, they are also reported if they fail
+        Ok(Some(Syscall {
+            pid: Self::syscall_pid(pid),
+            rel_ts: self.rel_ts(),
+            name: name.to_owned(),
+            args: Vec::new(),
+            ret_val: 0,
+        }))
+    }
+
+    /// Handle a syscall exit stop, and return the syscall if it succeeded
+    fn syscall_exit_stop(&mut self, pid: Pid) -> anyhow::Result<Option<Syscall>> {
+        let Some(PendingSyscall { name }) = self.tracees.get_mut(&pid).and_then(Option::take)
+        else {
+            return Ok(None);
+        };
+        let Some(regs) = Self::regs(pid)? else {
+            return Ok(None);
+        };
+        #[expect(clippy::cast_possible_wrap)]
+        let ret_val = regs.rax as i64;
+        if ERRNO_RANGE.contains(&ret_val) {
+            // Same as strace --successful-only
+            return Ok(None);
+        }
+        let raw_args = [regs.rdi, regs.rsi, regs.rdx, regs.r10, regs.r8, regs.r9];
+        Ok(Some(Syscall {
+            pid: Self::syscall_pid(pid),
+            rel_ts: self.rel_ts(),
+            name: name.to_owned(),
+            args: Self::decode_args(pid, name, &raw_args),
+            ret_val: ret_val.into(),
+        }))
+    }
+
+    /// Wait for the next tracing event, and handle it
+    fn wait(&mut self) -> anyhow::Result<TraceEvent> {
+        let status = match waitpid(None, Some(WaitPidFlag::__WALL)) {
+            Ok(status) => status,
+            Err(Errno::ECHILD) => return Ok(TraceEvent::Finished),
+            Err(e) => return Err(e.into()),
+        };
+        let syscall = match status {
+            WaitStatus::PtraceSyscall(pid) => {
+                let syscall = self.syscall_exit_stop(pid)?;
+                self.resume(pid, None)?;
+                syscall
+            }
+            WaitStatus::PtraceEvent(pid, _, event) => {
+                let syscall = if event == ptrace::Event::PTRACE_EVENT_SECCOMP as i32 {
+                    self.seccomp_stop(pid)?
+                } else if event == ptrace::Event::PTRACE_EVENT_EXEC as i32 {
+                    // A non leader thread that calls execve takes the identity of the thread group leader
+                    #[expect(clippy::cast_possible_truncation)]
+                    let former_pid = Pid::from_raw(ptrace::getevent(pid)? as i32);
+                    if former_pid != pid {
+                        if let Some(pending) = self.tracees.remove(&former_pid) {
+                            self.tracees.insert(pid, pending);
+                        }
+                    }
+                    let rel_ts = self.rel_ts();
+                    Some(Self::exec_syscall(pid, rel_ts)?)
+                } else {
+                    None
+                };
+                self.resume(pid, None)?;
+                syscall
+            }
+            WaitStatus::Stopped(pid, sig) => {
+                if (sig == Signal::SIGSTOP) && !self.tracees.contains_key(&pid) {
+                    // New thread or process, automatically attached
+                    self.tracees.insert(pid, None);
+                    self.resume(pid, None)?;
+                } else {
+                    self.resume(pid, Some(sig))?;
+                }
+                None
+            }
+            WaitStatus::Exited(pid, _) | WaitStatus::Signaled(pid, _, _) => {
+                // Syscalls that never return are still reported
+                self.tracees
+                    .remove(&pid)
+                    .flatten()
+                    .map(|PendingSyscall { name }| Syscall {
+                        pid: Self::syscall_pid(pid),
+                        rel_ts: 0.0,
+                        name: name.to_owned(),
+                        args: Vec::new(),
+                        ret_val: 0,
+                    })
+            }
+            WaitStatus::Continued(_) | WaitStatus::StillAlive => None,
+        };
+        Ok(syscall.map_or(TraceEvent::Other, TraceEvent::Syscall))
+    }
+}
+
+impl Iterator for Tracer {
+    type Item = anyhow::Result<Syscall>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(initial_exec) = self.initial_exec.take() {
+            return Some(Ok(initial_exec));
+        }
+        loop {
+            match self.wait() {
+                Ok(TraceEvent::Syscall(syscall)) => return Some(Ok(syscall)),
+                Ok(TraceEvent::Other) => {}
+                Ok(TraceEvent::Finished) => return None,
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+impl Drop for Tracer {
+    fn drop(&mut self) {
+        if !self.tracees.is_empty() {
+            let _ = signal::kill(self.root_pid, Signal::SIGKILL);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The tracer waits for any child, so trace in a separate test process to not reap children of other tests
+    #[test]
+    fn test_tracer_loop() {
+        let output = Command::new(std::env::current_exe().unwrap())
+            .args(["--exact", "ptrace::tests::tracer_loop", "--ignored"])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        assert!(String::from_utf8_lossy(&output.stdout).contains(" 1 passed;"));
+    }
+
+    #[test]
+    #[ignore = "run in a separate process by test_tracer_loop"]
+    fn tracer_loop() {
+        let identity_opts = RunIdentityOptions {
+            user: None,
+            group: None,
+        };
+        let syscalls: Vec<_> = Tracer::run(
+            &["sh", "-c", "head -c 1 /dev/zero > /dev/null & wait"],
+            &identity_opts,
+        )
+        .unwrap()
+        .collect::<anyhow::Result<_>>()
+        .unwrap();
+
+        assert_eq!(syscalls[0].name, "execve");
+        let root_pid = syscalls[0].pid;
+        // Exec syscalls are not stopped on, but still reported
+        assert!(syscalls
+            .iter()
+            .any(|s| s.name == "execve" && s.pid != root_pid));
+        assert!(syscalls.iter().any(|s| s.name == "openat"
+            && s.pid != root_pid
+            && s.args.iter().any(|a| matches!(
+                a,
+                Expression::Buffer(BufferExpression { value, .. }) if value == b"/dev/zero"
+            ))));
+        assert_eq!(
+            syscalls
+                .iter()
+                .filter(|s| s.name == "exit_group")
+                .map(|s| s.pid)
+                .collect::<std::collections::HashSet<_>>()
+                .len(),
+            2
+        );
+    }
+}
@@ -0,0 +1,268 @@
+//! Self-contained HTML report
+
+use std::{collections::BTreeSet, fmt::Write as _};
+
+use itertools::Itertools as _;
+
+use super::Report;
+use crate::{
+    path_tree::PathTree,
+    summarize::ProgramAction,
+    systemd::{self, OptionJustification},
+};
+
+const STYLE: &str = "body { font-family: sans-serif; max-width: 70em; margin: auto; padding: 1em; }
+table { border-collapse: collapse; width: 100%; }
+th, td { border: 1px solid #ccc; padding: 0.3em 0.6em; text-align: left; vertical-align: top; }
+th { background: #eee; }
+pre, code { background: #f6f6f6; }
+pre { padding: 0.6em; overflow-x: auto; }
+.exposure { font-size: 1.4em; }";
+
+/// Render report as a HTML document
+pub(super) fn render(report: &Report<'_>) -> anyhow::Result<String> {
+    let mut html = String::new();
+    writeln!(html, "<!DOCTYPE html>")?;
+    writeln!(html, "<html lang=\"en\">")?;
+    writeln!(html, "<head>")?;
+    writeln!(html, "<meta charset=\"utf-8\">")?;
+    writeln!(html, "<title>Hardening report</title>")?;
+    writeln!(html, "<style>\n{STYLE}\n</style>")?;
+    writeln!(html, "</head>")?;
+    writeln!(html, "<body>")?;
+    writeln!(html, "<h1>Hardening report</h1>")?;
+    writeln!(
+        html,
+        "<p>Generated by {} {}</p>",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION")
+    )?;
+
+    writeln!(html, "<h2>Exposure</h2>")?;
+    writeln!(
+        html,
+        "<p class=\"exposure\">{:.1} &rarr; {:.1} (out of {:.1})</p>",
+        report.exposure.0,
+        report.exposure.1,
+        systemd::MAX_EXPOSURE
+    )?;
+    writeln!(
+        html,
+        "<p>Estimated exposure without and with the options below, lower is better.</p>"
+    )?;
+
+    writeln!(html, "<h2>Options</h2>")?;
+    if report.options.is_empty() {
+        writeln!(html, "<p>No option could be safely enabled.</p>")?;
+    } else {
+        writeln!(
+            html,
+            "<table>\n<tr><th>Option</th><th>Breakage risk</th></tr>"
+        )?;
+        for (opt, risk) in report.options.iter().zip(&report.risks) {
+            writeln!(
+                html,
+                "<tr><td><pre>{}</pre></td><td>{risk}</td></tr>",
+                escape(&opt.to_string())
+            )?;
+        }
+        writeln!(html, "</table>")?;
+    }
+
+    writeln!(html, "<h2>Justifications</h2>")?;
+    if report.justifications.is_empty() {
+        writeln!(
+            html,
+            "<p>All options were set to their most restrictive value.</p>"
+        )?;
+    } else {
+        writeln!(
+            html,
+            "<table>\n<tr><th>Option</th><th>Value</th><th>Reason</th></tr>"
+        )?;
+        for justification in &report.justifications {
+            let (option, value, reason) = match justification {
+                OptionJustification::Rejected {
+                    option,
+                    value,
+                    action,
+                } => (option, value, format!("Not applied, would break: {action}")),
+                OptionJustification::Allowed {
+                    option,
+                    value,
+                    action,
+                } => (option, value, format!("Allowed, needed for: {action}")),
+            };
+            writeln!(
+                html,
+                "<tr><td><code>{}</code></td><td><code>{}</code></td><td>{}</td></tr>",
+                escape(option),
+                escape(value),
+                escape(&reason)
+            )?;
+        }
+        writeln!(html, "</table>")?;
+    }
+
+    writeln!(html, "<h2>Warnings</h2>")?;
+    if report.warnings.is_empty() && report.missing_mandatory.is_empty() {
+        writeln!(html, "<p>None.</p>")?;
+    } else {
+        writeln!(html, "<ul>")?;
+        for option in &report.missing_mandatory {
+            writeln!(
+                html,
+                "<li>Mandatory option <code>{}</code> could not be set</li>",
+                escape(option)
+            )?;
+        }
+        for warning in &report.warnings {
+            writeln!(html, "<li>{}</li>", escape(warning))?;
+        }
+        writeln!(html, "</ul>")?;
+    }
+
+    writeln!(html, "<h2>Loaded plugins</h2>")?;
+    if report.plugins.is_empty() {
+        writeln!(html, "<p>None.</p>")?;
+    } else {
+        writeln!(
+            html,
+            "<p>Known needs of these NSS and PAM modules are kept allowed, even if they were not exercised during profiling.</p>"
+        )?;
+        writeln!(html, "<ul>")?;
+        for plugin in &report.plugins {
+            writeln!(html, "<li>{}</li>", escape(&plugin.to_string()))?;
+        }
+        writeln!(html, "</ul>")?;
+    }
+
+    if !report.bus_advice.is_empty() {
+        writeln!(html, "<h2>D-Bus</h2>")?;
+        writeln!(html, "<ul>")?;
+        for advice in &report.bus_advice {
+            writeln!(html, "<li>{}</li>", escape(advice))?;
+        }
+        writeln!(html, "</ul>")?;
+    }
+
+    writeln!(html, "<h2>Observed paths</h2>")?;
+    let path_tree = PathTree::new(report.actions);
+    let paths = path_tree.paths();
+    if paths.is_empty() {
+        writeln!(html, "<p>None.</p>")?;
+    } else {
+        writeln!(html, "<table>\n<tr><th>Path</th><th>Access</th></tr>")?;
+        for (path, accesses) in paths {
+            writeln!(
+                html,
+                "<tr><td><code>{}</code></td><td>{}</td></tr>",
+                escape(&path.to_string_lossy()),
+                accesses.iter().map(ToString::to_string).join(", ")
+            )?;
+        }
+        writeln!(html, "</table>")?;
+    }
+
+    if !report.dropped_paths.is_empty() {
+        writeln!(html, "<h2>Left out paths</h2>")?;
+        writeln!(
+            html,
+            "<p>These paths were accessed too rarely during profiling, and are not allowed, check the program does not need them.</p>"
+        )?;
+        writeln!(html, "<ul>")?;
+        for path in &report.dropped_paths {
+            writeln!(
+                html,
+                "<li><code>{}</code></li>",
+                escape(&path.to_string_lossy())
+            )?;
+        }
+        writeln!(html, "</ul>")?;
+    }
+
+    writeln!(html, "<h2>Observed network activity</h2>")?;
+    let network: BTreeSet<_> = report
+        .actions
+        .iter()
+        .filter(|a| matches!(a, ProgramAction::NetworkActivity(_)))
+        .map(ToString::to_string)
+        .collect();
+    if network.is_empty() {
+        writeln!(html, "<p>None.</p>")?;
+    } else {
+        writeln!(html, "<ul>")?;
+        for activity in network {
+            writeln!(html, "<li>{}</li>", escape(&activity))?;
+        }
+        writeln!(html, "</ul>")?;
+    }
+
+    writeln!(html, "</body>")?;
+    writeln!(html, "</html>")?;
+    Ok(html)
+}
+
+/// Escape text for inclusion in HTML content or attribute values
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cl::HardeningOptions,
+        systemd::{build_options, ExposureModel, KernelVersion, SystemdVersion},
+    };
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            escape("<a href=\"x\">&'</a>"),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&#39;&lt;/a&gt;"
+        );
+    }
+
+    #[test]
+    fn test_html() {
+        let sd_opts = build_options(
+            &SystemdVersion::new(254, 0),
+            &KernelVersion::new(6, 4, 0),
+            &HardeningOptions::safe(),
+        );
+        let actions = vec![
+            ProgramAction::Read("/home/user/<file>".into()),
+            ProgramAction::Write("/var/lib/app".into()),
+        ];
+        let options = systemd::resolve(&sd_opts, &actions);
+        let html = render(&Report::new(
+            &sd_opts,
+            &actions,
+            &options,
+            &ExposureModel::default(),
+            vec![],
+            None,
+        ))
+        .unwrap();
+        assert!(
+            html.contains("<tr><td><code>/home/user/&lt;file&gt;</code></td><td>read</td></tr>")
+        );
+        assert!(html.contains("ProtectSystem=full"));
+        assert!(html.contains(
+            "<tr><td><code>ProtectSystem</code></td><td><code>strict</code></td><td>Not applied, would break: write /var/lib/app</td></tr>"
+        ));
+        assert!(!html.contains("<file>"));
+    }
+}
@@ -0,0 +1,147 @@
+//! JSON report, with options grouped by category, for tools and reviews of large fragments
+
+use std::collections::BTreeMap;
+
+use serde_json::json;
+
+use super::Report;
+use crate::{
+    cl::RiskLevel,
+    systemd::{OptionCategory, OptionJustification, OptionWithValue},
+};
+
+/// Options of a category, with their breakage risk
+#[derive(Default)]
+struct Category<'a> {
+    options: Vec<(&'a OptionWithValue, RiskLevel)>,
+    /// Number of restrictive values left out
+    rejected: usize,
+    /// Number of allow list values added
+    allowed: usize,
+}
+
+/// Render report as JSON, options being grouped by category with a summary of each
+pub(super) fn render(report: &Report<'_>) -> serde_json::Value {
+    let mut categories: BTreeMap<OptionCategory, Category<'_>> = BTreeMap::new();
+    for (opt, risk) in report.options.iter().zip(&report.risks) {
+        categories
+            .entry(opt.category())
+            .or_default()
+            .options
+            .push((opt, *risk));
+    }
+    for justification in &report.justifications {
+        let (OptionJustification::Rejected { option, .. }
+        | OptionJustification::Allowed { option, .. }) = justification;
+        let category = categories.entry(OptionCategory::of(option)).or_default();
+        if matches!(justification, OptionJustification::Rejected { .. }) {
+            category.rejected += 1;
+        } else {
+            category.allowed += 1;
+        }
+    }
+    let categories: Vec<_> = categories
+        .into_iter()
+        .map(|(category, content)| {
+            let mut names: Vec<_> = content.options.iter().map(|(o, _)| &o.name).collect();
+            // Options split on several lines (ie. allowed syscall exceptions) are counted once
+            names.dedup();
+            let options: Vec<_> = content
+                .options
+                .iter()
+                .map(|(opt, risk)| {
+                    json!({
+                        "name": opt.name,
+                        "value": opt
+                            .to_string()
+                            .lines()
+                            .map(|l| l.split_once('=').map_or("", |(_, v)| v))
+                            .collect::<Vec<_>>(),
+                        "risk": risk.to_string(),
+                    })
+                })
+                .collect();
+            json!({
+                "name": category.to_string(),
+                "summary": {
+                    "option_count": names.len(),
+                    "rejected_value_count": content.rejected,
+                    "allowed_value_count": content.allowed,
+                    "max_risk": content.options.iter().map(|(_, r)| *r).max().map(|r| r.to_string()),
+                },
+                "options": options,
+            })
+        })
+        .collect();
+    json!({
+        "exposure": {
+            "before": report.exposure.0,
+            "after": report.exposure.1,
+        },
+        "categories": categories,
+        "warnings": report.warnings,
+        "plugins": report.plugins.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        "dbus": report.bus_advice,
+        "dropped_paths": report.dropped_paths,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cl::HardeningOptions,
+        summarize::ProgramAction,
+        systemd::{self, build_options, ExposureModel, KernelVersion, SystemdVersion},
+    };
+
+    #[test]
+    fn test_render() {
+        let sd_opts = build_options(
+            &SystemdVersion::new(254, 0),
+            &KernelVersion::new(6, 4, 0),
+            &HardeningOptions::safe(),
+        );
+        let actions = vec![
+            ProgramAction::Read("/etc/foo".into()),
+            ProgramAction::WriteExecuteMemoryMapping,
+        ];
+        let options = systemd::resolve(&sd_opts, &actions);
+        let json = render(&Report::new(
+            &sd_opts,
+            &actions,
+            &options,
+            &ExposureModel::default(),
+            vec![],
+            None,
+        ));
+
+        let categories = json["categories"].as_array().unwrap();
+        assert_eq!(
+            categories
+                .iter()
+                .map(|c| c["summary"]["option_count"].as_u64().unwrap())
+                .sum::<u64>(),
+            options
+                .iter()
+                .map(|o| &o.name)
+                .collect::<std::collections::HashSet<_>>()
+                .len() as u64
+        );
+        let syscalls = categories
+            .iter()
+            .find(|c| c["name"] == "System calls")
+            .unwrap();
+        assert!(
+            syscalls["summary"]["rejected_value_count"]
+                .as_u64()
+                .unwrap()
+                >= 1
+        );
+        assert!(!syscalls["options"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|o| o["name"] == "MemoryDenyWriteExecute"));
+    }
+}
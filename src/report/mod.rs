@@ -0,0 +1,93 @@
+//! Hardening reports, for people and tools that do not run shh
+
+mod html;
+mod json;
+mod sarif;
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context as _;
+
+use crate::{
+    cl::RiskLevel,
+    summarize::ProgramAction,
+    systemd::{
+        self, ExposureModel, OptionDescription, OptionJustification, OptionWithValue, Plugin,
+    },
+};
+
+/// Everything that goes into a report
+pub(crate) struct Report<'a> {
+    actions: &'a [ProgramAction],
+    options: &'a [OptionWithValue],
+    /// Breakage risk of each option
+    risks: Vec<RiskLevel>,
+    justifications: Vec<OptionJustification>,
+    warnings: Vec<String>,
+    /// Estimated exposure without and with the options
+    exposure: (f64, f64),
+    /// Mandatory options of the exposure model that are not set
+    missing_mandatory: Vec<String>,
+    /// Loaded NSS and PAM plugins
+    plugins: Vec<Plugin>,
+    /// Advice to restrict D-Bus method calls
+    bus_advice: Vec<String>,
+    /// Paths left out because they were accessed too rarely
+    dropped_paths: Vec<PathBuf>,
+    /// Unit fragment the options are written to, for services
+    fragment_path: Option<PathBuf>,
+}
+
+impl<'a> Report<'a> {
+    pub(crate) fn new(
+        sd_opts: &[OptionDescription],
+        actions: &'a [ProgramAction],
+        options: &'a [OptionWithValue],
+        exposure_model: &ExposureModel,
+        dropped_paths: Vec<PathBuf>,
+        fragment_path: Option<PathBuf>,
+    ) -> Self {
+        let justifications = systemd::justify(sd_opts, actions);
+        Self {
+            actions,
+            options,
+            risks: systemd::option_risks(options, &justifications),
+            justifications,
+            warnings: systemd::warnings(actions, options),
+            exposure: (
+                exposure_model.exposure(&[]),
+                exposure_model.exposure(options),
+            ),
+            missing_mandatory: exposure_model
+                .missing_mandatory(options)
+                .into_iter()
+                .map(ToOwned::to_owned)
+                .collect(),
+            plugins: systemd::loaded_plugins(actions),
+            bus_advice: systemd::bus_advice(actions),
+            dropped_paths,
+            fragment_path,
+        }
+    }
+
+    pub(crate) fn write_html(&self, path: &Path) -> anyhow::Result<()> {
+        log::info!("Writing HTML report into {path:?}...");
+        fs::write(path, html::render(self)?)
+            .with_context(|| format!("Failed to write report to {}", path.display()))
+    }
+
+    pub(crate) fn write_sarif(&self, path: &Path) -> anyhow::Result<()> {
+        log::info!("Writing SARIF report into {path:?}...");
+        fs::write(path, serde_json::to_string_pretty(&sarif::render(self))?)
+            .with_context(|| format!("Failed to write report to {}", path.display()))
+    }
+
+    pub(crate) fn write_json(&self, path: &Path) -> anyhow::Result<()> {
+        log::info!("Writing JSON report into {path:?}...");
+        fs::write(path, serde_json::to_string_pretty(&json::render(self))?)
+            .with_context(|| format!("Failed to write report to {}", path.display()))
+    }
+}
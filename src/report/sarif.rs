@@ -0,0 +1,240 @@
+//! SARIF report, for code scanning dashboards
+//! See <https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html>
+
+use serde_json::json;
+use strum::IntoEnumIterator as _;
+
+use super::Report;
+use crate::{summarize::ProgramAction, systemd::OptionJustification};
+
+/// Kind of finding, with its description and SARIF level
+#[derive(Debug, Clone, Copy, Eq, PartialEq, strum::EnumIter, strum::IntoStaticStr)]
+#[strum(serialize_all = "kebab-case")]
+enum Rule {
+    OptionNotApplied,
+    MandatoryOptionMissing,
+    PermissionNeeded,
+    HardeningWarning,
+    RwxMemoryMapping,
+    ShellExec,
+}
+
+impl Rule {
+    fn description(self) -> &'static str {
+        match self {
+            Self::OptionNotApplied => {
+                "Hardening option value could not be applied without breaking the program"
+            }
+            Self::MandatoryOptionMissing => {
+                "Hardening option required by the exposure model could not be set"
+            }
+            Self::PermissionNeeded => "Allow list entry needed by the program",
+            Self::HardeningWarning => "Hardening option may change the program behavior",
+            Self::RwxMemoryMapping => "Program creates writable and executable memory mappings",
+            Self::ShellExec => "Program runs a shell",
+        }
+    }
+
+    fn level(self) -> &'static str {
+        match self {
+            Self::PermissionNeeded => "note",
+            Self::MandatoryOptionMissing => "error",
+            Self::OptionNotApplied
+            | Self::HardeningWarning
+            | Self::RwxMemoryMapping
+            | Self::ShellExec => "warning",
+        }
+    }
+}
+
+/// Executable names of common shells
+const SHELLS: [&str; 10] = [
+    "ash", "bash", "csh", "dash", "fish", "ksh", "mksh", "sh", "tcsh", "zsh",
+];
+
+/// Build report findings
+fn findings(report: &Report<'_>) -> Vec<(Rule, String)> {
+    let mut findings: Vec<_> = report
+        .justifications
+        .iter()
+        .map(|j| match j {
+            OptionJustification::Rejected {
+                option,
+                value,
+                action,
+            } => (
+                Rule::OptionNotApplied,
+                format!("{option}={value} was not applied, it would break: {action}"),
+            ),
+            OptionJustification::Allowed {
+                option,
+                value,
+                action,
+            } => (
+                Rule::PermissionNeeded,
+                format!("{option} allows {value}, it is needed for: {action}"),
+            ),
+        })
+        .collect();
+    findings.extend(report.missing_mandatory.iter().map(|o| {
+        (
+            Rule::MandatoryOptionMissing,
+            format!("Mandatory option {o} could not be set"),
+        )
+    }));
+    findings.extend(
+        report
+            .warnings
+            .iter()
+            .map(|w| (Rule::HardeningWarning, w.to_owned())),
+    );
+    if report
+        .actions
+        .contains(&ProgramAction::WriteExecuteMemoryMapping)
+    {
+        findings.push((
+            Rule::RwxMemoryMapping,
+            "Writable and executable memory mapping was created".to_owned(),
+        ));
+    }
+    for action in report.actions {
+        if let ProgramAction::Exec(path) = action {
+            if path
+                .file_name()
+                .is_some_and(|n| SHELLS.iter().any(|s| n == *s))
+            {
+                let message = format!("Shell {} was run", path.display());
+                if !findings.contains(&(Rule::ShellExec, message.clone())) {
+                    findings.push((Rule::ShellExec, message));
+                }
+            }
+        }
+    }
+    findings
+}
+
+/// Render report as a SARIF log
+pub(super) fn render(report: &Report<'_>) -> serde_json::Value {
+    let rules: Vec<_> = Rule::iter()
+        .map(|r| {
+            json!({
+                "id": <&str>::from(r),
+                "shortDescription": { "text": r.description() },
+                "defaultConfiguration": { "level": r.level() },
+            })
+        })
+        .collect();
+    // All findings are about the options of the unit, written in its hardening fragment
+    let locations: Vec<_> = report
+        .fragment_path
+        .iter()
+        .map(|p| {
+            json!({
+                "physicalLocation": {
+                    "artifactLocation": { "uri": format!("file://{}", p.display()) },
+                },
+            })
+        })
+        .collect();
+    let results: Vec<_> = findings(report)
+        .into_iter()
+        .map(|(rule, message)| {
+            json!({
+                "ruleId": <&str>::from(rule),
+                "ruleIndex": Rule::iter().position(|r| r == rule),
+                "level": rule.level(),
+                "message": { "text": message },
+                "locations": locations,
+            })
+        })
+        .collect();
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                },
+            },
+            "results": results,
+        }],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cl::HardeningOptions,
+        systemd::{self, build_options, ExposureModel, KernelVersion, SystemdVersion},
+    };
+
+    #[test]
+    fn test_render() {
+        let sd_opts = build_options(
+            &SystemdVersion::new(254, 0),
+            &KernelVersion::new(6, 4, 0),
+            &HardeningOptions::safe(),
+        );
+        let actions = vec![
+            ProgramAction::Exec("/usr/bin/sh".into()),
+            ProgramAction::Exec("/usr/bin/sh".into()),
+            ProgramAction::Exec("/usr/bin/shh".into()),
+            ProgramAction::WriteExecuteMemoryMapping,
+        ];
+        let options = systemd::resolve(&sd_opts, &actions);
+        let exposure_model: ExposureModel =
+            toml::from_str("mandatory = [\"PrivateNetwork\"]").unwrap();
+        let sarif = render(&Report::new(
+            &sd_opts,
+            &actions,
+            &options,
+            &exposure_model,
+            vec![],
+            Some("/etc/systemd/system/foo.service.d/zz_shh-harden.conf".into()),
+        ));
+
+        let run = &sarif["runs"][0];
+        assert_eq!(
+            run["tool"]["driver"]["rules"].as_array().unwrap().len(),
+            Rule::iter().count()
+        );
+        assert_eq!(
+            run["results"][0]["locations"][0]["physicalLocation"]["artifactLocation"]["uri"],
+            "file:///etc/systemd/system/foo.service.d/zz_shh-harden.conf"
+        );
+        let results: Vec<_> = run["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| {
+                (
+                    r["ruleId"].as_str().unwrap(),
+                    r["message"]["text"].as_str().unwrap(),
+                )
+            })
+            .collect();
+        assert!(results.contains(&(
+            "option-not-applied",
+            "MemoryDenyWriteExecute=true was not applied, it would break: write and execute memory mapping"
+        )));
+        assert!(results.contains(&(
+            "mandatory-option-missing",
+            "Mandatory option PrivateNetwork could not be set"
+        )));
+        assert!(results.contains(&(
+            "rwx-memory-mapping",
+            "Writable and executable memory mapping was created"
+        )));
+        assert_eq!(
+            results
+                .iter()
+                .filter(|(rule, _)| *rule == "shell-exec")
+                .collect::<Vec<_>>(),
+            vec![&("shell-exec", "Shell /usr/bin/sh was run")]
+        );
+    }
+}
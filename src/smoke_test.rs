@@ -0,0 +1,196 @@
+//! Smoke test script exercising key profiled actions, to check a hardened service can still perform them
+
+use std::{collections::BTreeSet, fmt::Write as _, path::Path};
+
+use crate::{
+    standalone::shell_quote,
+    summarize::{
+        CountableSetSpecifier, NetworkActivity, NetworkActivityKind, ProgramAction, SetSpecifier,
+    },
+    systemd::{SocketFamily, SocketProtocol},
+};
+
+/// Paths below these directories are virtual or ephemeral, so they may not exist when the test runs
+const EPHEMERAL_DIRS: [&str; 7] = [
+    "/dev", "/proc", "/run", "/sys", "/tmp", "/var/tmp", "/memfd:",
+];
+
+/// Maximum number of checks of each kind, to keep the script small
+const MAX_CHECKS_PER_KIND: usize = 16;
+
+/// A check the script performs
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd)]
+enum Check<'a> {
+    Read(&'a Path),
+    Write(&'a Path),
+    /// Create a file in a directory
+    Create(&'a Path),
+    Bind {
+        ipv6: bool,
+        tcp: bool,
+        port: String,
+    },
+}
+
+impl Check<'_> {
+    fn command(&self) -> (String, String) {
+        match self {
+            Self::Read(path) => (
+                format!("read {}", path.display()),
+                format!("test -r {}", shell_quote(&path.to_string_lossy())),
+            ),
+            Self::Write(path) => (
+                format!("write {}", path.display()),
+                format!("test -w {}", shell_quote(&path.to_string_lossy())),
+            ),
+            Self::Create(dir) => (
+                format!("create in {}", dir.display()),
+                format!("test -w {}", shell_quote(&dir.to_string_lossy())),
+            ),
+            Self::Bind { ipv6, tcp, port } => {
+                let (af, host) = if *ipv6 {
+                    ("AF_INET6", "::")
+                } else {
+                    ("AF_INET", "0.0.0.0")
+                };
+                let (proto, sock_type) = if *tcp {
+                    ("TCP", "SOCK_STREAM")
+                } else {
+                    ("UDP", "SOCK_DGRAM")
+                };
+                (
+                    format!("bind {proto} port {port} ({af})"),
+                    format!("bind_port {af} {sock_type} {host} {port}"),
+                )
+            }
+        }
+    }
+}
+
+fn is_ephemeral(path: &Path) -> bool {
+    EPHEMERAL_DIRS.iter().any(|d| path.starts_with(d))
+}
+
+/// Select the checks for key profiled actions
+fn checks(actions: &[ProgramAction]) -> Vec<Check<'_>> {
+    let created_paths: BTreeSet<&Path> = actions
+        .iter()
+        .filter_map(|a| match a {
+            ProgramAction::Create(path) => Some(path.as_path()),
+            _ => None,
+        })
+        .collect();
+    let mut reads = BTreeSet::new();
+    let mut writes = BTreeSet::new();
+    let mut creates = BTreeSet::new();
+    let mut binds = BTreeSet::new();
+    for action in actions {
+        match action {
+            ProgramAction::Read(path)
+                if !is_ephemeral(path) && !created_paths.contains(path.as_path()) =>
+            {
+                reads.insert(Check::Read(path));
+            }
+            ProgramAction::Write(path)
+                if !is_ephemeral(path) && !created_paths.contains(path.as_path()) =>
+            {
+                writes.insert(Check::Write(path));
+            }
+            ProgramAction::Create(path) if !is_ephemeral(path) => {
+                if let Some(dir) = path.parent() {
+                    creates.insert(Check::Create(dir));
+                }
+            }
+            ProgramAction::NetworkActivity(NetworkActivity {
+                af: SetSpecifier::One(af @ (SocketFamily::Ipv4 | SocketFamily::Ipv6)),
+                proto: SetSpecifier::One(proto @ (SocketProtocol::Tcp | SocketProtocol::Udp)),
+                kind: SetSpecifier::One(NetworkActivityKind::Bind),
+                local_port: CountableSetSpecifier::One(port),
+                ..
+            }) => {
+                binds.insert(Check::Bind {
+                    ipv6: *af == SocketFamily::Ipv6,
+                    tcp: *proto == SocketProtocol::Tcp,
+                    port: port.to_string(),
+                });
+            }
+            _ => {}
+        }
+    }
+    [reads, writes, creates, binds]
+        .into_iter()
+        .flat_map(|c| c.into_iter().take(MAX_CHECKS_PER_KIND))
+        .collect()
+}
+
+/// Build a shell script checking the key profiled actions (path reads and writes, port binds) are still allowed.
+/// The script is meant to run in the hardened service, ie. with `ExecStartPre=`, and does not modify anything.
+pub(crate) fn script(actions: &[ProgramAction]) -> String {
+    let mut script = format!(
+        r#"#!/bin/sh
+# Smoke test generated by {} from profiled actions
+# Run it in the hardened service (ie. temporarily with 'ExecStartPre=/path/to/this/script'), to check it can still
+# perform them
+set -u
+failed=0
+check() {{
+    desc="$1"
+    shift
+    if ! "$@" 2>/dev/null; then
+        echo "FAILED: $desc" >&2
+        failed=1
+    fi
+}}
+bind_port() {{
+    # Skip if no interpreter is available to bind sockets
+    command -v python3 >/dev/null || return 0
+    python3 -c "import socket; socket.socket(socket.$1, socket.$2).bind(('$3', $4))"
+}}
+"#,
+        env!("CARGO_PKG_NAME")
+    );
+    for check in checks(actions) {
+        let (desc, cmd) = check.command();
+        let _ = writeln!(script, "check {} {cmd}", shell_quote(&desc));
+    }
+    script.push_str("exit \"$failed\"\n");
+    script
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroU16;
+
+    use super::*;
+
+    #[test]
+    fn test_checks() {
+        let actions = vec![
+            ProgramAction::Read("/etc/foo.conf".into()),
+            ProgramAction::Read("/proc/1/status".into()),
+            ProgramAction::Write("/var/lib/foo/db".into()),
+            ProgramAction::Create("/var/lib/foo/db.tmp".into()),
+            ProgramAction::Write("/var/lib/foo/db.tmp".into()),
+            ProgramAction::Write("/tmp/foo".into()),
+            ProgramAction::NetworkActivity(NetworkActivity {
+                af: SetSpecifier::One(SocketFamily::Ipv6),
+                proto: SetSpecifier::One(SocketProtocol::Tcp),
+                kind: SetSpecifier::One(NetworkActivityKind::Bind),
+                local_port: CountableSetSpecifier::One(NonZeroU16::new(8080).unwrap().into()),
+                remote_addr: None,
+            }),
+        ];
+        assert_eq!(
+            checks(&actions)
+                .iter()
+                .map(|c| c.command().1)
+                .collect::<Vec<_>>(),
+            vec![
+                "test -r /etc/foo.conf",
+                "test -w /var/lib/foo/db",
+                "test -w /var/lib/foo",
+                "bind_port AF_INET6 SOCK_STREAM :: 8080",
+            ]
+        );
+    }
+}
@@ -0,0 +1,356 @@
+//! Standalone sandboxing artifacts, for hosts without systemd
+
+use std::{collections::BTreeMap, fs::File, io::BufWriter, path::Path};
+
+use crate::systemd::{OptionValue, OptionWithValue, SYSCALL_CLASSES};
+
+const START_SANDBOX_OUTPUT_SNIPPET: &str = "-------- Start of suggested sandbox command --------";
+const END_SANDBOX_OUTPUT_SNIPPET: &str = "-------- End of suggested sandbox command --------";
+
+/// Directories hidden or made read-only by `ProtectHome`
+const HOME_DIRS: [&str; 3] = ["/home", "/root", "/run/user"];
+
+/// Errno values of the `SystemCallFilter` actions
+const EPERM: u32 = 1;
+const ENOSYS: u32 = 38;
+
+fn list_values(opt: &OptionWithValue) -> &[String] {
+    match &opt.value {
+        OptionValue::List { values, .. } => values,
+        OptionValue::Boolean(_) | OptionValue::String(_) => &[],
+    }
+}
+
+fn string_value(opt: &OptionWithValue) -> Option<&str> {
+    match &opt.value {
+        OptionValue::Boolean(true) => Some("true"),
+        OptionValue::String(v) => Some(v),
+        OptionValue::Boolean(false) | OptionValue::List { .. } => None,
+    }
+}
+
+/// Build bubblewrap arguments with restrictions equivalent to the options that have one
+/// Options are applied in order, so later mounts override previous ones
+fn bwrap_args(opts: &[OptionWithValue]) -> Vec<String> {
+    let mut args: Vec<String> = vec!["--bind".into(), "/".into(), "/".into()];
+    let mut push = |new_args: &[&str]| args.extend(new_args.iter().map(|&a| a.to_owned()));
+    let mut home_binds = Vec::new();
+    let mut write_paths = Vec::new();
+    for opt in opts {
+        match (opt.name.as_str(), string_value(opt)) {
+            ("ProtectSystem", Some("strict")) => push(&["--ro-bind", "/", "/"]),
+            ("ProtectSystem", Some(v)) => {
+                let dirs: &[&str] = if v == "full" {
+                    &["/usr", "/boot", "/efi", "/etc"]
+                } else {
+                    &["/usr", "/boot", "/efi"]
+                };
+                for dir in dirs {
+                    push(&["--ro-bind-try", dir, dir]);
+                }
+            }
+            ("ProtectHome", Some("read-only")) => {
+                for dir in HOME_DIRS {
+                    push(&["--ro-bind-try", dir, dir]);
+                }
+            }
+            ("ProtectHome", Some(_)) => {
+                for dir in HOME_DIRS {
+                    push(&["--tmpfs", dir]);
+                }
+            }
+            ("BindReadOnlyPaths", _) => home_binds.extend(list_values(opt)),
+            ("ReadWritePaths", _) => write_paths.extend(list_values(opt)),
+            ("PrivateTmp", Some(_)) => push(&["--tmpfs", "/tmp", "--tmpfs", "/var/tmp"]),
+            ("PrivateDevices", Some(_)) => push(&["--dev", "/dev"]),
+            ("PrivateNetwork", Some(_)) => push(&["--unshare-net"]),
+            ("ProtectKernelTunables", Some(_)) => {
+                push(&[
+                    "--ro-bind",
+                    "/proc/sys",
+                    "/proc/sys",
+                    "--ro-bind",
+                    "/sys",
+                    "/sys",
+                ]);
+            }
+            ("ProtectControlGroups", Some(_)) => {
+                push(&["--ro-bind-try", "/sys/fs/cgroup", "/sys/fs/cgroup"]);
+            }
+            ("CapabilityBoundingSet", _) => {
+                if let OptionValue::List {
+                    values,
+                    negation_prefix,
+                    ..
+                } = &opt.value
+                {
+                    let cap_arg = if *negation_prefix {
+                        "--cap-drop"
+                    } else {
+                        push(&["--cap-drop", "ALL"]);
+                        "--cap-add"
+                    };
+                    for cap in values {
+                        push(&[cap_arg, cap]);
+                    }
+                }
+            }
+            // Translated to a seccomp profile instead
+            ("SystemCallFilter", _) => {}
+            _ => log::info!("Option {} has no equivalent in standalone mode", opt.name),
+        }
+    }
+    // Paths exposed again must come after the mounts hiding them
+    for bind in home_binds {
+        let bind = bind.trim_start_matches('-');
+        let (src, dst) = bind.split_once(':').unwrap_or((bind, bind));
+        push(&["--ro-bind-try", src, dst]);
+    }
+    // Writable paths must come after the read only mounts
+    for path in write_paths {
+        let path = path.trim_start_matches(['-', '+']);
+        push(&["--bind-try", path, path]);
+    }
+    args
+}
+
+/// Quote a shell argument if needed
+pub(crate) fn shell_quote(arg: &str) -> String {
+    if !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=@%+,".contains(c))
+    {
+        arg.to_owned()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+/// Build OCI seccomp profile (as used by container runtimes) from the `SystemCallFilter` option, if any
+fn seccomp_profile(opts: &[OptionWithValue]) -> Option<serde_json::Value> {
+    // Syscalls in a non negated filter are allowed again, as exceptions to the denied classes
+    let (deny_filters, allow_filters): (Vec<_>, Vec<_>) = opts
+        .iter()
+        .filter(|o| o.name == "SystemCallFilter")
+        .partition(|o| {
+            matches!(
+                o.value,
+                OptionValue::List {
+                    negation_prefix: true,
+                    ..
+                }
+            )
+        });
+    let filter = deny_filters.first()?;
+    let allowed: Vec<&str> = allow_filters
+        .into_iter()
+        .flat_map(list_values)
+        .map(String::as_str)
+        .collect();
+    // Group denied syscalls by errno, none to kill the process
+    let mut denied: BTreeMap<Option<u32>, Vec<&str>> = BTreeMap::new();
+    for value in list_values(filter) {
+        let (class, errno) = match value.split_once(':') {
+            Some((class, "ENOSYS")) => (class, Some(ENOSYS)),
+            Some((class, "kill")) => (class, None),
+            // EPERM, and errno names without a constant here
+            Some((class, _)) => (class, Some(EPERM)),
+            None => (value.as_str(), None),
+        };
+        let syscalls = denied.entry(errno).or_default();
+        let Some(class) = class.strip_prefix('@') else {
+            // Single syscall
+            syscalls.push(class);
+            continue;
+        };
+        let mut classes = vec![class];
+        while let Some(cur_class) = classes.pop() {
+            for &entry in SYSCALL_CLASSES.get(cur_class).into_iter().flatten() {
+                if let Some(subclass) = entry.strip_prefix('@') {
+                    classes.push(subclass);
+                } else {
+                    syscalls.push(entry);
+                }
+            }
+        }
+    }
+    let rules: Vec<_> = denied
+        .into_iter()
+        .map(|(errno, mut syscalls)| {
+            syscalls.retain(|s| !allowed.contains(s));
+            syscalls.sort_unstable();
+            syscalls.dedup();
+            match errno {
+                Some(errno) => serde_json::json!({
+                    "names": syscalls,
+                    "action": "SCMP_ACT_ERRNO",
+                    "errnoRet": errno,
+                }),
+                None => serde_json::json!({
+                    "names": syscalls,
+                    "action": "SCMP_ACT_KILL_PROCESS",
+                }),
+            }
+        })
+        .collect();
+    Some(serde_json::json!({
+        "defaultAction": "SCMP_ACT_ALLOW",
+        "syscalls": rules,
+    }))
+}
+
+/// Report sandboxing artifacts built from resolved options, instead of the options themselves
+pub(crate) fn report(
+    command: &[String],
+    opts: &[OptionWithValue],
+    seccomp_profile_path: Option<&Path>,
+) -> anyhow::Result<()> {
+    if let Some(path) = seccomp_profile_path {
+        if let Some(profile) = seccomp_profile(opts) {
+            let file = BufWriter::new(File::create(path)?);
+            serde_json::to_writer_pretty(file, &profile)?;
+            log::info!("Seccomp profile written to {path:?}");
+        } else {
+            log::warn!("No syscall can be denied, seccomp profile not written");
+        }
+    }
+
+    let cmdline = std::iter::once("bwrap".to_owned())
+        .chain(bwrap_args(opts))
+        .chain(std::iter::once("--".to_owned()))
+        .chain(command.iter().cloned())
+        .map(|a| shell_quote(&a))
+        .collect::<Vec<_>>()
+        .join(" ");
+    println!("{START_SANDBOX_OUTPUT_SNIPPET}");
+    println!("{cmdline}");
+    println!("{END_SANDBOX_OUTPUT_SNIPPET}");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::systemd::ListMode;
+
+    fn opts(opts: &[&str]) -> Vec<OptionWithValue> {
+        opts.iter().map(|o| o.parse().unwrap()).collect()
+    }
+
+    fn list(name: &str, values: &[&str], negation_prefix: bool) -> OptionWithValue {
+        OptionWithValue {
+            name: name.to_owned(),
+            value: OptionValue::List {
+                values: values.iter().map(|v| (*v).to_owned()).collect(),
+                value_if_empty: None,
+                negation_prefix,
+                repeat_option: false,
+                mode: ListMode::BlackList,
+            },
+        }
+    }
+
+    #[test]
+    fn test_bwrap_args() {
+        assert_eq!(
+            bwrap_args(&opts(&[
+                "ProtectSystem=strict",
+                "PrivateTmp=true",
+                "PrivateNetwork=true",
+                "LockPersonality=true",
+            ])),
+            vec![
+                "--bind",
+                "/",
+                "/",
+                "--ro-bind",
+                "/",
+                "/",
+                "--tmpfs",
+                "/tmp",
+                "--tmpfs",
+                "/var/tmp",
+                "--unshare-net"
+            ]
+        );
+
+        // Observed writes are still allowed
+        let mut write_opts = opts(&["ProtectSystem=strict", "ProtectKernelTunables=true"]);
+        write_opts.push(list(
+            "ReadWritePaths",
+            &[
+                "-/var/lib/foo",
+                "/sys/kernel/mm/transparent_hugepage/enabled",
+            ],
+            false,
+        ));
+        assert_eq!(
+            bwrap_args(&write_opts),
+            vec![
+                "--bind",
+                "/",
+                "/",
+                "--ro-bind",
+                "/",
+                "/",
+                "--ro-bind",
+                "/proc/sys",
+                "/proc/sys",
+                "--ro-bind",
+                "/sys",
+                "/sys",
+                "--bind-try",
+                "/var/lib/foo",
+                "/var/lib/foo",
+                "--bind-try",
+                "/sys/kernel/mm/transparent_hugepage/enabled",
+                "/sys/kernel/mm/transparent_hugepage/enabled",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_seccomp_profile() {
+        let filter = |values: &[&str], negation_prefix: bool| {
+            list("SystemCallFilter", values, negation_prefix)
+        };
+        assert!(seccomp_profile(&opts(&["LockPersonality=true"])).is_none());
+        assert_eq!(
+            seccomp_profile(&[
+                filter(
+                    &["@reboot:kill", "mount:EPERM", "swapon:ENOSYS", "swapoff"],
+                    true
+                ),
+                filter(&["reboot"], false),
+            ])
+            .unwrap(),
+            serde_json::json!({
+                "defaultAction": "SCMP_ACT_ALLOW",
+                "syscalls": [
+                    {
+                        "names": ["kexec_file_load", "kexec_load", "swapoff"],
+                        "action": "SCMP_ACT_KILL_PROCESS",
+                    },
+                    {
+                        "names": ["mount"],
+                        "action": "SCMP_ACT_ERRNO",
+                        "errnoRet": EPERM,
+                    },
+                    {
+                        "names": ["swapon"],
+                        "action": "SCMP_ACT_ERRNO",
+                        "errnoRet": ENOSYS,
+                    },
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("/usr/bin/ls"), "/usr/bin/ls");
+        assert_eq!(shell_quote("a b"), "'a b'");
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+    }
+}
@@ -0,0 +1,244 @@
+//! Profile data statistics, to check profiling coverage before generating options
+
+use std::{
+    cmp::Reverse,
+    collections::BTreeMap,
+    io::{self, Write},
+    path::PathBuf,
+    time::Duration,
+};
+
+use crate::{
+    path_tree::{PathAccess, PathTree},
+    profile::ProfileData,
+    summarize::ProgramAction,
+    systemd::{group_coverage, DenySyscalls, SYSCALL_CLASSES},
+};
+
+/// Depth of the directories paths are grouped by
+const DIR_DEPTH: usize = 2;
+
+/// Short name of an action kind
+fn action_kind(action: &ProgramAction) -> &'static str {
+    match action {
+        ProgramAction::Read(_) => "read",
+        ProgramAction::Write(_) => "write",
+        ProgramAction::Create(_) => "create",
+        ProgramAction::NetworkActivity(_) => "network",
+        ProgramAction::AbstractSocket(_) => "abstract socket",
+        ProgramAction::WriteExecuteMemoryMapping => "write and execute memory mapping",
+        ProgramAction::SetRealtimeScheduler => "set real time scheduler",
+        ProgramAction::SetSchedulingPolicy(_) => "set scheduling policy",
+        ProgramAction::SetNice(_) => "set nice value",
+        ProgramAction::SetCredentials { .. } => "change credentials",
+        ProgramAction::Wakeup => "inhibit suspend",
+        ProgramAction::MknodSpecial => "create special file",
+        ProgramAction::SetAlarm => "set privileged timer alarm",
+        ProgramAction::Exec(_) => "exec",
+        ProgramAction::SetuidExec(_) => "setuid exec",
+        ProgramAction::Syscalls(_) => "syscalls",
+        ProgramAction::WorkingDirectory(_) => "working directory",
+        ProgramAction::OpenFiles(_) => "open files peak",
+        ProgramAction::Tasks(_) => "tasks peak",
+        ProgramAction::Capability(_) | ProgramAction::CapabilityUse(_) => "capability",
+        ProgramAction::TempDirectory(_) => "temporary directory",
+        ProgramAction::ChangeOwner { .. } => "change owner",
+        ProgramAction::DeviceIoctl { .. } => "device ioctl",
+        ProgramAction::NamespaceUse(_) => "namespace",
+        ProgramAction::KeyringUse(_) => "keyring",
+        ProgramAction::IoUring => "io_uring",
+    }
+}
+
+/// Count actions by kind
+fn action_counts(actions: &[ProgramAction]) -> BTreeMap<&'static str, usize> {
+    let mut counts = BTreeMap::new();
+    for action in actions {
+        *counts.entry(action_kind(action)).or_default() += 1;
+    }
+    counts
+}
+
+/// Directories with the most accessed paths, with access counts by kind
+fn top_dirs(paths: &PathTree, top: usize) -> Vec<(PathBuf, &BTreeMap<PathAccess, usize>)> {
+    let mut dirs = paths.dirs(DIR_DEPTH);
+    // Stable sort keeps directories with the same count in path order
+    dirs.sort_by_key(|(_, node)| Reverse(node.total()));
+    dirs.into_iter()
+        .take(top)
+        .map(|(dir, node)| (dir, &node.counts))
+        .collect()
+}
+
+/// Number of syscalls used by the program in each syscall group, with group size
+fn syscall_groups(actions: &[ProgramAction]) -> Vec<(&'static str, usize, usize)> {
+    let Some(syscalls) = actions.iter().find_map(|a| match a {
+        ProgramAction::Syscalls(s) => Some(s),
+        _ => None,
+    }) else {
+        return vec![];
+    };
+    let mut classes: Vec<_> = SYSCALL_CLASSES.keys().copied().collect();
+    classes.sort_unstable();
+    classes
+        .into_iter()
+        .filter_map(|class| {
+            let members = DenySyscalls::Class(class).syscalls();
+            let used = members.iter().filter(|s| syscalls.contains(**s)).count();
+            (used > 0).then_some((class, used, members.len()))
+        })
+        .collect()
+}
+
+/// Format a duration with a coarse human readable form
+pub(crate) fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    format!("{}h{:02}m{:02}s", secs / 3600, (secs / 60) % 60, secs % 60)
+}
+
+/// Write human readable statistics of profile data
+pub(crate) fn write<W: Write>(data: &ProfileData, top: usize, out: &mut W) -> io::Result<()> {
+    let actions = &data.actions;
+    writeln!(
+        out,
+        "Command: {}\nHost: {}\nProfiled by: shh {}",
+        data.metadata.command.join(" "),
+        data.metadata.hostname,
+        data.metadata.shh_version
+    )?;
+    writeln!(
+        out,
+        "Profiling duration: {}\nSyscalls: {} total, {} distinct",
+        format_duration(data.duration()),
+        data.syscall_count,
+        data.distinct_syscall_count()
+    )?;
+
+    writeln!(out, "\nActions ({} total):", actions.len())?;
+    for (kind, count) in action_counts(actions) {
+        writeln!(out, "  {kind}: {count}")?;
+    }
+
+    writeln!(out, "\nTop accessed directories:")?;
+    for (dir, counts) in top_dirs(&PathTree::new(actions), top) {
+        writeln!(
+            out,
+            "  {} ({})",
+            dir.display(),
+            counts
+                .iter()
+                .map(|(kind, count)| format!("{kind} {count}"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )?;
+    }
+
+    writeln!(out, "\nSyscalls used by group:")?;
+    for (class, used, total) in syscall_groups(actions) {
+        writeln!(out, "  @{class}: {used}/{total}")?;
+    }
+
+    writeln!(out, "\nNetwork activity:")?;
+    for action in actions.iter().filter(|a| {
+        matches!(
+            a,
+            ProgramAction::NetworkActivity(_) | ProgramAction::AbstractSocket(_)
+        )
+    }) {
+        writeln!(out, "  {action}")?;
+    }
+
+    writeln!(out, "\nPeak usage:")?;
+    for action in actions
+        .iter()
+        .filter(|a| matches!(a, ProgramAction::OpenFiles(_) | ProgramAction::Tasks(_)))
+    {
+        writeln!(out, "  {action}")?;
+    }
+    Ok(())
+}
+
+/// Write the coverage of each syscall group by the syscalls used by the program, and whether `SystemCallFilter=`
+/// denies it, to spot partially used groups
+pub(crate) fn write_syscall_groups<W: Write>(data: &ProfileData, out: &mut W) -> io::Result<()> {
+    writeln!(out, "Syscall group coverage:")?;
+    for group in group_coverage(&data.actions) {
+        write!(
+            out,
+            "  @{}: {}/{} ({}%), ",
+            group.name,
+            group.used.len(),
+            group.size,
+            group.used.len() * 100 / group.size
+        )?;
+        let used = group.used.into_iter().collect::<Vec<_>>().join(" ");
+        if !group.denied {
+            writeln!(out, "kept, used: {used}")?;
+        } else if used.is_empty() {
+            writeln!(out, "denied")?;
+        } else {
+            writeln!(out, "denied, except: {used}")?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_top_dirs() {
+        let actions = vec![
+            ProgramAction::Read("/usr/lib/libc.so".into()),
+            ProgramAction::Read("/usr/lib/libm.so".into()),
+            ProgramAction::Exec("/usr/bin/sh".into()),
+            ProgramAction::Write("/var/lib/app/db".into()),
+            ProgramAction::Create("/var/lib/app/db-journal".into()),
+            ProgramAction::Read("/etc".into()),
+            ProgramAction::Tasks(1),
+        ];
+        assert_eq!(
+            action_counts(&actions),
+            BTreeMap::from([
+                ("create", 1),
+                ("exec", 1),
+                ("read", 3),
+                ("tasks peak", 1),
+                ("write", 1)
+            ])
+        );
+        assert_eq!(
+            top_dirs(&PathTree::new(&actions), 2),
+            vec![
+                (
+                    PathBuf::from("/usr/lib"),
+                    &BTreeMap::from([(PathAccess::Read, 2)])
+                ),
+                (
+                    PathBuf::from("/var/lib"),
+                    &BTreeMap::from([(PathAccess::Write, 1), (PathAccess::Create, 1)])
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_write_totals() {
+        let start_time = std::time::SystemTime::UNIX_EPOCH;
+        let data = ProfileData {
+            metadata: crate::profile::ProfileMetadata::local(&["true".to_owned()]).unwrap(),
+            actions: vec![ProgramAction::Syscalls(
+                ["read".to_owned(), "write".to_owned()].into(),
+            )],
+            start_time,
+            end_time: start_time + Duration::from_secs(3725),
+            syscall_count: 1234,
+            timeline: vec![],
+        };
+        let mut out = Vec::new();
+        write(&data, 1, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("\nProfiling duration: 1h02m05s\nSyscalls: 1234 total, 2 distinct\n"));
+    }
+}
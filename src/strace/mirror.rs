@@ -0,0 +1,267 @@
+//! Strace log mirror, with size based rotation
+
+use std::{
+    ffi::OsString,
+    fs::{self, File},
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+
+use super::parser::redact;
+use crate::cl::StraceLogOptions;
+
+/// Extension of compressed rotated log files
+const COMPRESSED_EXTENSION: &str = "gz";
+
+/// Path of the nth rotated file of a log
+fn rotated_path(path: &Path, n: usize, compress: bool) -> PathBuf {
+    let mut path: OsString = path.to_owned().into();
+    path.push(format!(".{n}"));
+    if compress {
+        path.push(".");
+        path.push(COMPRESSED_EXTENSION);
+    }
+    path.into()
+}
+
+/// A strace log file, or one of its rotated files
+pub(crate) struct LogSegment {
+    pub path: PathBuf,
+    /// Log lines reader, decompressing them if needed
+    pub reader: Box<dyn BufRead>,
+}
+
+/// Get strace log and its rotated files, oldest first, to read them one at a time instead of loading them all at once
+pub(crate) fn log_segments(path: &Path) -> impl Iterator<Item = io::Result<LogSegment>> {
+    let mut paths = vec![(path.to_owned(), false)];
+    for n in 1.. {
+        let Some(rotated) = [false, true]
+            .into_iter()
+            .map(|compress| (rotated_path(path, n, compress), compress))
+            .find(|(p, _)| p.is_file())
+        else {
+            break;
+        };
+        paths.push(rotated);
+    }
+    paths.into_iter().rev().map(|(segment_path, compressed)| {
+        let file = File::open(&segment_path)?;
+        let reader: Box<dyn BufRead> = if compressed {
+            Box::new(BufReader::new(GzDecoder::new(file)))
+        } else {
+            Box::new(BufReader::new(file))
+        };
+        Ok(LogSegment {
+            path: segment_path,
+            reader,
+        })
+    })
+}
+
+/// Writes strace output lines to a file, rotating it if it grows too large
+pub(crate) struct LogMirror {
+    path: PathBuf,
+    file: BufWriter<File>,
+    /// Current file size
+    size: u64,
+    max_size: Option<u64>,
+    keep: usize,
+    compress: bool,
+    redact: bool,
+    /// Whether we already warned about secrets in the log
+    secrets_warned: bool,
+}
+
+impl LogMirror {
+    pub(crate) fn new(opts: &StraceLogOptions) -> anyhow::Result<Option<Self>> {
+        let Some(path) = opts.path.as_ref() else {
+            return Ok(None);
+        };
+        let file = Self::open(path)?;
+        Ok(Some(Self {
+            path: path.to_owned(),
+            size: file.get_ref().metadata()?.len(),
+            file,
+            max_size: opts.max_size_mb.map(|s| s * 1024 * 1024),
+            keep: opts.keep,
+            compress: opts.compress,
+            redact: opts.redact,
+            secrets_warned: false,
+        }))
+    }
+
+    fn open(path: &Path) -> io::Result<BufWriter<File>> {
+        let file = File::options().create(true).append(true).open(path)?;
+        Ok(BufWriter::with_capacity(64 * 1024, file))
+    }
+
+    pub(crate) fn write_line(&mut self, line: &str) -> io::Result<()> {
+        let redacted_line = redact::redact(line).map(|(kinds, redacted_line)| {
+            if !self.secrets_warned {
+                let kinds = kinds
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                if self.redact {
+                    log::warn!("Redacting secrets ({kinds}) from strace log {:?}", self.path);
+                } else {
+                    log::warn!(
+                        "Strace log {:?} contains secrets ({kinds}), review it before sharing it, or use --strace-log-redact",
+                        self.path
+                    );
+                }
+                self.secrets_warned = true;
+            }
+            redacted_line
+        });
+        let line = match &redacted_line {
+            Some(redacted) if self.redact => redacted,
+            _ => line,
+        };
+        let line_size = line.len() as u64 + 1;
+        if self
+            .max_size
+            .is_some_and(|max_size| (self.size > 0) && (self.size + line_size > max_size))
+        {
+            self.rotate()?;
+        }
+        writeln!(self.file, "{line}")?;
+        self.size += line_size;
+        Ok(())
+    }
+
+    /// Path of the nth rotated file
+    fn rotated_path(&self, n: usize) -> PathBuf {
+        rotated_path(&self.path, n, self.compress)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+
+        // Shift previous rotated files, dropping the oldest one
+        if self.keep == 0 {
+            fs::remove_file(&self.path)?;
+        } else {
+            let _ = fs::remove_file(self.rotated_path(self.keep));
+            for n in (1..self.keep).rev() {
+                let src = self.rotated_path(n);
+                if src.is_file() {
+                    fs::rename(src, self.rotated_path(n + 1))?;
+                }
+            }
+            let dst = self.rotated_path(1);
+            if self.compress {
+                let mut encoder = GzEncoder::new(File::create(&dst)?, Compression::default());
+                io::copy(&mut File::open(&self.path)?, &mut encoder)?;
+                encoder.finish()?;
+                fs::remove_file(&self.path)?;
+            } else {
+                fs::rename(&self.path, dst)?;
+            }
+        }
+        log::debug!("Rotated strace log {:?}", self.path);
+
+        self.file = Self::open(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl Drop for LogMirror {
+    fn drop(&mut self) {
+        let _ = self.file.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read as _;
+
+    use flate2::read::GzDecoder;
+
+    use super::*;
+
+    #[test]
+    fn test_rotate() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("strace.log");
+        let mut opts = StraceLogOptions {
+            path: Some(path.clone()),
+            max_size_mb: Some(1),
+            keep: 2,
+            compress: false,
+            redact: false,
+        };
+        let line = "a".repeat(1024 * 1024 - 1);
+
+        let mut mirror = LogMirror::new(&opts).unwrap().unwrap();
+        for _ in 0..4 {
+            mirror.write_line(&line).unwrap();
+        }
+        drop(mirror);
+        assert_eq!(fs::metadata(&path).unwrap().len(), 1024 * 1024);
+        assert!(temp_dir.path().join("strace.log.1").is_file());
+        assert!(temp_dir.path().join("strace.log.2").is_file());
+        assert!(!temp_dir.path().join("strace.log.3").exists());
+
+        opts.compress = true;
+        let mut gz_mirror = LogMirror::new(&opts).unwrap().unwrap();
+        gz_mirror.write_line("b").unwrap();
+        drop(gz_mirror);
+        let mut decompressed = String::new();
+        GzDecoder::new(File::open(temp_dir.path().join("strace.log.1.gz")).unwrap())
+            .read_to_string(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, format!("{line}\n"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "b\n");
+    }
+
+    #[test]
+    fn test_log_segments() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("strace.log");
+        let opts = StraceLogOptions {
+            path: Some(path.clone()),
+            max_size_mb: Some(1),
+            keep: 3,
+            compress: true,
+            redact: false,
+        };
+        let lines: Vec<_> = ['a', 'b', 'c']
+            .into_iter()
+            .map(|c| c.to_string().repeat(1024 * 1024 - 1))
+            .collect();
+
+        let mut mirror = LogMirror::new(&opts).unwrap().unwrap();
+        for line in &lines {
+            mirror.write_line(line).unwrap();
+        }
+        drop(mirror);
+
+        let segments: Vec<_> = log_segments(&path)
+            .map(|s| {
+                let mut segment = s.unwrap();
+                let mut content = String::new();
+                segment.reader.read_to_string(&mut content).unwrap();
+                (segment.path, content)
+            })
+            .collect();
+        assert_eq!(
+            segments,
+            vec![
+                (
+                    temp_dir.path().join("strace.log.2.gz"),
+                    format!("{}\n", lines[0])
+                ),
+                (
+                    temp_dir.path().join("strace.log.1.gz"),
+                    format!("{}\n", lines[1])
+                ),
+                (path, format!("{}\n", lines[2])),
+            ]
+        );
+    }
+}
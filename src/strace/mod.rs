@@ -1,10 +1,22 @@
 //! Strace related code
 
-use std::{collections::HashMap, fmt, io::BufRead, process::Command, str};
-
+use std::{
+    collections::HashMap,
+    fmt,
+    hash::{Hash, Hasher},
+    path::Path,
+    process::Command,
+    str::{self, FromStr},
+};
+
+use anyhow::Context as _;
+
+mod mirror;
 mod parser;
 mod run;
 
+pub(crate) use mirror::log_segments;
+pub(crate) use parser::check_log;
 pub(crate) use run::Strace;
 
 #[derive(Debug, Clone, PartialEq)]
@@ -16,19 +28,19 @@ pub(crate) struct Syscall {
     pub ret_val: SyscallRetVal,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Hash)]
 pub(crate) enum BufferType {
     AbstractPath,
     Unknown,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Hash)]
 pub(crate) struct IntegerExpression {
     pub value: IntegerExpressionValue,
     pub metadata: Option<Vec<u8>>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Hash)]
 pub(crate) struct BufferExpression {
     pub value: Vec<u8>,
     pub type_: BufferType,
@@ -54,6 +66,31 @@ pub(crate) enum Expression {
     DestinationAddress(String),
 }
 
+impl Hash for Expression {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Self::Buffer(b) => b.hash(state),
+            Self::Integer(i) => i.hash(state),
+            Self::Struct(members) => {
+                // Map iteration order is not stable
+                let mut members: Vec<_> = members.iter().collect();
+                members.sort_unstable_by_key(|(k, _)| *k);
+                members.hash(state);
+            }
+            Self::Collection { complement, values } => {
+                complement.hash(state);
+                values.hash(state);
+            }
+            Self::Macro { name, args } => {
+                name.hash(state);
+                args.hash(state);
+            }
+            Self::DestinationAddress(a) => a.hash(state),
+        }
+    }
+}
+
 impl Expression {
     pub(crate) fn metadata(&self) -> Option<&[u8]> {
         match self {
@@ -63,7 +100,7 @@ impl Expression {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Hash)]
 pub(crate) enum IntegerExpressionValue {
     BinaryOr(Vec<IntegerExpressionValue>),
     Multiplication(Vec<IntegerExpressionValue>),
@@ -97,7 +134,8 @@ impl IntegerExpressionValue {
 
 pub(crate) type SyscallRetVal = i128; // allows holding both signed and unsigned 64 bit integers
 
-#[derive(Ord, PartialOrd, Eq, PartialEq)]
+#[derive(Debug, Clone, Ord, PartialOrd, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(try_from = "String", into = "String")]
 pub(crate) struct StraceVersion {
     pub major: u16,
     pub minor: u16,
@@ -108,22 +146,36 @@ impl StraceVersion {
         Self { major, minor }
     }
 
-    pub(crate) fn local_system() -> anyhow::Result<Self> {
-        let output = Command::new("strace").arg("--version").output()?;
+    pub(crate) fn local_system(strace_bin: &Path) -> anyhow::Result<Self> {
+        let output = Command::new(strace_bin)
+            .arg("--version")
+            .output()
+            .with_context(|| format!("Failed to run {}", strace_bin.display()))?;
         if !output.status.success() {
             anyhow::bail!("strace invocation failed with code {:?}", output.status);
         }
-        let version_line = output
-            .stdout
+        Self::from_version_output(str::from_utf8(&output.stdout)?)
+    }
+
+    /// Parse `strace --version` output, ie. `strace -- version 6.8` followed by copyright and features
+    fn from_version_output(output: &str) -> anyhow::Result<Self> {
+        output
             .lines()
             .next()
-            .ok_or_else(|| anyhow::anyhow!("Unable to get strace version"))??;
-        let (major, minor) = version_line
-            .rsplit_once(' ')
+            .and_then(|l| l.rsplit_once(' '))
             .ok_or_else(|| anyhow::anyhow!("Unable to get strace version"))?
             .1
+            .parse()
+    }
+}
+
+impl FromStr for StraceVersion {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (major, minor) = s
             .split_once('.')
-            .ok_or_else(|| anyhow::anyhow!("Unable to get strace version"))?;
+            .ok_or_else(|| anyhow::anyhow!("Unable to parse strace version {s:?}"))?;
         Ok(Self {
             major: major.parse()?,
             minor: minor.parse()?,
@@ -136,3 +188,56 @@ impl fmt::Display for StraceVersion {
         write!(f, "{}.{}", self.major, self.minor)
     }
 }
+
+impl TryFrom<String> for StraceVersion {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<StraceVersion> for String {
+    fn from(v: StraceVersion) -> Self {
+        v.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_version_output() {
+        assert_eq!(
+            StraceVersion::from_version_output(
+                "strace -- version 6.8
+Copyright (c) 1991-2024 The strace developers <https://strace.io>.
+This is free software; see the source for copying conditions.  There is NO
+warranty; not even for MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+
+Optional features enabled: stack-trace=libunwind stack-demangle m32-mpers mx32-mpers
+"
+            )
+            .unwrap(),
+            StraceVersion::new(6, 8)
+        );
+
+        // Released before long options, like --seccomp-bpf, were added
+        let old = StraceVersion::from_version_output(
+            "strace -- version 5.2
+Copyright (c) 1991-2019 The strace developers <https://strace.io>.
+This is free software; see the source for copying conditions.  There is NO
+warranty; not even for MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+
+Optional features enabled: (none)
+",
+        )
+        .unwrap();
+        assert_eq!(old, StraceVersion::new(5, 2));
+        assert!(old < StraceVersion::new(6, 4));
+
+        assert!(StraceVersion::from_version_output("").is_err());
+        assert!(StraceVersion::from_version_output("strace: invalid option -- 'V'").is_err());
+    }
+}
@@ -31,7 +31,9 @@ macro_rules! dbg_parser {
 pub(crate) fn parse_line(line: &str) -> anyhow::Result<ParseResult> {
     match parse_syscall_line(line).map(|s| s.1) {
         Err(nom::Err::Incomplete(_) | nom::Err::Error(_)) => Ok(ParseResult::IgnoredLine),
-        Err(nom::Err::Failure(e)) => Err(anyhow::anyhow!("{e}")),
+        Err(nom::Err::Failure(e)) => {
+            Err(anyhow::anyhow!("{e}").context(crate::error::ErrorKind::Parsing))
+        }
         Ok(res) => Ok(res),
     }
 }
@@ -523,5 +525,16 @@ fn parse_buffer_byte(i: &str) -> IResult<&str, u8> {
 #[function_name::named]
 fn parse_struct_member(i: &str) -> IResult<&str, (&str, Expression)> {
     dbg_parser!(i);
-    separated_pair(parse_symbol, char('='), parse_expression)(i)
+    separated_pair(
+        parse_symbol,
+        char('='),
+        // In/out members, ie. `msg_namelen=128 => 16`, keep the input value like arguments
+        map(
+            pair(
+                parse_expression,
+                opt(preceded(tag(" => "), parse_expression)),
+            ),
+            |(ie, _oe)| ie,
+        ),
+    )(i)
 }
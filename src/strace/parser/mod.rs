@@ -1,39 +1,33 @@
 //! Strace output parser
 
 use std::{
-    fs::File,
-    io::{self, BufRead, BufWriter, Write},
-    path::Path,
+    fmt,
+    io::{self, BufRead},
 };
 
-use crate::strace::Syscall;
+use crate::strace::{mirror::LogMirror, Syscall};
 
 mod combinator;
+pub(super) mod redact;
 use combinator::parse_line;
 
 use super::{Expression, SyscallRetVal};
 
 pub(crate) struct LogParser {
     reader: Box<dyn BufRead>,
-    log: Option<BufWriter<File>>,
+    log: Option<LogMirror>,
     buf: String,
     unfinished_syscalls: Vec<SyscallStart>,
 }
 
 impl LogParser {
-    pub(crate) fn new(reader: Box<dyn BufRead>, log_path: Option<&Path>) -> anyhow::Result<Self> {
-        let log = log_path
-            .map(|p| -> io::Result<_> {
-                let file = File::options().create(true).append(true).open(p)?;
-                Ok(BufWriter::with_capacity(64 * 1024, file))
-            })
-            .transpose()?;
-        Ok(Self {
+    pub(crate) fn new(reader: Box<dyn BufRead>, log: Option<LogMirror>) -> Self {
+        Self {
             reader,
             log,
             buf: String::new(),
             unfinished_syscalls: Vec::new(),
-        })
+        }
     }
 }
 
@@ -103,7 +97,7 @@ impl Iterator for LogParser {
             }
 
             if let Some(log) = self.log.as_mut() {
-                if let Err(e) = writeln!(log, "{line}") {
+                if let Err(e) = log.write_line(line) {
                     return Some(Err(e.into()));
                 }
             }
@@ -115,7 +109,6 @@ impl Iterator for LogParser {
                 }
                 Ok(ParseResult::SyscallStart(sc)) => {
                     self.unfinished_syscalls.push(sc);
-                    continue;
                 }
                 Ok(ParseResult::SyscallEnd(sc_end)) => {
                     let Some(unfinished_index) = self
@@ -131,7 +124,6 @@ impl Iterator for LogParser {
                 }
                 Ok(ParseResult::IgnoredLine) => {
                     log::warn!("Ignored line: {line:?}");
-                    continue;
                 }
                 Err(e) => {
                     // Unfortunately, some versions of strace output inconsistent line format,
@@ -140,15 +132,96 @@ impl Iterator for LogParser {
                     // log::error!("Failed to parse line: {line:?}");
                     // return Some(Err(e));
                     log::warn!("Failed to parse line ({e}): {line:?}");
-                    continue;
                 }
-            };
+            }
         };
         Some(Ok(sc))
     }
 }
 
-#[expect(clippy::unreadable_literal, clippy::shadow_unrelated)]
+/// Problem found on a strace output line, when checking a log in strict mode
+#[derive(Debug, PartialEq)]
+pub(crate) enum LineIssue {
+    /// Line was not recognized as a syscall, and would be ignored
+    Ignored,
+    /// Line was recognized as a syscall, but failed to parse
+    ParseError(String),
+    /// Line ends a syscall that was never started
+    UnmatchedEnd,
+}
+
+impl fmt::Display for LineIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ignored => write!(f, "unrecognized line"),
+            Self::ParseError(e) => write!(f, "parse error: {e}"),
+            Self::UnmatchedEnd => write!(f, "end of a syscall that was not started"),
+        }
+    }
+}
+
+/// Number of lines printed before and after each line with an issue
+const CHECK_CONTEXT_LINES: usize = 2;
+
+/// Check all strace output lines, and return issues with the index of the line they were found on
+pub(crate) fn check_lines(lines: &[&str]) -> Vec<(usize, LineIssue)> {
+    let mut issues = Vec::new();
+    let mut unfinished_syscalls: Vec<(u32, String)> = Vec::new();
+    for (idx, line) in lines.iter().enumerate() {
+        if line.ends_with(" +++") || line.ends_with(" ---") {
+            continue;
+        }
+        match parse_line(line) {
+            Ok(ParseResult::Syscall(_)) => {}
+            Ok(ParseResult::SyscallStart(sc)) => unfinished_syscalls.push((sc.pid, sc.name)),
+            Ok(ParseResult::SyscallEnd(sc_end)) => {
+                if let Some(unfinished_index) = unfinished_syscalls
+                    .iter()
+                    .position(|(pid, name)| (*name == sc_end.name) && (*pid == sc_end.pid))
+                {
+                    unfinished_syscalls.swap_remove(unfinished_index);
+                } else {
+                    issues.push((idx, LineIssue::UnmatchedEnd));
+                }
+            }
+            Ok(ParseResult::IgnoredLine) => issues.push((idx, LineIssue::Ignored)),
+            Err(e) => issues.push((idx, LineIssue::ParseError(e.to_string()))),
+        }
+    }
+    issues
+}
+
+/// Check a strace log, and write its issues with line numbers and surrounding lines, returning the issue count
+pub(crate) fn check_log<W: io::Write>(
+    name: &str,
+    log: &str,
+    output: &mut W,
+) -> anyhow::Result<usize> {
+    let lines: Vec<_> = log.lines().collect();
+    let issues = check_lines(&lines);
+    for (idx, issue) in &issues {
+        writeln!(output, "{name}:{}: {issue}", idx + 1)?;
+        let context_start = idx.saturating_sub(CHECK_CONTEXT_LINES);
+        let context_lines = lines
+            .iter()
+            .enumerate()
+            .skip(context_start)
+            .take(idx - context_start + CHECK_CONTEXT_LINES + 1);
+        for (context_idx, context_line) in context_lines {
+            let marker = if context_idx == *idx { '>' } else { ' ' };
+            writeln!(output, "{marker} {:>6} | {context_line}", context_idx + 1)?;
+        }
+    }
+    writeln!(
+        output,
+        "{} issue(s) in {} line(s)",
+        issues.len(),
+        lines.len()
+    )?;
+    Ok(issues.len())
+}
+
+#[expect(clippy::unreadable_literal)]
 #[cfg(test)]
 mod tests {
     use std::{collections::HashMap, io::Cursor};
@@ -901,6 +974,58 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_openat2() {
+        let _ = simple_logger::SimpleLogger::new().init();
+
+        assert_eq!(
+            parse_line(
+                "998518      0.000033 openat2(AT_FDCWD<\\x2f>, \"\\x2f\\x65\\x74\\x63\", {flags=O_RDONLY|O_CLOEXEC, resolve=RESOLVE_NO_SYMLINKS}, 24) = 3<\\x2f\\x65\\x74\\x63>",
+            ).unwrap(),
+            ParseResult::Syscall(Syscall {
+                pid: 998518,
+                rel_ts: 0.000033,
+                name: "openat2".to_owned(),
+                args: vec![
+                    Expression::Integer(IntegerExpression {
+                        value: IntegerExpressionValue::NamedConst("AT_FDCWD".to_owned()),
+                        metadata: Some("/".as_bytes().to_vec()),
+                    }),
+                    Expression::Buffer(BufferExpression {
+                        value: "/etc".as_bytes().to_vec(),
+                        type_: BufferType::Unknown,
+                    }),
+                    Expression::Struct(HashMap::from([
+                        (
+                            "flags".to_owned(),
+                            Expression::Integer(IntegerExpression {
+                                value: IntegerExpressionValue::BinaryOr(vec![
+                                    IntegerExpressionValue::NamedConst("O_RDONLY".to_owned()),
+                                    IntegerExpressionValue::NamedConst("O_CLOEXEC".to_owned()),
+                                ]),
+                                metadata: None,
+                            }),
+                        ),
+                        (
+                            "resolve".to_owned(),
+                            Expression::Integer(IntegerExpression {
+                                value: IntegerExpressionValue::NamedConst(
+                                    "RESOLVE_NO_SYMLINKS".to_owned()
+                                ),
+                                metadata: None,
+                            }),
+                        ),
+                    ])),
+                    Expression::Integer(IntegerExpression {
+                        value: IntegerExpressionValue::Literal(24),
+                        metadata: None,
+                    }),
+                ],
+                ret_val: 3
+            })
+        );
+    }
+
     #[test]
     fn test_truncated() {
         let _ = simple_logger::SimpleLogger::new().init();
@@ -1366,6 +1491,28 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_check_lines() {
+        let lines = [
+            "1       0.000001 select(4, [3], NULL, NULL, NULL <unfinished ...>",
+            "2       0.000002 clock_gettime(CLOCK_REALTIME, {tv_sec=1130322148, tv_nsec=3977000}) = 0",
+            "1       0.000003 <... select resumed> )      = 1 (in [3])",
+            "2       0.000004 <... read resumed> )      = 1",
+            "1008333      0.000045 ???( <unfinished ...>",
+            "2       0.000005 +++ exited with 0 +++",
+        ];
+        assert_eq!(
+            check_lines(&lines),
+            vec![(3, LineIssue::UnmatchedEnd), (4, LineIssue::Ignored)]
+        );
+
+        let mut output = Vec::new();
+        assert_eq!(check_log("log", &lines.join("\n"), &mut output).unwrap(), 2);
+        assert!(String::from_utf8(output).unwrap().starts_with(
+            "log:4: end of a syscall that was not started\n       2 | 2       0.000002"
+        ));
+    }
+
     #[test]
     fn test_interleave() {
         let _ = simple_logger::SimpleLogger::new().init();
@@ -1377,7 +1524,7 @@ mod tests {
                 .as_bytes()
                 .to_vec(),
         );
-        let parser = LogParser::new(Box::new(lines), None).unwrap();
+        let parser = LogParser::new(Box::new(lines), None);
         let syscalls: Vec<Syscall> = parser.into_iter().collect::<Result<_, _>>().unwrap();
 
         assert_eq!(
@@ -2447,13 +2594,107 @@ mod tests {
             })
         );
     }
+
+    #[test]
+    fn test_in_out_struct_member() {
+        let _ = simple_logger::SimpleLogger::new().init();
+
+        assert_eq!(
+            parse_line(
+                "813299      0.000021 recvmsg(5, {msg_name={sa_family=AF_INET, sin_port=htons(53), sin_addr=inet_addr(\"1.1.1.1\")}, msg_namelen=128 => 16, msg_iovlen=1, msg_controllen=0, msg_flags=0}, 0) = 42",
+            )
+            .unwrap(),
+            ParseResult::Syscall(Syscall {
+                pid: 813299,
+                rel_ts: 0.000021,
+                name: "recvmsg".to_owned(),
+                args: vec![
+                    Expression::Integer(IntegerExpression {
+                        value: IntegerExpressionValue::Literal(5),
+                        metadata: None,
+                    }),
+                    Expression::Struct(HashMap::from([
+                        (
+                            "msg_name".to_owned(),
+                            Expression::Struct(HashMap::from([
+                                (
+                                    "sa_family".to_owned(),
+                                    Expression::Integer(IntegerExpression {
+                                        value: IntegerExpressionValue::NamedConst("AF_INET".to_owned()),
+                                        metadata: None,
+                                    }),
+                                ),
+                                (
+                                    "sin_port".to_owned(),
+                                    Expression::Macro {
+                                        name: "htons".to_owned(),
+                                        args: vec![
+                                            Expression::Integer(IntegerExpression {
+                                                value: IntegerExpressionValue::Literal(53),
+                                                metadata: None,
+                                            }),
+                                        ],
+                                    }
+                                ),
+                                (
+                                    "sin_addr".to_owned(),
+                                    Expression::Macro {
+                                        name: "inet_addr".to_owned(),
+                                        args: vec![
+                                            Expression::Buffer(BufferExpression {
+                                                value: "1.1.1.1".as_bytes().to_vec(),
+                                                type_: BufferType::Unknown
+                                            }),
+                                        ],
+                                    }
+                                ),
+                            ])),
+                        ),
+                        (
+                            "msg_namelen".to_owned(),
+                            Expression::Integer(IntegerExpression {
+                                value: IntegerExpressionValue::Literal(128),
+                                metadata: None,
+                            }),
+                        ),
+                        (
+                            "msg_iovlen".to_owned(),
+                            Expression::Integer(IntegerExpression {
+                                value: IntegerExpressionValue::Literal(1),
+                                metadata: None,
+                            }),
+                        ),
+                        (
+                            "msg_controllen".to_owned(),
+                            Expression::Integer(IntegerExpression {
+                                value: IntegerExpressionValue::Literal(0),
+                                metadata: None,
+                            }),
+                        ),
+                        (
+                            "msg_flags".to_owned(),
+                            Expression::Integer(IntegerExpression {
+                                value: IntegerExpressionValue::Literal(0),
+                                metadata: None,
+                            }),
+                        ),
+                    ])),
+                    Expression::Integer(IntegerExpression {
+                        value: IntegerExpressionValue::Literal(0),
+                        metadata: None,
+                    }),
+                ],
+                ret_val: 42
+            })
+        );
+    }
 }
 
 #[cfg(all(feature = "nightly", test))]
 mod benchs {
     extern crate test;
 
-    use std::io::BufReader;
+    use std::{fs::File, io::BufReader, path::Path};
 
     use super::*;
 
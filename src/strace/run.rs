@@ -3,25 +3,34 @@
 use std::{
     fs::File,
     io::BufReader,
-    path::PathBuf,
+    iter,
+    path::{Path, PathBuf},
     process::{Child, Command, Stdio},
 };
 
 use anyhow::Context as _;
 
-use crate::strace::parser::LogParser;
+use crate::{
+    cl::{RunIdentityOptions, StraceLogOptions},
+    strace::{mirror::LogMirror, parser::LogParser},
+};
 
 pub(crate) struct Strace {
     /// Strace process
     process: Child,
     /// Temp dir for pipe location
     pipe_dir: tempfile::TempDir,
-    /// Strace log mirror path
-    log_path: Option<PathBuf>,
+    /// Strace log mirror options
+    log_opts: StraceLogOptions,
 }
 
 impl Strace {
-    pub(crate) fn run(command: &[&str], log_path: Option<PathBuf>) -> anyhow::Result<Self> {
+    pub(crate) fn run(
+        strace_bin: &Path,
+        command: &[&str],
+        identity_opts: &RunIdentityOptions,
+        log_opts: StraceLogOptions,
+    ) -> anyhow::Result<Self> {
         // Create named pipe
         let pipe_dir = tempfile::tempdir()?;
         let pipe_path = Self::pipe_path(&pipe_dir);
@@ -30,43 +39,155 @@ impl Strace {
 
         // Start process
         // TODO setuid/setgid execution will be broken unless strace runs as root
-        let child = Command::new("strace")
-            .args([
-                "--daemonize=grandchild",
-                "--relative-timestamps",
-                "--follow-forks",
-                // TODO APPROXIMATION this can make us miss interesting stuff like open with O_EXCL|O_CREAT which
-                // returns -1 because file exists
-                "--successful-only",
-                "--strings-in-hex=all",
-                // Despite this, some structs are still truncated
-                "-e",
-                "abbrev=none",
-                // "-e",
-                // "read=all",
-                // "-e",
-                // "write=all",
-                "-e",
-                "decode-fds=path",
-                "--output-append-mode",
-                "-o",
-                #[expect(clippy::unwrap_used)]
+        anyhow::ensure!(
+            identity_opts.user.is_none() || nix::unistd::geteuid().is_root(),
+            "Running the command as another user requires root"
+        );
+        #[expect(clippy::unwrap_used)]
+        let child = Command::new(strace_bin)
+            .args(Self::args(
+                identity_opts,
                 pipe_path.to_str().unwrap(),
-                "--",
-            ])
-            .args(command)
+                command,
+            )?)
             .env("LANG", "C") // avoids locale side effects
             .stdin(Stdio::null())
             .spawn()
-            .context("Failed to start strace")?;
+            .with_context(|| format!("Failed to start {}", strace_bin.display()))?;
 
         Ok(Self {
             process: child,
             pipe_dir,
-            log_path,
+            log_opts,
         })
     }
 
+    /// Full strace arguments to run and trace a command
+    fn args(
+        identity_opts: &RunIdentityOptions,
+        output_path: &str,
+        command: &[&str],
+    ) -> anyhow::Result<Vec<String>> {
+        Ok(Self::user_arg(identity_opts)?
+            .into_iter()
+            .chain(
+                Self::trace_args(output_path)
+                    .into_iter()
+                    .chain(iter::once("--"))
+                    .chain(command.iter().copied())
+                    .map(ToOwned::to_owned),
+            )
+            .collect())
+    }
+
+    /// Strace arguments to trace a command and write its syscalls to a file
+    fn trace_args(output_path: &str) -> [&str; 14] {
+        [
+            "--daemonize=grandchild",
+            "--relative-timestamps",
+            "--follow-forks",
+            // TODO APPROXIMATION this can make us miss interesting stuff like open with O_EXCL|O_CREAT which
+            // returns -1 because file exists
+            "--successful-only",
+            "--strings-in-hex=all",
+            // Despite this, some structs are still truncated
+            "-e",
+            "abbrev=none",
+            // "-e",
+            // "read=all",
+            // "-e",
+            // "write=all",
+            "-e",
+            "decode-fds=path",
+            // ioctl request classes are only known from raw request numbers
+            "-e",
+            "raw=ioctl",
+            "--output-append-mode",
+            "-o",
+            output_path,
+        ]
+    }
+
+    /// Check a strace binary supports all the arguments we need, by tracing a trivial command with them
+    pub(crate) fn probe(strace_bin: &Path) -> anyhow::Result<()> {
+        let output = Command::new(strace_bin)
+            .args(Self::trace_args("/dev/null"))
+            .args(["--", "true"])
+            .env("LANG", "C")
+            .stdin(Stdio::null())
+            .output()
+            .with_context(|| format!("Failed to run {}", strace_bin.display()))?;
+        if output.status.success() {
+            return Ok(());
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let unsupported = Self::unsupported_args(&stderr);
+        if unsupported.is_empty() {
+            anyhow::bail!(
+                "{} failed to trace with the arguments needed for profiling: {}",
+                strace_bin.display(),
+                stderr.trim()
+            );
+        }
+        anyhow::bail!(
+            "{} does not support arguments needed for profiling: {}",
+            strace_bin.display(),
+            unsupported.join(", ")
+        );
+    }
+
+    /// Get arguments strace rejected, from its error output, ie. `strace: unrecognized option '--seccomp-bpf'` or
+    /// `strace: invalid -e decode-fds value 'path'`
+    fn unsupported_args(stderr: &str) -> Vec<&str> {
+        stderr
+            .lines()
+            .filter_map(|l| l.strip_prefix("strace: "))
+            .filter_map(|l| {
+                if let Some(opt) = l.strip_prefix("unrecognized option ") {
+                    Some(opt.trim_matches(['\'', '`']))
+                } else if l.starts_with("invalid ") {
+                    Some(l)
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Build strace argument to run the command as another user
+    fn user_arg(identity_opts: &RunIdentityOptions) -> anyhow::Result<Option<String>> {
+        let Some(user) = identity_opts.user.as_ref() else {
+            return Ok(None);
+        };
+        let Some(group) = identity_opts.group.as_ref() else {
+            // strace will also set the user's primary and supplementary groups, but needs a name for that
+            let user_name = match user.parse() {
+                Ok(uid) => {
+                    nix::unistd::User::from_uid(nix::unistd::Uid::from_raw(uid))?
+                        .ok_or_else(|| anyhow::anyhow!("Unknown user {user:?}"))?
+                        .name
+                }
+                Err(_) => user.to_owned(),
+            };
+            return Ok(Some(format!("--user={user_name}")));
+        };
+        let uid = match user.parse() {
+            Ok(uid) => uid,
+            Err(_) => nix::unistd::User::from_name(user)?
+                .ok_or_else(|| anyhow::anyhow!("Unknown user {user:?}"))?
+                .uid
+                .as_raw(),
+        };
+        let gid = match group.parse() {
+            Ok(gid) => gid,
+            Err(_) => nix::unistd::Group::from_name(group)?
+                .ok_or_else(|| anyhow::anyhow!("Unknown group {group:?}"))?
+                .gid
+                .as_raw(),
+        };
+        Ok(Some(format!("--user={uid}:{gid}")))
+    }
+
     fn pipe_path(dir: &tempfile::TempDir) -> PathBuf {
         dir.path().join("strace.pipe")
     }
@@ -74,7 +195,10 @@ impl Strace {
     pub(crate) fn log_lines(&self) -> anyhow::Result<LogParser> {
         let pipe_path = Self::pipe_path(&self.pipe_dir);
         let reader = BufReader::new(File::open(pipe_path)?);
-        LogParser::new(Box::new(reader), self.log_path.as_deref())
+        Ok(LogParser::new(
+            Box::new(reader),
+            LogMirror::new(&self.log_opts)?,
+        ))
     }
 }
 
@@ -84,3 +208,74 @@ impl Drop for Strace {
         let _ = self.process.wait();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_args() {
+        let mut identity_opts = RunIdentityOptions {
+            user: None,
+            group: None,
+        };
+        let args = Strace::args(&identity_opts, "/tmp/strace.pipe", &["ls", "-l"]).unwrap();
+        assert_eq!(args[0], "--daemonize=grandchild");
+        assert_eq!(
+            &args[args.len() - 5..],
+            ["-o", "/tmp/strace.pipe", "--", "ls", "-l"]
+        );
+
+        // User name is needed for strace to set its groups
+        identity_opts.user = Some("0".to_owned());
+        let user_args = Strace::args(&identity_opts, "/tmp/strace.pipe", &["ls"]).unwrap();
+        assert_eq!(user_args[..2], ["--user=root", "--daemonize=grandchild"]);
+        assert_eq!(user_args.len(), args.len());
+
+        identity_opts.user = Some("root".to_owned());
+        identity_opts.group = Some("0".to_owned());
+        assert_eq!(
+            Strace::args(&identity_opts, "/tmp/strace.pipe", &["ls"]).unwrap()[0],
+            "--user=0:0"
+        );
+
+        identity_opts.user = Some("nonexistent-shh-test-user".to_owned());
+        assert!(Strace::args(&identity_opts, "/tmp/strace.pipe", &["ls"]).is_err());
+    }
+
+    #[test]
+    fn test_unsupported_args() {
+        // Nothing rejected
+        assert!(Strace::unsupported_args("").is_empty());
+
+        // Strace 5.2, released before long options, like --seccomp-bpf, were added
+        assert_eq!(
+            Strace::unsupported_args(
+                "strace: unrecognized option '--daemonize=grandchild'
+Try 'strace -h' for more information.
+"
+            ),
+            vec!["--daemonize=grandchild"]
+        );
+        assert_eq!(
+            Strace::unsupported_args(
+                "strace: unrecognized option '--seccomp-bpf'
+Try 'strace -h' for more information.
+"
+            ),
+            vec!["--seccomp-bpf"]
+        );
+
+        // Option known, but not its value
+        assert_eq!(
+            Strace::unsupported_args("strace: invalid -e decode-fds value 'path'\n"),
+            vec!["invalid -e decode-fds value 'path'"]
+        );
+
+        // Other failure
+        assert!(Strace::unsupported_args(
+            "strace: ptrace(PTRACE_TRACEME, ...): Operation not permitted\n"
+        )
+        .is_empty());
+    }
+}
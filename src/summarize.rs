@@ -1,18 +1,25 @@
 //! Summarize program syscalls into higher level action
 
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{hash_map::Entry, BTreeSet, HashMap, HashSet},
     ffi::OsStr,
     fmt::{self, Display},
+    fs,
+    hash::{DefaultHasher, Hash as _, Hasher as _},
+    net::{IpAddr, SocketAddr},
     num::NonZeroU16,
     ops::{Add, RangeInclusive, Sub},
-    os::unix::ffi::OsStrExt,
-    path::{Path, PathBuf},
-    slice,
+    os::unix::{ffi::OsStrExt, fs::MetadataExt as _},
+    path::{Component, Path, PathBuf},
+    slice, str,
     sync::LazyLock,
+    time::Duration,
 };
 
+use nix::sys::stat::Mode;
+
 use crate::{
+    capabilities::CAPABILITIES,
     strace::{
         BufferExpression, BufferType, Expression, IntegerExpression, IntegerExpressionValue,
         Syscall,
@@ -32,7 +39,9 @@ pub(crate) enum ProgramAction {
     Create(PathBuf),
     /// Network (socket) activity
     NetworkActivity(NetworkActivity),
-    /// Memory mapping with write and execute bits
+    /// Abstract UNIX socket was bound or connected to, by name (without the leading NUL byte)
+    AbstractSocket(String),
+    /// Memory mapping with write and execute bits, executable memfd mapping, or mapping made executable
     WriteExecuteMemoryMapping,
     /// Set scheduler to a real time one
     SetRealtimeScheduler,
@@ -42,8 +51,118 @@ pub(crate) enum ProgramAction {
     MknodSpecial,
     /// Set privileged timer alarm
     SetAlarm,
+    /// Executable was run
+    Exec(PathBuf),
+    /// Executable with setuid or setgid bit was run
+    SetuidExec(PathBuf),
     /// Names of the syscalls made by the program
-    Syscalls(HashSet<String>),
+    Syscalls(BTreeSet<String>),
+    /// Relative paths were resolved from this working directory, before the program changed it
+    WorkingDirectory(PathBuf),
+    /// Peak number of file descriptors open at the same time
+    OpenFiles(u64),
+    /// Peak number of tasks (processes and threads) running at the same time
+    Tasks(u64),
+    /// Capability was checked by the kernel
+    Capability(String),
+    /// Temporary directory set with `$TMPDIR`, if it is not one made private by `PrivateTmp=`
+    TempDirectory(PathBuf),
+    /// Owner user and/or group of a path was changed, to these ids
+    ChangeOwner {
+        path: PathBuf,
+        uid: Option<u32>,
+        gid: Option<u32>,
+    },
+    /// Scheduling policy set by the program for itself, ie. `SCHED_BATCH`
+    SetSchedulingPolicy(String),
+    /// Nice value set by the program for itself
+    SetNice(i32),
+    /// User and/or group ids of the program were changed, to these effective ids if known
+    SetCredentials { uid: Option<u32>, gid: Option<u32> },
+    /// Capability was explicitly kept in the permitted set of the program with `capset`
+    CapabilityUse(String),
+    /// Device was sent an ioctl request, of this class (`_IOC_TYPE`, ie. `'T'` for terminals)
+    DeviceIoctl { path: PathBuf, class: u8 },
+    /// Namespace of this type was created or joined, with its systemd name (ie. `net`)
+    NamespaceUse(String),
+    /// Special kernel keyring was used (ie. `session`)
+    KeyringUse(String),
+    /// An `io_uring` instance was set up, operations submitted through it are not seen as syscalls
+    IoUring,
+}
+
+impl Display for ProgramAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Read(path) => write!(f, "read {}", path.display()),
+            Self::Write(path) => write!(f, "write {}", path.display()),
+            Self::Create(path) => write!(f, "create {}", path.display()),
+            Self::NetworkActivity(NetworkActivity {
+                af,
+                proto,
+                kind,
+                local_port,
+                remote_addr,
+            }) => {
+                write!(f, "network {kind}: {af}/{proto} port {local_port}")?;
+                if let Some(remote_addr) = remote_addr {
+                    write!(f, " to {remote_addr}")?;
+                }
+                Ok(())
+            }
+            Self::AbstractSocket(name) => write!(f, "abstract UNIX socket @{name}"),
+            Self::WriteExecuteMemoryMapping => write!(f, "write and execute memory mapping"),
+            Self::SetRealtimeScheduler => write!(f, "set real time scheduler"),
+            Self::SetSchedulingPolicy(policy) => write!(f, "set scheduling policy {policy}"),
+            Self::SetNice(nice) => write!(f, "set nice value {nice}"),
+            Self::SetCredentials { uid, gid } => {
+                write!(f, "change credentials")?;
+                if let Some(uid) = uid {
+                    write!(f, " to uid {uid}")?;
+                }
+                if let Some(gid) = gid {
+                    write!(f, "{} gid {gid}", if uid.is_some() { "," } else { " to" })?;
+                }
+                Ok(())
+            }
+            Self::Wakeup => write!(f, "inhibit suspend"),
+            Self::MknodSpecial => write!(f, "create special file"),
+            Self::SetAlarm => write!(f, "set privileged timer alarm"),
+            Self::Exec(path) => write!(f, "run executable {}", path.display()),
+            Self::SetuidExec(path) => write!(f, "run setuid/setgid executable {}", path.display()),
+            Self::Syscalls(syscalls) => write!(
+                f,
+                "syscalls {}",
+                syscalls
+                    .iter()
+                    .map(String::as_str)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Self::WorkingDirectory(path) => write!(f, "working directory {}", path.display()),
+            Self::OpenFiles(count) => write!(f, "{count} open files"),
+            Self::Tasks(count) => write!(f, "{count} tasks"),
+            Self::Capability(cap) => write!(f, "capability {cap} check"),
+            Self::CapabilityUse(cap) => write!(f, "capability {cap} use"),
+            Self::NamespaceUse(ns) => write!(f, "namespace {ns} use"),
+            Self::KeyringUse(keyring) => write!(f, "keyring {keyring} use"),
+            Self::IoUring => write!(f, "io_uring use"),
+            Self::DeviceIoctl { path, class } => {
+                write!(f, "ioctl of class {class:#04x} on {}", path.display())
+            }
+            Self::TempDirectory(path) => write!(f, "temporary directory {}", path.display()),
+            Self::ChangeOwner { path, uid, gid } => {
+                write!(f, "change owner of {}", path.display())?;
+                if let Some(uid) = uid {
+                    write!(f, " to uid {uid}")?;
+                }
+                if let Some(gid) = gid {
+                    write!(f, "{} gid {gid}", if uid.is_some() { "," } else { " to" })?;
+                }
+                Ok(())
+            }
+        }
+    }
 }
 
 /// Network (socket) activity
@@ -53,6 +172,9 @@ pub(crate) struct NetworkActivity {
     pub proto: SetSpecifier<SocketProtocol>,
     pub kind: SetSpecifier<NetworkActivityKind>,
     pub local_port: CountableSetSpecifier<NetworkPort>,
+    /// Address connected to, if known, not taken into account when checking denied activity
+    #[serde(default)]
+    pub remote_addr: Option<SocketAddr>,
 }
 
 /// Quantify something that is done or denied
@@ -93,6 +215,24 @@ impl<T: Eq + Clone> SetSpecifier<T> {
     }
 }
 
+impl<T: Display> Display for SetSpecifier<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::None => write!(f, "none"),
+            Self::One(e) => e.fmt(f),
+            Self::Some(es) => write!(
+                f,
+                "{}",
+                es.iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(",")
+            ),
+            Self::All => write!(f, "any"),
+        }
+    }
+}
+
 pub(crate) trait ValueCounted {
     fn value_count() -> usize;
 
@@ -168,15 +308,38 @@ impl<T: Eq + Ord + Clone + Display + ValueCounted + Sub<Output = T> + Add<Output
         }
     }
 
+    /// Build ranges from ordered elements, merging adjacent ones
+    fn coalesce(elements: &[T]) -> Vec<RangeInclusive<T>> {
+        let mut ranges: Vec<RangeInclusive<T>> = Vec::new();
+        for element in elements {
+            if let Some(last) = ranges.last_mut() {
+                if (*last.end() != T::max_value()) && (last.end().to_owned() + T::one() == *element)
+                {
+                    *last = last.start().to_owned()..=element.to_owned();
+                    continue;
+                }
+            }
+            ranges.push(element.to_owned()..=element.to_owned());
+        }
+        ranges
+    }
+
+    /// Get ranges of elements *not* in the set
+    pub(crate) fn complement_ranges(&self) -> Vec<RangeInclusive<T>> {
+        match self {
+            CountableSetSpecifier::None => Self::All.ranges(),
+            CountableSetSpecifier::One(e) => Self::AllExcept(vec![e.to_owned()]).ranges(),
+            CountableSetSpecifier::Some(es) => Self::AllExcept(es.to_owned()).ranges(),
+            CountableSetSpecifier::AllExcept(excs) => Self::coalesce(excs),
+            CountableSetSpecifier::All => vec![],
+        }
+    }
+
     pub(crate) fn ranges(&self) -> Vec<RangeInclusive<T>> {
         match self {
             CountableSetSpecifier::None => vec![],
             CountableSetSpecifier::One(e) => vec![e.to_owned()..=e.to_owned()],
-            CountableSetSpecifier::Some(es) => {
-                // Build single element ranges, we could merge adjacent elements, but
-                // the effort has very little upsides
-                es.iter().map(|e| e.to_owned()..=e.to_owned()).collect()
-            }
+            CountableSetSpecifier::Some(es) => Self::coalesce(es),
             CountableSetSpecifier::AllExcept(excs) => {
                 let mut ranges = Vec::with_capacity(excs.len() + 1);
                 let mut start = None;
@@ -208,13 +371,40 @@ impl<T: Eq + Ord + Clone + Display + ValueCounted + Sub<Output = T> + Add<Output
     }
 }
 
+impl<T: Eq + Ord + Clone + Display + ValueCounted + Sub<Output = T> + Add<Output = T>> Display
+    for CountableSetSpecifier<T>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::None => write!(f, "none"),
+            Self::All => write!(f, "any"),
+            _ => write!(
+                f,
+                "{}",
+                self.ranges()
+                    .into_iter()
+                    .map(|r| if r.start() == r.end() {
+                        r.start().to_string()
+                    } else {
+                        format!("{}-{}", r.start(), r.end())
+                    })
+                    .collect::<Vec<_>>()
+                    .join(",")
+            ),
+        }
+    }
+}
+
 /// Socket activity
-#[derive(Debug, Clone, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq, serde::Serialize, serde::Deserialize, strum::Display)]
+#[strum(serialize_all = "snake_case")]
 pub(crate) enum NetworkActivityKind {
     SocketCreation,
     Bind,
+    Connect,
+    Listen,
+    Accept,
     // TODO
-    // Connect,
     // Send,
     // Recv,
 }
@@ -222,6 +412,18 @@ pub(crate) enum NetworkActivityKind {
 #[derive(Debug, Clone, Ord, PartialOrd, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
 pub(crate) struct NetworkPort(NonZeroU16);
 
+impl NetworkPort {
+    pub(crate) fn get(&self) -> u16 {
+        self.0.get()
+    }
+}
+
+impl From<NonZeroU16> for NetworkPort {
+    fn from(port: NonZeroU16) -> Self {
+        Self(port)
+    }
+}
+
 impl ValueCounted for NetworkPort {
     fn value_count() -> usize {
         // 0 is excluded
@@ -271,20 +473,76 @@ impl Display for NetworkPort {
 /// Meta structure to group syscalls that have similar summary handling
 /// and store argument indexes
 enum SyscallInfo {
+    CapSet,
+    Chdir,
+    Chown {
+        fd_idx: Option<usize>,
+        path_idx: Option<usize>,
+        /// Index of the user id, followed by the group id
+        uid_idx: usize,
+    },
+    CopyFd {
+        fd_in_idx: usize,
+        fd_out_idx: usize,
+    },
+    Exec {
+        relfd_idx: Option<usize>,
+        path_idx: usize,
+    },
+    Ioctl {
+        fd_idx: usize,
+        request_idx: usize,
+    },
+    IoUring,
+    Keyring,
+    Lock {
+        fd_idx: usize,
+        /// Index of the `flock` operation, or of the `fcntl` command, followed by its `flock` structure
+        op_idx: usize,
+        fcntl: bool,
+    },
     Mknod {
         mode_idx: usize,
     },
+    Namespaces {
+        /// Index of the namespace flags, possibly as the `flags` member of a structure
+        flags_idx: usize,
+        /// No flag means a namespace of any type can be joined
+        join: bool,
+    },
+    /// Mount with the new mount API, from a source tree to a mount point, each given by an
+    /// optional directory fd and a path
+    Mount {
+        src: Option<(Option<usize>, usize)>,
+        dst: Option<(Option<usize>, usize)>,
+    },
+    MountConfig {
+        /// Index of the parameter key, followed by its value
+        key_idx: usize,
+    },
     Mmap {
         prot_idx: usize,
+        /// Index of the fd of the mapped file, if any
+        fd_idx: Option<usize>,
+    },
+    /// Protection change of existing mappings
+    MmapProtect {
+        prot_idx: usize,
     },
     Network {
         sockaddr_idx: usize,
+        /// The socket address is the `msg_name` member of a `msghdr` structure at `sockaddr_idx`
+        in_msghdr: bool,
     },
     Open {
         relfd_idx: Option<usize>,
         path_idx: usize,
         flags_idx: usize,
     },
+    OpenHandle {
+        mount_fd_idx: usize,
+        flags_idx: usize,
+    },
     Rename {
         relfd_src_idx: Option<usize>,
         path_src_idx: usize,
@@ -292,8 +550,23 @@ enum SyscallInfo {
         path_dst_idx: usize,
         flags_idx: Option<usize>,
     },
-    SetScheduler,
+    SetCredentials {
+        uid_idx: Option<usize>,
+        gid_idx: Option<usize>,
+    },
+    SetPriority,
+    SetScheduler {
+        /// The policy is the `sched_policy` member of a `sched_attr` structure
+        in_sched_attr: bool,
+    },
     Socket,
+    SocketAccept {
+        fd_idx: usize,
+        sockaddr_idx: usize,
+    },
+    SocketListen {
+        fd_idx: usize,
+    },
     StatFd {
         fd_idx: usize,
     },
@@ -311,21 +584,225 @@ enum SyscallInfo {
 //
 static SYSCALL_MAP: LazyLock<HashMap<&'static str, SyscallInfo>> = LazyLock::new(|| {
     HashMap::from([
+        // capabilities
+        ("capset", SyscallInfo::CapSet),
+        // chdir
+        ("chdir", SyscallInfo::Chdir),
+        ("fchdir", SyscallInfo::Chdir),
+        // chown
+        (
+            "chown",
+            SyscallInfo::Chown {
+                fd_idx: None,
+                path_idx: Some(0),
+                uid_idx: 1,
+            },
+        ),
+        (
+            "chown32",
+            SyscallInfo::Chown {
+                fd_idx: None,
+                path_idx: Some(0),
+                uid_idx: 1,
+            },
+        ),
+        (
+            "fchown",
+            SyscallInfo::Chown {
+                fd_idx: Some(0),
+                path_idx: None,
+                uid_idx: 1,
+            },
+        ),
+        (
+            "fchown32",
+            SyscallInfo::Chown {
+                fd_idx: Some(0),
+                path_idx: None,
+                uid_idx: 1,
+            },
+        ),
+        (
+            "fchownat",
+            SyscallInfo::Chown {
+                fd_idx: Some(0),
+                path_idx: Some(1),
+                uid_idx: 2,
+            },
+        ),
+        (
+            "lchown",
+            SyscallInfo::Chown {
+                fd_idx: None,
+                path_idx: Some(0),
+                uid_idx: 1,
+            },
+        ),
+        (
+            "lchown32",
+            SyscallInfo::Chown {
+                fd_idx: None,
+                path_idx: Some(0),
+                uid_idx: 1,
+            },
+        ),
+        // copy between fds
+        (
+            "copy_file_range",
+            SyscallInfo::CopyFd {
+                fd_in_idx: 0,
+                fd_out_idx: 2,
+            },
+        ),
+        (
+            "sendfile",
+            SyscallInfo::CopyFd {
+                fd_in_idx: 1,
+                fd_out_idx: 0,
+            },
+        ),
+        (
+            "sendfile64",
+            SyscallInfo::CopyFd {
+                fd_in_idx: 1,
+                fd_out_idx: 0,
+            },
+        ),
+        (
+            "splice",
+            SyscallInfo::CopyFd {
+                fd_in_idx: 0,
+                fd_out_idx: 2,
+            },
+        ),
+        (
+            "tee",
+            SyscallInfo::CopyFd {
+                fd_in_idx: 0,
+                fd_out_idx: 1,
+            },
+        ),
+        // exec
+        (
+            "execve",
+            SyscallInfo::Exec {
+                relfd_idx: None,
+                path_idx: 0,
+            },
+        ),
+        (
+            "execveat",
+            SyscallInfo::Exec {
+                relfd_idx: Some(0),
+                path_idx: 1,
+            },
+        ),
+        // ioctl
+        (
+            "ioctl",
+            SyscallInfo::Ioctl {
+                fd_idx: 0,
+                request_idx: 1,
+            },
+        ),
+        // io_uring, only its setup is needed to know it is used
+        ("io_uring_setup", SyscallInfo::IoUring),
+        // file locks
+        (
+            "fcntl",
+            SyscallInfo::Lock {
+                fd_idx: 0,
+                op_idx: 1,
+                fcntl: true,
+            },
+        ),
+        (
+            "fcntl64",
+            SyscallInfo::Lock {
+                fd_idx: 0,
+                op_idx: 1,
+                fcntl: true,
+            },
+        ),
+        (
+            "flock",
+            SyscallInfo::Lock {
+                fd_idx: 0,
+                op_idx: 1,
+                fcntl: false,
+            },
+        ),
         // mknod
         ("mknod", SyscallInfo::Mknod { mode_idx: 1 }),
         ("mknodat", SyscallInfo::Mknod { mode_idx: 2 }),
         // mmap
-        ("mmap", SyscallInfo::Mmap { prot_idx: 2 }),
-        ("mmap2", SyscallInfo::Mmap { prot_idx: 2 }),
-        ("shmat", SyscallInfo::Mmap { prot_idx: 2 }),
-        ("mprotect", SyscallInfo::Mmap { prot_idx: 2 }),
-        ("pkey_mprotect", SyscallInfo::Mmap { prot_idx: 2 }),
+        (
+            "mmap",
+            SyscallInfo::Mmap {
+                prot_idx: 2,
+                fd_idx: Some(4),
+            },
+        ),
+        (
+            "mmap2",
+            SyscallInfo::Mmap {
+                prot_idx: 2,
+                fd_idx: Some(4),
+            },
+        ),
+        (
+            "shmat",
+            SyscallInfo::Mmap {
+                prot_idx: 2,
+                fd_idx: None,
+            },
+        ),
+        ("mprotect", SyscallInfo::MmapProtect { prot_idx: 2 }),
+        ("pkey_mprotect", SyscallInfo::MmapProtect { prot_idx: 2 }),
         // network
-        ("connect", SyscallInfo::Network { sockaddr_idx: 1 }),
-        ("bind", SyscallInfo::Network { sockaddr_idx: 1 }),
-        ("recvfrom", SyscallInfo::Network { sockaddr_idx: 4 }),
-        ("sendto", SyscallInfo::Network { sockaddr_idx: 4 }),
-        // TODO recvmsg/sendmsg
+        (
+            "connect",
+            SyscallInfo::Network {
+                sockaddr_idx: 1,
+                in_msghdr: false,
+            },
+        ),
+        (
+            "bind",
+            SyscallInfo::Network {
+                sockaddr_idx: 1,
+                in_msghdr: false,
+            },
+        ),
+        (
+            "recvfrom",
+            SyscallInfo::Network {
+                sockaddr_idx: 4,
+                in_msghdr: false,
+            },
+        ),
+        (
+            "recvmsg",
+            SyscallInfo::Network {
+                sockaddr_idx: 1,
+                in_msghdr: true,
+            },
+        ),
+        (
+            "sendmsg",
+            SyscallInfo::Network {
+                sockaddr_idx: 1,
+                in_msghdr: true,
+            },
+        ),
+        (
+            "sendto",
+            SyscallInfo::Network {
+                sockaddr_idx: 4,
+                in_msghdr: false,
+            },
+        ),
+        // TODO recvmmsg/sendmmsg
 
         // open
         (
@@ -344,6 +821,15 @@ static SYSCALL_MAP: LazyLock<HashMap<&'static str, SyscallInfo>> = LazyLock::new
                 flags_idx: 2,
             },
         ),
+        // flags are a member of the open_how structure
+        (
+            "openat2",
+            SyscallInfo::Open {
+                relfd_idx: Some(0),
+                path_idx: 1,
+                flags_idx: 2,
+            },
+        ),
         // rename
         (
             "rename",
@@ -376,48 +862,458 @@ static SYSCALL_MAP: LazyLock<HashMap<&'static str, SyscallInfo>> = LazyLock::new
             },
         ),
         // set scheduler
-        ("sched_setscheduler", SyscallInfo::SetScheduler),
+        (
+            "sched_setattr",
+            SyscallInfo::SetScheduler {
+                in_sched_attr: true,
+            },
+        ),
+        (
+            "sched_setscheduler",
+            SyscallInfo::SetScheduler {
+                in_sched_attr: false,
+            },
+        ),
+        ("setpriority", SyscallInfo::SetPriority),
+        // set credentials, only the new effective (or file system) ids are kept
+        (
+            "setfsgid",
+            SyscallInfo::SetCredentials {
+                uid_idx: None,
+                gid_idx: Some(0),
+            },
+        ),
+        (
+            "setfsgid32",
+            SyscallInfo::SetCredentials {
+                uid_idx: None,
+                gid_idx: Some(0),
+            },
+        ),
+        (
+            "setfsuid",
+            SyscallInfo::SetCredentials {
+                uid_idx: Some(0),
+                gid_idx: None,
+            },
+        ),
+        (
+            "setfsuid32",
+            SyscallInfo::SetCredentials {
+                uid_idx: Some(0),
+                gid_idx: None,
+            },
+        ),
+        (
+            "setgid",
+            SyscallInfo::SetCredentials {
+                uid_idx: None,
+                gid_idx: Some(0),
+            },
+        ),
+        (
+            "setgid32",
+            SyscallInfo::SetCredentials {
+                uid_idx: None,
+                gid_idx: Some(0),
+            },
+        ),
+        (
+            "setgroups",
+            SyscallInfo::SetCredentials {
+                uid_idx: None,
+                gid_idx: None,
+            },
+        ),
+        (
+            "setgroups32",
+            SyscallInfo::SetCredentials {
+                uid_idx: None,
+                gid_idx: None,
+            },
+        ),
+        (
+            "setregid",
+            SyscallInfo::SetCredentials {
+                uid_idx: None,
+                gid_idx: Some(1),
+            },
+        ),
+        (
+            "setregid32",
+            SyscallInfo::SetCredentials {
+                uid_idx: None,
+                gid_idx: Some(1),
+            },
+        ),
+        (
+            "setresgid",
+            SyscallInfo::SetCredentials {
+                uid_idx: None,
+                gid_idx: Some(1),
+            },
+        ),
+        (
+            "setresgid32",
+            SyscallInfo::SetCredentials {
+                uid_idx: None,
+                gid_idx: Some(1),
+            },
+        ),
+        (
+            "setresuid",
+            SyscallInfo::SetCredentials {
+                uid_idx: Some(1),
+                gid_idx: None,
+            },
+        ),
+        (
+            "setresuid32",
+            SyscallInfo::SetCredentials {
+                uid_idx: Some(1),
+                gid_idx: None,
+            },
+        ),
+        (
+            "setreuid",
+            SyscallInfo::SetCredentials {
+                uid_idx: Some(1),
+                gid_idx: None,
+            },
+        ),
+        (
+            "setreuid32",
+            SyscallInfo::SetCredentials {
+                uid_idx: Some(1),
+                gid_idx: None,
+            },
+        ),
+        (
+            "setuid",
+            SyscallInfo::SetCredentials {
+                uid_idx: Some(0),
+                gid_idx: None,
+            },
+        ),
+        (
+            "setuid32",
+            SyscallInfo::SetCredentials {
+                uid_idx: Some(0),
+                gid_idx: None,
+            },
+        ),
         // socket
         ("socket", SyscallInfo::Socket),
-        // stat fd
-        ("fstat", SyscallInfo::StatFd { fd_idx: 0 }),
-        ("getdents", SyscallInfo::StatFd { fd_idx: 0 }),
-        // stat path
+        ("socketpair", SyscallInfo::Socket),
+        // server sockets
         (
-            "stat",
-            SyscallInfo::StatPath {
-                relfd_idx: None,
-                path_idx: 0,
+            "accept",
+            SyscallInfo::SocketAccept {
+                fd_idx: 0,
+                sockaddr_idx: 1,
             },
         ),
         (
-            "lstat",
-            SyscallInfo::StatPath {
-                relfd_idx: None,
-                path_idx: 0,
+            "accept4",
+            SyscallInfo::SocketAccept {
+                fd_idx: 0,
+                sockaddr_idx: 1,
             },
         ),
+        ("listen", SyscallInfo::SocketListen { fd_idx: 0 }),
+        // keyrings
+        ("add_key", SyscallInfo::Keyring),
+        ("keyctl", SyscallInfo::Keyring),
+        ("request_key", SyscallInfo::Keyring),
+        // namespaces
         (
-            "newfstatat",
-            SyscallInfo::StatPath {
-                relfd_idx: Some(0),
-                path_idx: 1,
+            "clone",
+            SyscallInfo::Namespaces {
+                flags_idx: 0,
+                join: false,
             },
         ),
-    ])
-});
-
-/// Resolve relative path if possible, and normalize it
-fn resolve_path(path: &Path, relfd_idx: Option<usize>, syscall: &Syscall) -> Option<PathBuf> {
-    let path = if path.is_relative() {
-        let metadata = relfd_idx
-            .and_then(|idx| syscall.args.get(idx))
-            .and_then(|a| a.metadata());
-        if let Some(metadata) = metadata {
-            if is_fd_pseudo_path(metadata) {
-                return None;
-            }
-            let rel_path = PathBuf::from(OsStr::from_bytes(metadata));
+        (
+            "clone3",
+            SyscallInfo::Namespaces {
+                flags_idx: 0,
+                join: false,
+            },
+        ),
+        (
+            "setns",
+            SyscallInfo::Namespaces {
+                flags_idx: 1,
+                join: true,
+            },
+        ),
+        (
+            "unshare",
+            SyscallInfo::Namespaces {
+                flags_idx: 0,
+                join: false,
+            },
+        ),
+        // new mount API, fsopen and fsmount have no path argument
+        ("fsconfig", SyscallInfo::MountConfig { key_idx: 2 }),
+        (
+            "fspick",
+            SyscallInfo::Mount {
+                src: None,
+                dst: Some((Some(0), 1)),
+            },
+        ),
+        (
+            "mount_setattr",
+            SyscallInfo::Mount {
+                src: None,
+                dst: Some((Some(0), 1)),
+            },
+        ),
+        (
+            "move_mount",
+            SyscallInfo::Mount {
+                src: Some((Some(0), 1)),
+                dst: Some((Some(2), 3)),
+            },
+        ),
+        (
+            "open_tree",
+            SyscallInfo::Mount {
+                src: Some((Some(0), 1)),
+                dst: None,
+            },
+        ),
+        // stat fd
+        ("fstat", SyscallInfo::StatFd { fd_idx: 0 }),
+        ("fstat64", SyscallInfo::StatFd { fd_idx: 0 }),
+        // directory listings need the directory to be readable
+        ("getdents", SyscallInfo::StatFd { fd_idx: 0 }),
+        ("getdents64", SyscallInfo::StatFd { fd_idx: 0 }),
+        // stat path
+        (
+            "stat",
+            SyscallInfo::StatPath {
+                relfd_idx: None,
+                path_idx: 0,
+            },
+        ),
+        (
+            "lstat",
+            SyscallInfo::StatPath {
+                relfd_idx: None,
+                path_idx: 0,
+            },
+        ),
+        (
+            "stat64",
+            SyscallInfo::StatPath {
+                relfd_idx: None,
+                path_idx: 0,
+            },
+        ),
+        (
+            "lstat64",
+            SyscallInfo::StatPath {
+                relfd_idx: None,
+                path_idx: 0,
+            },
+        ),
+        (
+            "newfstatat",
+            SyscallInfo::StatPath {
+                relfd_idx: Some(0),
+                path_idx: 1,
+            },
+        ),
+        (
+            "fstatat64",
+            SyscallInfo::StatPath {
+                relfd_idx: Some(0),
+                path_idx: 1,
+            },
+        ),
+        // musl and recent glibc use statx for all stat variants
+        (
+            "statx",
+            SyscallInfo::StatPath {
+                relfd_idx: Some(0),
+                path_idx: 1,
+            },
+        ),
+        // access checks need the same permissions as stat
+        (
+            "access",
+            SyscallInfo::StatPath {
+                relfd_idx: None,
+                path_idx: 0,
+            },
+        ),
+        (
+            "faccessat",
+            SyscallInfo::StatPath {
+                relfd_idx: Some(0),
+                path_idx: 1,
+            },
+        ),
+        (
+            "faccessat2",
+            SyscallInfo::StatPath {
+                relfd_idx: Some(0),
+                path_idx: 1,
+            },
+        ),
+        // file handles
+        (
+            "name_to_handle_at",
+            SyscallInfo::StatPath {
+                relfd_idx: Some(0),
+                path_idx: 1,
+            },
+        ),
+        (
+            "open_by_handle_at",
+            SyscallInfo::OpenHandle {
+                mount_fd_idx: 0,
+                flags_idx: 2,
+            },
+        ),
+    ])
+});
+
+/// Memoized path canonicalization, for big traces that access the same directories over and over
+#[derive(Default)]
+struct PathResolver {
+    /// Canonical paths of directories that exist
+    dirs: HashMap<PathBuf, PathBuf>,
+    /// Working directory the first relative path was resolved from, if the program did not change it before
+    initial_working_dir: Option<PathBuf>,
+    /// Whether the program changed its working directory
+    working_dir_changed: bool,
+}
+
+impl PathResolver {
+    /// Same as `Path::canonicalize`, falling back to the path itself, but only resolves parent directories once
+    fn canonicalize(&mut self, path: PathBuf) -> PathBuf {
+        let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+            return path.canonicalize().unwrap_or(path);
+        };
+        if path
+            .components()
+            .any(|c| matches!(c, Component::CurDir | Component::ParentDir))
+        {
+            return path.canonicalize().unwrap_or(path);
+        }
+        let canonical_parent = if let Some(canonical_parent) = self.dirs.get(parent) {
+            canonical_parent
+        } else {
+            // Failures are not cached, since the directory may be created later
+            let Ok(canonical_parent) = parent.canonicalize() else {
+                return path;
+            };
+            self.dirs
+                .entry(parent.to_path_buf())
+                .or_insert(canonical_parent)
+        };
+        let candidate = canonical_parent.join(name);
+        // Only the last component needs checking now
+        match fs::symlink_metadata(&candidate) {
+            Ok(metadata) if metadata.is_symlink() => candidate.canonicalize().unwrap_or(path),
+            Ok(_) => candidate,
+            Err(_) => path,
+        }
+    }
+}
+
+/// Push read and write actions for a path opened with the given flags
+fn push_open_actions(
+    actions: &mut Vec<ProgramAction>,
+    path: PathBuf,
+    flags: &IntegerExpressionValue,
+) {
+    if flags.is_flag_set("O_WRONLY") || flags.is_flag_set("O_RDWR") || flags.is_flag_set("O_TRUNC")
+    {
+        actions.push(ProgramAction::Write(path.clone()));
+    }
+    if !flags.is_flag_set("O_WRONLY") {
+        actions.push(ProgramAction::Read(path));
+    }
+}
+
+/// Get a user or group id argument, `None` if it is -1, which leaves the id unchanged
+fn id_arg(syscall: &Syscall, idx: usize) -> anyhow::Result<Option<u32>> {
+    let Some(Expression::Integer(IntegerExpression {
+        value: IntegerExpressionValue::Literal(id),
+        ..
+    })) = syscall.args.get(idx)
+    else {
+        anyhow::bail!("Unexpected args for {}: {:?}", syscall.name, syscall.args);
+    };
+    Ok(u32::try_from(*id).ok().filter(|id| *id != u32::MAX))
+}
+
+/// Whether a process id argument designates the calling process, ie. 0 or its own id
+fn is_own_process(arg: Option<&Expression>, pid: u32) -> bool {
+    matches!(arg, Some(Expression::Integer(IntegerExpression {
+        value: IntegerExpressionValue::Literal(target),
+        ..
+    })) if *target == 0 || *target == i128::from(pid))
+}
+
+/// Get capability names of a capability set, ie. `1<<CAP_CHOWN|1<<CAP_KILL`
+fn capability_names(set: &IntegerExpressionValue) -> Vec<String> {
+    match set {
+        IntegerExpressionValue::BinaryOr(vs) => vs.iter().flat_map(capability_names).collect(),
+        IntegerExpressionValue::LeftBitShift { bits, shift }
+            if **bits == IntegerExpressionValue::Literal(1) =>
+        {
+            match shift.as_ref() {
+                IntegerExpressionValue::NamedConst(cap) => vec![cap.to_owned()],
+                // Capabilities unknown to strace
+                IntegerExpressionValue::Literal(bit) => usize::try_from(*bit)
+                    .ok()
+                    .and_then(|b| CAPABILITIES.get(b))
+                    .map(|c| vec![(*c).to_owned()])
+                    .unwrap_or_default(),
+                _ => vec![],
+            }
+        }
+        _ => vec![],
+    }
+}
+
+/// Resolve relative path if possible, and normalize it
+fn resolve_path(
+    resolver: &mut PathResolver,
+    path: &Path,
+    relfd_idx: Option<usize>,
+    syscall: &Syscall,
+) -> Option<PathBuf> {
+    let path = if path.is_relative() {
+        let metadata = relfd_idx
+            .and_then(|idx| syscall.args.get(idx))
+            .and_then(|a| a.metadata());
+        if let Some(metadata) = metadata {
+            if is_fd_pseudo_path(metadata) {
+                return None;
+            }
+            let rel_path = PathBuf::from(OsStr::from_bytes(metadata));
+            let from_cwd = relfd_idx
+                .and_then(|idx| syscall.args.get(idx))
+                .is_some_and(|a| {
+                    matches!(a, Expression::Integer(IntegerExpression {
+                    value: IntegerExpressionValue::NamedConst(n),
+                    ..
+                }) if n == "AT_FDCWD")
+                });
+            // TODO APPROXIMATION
+            // Relative paths of syscalls without a directory fd argument are ignored, because
+            // the working directory is not known for them
+            if from_cwd && !resolver.working_dir_changed {
+                resolver
+                    .initial_working_dir
+                    .get_or_insert_with(|| rel_path.clone());
+            }
             rel_path.join(path)
         } else {
             return None;
@@ -425,10 +1321,34 @@ fn resolve_path(path: &Path, relfd_idx: Option<usize>, syscall: &Syscall) -> Opt
     } else {
         path.to_path_buf()
     };
+    if let Some(path) = proc_self_path(&path, syscall.pid) {
+        return Some(path);
+    }
     // TODO APPROXIMATION
     // canonicalize relies on the FS state at profiling time which may have changed
     // and may follow links, therefore lead to different filesystem actions
-    Some(path.canonicalize().unwrap_or(path))
+    Some(resolver.canonicalize(path))
+}
+
+/// Normalize paths of the calling process in `/proc` to `/proc/self`, since canonicalizing them would follow the
+/// links of the profiling process instead
+// TODO APPROXIMATION paths of other profiled processes (ie. children) keep their PID, and are seen as paths of
+// unrelated processes
+fn proc_self_path(path: &Path, pid: u32) -> Option<PathBuf> {
+    let mut components = path.strip_prefix("/proc").ok()?.components();
+    let first = components.next()?.as_os_str();
+    if (first == "self") || (first == "thread-self") {
+        return Some(path.to_path_buf());
+    }
+    if first.to_str()?.parse::<u32>().ok()? != pid {
+        return None;
+    }
+    let rel_path = components.as_path();
+    Some(if rel_path.as_os_str().is_empty() {
+        PathBuf::from("/proc/self")
+    } else {
+        Path::new("/proc/self").join(rel_path)
+    })
 }
 
 #[expect(clippy::unwrap_used)]
@@ -439,8 +1359,24 @@ fn is_fd_pseudo_path(path: &[u8]) -> bool {
     FD_PSEUDO_PATH_REGEX.is_match(path)
 }
 
+/// Extract name of abstract socket address structure
+fn socket_address_abstract_name(members: &HashMap<String, Expression>) -> Option<String> {
+    if let Some(Expression::Buffer(BufferExpression {
+        value: b,
+        type_: BufferType::AbstractPath,
+    })) = members.get("sun_path")
+    {
+        // Names are not nul terminated, but some programs pass the full structure size as address length
+        let len = b.iter().rposition(|c| *c != 0).map_or(0, |i| i + 1);
+        Some(String::from_utf8_lossy(&b[..len]).into_owned())
+    } else {
+        None
+    }
+}
+
 /// Extract path for socket address structure if it's a non abstract one
 fn socket_address_uds_path(
+    resolver: &mut PathResolver,
     members: &HashMap<String, Expression>,
     syscall: &Syscall,
 ) -> Option<PathBuf> {
@@ -449,69 +1385,442 @@ fn socket_address_uds_path(
         type_: BufferType::Unknown,
     })) = members.get("sun_path")
     {
-        resolve_path(&PathBuf::from(OsStr::from_bytes(b)), None, syscall)
+        resolve_path(
+            resolver,
+            &PathBuf::from(OsStr::from_bytes(b)),
+            None,
+            syscall,
+        )
     } else {
         None
     }
 }
 
-#[expect(clippy::too_many_lines)]
-pub(crate) fn summarize<I>(syscalls: I) -> anyhow::Result<Vec<ProgramAction>>
-where
-    I: IntoIterator<Item = anyhow::Result<Syscall>>,
-{
-    let mut actions = Vec::new();
-    let mut stats: HashMap<String, u64> = HashMap::new();
-    // Keep known socket protocols (per process) for bind handling, we don't care for the socket closings
-    // because the fd will be reused or never bound again
-    let mut known_sockets_proto: HashMap<(u32, i128), SocketProtocol> = HashMap::new();
-    for syscall in syscalls {
-        let syscall = syscall?;
+/// Extract port of IP socket address structure, 0 if the kernel picks it
+fn socket_address_port(members: &HashMap<String, Expression>) -> Option<u16> {
+    match members
+        .iter()
+        .find_map(|(k, v)| k.ends_with("_port").then_some(v))
+    {
+        Some(Expression::Macro { name, args }) if name == "htons" => match args.first() {
+            Some(Expression::Integer(IntegerExpression {
+                value: IntegerExpressionValue::Literal(port),
+                ..
+            })) => u16::try_from(*port).ok(),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Extract address of IP socket address structure
+fn socket_address_ip(members: &HashMap<String, Expression>) -> Option<IpAddr> {
+    // Formatted as `inet_addr("127.0.0.1")` or `inet_pton(AF_INET6, "::1", &sin6_addr)`
+    let Some(Expression::Macro { name, args }) =
+        members.get("sin_addr").or_else(|| members.get("sin6_addr"))
+    else {
+        return None;
+    };
+    let addr_idx = match name.as_str() {
+        "inet_addr" => 0,
+        "inet_pton" => 1,
+        _ => return None,
+    };
+    let Some(Expression::Buffer(BufferExpression { value, .. })) = args.get(addr_idx) else {
+        return None;
+    };
+    str::from_utf8(value).ok()?.parse().ok()
+}
+
+/// SCTP protocol number, for sockets created with an unnamed protocol argument
+const IPPROTO_SCTP: i128 = 132;
+
+/// Syscalls returning a new file descriptor
+pub(crate) const FD_CREATE_SYSCALLS: [&str; 34] = [
+    "accept",
+    "accept4",
+    "creat",
+    "dup",
+    "dup2",
+    "dup3",
+    "epoll_create",
+    "epoll_create1",
+    "eventfd",
+    "eventfd2",
+    "fanotify_init",
+    "fsmount",
+    "fsopen",
+    "fspick",
+    "inotify_init",
+    "inotify_init1",
+    "io_uring_setup",
+    "landlock_create_ruleset",
+    "memfd_create",
+    "memfd_secret",
+    "mq_open",
+    "open",
+    "open_by_handle_at",
+    "open_tree",
+    "openat",
+    "openat2",
+    "perf_event_open",
+    "pidfd_getfd",
+    "pidfd_open",
+    "signalfd",
+    "signalfd4",
+    "socket",
+    "timerfd_create",
+    "userfaultfd",
+];
+
+/// Path prefix of memfd file descriptors
+const MEMFD_PATH_PREFIX: &[u8] = b"/memfd:";
+
+/// Special keyring ids, with the keyring names
+const KEYRINGS: [(&str, &str); 6] = [
+    ("KEY_SPEC_THREAD_KEYRING", "thread"),
+    ("KEY_SPEC_PROCESS_KEYRING", "process"),
+    ("KEY_SPEC_SESSION_KEYRING", "session"),
+    ("KEY_SPEC_USER_KEYRING", "user"),
+    ("KEY_SPEC_USER_SESSION_KEYRING", "user-session"),
+    ("KEY_SPEC_GROUP_KEYRING", "group"),
+];
+
+/// Namespace flags, with the namespace names systemd uses
+// TODO time namespaces (CLONE_NEWTIME) are not modeled
+pub(crate) const NAMESPACE_FLAGS: [(&str, &str); 7] = [
+    ("CLONE_NEWCGROUP", "cgroup"),
+    ("CLONE_NEWIPC", "ipc"),
+    ("CLONE_NEWNET", "net"),
+    ("CLONE_NEWNS", "mnt"),
+    ("CLONE_NEWPID", "pid"),
+    ("CLONE_NEWUSER", "user"),
+    ("CLONE_NEWUTS", "uts"),
+];
+
+/// Syscalls creating a new task, returning its id
+pub(crate) const TASK_CREATE_SYSCALLS: [&str; 4] = ["clone", "clone3", "fork", "vfork"];
+
+/// Syscalls ending a task
+const TASK_EXIT_SYSCALLS: [&str; 2] = ["exit", "exit_group"];
+
+/// Peak resource usage tracking
+#[derive(Default)]
+struct ResourceUsage {
+    /// Highest file descriptor number seen
+    // TODO APPROXIMATION fds created by pipe/socketpair are not returned by the syscall, and are not counted
+    max_fd: Option<u64>,
+    /// Tasks currently running
+    // TODO APPROXIMATION threads killed by exit_group in another thread are only removed when they are reused
+    live_tasks: HashSet<u32>,
+    /// Highest number of tasks running at the same time
+    max_tasks: usize,
+}
+
+impl ResourceUsage {
+    fn add_syscall(&mut self, syscall: &Syscall) {
+        let name = syscall.name.as_str();
+        if FD_CREATE_SYSCALLS.contains(&name) {
+            if let Ok(fd) = u64::try_from(syscall.ret_val) {
+                self.max_fd = self.max_fd.max(Some(fd));
+            }
+        }
+        if TASK_EXIT_SYSCALLS.contains(&name) {
+            self.live_tasks.remove(&syscall.pid);
+        } else {
+            self.live_tasks.insert(syscall.pid);
+            if TASK_CREATE_SYSCALLS.contains(&name) {
+                if let Ok(pid) = u32::try_from(syscall.ret_val) {
+                    if pid > 0 {
+                        self.live_tasks.insert(pid);
+                    }
+                }
+            }
+        }
+        self.max_tasks = self.max_tasks.max(self.live_tasks.len());
+    }
+
+    fn actions(&self) -> Vec<ProgramAction> {
+        let mut actions = Vec::new();
+        if let Some(max_fd) = self.max_fd {
+            actions.push(ProgramAction::OpenFiles(max_fd + 1));
+        }
+        if self.max_tasks > 0 {
+            actions.push(ProgramAction::Tasks(self.max_tasks as u64));
+        }
+        actions
+    }
+}
+
+/// Skips syscalls identical to one already summarized recently, to reduce work for syscall heavy programs
+struct Sampler {
+    /// Minimum time between summarization of identical syscalls, in seconds
+    interval: f64,
+    /// Time since the start of profiling, in seconds
+    clock: f64,
+    /// Time of the last cleanup of `last_seen`
+    last_prune: f64,
+    /// Time each syscall key was last summarized
+    last_seen: HashMap<u64, f64>,
+}
+
+impl Sampler {
+    fn new(interval: Duration) -> Self {
+        Self {
+            interval: interval.as_secs_f64(),
+            clock: 0.0,
+            last_prune: 0.0,
+            last_seen: HashMap::new(),
+        }
+    }
+
+    /// Key identifying syscalls that would lead to the same actions, for the same process (relative paths depend on
+    /// its working directory).
+    /// Top level fds are identified by their metadata (fd paths...) only, other integer values (ids, pids...) are kept.
+    /// The return value is ignored, syscalls whose summary depends on it are never sampled.
+    fn key(syscall: &Syscall) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        syscall.name.hash(&mut hasher);
+        syscall.pid.hash(&mut hasher);
+        for arg in &syscall.args {
+            match arg {
+                Expression::Integer(IntegerExpression {
+                    value: IntegerExpressionValue::Literal(_),
+                    metadata: Some(metadata),
+                }) => metadata.hash(&mut hasher),
+                _ => arg.hash(&mut hasher),
+            }
+        }
+        hasher.finish()
+    }
+
+    /// Return true if the syscall should be summarized
+    fn sample(&mut self, syscall: &Syscall) -> bool {
+        self.clock += syscall.rel_ts;
+        if self.clock - self.last_prune > self.interval {
+            let (clock, interval) = (self.clock, self.interval);
+            self.last_seen.retain(|_, t| clock - *t < interval);
+            self.last_prune = clock;
+        }
+        match self.last_seen.entry(Self::key(syscall)) {
+            Entry::Occupied(mut e) => {
+                if self.clock - *e.get() < self.interval {
+                    false
+                } else {
+                    e.insert(self.clock);
+                    true
+                }
+            }
+            Entry::Vacant(e) => {
+                e.insert(self.clock);
+                true
+            }
+        }
+    }
+}
+
+/// Incremental syscall summarizer, that can be queried for intermediate results
+#[derive(Default)]
+pub(crate) struct Summarizer {
+    actions: Vec<ProgramAction>,
+    /// Time each action was added, in seconds since the start of profiling
+    action_times: Vec<f64>,
+    stats: HashMap<String, u64>,
+    /// Time each syscall was first seen, in seconds since the start of profiling
+    syscall_times: HashMap<String, f64>,
+    /// Time since the start of profiling, in seconds, from syscall relative timestamps
+    clock: f64,
+    /// Keep known socket families and protocols (per process) for bind/connect/listen/accept handling, we don't
+    /// care for the socket closings because the fd will be reused or never bound again
+    known_sockets: HashMap<(u32, i128), (SocketFamily, SocketProtocol)>,
+    /// Optional sampling of repeated syscalls
+    sampler: Option<Sampler>,
+    /// Executables already run, exec actions are only added once per path
+    exec_paths: HashSet<PathBuf>,
+    resource_usage: ResourceUsage,
+    path_resolver: PathResolver,
+}
+
+impl Summarizer {
+    /// Build a summarizer that only handles identical syscalls once per interval
+    pub(crate) fn with_sampling(interval: Duration) -> Self {
+        Self {
+            sampler: Some(Sampler::new(interval)),
+            ..Self::default()
+        }
+    }
+
+    /// Summarize a single syscall
+    pub(crate) fn add_syscall(&mut self, syscall: &Syscall) -> anyhow::Result<()> {
+        self.clock += syscall.rel_ts;
+        if !self.syscall_times.contains_key(&syscall.name) {
+            self.syscall_times.insert(syscall.name.clone(), self.clock);
+        }
+        let res = self.summarize_syscall(syscall);
+        self.action_times.resize(self.actions.len(), self.clock);
+        res
+    }
+
+    #[expect(clippy::too_many_lines)]
+    fn summarize_syscall(&mut self, syscall: &Syscall) -> anyhow::Result<()> {
+        let actions = &mut self.actions;
+        let known_sockets = &mut self.known_sockets;
+        let path_resolver = &mut self.path_resolver;
         log::trace!("{syscall:?}");
-        stats
+        self.stats
             .entry(syscall.name.clone())
             .and_modify(|c| *c += 1)
             .or_insert(1);
         let name = syscall.name.as_str();
+        let info = SYSCALL_MAP.get(name);
+        self.resource_usage.add_syscall(syscall);
 
-        match SYSCALL_MAP.get(name) {
+        // Socket creations are never skipped, because their returned fd is used to summarize later syscalls, and
+        // neither are working directory changes, which may fail, and change how later paths are resolved
+        let sampled = self.sampler.as_mut().is_none_or(|s| s.sample(syscall));
+        if !sampled
+            && !matches!(
+                info,
+                Some(SyscallInfo::Socket | SyscallInfo::SocketAccept { .. } | SyscallInfo::Chdir)
+            )
+        {
+            return Ok(());
+        }
+
+        match info {
+            Some(SyscallInfo::Chown {
+                fd_idx,
+                path_idx,
+                uid_idx,
+            }) => {
+                let path = if let Some(path_idx) = path_idx {
+                    let Some(Expression::Buffer(BufferExpression {
+                        value: b,
+                        type_: BufferType::Unknown,
+                    })) = syscall.args.get(*path_idx)
+                    else {
+                        anyhow::bail!("Unexpected args for {}: {:?}", name, syscall.args);
+                    };
+                    // fchownat with AT_EMPTY_PATH changes the owner of its directory fd
+                    resolve_path(
+                        path_resolver,
+                        Path::new(OsStr::from_bytes(b)),
+                        *fd_idx,
+                        syscall,
+                    )
+                } else {
+                    let path = fd_idx
+                        .and_then(|idx| syscall.args.get(idx))
+                        .and_then(|a| a.metadata())
+                        .map(|m| PathBuf::from(OsStr::from_bytes(m)))
+                        .ok_or_else(|| anyhow::anyhow!("Unexpected args for {name}"))?;
+                    resolve_path(path_resolver, &path, None, syscall)
+                };
+                let Some(path) = path else {
+                    return Ok(());
+                };
+                let uid = id_arg(syscall, *uid_idx)?;
+                let gid = id_arg(syscall, uid_idx + 1)?;
+                // Even without id changes, this updates the change time of the path
+                actions.push(ProgramAction::Write(path.clone()));
+                if uid.is_some() || gid.is_some() {
+                    actions.push(ProgramAction::ChangeOwner { path, uid, gid });
+                }
+            }
+            Some(SyscallInfo::Chdir) => {
+                // chdir takes a path, fchdir a fd
+                let path = match syscall.args.first() {
+                    Some(Expression::Buffer(BufferExpression {
+                        value: b,
+                        type_: BufferType::Unknown,
+                    })) => PathBuf::from(OsStr::from_bytes(b)),
+                    Some(Expression::Integer(IntegerExpression {
+                        metadata: Some(m), ..
+                    })) => PathBuf::from(OsStr::from_bytes(m)),
+                    _ => anyhow::bail!("Unexpected args for {}: {:?}", name, syscall.args),
+                };
+                if let Some(path) = resolve_path(path_resolver, &path, None, syscall) {
+                    actions.push(ProgramAction::Read(path));
+                }
+                if syscall.ret_val == 0 {
+                    path_resolver.working_dir_changed = true;
+                }
+            }
             Some(SyscallInfo::Open {
                 relfd_idx,
                 path_idx,
                 flags_idx,
             }) => {
+                // openat2 passes flags in a open_how structure, with path resolution flags
+                let (flags_arg, resolve_arg) = match syscall.args.get(*flags_idx) {
+                    Some(Expression::Struct(members)) => {
+                        (members.get("flags"), members.get("resolve"))
+                    }
+                    arg => (arg, None),
+                };
+                // Absolute paths are resolved from the directory fd, as if it was the root directory
+                let in_root = matches!(
+                    resolve_arg,
+                    Some(Expression::Integer(IntegerExpression { value: r, .. })) if r.is_flag_set("RESOLVE_IN_ROOT")
+                );
                 let (mut path, flags) = if let (
                     Some(Expression::Buffer(BufferExpression {
                         value: b,
                         type_: BufferType::Unknown,
                     })),
                     Some(Expression::Integer(IntegerExpression { value: e, .. })),
-                ) =
-                    (syscall.args.get(*path_idx), syscall.args.get(*flags_idx))
+                ) = (syscall.args.get(*path_idx), flags_arg)
                 {
                     (PathBuf::from(OsStr::from_bytes(b)), e)
                 } else {
                     anyhow::bail!("Unexpected args for {}: {:?}", name, syscall.args);
                 };
+                if in_root {
+                    if let Ok(rel_path) = path.strip_prefix("/") {
+                        path = rel_path.to_path_buf();
+                    }
+                }
 
-                path = if let Some(path) = resolve_path(&path, *relfd_idx, &syscall) {
+                path = if let Some(path) = resolve_path(path_resolver, &path, *relfd_idx, syscall) {
                     path
                 } else {
-                    continue;
+                    return Ok(());
                 };
 
                 if flags.is_flag_set("O_CREAT") {
                     actions.push(ProgramAction::Create(path.clone()));
                 }
-                if flags.is_flag_set("O_WRONLY")
-                    || flags.is_flag_set("O_RDWR")
-                    || flags.is_flag_set("O_TRUNC")
-                {
-                    actions.push(ProgramAction::Write(path.clone()));
-                }
-                if !flags.is_flag_set("O_WRONLY") {
-                    actions.push(ProgramAction::Read(path));
-                }
+                push_open_actions(actions, path, flags);
+            }
+            Some(SyscallInfo::OpenHandle {
+                mount_fd_idx,
+                flags_idx,
+            }) => {
+                let (
+                    Some(mount_path),
+                    Some(Expression::Integer(IntegerExpression { value: flags, .. })),
+                ) = (
+                    syscall
+                        .args
+                        .get(*mount_fd_idx)
+                        .and_then(Expression::metadata),
+                    syscall.args.get(*flags_idx),
+                )
+                else {
+                    anyhow::bail!("Unexpected args for {}: {:?}", name, syscall.args);
+                };
+                let Some(path) = resolve_path(
+                    path_resolver,
+                    &PathBuf::from(OsStr::from_bytes(mount_path)),
+                    None,
+                    syscall,
+                ) else {
+                    return Ok(());
+                };
+                // TODO APPROXIMATION
+                // The handle can reference any file of the filesystem the mount fd is on, which we can not know,
+                // so consider the mount fd path is accessed
+                push_open_actions(actions, path, flags);
             }
             Some(SyscallInfo::Rename {
                 relfd_src_idx,
@@ -542,10 +1851,10 @@ where
                 };
 
                 let (Some(path_src), Some(path_dst)) = (
-                    resolve_path(&path_src, *relfd_src_idx, &syscall),
-                    resolve_path(&path_dst, *relfd_dst_idx, &syscall),
+                    resolve_path(path_resolver, &path_src, *relfd_src_idx, syscall),
+                    resolve_path(path_resolver, &path_dst, *relfd_dst_idx, syscall),
                 ) else {
-                    continue;
+                    return Ok(());
                 };
 
                 let exchange = if let Some(flags_idx) = flags_idx {
@@ -569,6 +1878,34 @@ where
                 }
                 actions.push(ProgramAction::Write(path_dst.clone()));
             }
+            Some(SyscallInfo::CopyFd {
+                fd_in_idx,
+                fd_out_idx,
+            }) => {
+                let (Some(path_in), Some(path_out)) = (
+                    syscall.args.get(*fd_in_idx).and_then(Expression::metadata),
+                    syscall.args.get(*fd_out_idx).and_then(Expression::metadata),
+                ) else {
+                    anyhow::bail!("Unexpected args for {}: {:?}", name, syscall.args);
+                };
+                // Pipes and sockets have relative pseudo paths, which are ignored
+                if let Some(path) = resolve_path(
+                    path_resolver,
+                    &PathBuf::from(OsStr::from_bytes(path_in)),
+                    None,
+                    syscall,
+                ) {
+                    actions.push(ProgramAction::Read(path));
+                }
+                if let Some(path) = resolve_path(
+                    path_resolver,
+                    &PathBuf::from(OsStr::from_bytes(path_out)),
+                    None,
+                    syscall,
+                ) {
+                    actions.push(ProgramAction::Write(path));
+                }
+            }
             Some(SyscallInfo::StatFd { fd_idx }) => {
                 let mut path = syscall
                     .args
@@ -576,10 +1913,10 @@ where
                     .and_then(|a| a.metadata())
                     .map(|m| PathBuf::from(OsStr::from_bytes(m)))
                     .ok_or_else(|| anyhow::anyhow!("Unexpected args for {name}"))?;
-                path = if let Some(path) = resolve_path(&path, None, &syscall) {
+                path = if let Some(path) = resolve_path(path_resolver, &path, None, syscall) {
                     path
                 } else {
-                    continue;
+                    return Ok(());
                 };
                 actions.push(ProgramAction::Read(path));
             }
@@ -596,35 +1933,144 @@ where
                 } else {
                     anyhow::bail!("Unexpected args for {}: {:?}", name, syscall.args);
                 };
-                path = if let Some(path) = resolve_path(&path, *relfd_idx, &syscall) {
+                path = if let Some(path) = resolve_path(path_resolver, &path, *relfd_idx, syscall) {
                     path
                 } else {
-                    continue;
+                    return Ok(());
                 };
                 actions.push(ProgramAction::Read(path));
             }
-            Some(SyscallInfo::Network { sockaddr_idx }) => {
-                let (af, addr) =
-                    if let Some(Expression::Struct(members)) = syscall.args.get(*sockaddr_idx) {
-                        let Some(Expression::Integer(IntegerExpression {
-                            value: IntegerExpressionValue::NamedConst(af),
-                            ..
-                        })) = members.get("sa_family")
-                        else {
-                            anyhow::bail!("Unexpected args for {}: {:?}", name, syscall.args);
-                        };
-                        (af.as_str(), members)
+            Some(SyscallInfo::IoUring) => {
+                actions.push(ProgramAction::IoUring);
+            }
+            Some(SyscallInfo::Keyring) => {
+                for arg in &syscall.args {
+                    let Expression::Integer(IntegerExpression {
+                        value: IntegerExpressionValue::NamedConst(const_name),
+                        ..
+                    }) = arg
+                    else {
+                        continue;
+                    };
+                    // Joining a session keyring replaces the one set up by systemd
+                    let keyring = if const_name == "KEYCTL_JOIN_SESSION_KEYRING" {
+                        Some("session")
                     } else {
-                        // Can be NULL in some cases, ie AF_NETLINK sockets
+                        KEYRINGS
+                            .iter()
+                            .find(|(spec, _)| spec == const_name)
+                            .map(|(_, keyring)| *keyring)
+                    };
+                    if let Some(keyring) = keyring {
+                        actions.push(ProgramAction::KeyringUse(keyring.to_owned()));
+                    }
+                }
+            }
+            Some(SyscallInfo::Namespaces { flags_idx, join }) => {
+                let flags = match syscall.args.get(*flags_idx) {
+                    // strace shows clone arguments as named, and clone3 ones in a clone_args structure
+                    Some(Expression::Struct(members)) => members.get("flags"),
+                    arg => arg,
+                };
+                let Some(Expression::Integer(IntegerExpression { value: flags, .. })) = flags
+                else {
+                    anyhow::bail!("Unexpected args for {}: {:?}", name, syscall.args);
+                };
+                let mut namespaces: Vec<_> = NAMESPACE_FLAGS
+                    .iter()
+                    .filter(|(flag, _)| flags.is_flag_set(flag))
+                    .collect();
+                if *join && namespaces.is_empty() {
+                    namespaces.extend(NAMESPACE_FLAGS.iter());
+                }
+                actions.extend(
+                    namespaces
+                        .into_iter()
+                        .map(|(_, ns)| ProgramAction::NamespaceUse((*ns).to_owned())),
+                );
+            }
+            Some(SyscallInfo::Mount { src, dst }) => {
+                for (relfd_and_path, write) in [(src, false), (dst, true)] {
+                    let Some((relfd_idx, path_idx)) = relfd_and_path else {
                         continue;
                     };
+                    let path = if let Some(Expression::Buffer(BufferExpression {
+                        value: b,
+                        type_: BufferType::Unknown,
+                    })) = syscall.args.get(*path_idx)
+                    {
+                        PathBuf::from(OsStr::from_bytes(b))
+                    } else {
+                        anyhow::bail!("Unexpected args for {}: {:?}", name, syscall.args);
+                    };
+                    // Empty paths refer to the directory fd itself, which may be a detached tree
+                    // from open_tree or fsmount, not backed by a path
+                    if path.as_os_str().is_empty() {
+                        continue;
+                    }
+                    let Some(path) = resolve_path(path_resolver, &path, *relfd_idx, syscall) else {
+                        continue;
+                    };
+                    // The mount point is changed, even if its content is not
+                    actions.push(if write {
+                        ProgramAction::Write(path)
+                    } else {
+                        ProgramAction::Read(path)
+                    });
+                }
+            }
+            Some(SyscallInfo::MountConfig { key_idx }) => {
+                // Only string parameters have a buffer key and value, the block device or
+                // directory to mount is the "source" one
+                if let (
+                    Some(Expression::Buffer(BufferExpression { value: key, .. })),
+                    Some(Expression::Buffer(BufferExpression { value, .. })),
+                ) = (syscall.args.get(*key_idx), syscall.args.get(key_idx + 1))
+                {
+                    let path = PathBuf::from(OsStr::from_bytes(value));
+                    if key == b"source" && path.is_absolute() {
+                        actions.push(ProgramAction::Read(path));
+                    }
+                }
+            }
+            Some(SyscallInfo::Network {
+                sockaddr_idx,
+                in_msghdr,
+            }) => {
+                let mut sockaddr = syscall.args.get(*sockaddr_idx);
+                if *in_msghdr {
+                    sockaddr = if let Some(Expression::Struct(msghdr)) = sockaddr {
+                        msghdr.get("msg_name")
+                    } else {
+                        // Address of an unreadable structure
+                        None
+                    };
+                }
+                let (af, addr) = if let Some(Expression::Struct(members)) = sockaddr {
+                    let Some(Expression::Integer(IntegerExpression {
+                        value: IntegerExpressionValue::NamedConst(af),
+                        ..
+                    })) = members.get("sa_family")
+                    else {
+                        anyhow::bail!("Unexpected args for {}: {:?}", name, syscall.args);
+                    };
+                    (af.as_str(), members)
+                } else {
+                    // Can be NULL in some cases, ie AF_NETLINK sockets, or connected sockets with
+                    // sendmsg/recvmsg
+                    return Ok(());
+                };
 
                 #[expect(clippy::single_match)]
                 match af {
                     "AF_UNIX" => {
-                        if let Some(path) = socket_address_uds_path(addr, &syscall) {
+                        if let Some(socket_name) = socket_address_abstract_name(addr) {
+                            actions.push(ProgramAction::AbstractSocket(socket_name));
+                        } else if let Some(path) =
+                            socket_address_uds_path(path_resolver, addr, syscall)
+                        {
                             actions.push(ProgramAction::Read(path));
-                        };
+                        }
                     }
                     _ => (),
                 }
@@ -640,51 +2086,127 @@ where
                     let af = af
                         .parse()
                         .map_err(|()| anyhow::anyhow!("Unable to parse socket family {af:?}"))?;
-                    let local_port = match addr
-                        .iter()
-                        .find_map(|(k, v)| k.ends_with("_port").then_some(v))
-                    {
-                        Some(Expression::Macro {
-                            name: macro_name,
-                            args,
-                        }) if macro_name == "htons" => match args.first() {
-                            Some(Expression::Integer(IntegerExpression {
-                                value: IntegerExpressionValue::Literal(port_val),
-                                ..
-                            })) =>
-                            {
-                                #[expect(
-                                    clippy::cast_possible_truncation,
-                                    clippy::cast_sign_loss,
-                                    clippy::unwrap_used
-                                )]
-                                CountableSetSpecifier::One(NetworkPort(
-                                    (*port_val as u16).try_into().unwrap(),
-                                ))
-                            }
-                            _ => todo!(),
-                        },
-                        _ => CountableSetSpecifier::None,
+                    let local_port = match socket_address_port(addr).map(NonZeroU16::new) {
+                        Some(Some(port)) => CountableSetSpecifier::One(NetworkPort(port)),
+                        // Port 0 lets the kernel pick a free one
+                        Some(None) => CountableSetSpecifier::All,
+                        None => CountableSetSpecifier::None,
                     };
-                    if let Some(proto) = known_sockets_proto.get(&(syscall.pid, *fd)) {
+                    if let Some((_, proto)) = known_sockets.get(&(syscall.pid, *fd)) {
                         actions.push(ProgramAction::NetworkActivity(NetworkActivity {
                             af: SetSpecifier::One(af),
                             proto: SetSpecifier::One(proto.to_owned()),
                             kind: SetSpecifier::One(NetworkActivityKind::Bind),
                             local_port,
+                            remote_addr: None,
+                        }));
+                    }
+                } else if name == "connect" && af != "AF_UNSPEC" {
+                    // AF_UNSPEC dissolves the association of datagram sockets
+                    // TODO non blocking connects fail with EINPROGRESS, and are not reported by tracers
+                    let Some(Expression::Integer(IntegerExpression {
+                        value: IntegerExpressionValue::Literal(fd),
+                        ..
+                    })) = syscall.args.first()
+                    else {
+                        anyhow::bail!("Unexpected args for {}: {:?}", name, syscall.args);
+                    };
+                    let af = af
+                        .parse()
+                        .map_err(|()| anyhow::anyhow!("Unable to parse socket family {af:?}"))?;
+                    let remote_addr = socket_address_ip(addr)
+                        .zip(socket_address_port(addr))
+                        .map(|(ip, port)| SocketAddr::new(ip, port));
+                    if let Some((_, proto)) = known_sockets.get(&(syscall.pid, *fd)) {
+                        actions.push(ProgramAction::NetworkActivity(NetworkActivity {
+                            af: SetSpecifier::One(af),
+                            proto: SetSpecifier::One(proto.to_owned()),
+                            kind: SetSpecifier::One(NetworkActivityKind::Connect),
+                            // Local port is picked by the kernel
+                            local_port: CountableSetSpecifier::All,
+                            remote_addr,
                         }));
                     }
                 }
             }
-            Some(SyscallInfo::SetScheduler) => {
-                let Some(Expression::Integer(IntegerExpression { value: policy, .. })) =
+            Some(SyscallInfo::SetScheduler { in_sched_attr }) => {
+                let policy = if *in_sched_attr {
+                    match syscall.args.get(1) {
+                        Some(Expression::Struct(attr)) => attr.get("sched_policy"),
+                        // Address of an unreadable structure
+                        _ => return Ok(()),
+                    }
+                } else {
                     syscall.args.get(1)
+                };
+                let Some(Expression::Integer(IntegerExpression { value: policy, .. })) = policy
                 else {
                     anyhow::bail!("Unexpected args for {}: {:?}", name, syscall.args);
                 };
-                if policy.is_flag_set("SCHED_FIFO") | policy.is_flag_set("SCHED_RR") {
+                if ["SCHED_FIFO", "SCHED_RR", "SCHED_DEADLINE"]
+                    .iter()
+                    .any(|p| policy.is_flag_set(p))
+                {
                     actions.push(ProgramAction::SetRealtimeScheduler);
                 }
+                if is_own_process(syscall.args.first(), syscall.pid) {
+                    if let Some(policy) = policy
+                        .flags()
+                        .into_iter()
+                        .find(|f| f != "SCHED_RESET_ON_FORK")
+                    {
+                        actions.push(ProgramAction::SetSchedulingPolicy(policy));
+                    }
+                }
+            }
+            Some(SyscallInfo::CapSet) => {
+                // Address of an unreadable structure, or NULL
+                let (Some(Expression::Struct(header)), Some(Expression::Struct(data))) =
+                    (syscall.args.first(), syscall.args.get(1))
+                else {
+                    return Ok(());
+                };
+                if is_own_process(header.get("pid"), syscall.pid) {
+                    // Effective capabilities are a subset of the permitted ones
+                    if let Some(Expression::Integer(IntegerExpression {
+                        value: permitted, ..
+                    })) = data.get("permitted")
+                    {
+                        actions.extend(
+                            capability_names(permitted)
+                                .into_iter()
+                                .map(ProgramAction::CapabilityUse),
+                        );
+                    }
+                }
+            }
+            Some(SyscallInfo::SetCredentials { uid_idx, gid_idx }) => {
+                let uid = uid_idx.map(|idx| id_arg(syscall, idx)).transpose()?;
+                let gid = gid_idx.map(|idx| id_arg(syscall, idx)).transpose()?;
+                actions.push(ProgramAction::SetCredentials {
+                    uid: uid.flatten(),
+                    gid: gid.flatten(),
+                });
+            }
+            Some(SyscallInfo::SetPriority) => {
+                let (
+                    Some(Expression::Integer(IntegerExpression { value: which, .. })),
+                    Some(Expression::Integer(IntegerExpression {
+                        value: IntegerExpressionValue::Literal(nice),
+                        ..
+                    })),
+                ) = (syscall.args.first(), syscall.args.get(2))
+                else {
+                    anyhow::bail!("Unexpected args for {}: {:?}", name, syscall.args);
+                };
+                // Process groups and users are not the program's own processes
+                let prio_process = matches!(which, IntegerExpressionValue::NamedConst(w) if w == "PRIO_PROCESS")
+                    || matches!(which, IntegerExpressionValue::Literal(0));
+                if prio_process && is_own_process(syscall.args.get(1), syscall.pid) {
+                    if let Ok(nice) = i32::try_from(*nice) {
+                        actions.push(ProgramAction::SetNice(nice));
+                    }
+                }
             }
             Some(SyscallInfo::Socket) => {
                 let af = if let Some(Expression::Integer(IntegerExpression {
@@ -712,18 +2234,202 @@ where
                         .ok_or_else(|| {
                             anyhow::anyhow!("Unable to parse socket protocol from flags {flags:?}")
                         })?;
-                let proto = proto_flag.parse::<SocketProtocol>().map_err(|_e| {
+                let mut proto = proto_flag.parse::<SocketProtocol>().map_err(|_e| {
                     anyhow::anyhow!("Unable to parse socket protocol {proto_flag:?}")
                 })?;
-                known_sockets_proto.insert((syscall.pid, syscall.ret_val), proto.clone());
+                // SCTP uses stream (one to one) or sequential packet (one to many) sockets, the protocol argument
+                // is not named by the ptrace tracer because its meaning depends on the family
+                if matches!(af, SocketFamily::Ipv4 | SocketFamily::Ipv6)
+                    && matches!(&proto, SocketProtocol::Tcp | SocketProtocol::Other(_))
+                    && matches!(syscall.args.get(2), Some(Expression::Integer(IntegerExpression { value, .. }))
+                        if value.is_flag_set("IPPROTO_SCTP")
+                            || matches!(value, IntegerExpressionValue::Literal(IPPROTO_SCTP)))
+                {
+                    proto = SocketProtocol::Sctp;
+                }
+                if name == "socket" {
+                    known_sockets
+                        .insert((syscall.pid, syscall.ret_val), (af.clone(), proto.clone()));
+                }
 
                 actions.push(ProgramAction::NetworkActivity(NetworkActivity {
                     af: SetSpecifier::One(af),
                     proto: SetSpecifier::One(proto),
                     kind: SetSpecifier::One(NetworkActivityKind::SocketCreation),
                     local_port: CountableSetSpecifier::All,
+                    remote_addr: None,
+                }));
+            }
+            Some(SyscallInfo::SocketListen { fd_idx }) => {
+                let Some(Expression::Integer(IntegerExpression {
+                    value: IntegerExpressionValue::Literal(fd),
+                    ..
+                })) = syscall.args.get(*fd_idx)
+                else {
+                    anyhow::bail!("Unexpected args for {}: {:?}", name, syscall.args);
+                };
+                if let Some((af, proto)) = known_sockets.get(&(syscall.pid, *fd)) {
+                    actions.push(ProgramAction::NetworkActivity(NetworkActivity {
+                        af: SetSpecifier::One(af.to_owned()),
+                        proto: SetSpecifier::One(proto.to_owned()),
+                        kind: SetSpecifier::One(NetworkActivityKind::Listen),
+                        // The listening port is the bound one, or picked by the kernel
+                        local_port: CountableSetSpecifier::All,
+                        remote_addr: None,
+                    }));
+                }
+            }
+            Some(SyscallInfo::SocketAccept {
+                fd_idx,
+                sockaddr_idx,
+            }) => {
+                let Some(Expression::Integer(IntegerExpression {
+                    value: IntegerExpressionValue::Literal(fd),
+                    ..
+                })) = syscall.args.get(*fd_idx)
+                else {
+                    anyhow::bail!("Unexpected args for {}: {:?}", name, syscall.args);
+                };
+                // The listening socket can be inherited, ie. with socket activation, in which case its family and
+                // protocol are unknown
+                let Some((af, proto)) = known_sockets.get(&(syscall.pid, *fd)).cloned() else {
+                    return Ok(());
+                };
+                // Peer address can be NULL if the caller does not need it
+                let remote_addr =
+                    if let Some(Expression::Struct(addr)) = syscall.args.get(*sockaddr_idx) {
+                        socket_address_ip(addr)
+                            .zip(socket_address_port(addr))
+                            .map(|(ip, port)| SocketAddr::new(ip, port))
+                    } else {
+                        None
+                    };
+                known_sockets.insert((syscall.pid, syscall.ret_val), (af.clone(), proto.clone()));
+                actions.push(ProgramAction::NetworkActivity(NetworkActivity {
+                    af: SetSpecifier::One(af),
+                    proto: SetSpecifier::One(proto),
+                    kind: SetSpecifier::One(NetworkActivityKind::Accept),
+                    local_port: CountableSetSpecifier::All,
+                    remote_addr,
                 }));
             }
+            Some(SyscallInfo::Exec {
+                relfd_idx,
+                path_idx,
+            }) => {
+                let mut path = if let Some(Expression::Buffer(BufferExpression {
+                    value: b,
+                    type_: BufferType::Unknown,
+                })) = syscall.args.get(*path_idx)
+                {
+                    PathBuf::from(OsStr::from_bytes(b))
+                } else {
+                    anyhow::bail!("Unexpected args for {}: {:?}", name, syscall.args);
+                };
+                path = if let Some(path) = resolve_path(path_resolver, &path, *relfd_idx, syscall) {
+                    path
+                } else {
+                    return Ok(());
+                };
+                if !self.exec_paths.insert(path.clone()) {
+                    return Ok(());
+                }
+                // TODO APPROXIMATION
+                // This relies on the FS state at profiling time, and ignores file capabilities
+                let setid_bits = (Mode::S_ISUID | Mode::S_ISGID).bits();
+                if path.metadata().is_ok_and(|m| (m.mode() & setid_bits) != 0) {
+                    actions.push(ProgramAction::SetuidExec(path.clone()));
+                }
+                actions.push(ProgramAction::Exec(path));
+            }
+            Some(SyscallInfo::Ioctl {
+                fd_idx,
+                request_idx,
+            }) => {
+                let (
+                    Some(Expression::Integer(IntegerExpression { metadata, .. })),
+                    Some(Expression::Integer(IntegerExpression { value: request, .. })),
+                ) = (syscall.args.get(*fd_idx), syscall.args.get(*request_idx))
+                else {
+                    anyhow::bail!("Unexpected args for {}: {:?}", name, syscall.args);
+                };
+                // Requests are only raw numbers if strace does not decode them
+                if let (Some(path), IntegerExpressionValue::Literal(request)) = (metadata, request)
+                {
+                    let path = Path::new(OsStr::from_bytes(path));
+                    if path.starts_with("/dev") {
+                        if let Ok(class) = u8::try_from((request >> 8) & 0xff) {
+                            actions.push(ProgramAction::DeviceIoctl {
+                                path: path.to_owned(),
+                                class,
+                            });
+                        }
+                    }
+                }
+            }
+            Some(SyscallInfo::Lock {
+                fd_idx,
+                op_idx,
+                fcntl,
+            }) => {
+                let Some(Expression::Integer(IntegerExpression { value: op, .. })) =
+                    syscall.args.get(*op_idx)
+                else {
+                    anyhow::bail!("Unexpected args for {}: {:?}", name, syscall.args);
+                };
+                // Exclusive locks are taken to write, shared ones to read
+                let exclusive = if *fcntl {
+                    const LOCK_CMDS: [&str; 6] = [
+                        "F_SETLK",
+                        "F_SETLKW",
+                        "F_SETLK64",
+                        "F_SETLKW64",
+                        "F_OFD_SETLK",
+                        "F_OFD_SETLKW",
+                    ];
+                    if !LOCK_CMDS.iter().any(|c| op.is_flag_set(c)) {
+                        return Ok(());
+                    }
+                    match syscall.args.get(op_idx + 1) {
+                        Some(Expression::Struct(lock)) => match lock.get("l_type") {
+                            Some(Expression::Integer(IntegerExpression {
+                                value: l_type, ..
+                            })) => {
+                                if l_type.is_flag_set("F_WRLCK") {
+                                    true
+                                } else if l_type.is_flag_set("F_RDLCK") {
+                                    false
+                                } else {
+                                    return Ok(());
+                                }
+                            }
+                            _ => return Ok(()),
+                        },
+                        // Address of an unreadable structure
+                        _ => return Ok(()),
+                    }
+                } else if op.is_flag_set("LOCK_EX") {
+                    true
+                } else if op.is_flag_set("LOCK_SH") {
+                    false
+                } else {
+                    return Ok(());
+                };
+                let Some(path) = syscall
+                    .args
+                    .get(*fd_idx)
+                    .and_then(Expression::metadata)
+                    .map(|m| PathBuf::from(OsStr::from_bytes(m)))
+                    .and_then(|p| resolve_path(path_resolver, &p, None, syscall))
+                else {
+                    return Ok(());
+                };
+                actions.push(if exclusive {
+                    ProgramAction::Write(path)
+                } else {
+                    ProgramAction::Read(path)
+                });
+            }
             Some(SyscallInfo::Mknod { mode_idx }) => {
                 const PRIVILEGED_ST_MODES: [&str; 2] = ["S_IFBLK", "S_IFCHR"];
                 if let Some(Expression::Integer(mode)) = syscall.args.get(*mode_idx) {
@@ -737,44 +2443,64 @@ where
                     anyhow::bail!("Unexpected args for {}: {:?}", name, syscall.args);
                 }
             }
-            Some(SyscallInfo::Mmap { prot_idx }) => {
+            Some(SyscallInfo::Mmap { prot_idx, fd_idx }) => {
                 let Some(Expression::Integer(IntegerExpression { value: prot, .. })) =
                     syscall.args.get(*prot_idx)
                 else {
                     anyhow::bail!("Unexpected args for {}: {:?}", name, syscall.args);
                 };
-                if prot.is_flag_set("PROT_WRITE") && prot.is_flag_set("PROT_EXEC") {
+                // A memfd stays writable through its fd or other mappings, so mapping it executable is code
+                // generated at runtime, like with a writable and executable mapping (ie. JIT double mapping)
+                let memfd = fd_idx.and_then(|i| syscall.args.get(i)).is_some_and(|fd| {
+                    matches!(fd, Expression::Integer(IntegerExpression {
+                        metadata: Some(path),
+                        ..
+                    }) if path.starts_with(MEMFD_PATH_PREFIX))
+                });
+                if prot.is_flag_set("PROT_EXEC") && (prot.is_flag_set("PROT_WRITE") || memfd) {
+                    actions.push(ProgramAction::WriteExecuteMemoryMapping);
+                }
+            }
+            Some(SyscallInfo::MmapProtect { prot_idx }) => {
+                let Some(Expression::Integer(IntegerExpression { value: prot, .. })) =
+                    syscall.args.get(*prot_idx)
+                else {
+                    anyhow::bail!("Unexpected args for {}: {:?}", name, syscall.args);
+                };
+                // Making existing mappings executable is denied even without write access, since they may have been
+                // written to before (ie. a memfd mapped writable, then made executable)
+                if prot.is_flag_set("PROT_EXEC") {
                     actions.push(ProgramAction::WriteExecuteMemoryMapping);
                 }
             }
             None => match name {
-                "epoll_ctl" => {
+                "epoll_ctl"
                     if syscall.args.get(1).is_some_and(|op| {
                         matches!(op, Expression::Integer(IntegerExpression {
-                            value: IntegerExpressionValue::NamedConst(op_name),
-                            ..
-                        }) if op_name == "EPOLL_CTL_ADD")
-                    }) {
-                        // Get the event
-                        let evt_arg = syscall
-                            .args
-                            .get(3)
-                            .ok_or_else(|| anyhow::anyhow!("Missing epoll event argument"))?;
-                        let evt_flags = if let Expression::Struct(evt_struct) = evt_arg {
-                            let evt_member = evt_struct.get("events").ok_or_else(|| {
-                                anyhow::anyhow!("Missing epoll events struct member")
-                            })?;
-                            if let Expression::Integer(ie) = evt_member {
-                                ie
-                            } else {
-                                anyhow::bail!("Invalid epoll struct member");
-                            }
+                        value: IntegerExpressionValue::NamedConst(op_name),
+                        ..
+                    }) if op_name == "EPOLL_CTL_ADD")
+                    }) =>
+                {
+                    // Get the event
+                    let evt_arg = syscall
+                        .args
+                        .get(3)
+                        .ok_or_else(|| anyhow::anyhow!("Missing epoll event argument"))?;
+                    let evt_flags = if let Expression::Struct(evt_struct) = evt_arg {
+                        let evt_member = evt_struct
+                            .get("events")
+                            .ok_or_else(|| anyhow::anyhow!("Missing epoll events struct member"))?;
+                        if let Expression::Integer(ie) = evt_member {
+                            ie
                         } else {
-                            anyhow::bail!("Invalid epoll event argument");
-                        };
-                        if evt_flags.value.is_flag_set("EPOLLWAKEUP") {
-                            actions.push(ProgramAction::Wakeup);
+                            anyhow::bail!("Invalid epoll struct member");
                         }
+                    } else {
+                        anyhow::bail!("Invalid epoll event argument");
+                    };
+                    if evt_flags.value.is_flag_set("EPOLLWAKEUP") {
+                        actions.push(ProgramAction::Wakeup);
                     }
                 }
                 "timer_create" => {
@@ -794,31 +2520,157 @@ where
                 _ => {}
             },
         }
+        Ok(())
+    }
+
+    /// Get the actions summarized so far, in the order they were added, without post processing
+    pub(crate) fn raw_actions(&self) -> &[ProgramAction] {
+        &self.actions
     }
 
-    // Almost free optimization
-    actions.dedup();
+    /// Get the actions summarized so far, with the time they were added, in seconds since the start of profiling
+    pub(crate) fn timed_actions(&self) -> impl Iterator<Item = (f64, &ProgramAction)> {
+        self.action_times.iter().copied().zip(&self.actions)
+    }
 
-    // Create single action with all syscalls for efficient handling of seccomp filters
-    actions.push(ProgramAction::Syscalls(stats.keys().cloned().collect()));
+    /// Get the syscalls seen so far, with the time they were first seen, in seconds since the start of profiling
+    pub(crate) fn timed_syscalls(&self) -> impl Iterator<Item = (f64, &str)> {
+        self.syscall_times.iter().map(|(s, t)| (*t, s.as_str()))
+    }
 
-    // Report stats
-    let mut syscall_names = stats.keys().collect::<Vec<_>>();
-    syscall_names.sort();
-    for syscall_name in syscall_names {
-        #[expect(clippy::unwrap_used)]
-        let count = stats.get(syscall_name).unwrap();
-        log::debug!("{:24} {: >12}", format!("{syscall_name}:"), count);
+    /// Number of distinct syscalls seen so far
+    pub(crate) fn syscall_count(&self) -> usize {
+        self.stats.len()
+    }
+
+    /// Number of syscalls seen so far
+    pub(crate) fn syscall_total(&self) -> u64 {
+        self.stats.values().sum()
+    }
+
+    /// Names of the syscalls seen so far
+    pub(crate) fn syscall_names(&self) -> impl Iterator<Item = &str> {
+        self.stats.keys().map(String::as_str)
+    }
+
+    /// Get the actions summarized so far
+    pub(crate) fn actions(&self) -> Vec<ProgramAction> {
+        let mut actions = self.actions.clone();
+
+        // Almost free optimization
+        actions.dedup();
+
+        // Create single action with all syscalls for efficient handling of seccomp filters
+        actions.push(ProgramAction::Syscalls(
+            self.stats.keys().cloned().collect(),
+        ));
+
+        if let Some(working_dir) = &self.path_resolver.initial_working_dir {
+            actions.push(ProgramAction::WorkingDirectory(working_dir.clone()));
+        }
+
+        actions.extend(self.resource_usage.actions());
+
+        actions
+    }
+
+    /// Report stats, and get the final actions
+    pub(crate) fn finish(self) -> Vec<ProgramAction> {
+        let mut syscall_names = self.stats.keys().collect::<Vec<_>>();
+        syscall_names.sort();
+        for syscall_name in syscall_names {
+            #[expect(clippy::unwrap_used)]
+            let count = self.stats.get(syscall_name).unwrap();
+            log::debug!("{:24} {: >12}", format!("{syscall_name}:"), count);
+        }
+
+        self.actions()
     }
+}
 
-    Ok(actions)
+#[cfg_attr(not(test), expect(dead_code))]
+pub(crate) fn summarize<I>(syscalls: I) -> anyhow::Result<Vec<ProgramAction>>
+where
+    I: IntoIterator<Item = anyhow::Result<Syscall>>,
+{
+    let mut summarizer = Summarizer::default();
+    for syscall in syscalls {
+        summarizer.add_syscall(&syscall?)?;
+    }
+    Ok(summarizer.finish())
 }
 
 #[expect(clippy::unreadable_literal, clippy::shadow_unrelated)]
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::strace::*;
+    use crate::{strace::*, systemd::SYSCALL_CLASSES};
+
+    /// Integer argument from a named constant
+    fn named(name: &str) -> Expression {
+        Expression::Integer(IntegerExpression {
+            value: IntegerExpressionValue::NamedConst(name.to_owned()),
+            metadata: None,
+        })
+    }
+
+    /// Integer argument from a literal value
+    fn literal(value: i128) -> Expression {
+        Expression::Integer(IntegerExpression {
+            value: IntegerExpressionValue::Literal(value),
+            metadata: None,
+        })
+    }
+
+    /// Integer argument from or'ed named constants
+    fn flags(names: &[&str]) -> Expression {
+        Expression::Integer(IntegerExpression {
+            value: IntegerExpressionValue::BinaryOr(
+                names
+                    .iter()
+                    .map(|n| IntegerExpressionValue::NamedConst((*n).to_owned()))
+                    .collect(),
+            ),
+            metadata: None,
+        })
+    }
+
+    /// File descriptor argument, with its path as decoded by strace
+    fn fd(value: i128, path: &str) -> Expression {
+        Expression::Integer(IntegerExpression {
+            value: IntegerExpressionValue::Literal(value),
+            metadata: Some(path.as_bytes().to_vec()),
+        })
+    }
+
+    /// String argument
+    fn buffer(s: &str) -> Expression {
+        Expression::Buffer(BufferExpression {
+            value: s.as_bytes().to_vec(),
+            type_: BufferType::Unknown,
+        })
+    }
+
+    /// Syscall of the traced process, returning a given value, as a summarize input item
+    #[expect(clippy::unnecessary_wraps)]
+    fn syscall_returning(
+        name: &str,
+        args: Vec<Expression>,
+        ret_val: i128,
+    ) -> anyhow::Result<Syscall> {
+        Ok(Syscall {
+            pid: 1234,
+            rel_ts: 0.000_010,
+            name: name.to_owned(),
+            args,
+            ret_val,
+        })
+    }
+
+    /// Successful syscall of the traced process
+    fn syscall(name: &str, args: Vec<Expression>) -> anyhow::Result<Syscall> {
+        syscall_returning(name, args, 0)
+    }
 
     #[test]
     fn test_is_socket_or_pipe_pseudo_path() {
@@ -868,7 +2720,395 @@ mod tests {
                 ProgramAction::Write(temp_dir_src.path().join("a")),
                 ProgramAction::Create(temp_dir_dst.path().join("b")),
                 ProgramAction::Write(temp_dir_dst.path().join("b")),
-                ProgramAction::Syscalls(["renameat".to_owned()].into())
+                ProgramAction::Syscalls(["renameat".to_owned()].into()),
+                ProgramAction::WorkingDirectory(temp_dir_src.path().to_path_buf()),
+                ProgramAction::Tasks(1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_working_directory() {
+        let _ = simple_logger::SimpleLogger::new().init();
+
+        let openat = |cwd: &str, path: &str| {
+            Ok(Syscall {
+                pid: 1068781,
+                rel_ts: 0.000083,
+                name: "openat".to_owned(),
+                args: vec![
+                    Expression::Integer(IntegerExpression {
+                        value: IntegerExpressionValue::NamedConst("AT_FDCWD".to_owned()),
+                        metadata: Some(cwd.as_bytes().to_vec()),
+                    }),
+                    Expression::Buffer(BufferExpression {
+                        value: path.as_bytes().to_vec(),
+                        type_: BufferType::Unknown,
+                    }),
+                    Expression::Integer(IntegerExpression {
+                        value: IntegerExpressionValue::NamedConst("O_RDONLY".to_owned()),
+                        metadata: None,
+                    }),
+                ],
+                ret_val: 3,
+            })
+        };
+        let syscalls = [
+            openat("/srv/app", "conf/app.toml"),
+            Ok(Syscall {
+                pid: 1068781,
+                rel_ts: 0.000083,
+                name: "chdir".to_owned(),
+                args: vec![Expression::Buffer(BufferExpression {
+                    value: "/var/lib/app".as_bytes().to_vec(),
+                    type_: BufferType::Unknown,
+                })],
+                ret_val: 0,
+            }),
+            openat("/var/lib/app", "db"),
+        ];
+        assert_eq!(
+            summarize(syscalls).unwrap(),
+            vec![
+                ProgramAction::Read("/srv/app/conf/app.toml".into()),
+                ProgramAction::Read("/var/lib/app".into()),
+                ProgramAction::Read("/var/lib/app/db".into()),
+                ProgramAction::Syscalls(["chdir".to_owned(), "openat".to_owned()].into()),
+                ProgramAction::WorkingDirectory("/srv/app".into()),
+                ProgramAction::OpenFiles(4),
+                ProgramAction::Tasks(1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_musl_syscalls() {
+        let _ = simple_logger::SimpleLogger::new().init();
+
+        let cwd = || {
+            Expression::Integer(IntegerExpression {
+                value: IntegerExpressionValue::NamedConst("AT_FDCWD".to_owned()),
+                metadata: Some("/".as_bytes().to_vec()),
+            })
+        };
+        let syscalls = [
+            syscall(
+                "openat2",
+                vec![
+                    cwd(),
+                    buffer("/srv/app/db"),
+                    Expression::Struct(HashMap::from([(
+                        "flags".to_owned(),
+                        flags(&["O_RDWR", "O_CREAT"]),
+                    )])),
+                    literal(24),
+                ],
+            ),
+            syscall(
+                "statx",
+                vec![
+                    cwd(),
+                    buffer("/etc/app.conf"),
+                    named("AT_STATX_SYNC_AS_STAT"),
+                    named("STATX_BASIC_STATS"),
+                ],
+            ),
+            syscall(
+                "faccessat2",
+                vec![cwd(), buffer("/usr/bin/helper"), named("X_OK"), literal(0)],
+            ),
+        ];
+        assert_eq!(
+            summarize(syscalls).unwrap(),
+            vec![
+                ProgramAction::Create("/srv/app/db".into()),
+                ProgramAction::Write("/srv/app/db".into()),
+                ProgramAction::Read("/srv/app/db".into()),
+                ProgramAction::Read("/etc/app.conf".into()),
+                ProgramAction::Read("/usr/bin/helper".into()),
+                ProgramAction::Syscalls(
+                    [
+                        "faccessat2".to_owned(),
+                        "openat2".to_owned(),
+                        "statx".to_owned()
+                    ]
+                    .into()
+                ),
+                ProgramAction::OpenFiles(1),
+                ProgramAction::Tasks(1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_copy_fd() {
+        let _ = simple_logger::SimpleLogger::new().init();
+
+        let syscalls = [
+            Ok(Syscall {
+                pid: 598056,
+                rel_ts: 0.000036,
+                name: "sendfile".to_owned(),
+                args: vec![
+                    fd(5, "socket:[4075612]"),
+                    fd(6, "/srv/www/index.html"),
+                    literal(0),
+                    literal(4096),
+                ],
+                ret_val: 4096,
+            }),
+            Ok(Syscall {
+                pid: 598056,
+                rel_ts: 0.000037,
+                name: "copy_file_range".to_owned(),
+                args: vec![
+                    fd(3, "/var/lib/app/db"),
+                    literal(0),
+                    fd(4, "/backup/db"),
+                    literal(0),
+                    literal(65536),
+                    literal(0),
+                ],
+                ret_val: 65536,
+            }),
+        ];
+        assert_eq!(
+            summarize(syscalls).unwrap(),
+            vec![
+                ProgramAction::Read("/srv/www/index.html".into()),
+                ProgramAction::Read("/var/lib/app/db".into()),
+                ProgramAction::Write("/backup/db".into()),
+                ProgramAction::Syscalls(
+                    ["copy_file_range".to_owned(), "sendfile".to_owned()].into()
+                ),
+                ProgramAction::Tasks(1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_getdents64() {
+        let _ = simple_logger::SimpleLogger::new().init();
+
+        let syscalls = [Ok(Syscall {
+            pid: 598056,
+            rel_ts: 0.000036,
+            name: "getdents64".to_owned(),
+            args: vec![
+                Expression::Integer(IntegerExpression {
+                    value: IntegerExpressionValue::Literal(3),
+                    metadata: Some("/var/log/app".as_bytes().to_vec()),
+                }),
+                Expression::Buffer(BufferExpression {
+                    value: vec![],
+                    type_: BufferType::Unknown,
+                }),
+                Expression::Integer(IntegerExpression {
+                    value: IntegerExpressionValue::Literal(32768),
+                    metadata: None,
+                }),
+            ],
+            ret_val: 120,
+        })];
+        assert_eq!(
+            summarize(syscalls).unwrap(),
+            vec![
+                ProgramAction::Read("/var/log/app".into()),
+                ProgramAction::Syscalls(["getdents64".to_owned()].into()),
+                ProgramAction::Tasks(1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_event_fds() {
+        let _ = simple_logger::SimpleLogger::new().init();
+
+        let syscalls = [
+            syscall_returning("eventfd2", vec![literal(0), literal(0o2_000_000)], 3),
+            syscall_returning(
+                "signalfd4",
+                vec![
+                    literal(-1),
+                    Expression::Collection {
+                        complement: false,
+                        values: vec![],
+                    },
+                    literal(8),
+                    literal(0o2_000_000),
+                ],
+                4,
+            ),
+            syscall_returning("timerfd_create", vec![literal(1), literal(0o2_000_000)], 5),
+            syscall_returning(
+                "timerfd_settime",
+                vec![
+                    fd(5, "anon_inode:[timerfd]"),
+                    literal(0),
+                    Expression::Struct(HashMap::new()),
+                    literal(0),
+                ],
+                0,
+            ),
+            syscall_returning(
+                "read",
+                vec![
+                    fd(4, "anon_inode:[signalfd]"),
+                    Expression::Struct(HashMap::new()),
+                    literal(128),
+                ],
+                128,
+            ),
+        ];
+        let actions = summarize(syscalls).unwrap();
+        assert_eq!(
+            actions,
+            vec![
+                ProgramAction::Syscalls(
+                    [
+                        "eventfd2".to_owned(),
+                        "read".to_owned(),
+                        "signalfd4".to_owned(),
+                        "timerfd_create".to_owned(),
+                        "timerfd_settime".to_owned(),
+                    ]
+                    .into()
+                ),
+                ProgramAction::OpenFiles(6),
+                ProgramAction::Tasks(1),
+            ]
+        );
+        // All of them are grouped, so that SystemCallFilter keeps what they need
+        let ProgramAction::Syscalls(names) = &actions[0] else {
+            unreachable!();
+        };
+        for name in names {
+            assert!(
+                SYSCALL_CLASSES.values().any(|c| c.contains(name.as_str())),
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_openat2_in_root() {
+        let _ = simple_logger::SimpleLogger::new().init();
+
+        let syscalls = [Ok(Syscall {
+            pid: 598056,
+            rel_ts: 0.000036,
+            name: "openat2".to_owned(),
+            args: vec![
+                Expression::Integer(IntegerExpression {
+                    value: IntegerExpressionValue::Literal(3),
+                    metadata: Some("/var/lib/machines/foo".as_bytes().to_vec()),
+                }),
+                Expression::Buffer(BufferExpression {
+                    value: "/etc/os-release".as_bytes().to_vec(),
+                    type_: BufferType::Unknown,
+                }),
+                Expression::Struct(HashMap::from([
+                    (
+                        "flags".to_owned(),
+                        Expression::Integer(IntegerExpression {
+                            value: IntegerExpressionValue::NamedConst("O_RDONLY".to_owned()),
+                            metadata: None,
+                        }),
+                    ),
+                    (
+                        "resolve".to_owned(),
+                        Expression::Integer(IntegerExpression {
+                            value: IntegerExpressionValue::BinaryOr(vec![
+                                IntegerExpressionValue::NamedConst("RESOLVE_IN_ROOT".to_owned()),
+                                IntegerExpressionValue::NamedConst(
+                                    "RESOLVE_NO_MAGICLINKS".to_owned(),
+                                ),
+                            ]),
+                            metadata: None,
+                        }),
+                    ),
+                ])),
+                Expression::Integer(IntegerExpression {
+                    value: IntegerExpressionValue::Literal(24),
+                    metadata: None,
+                }),
+            ],
+            ret_val: 4,
+        })];
+        assert_eq!(
+            summarize(syscalls).unwrap(),
+            vec![
+                ProgramAction::Read("/var/lib/machines/foo/etc/os-release".into()),
+                ProgramAction::Syscalls(["openat2".to_owned()].into()),
+                ProgramAction::OpenFiles(5),
+                ProgramAction::Tasks(1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_file_handles() {
+        let _ = simple_logger::SimpleLogger::new().init();
+
+        let syscalls = [
+            Ok(Syscall {
+                pid: 598056,
+                rel_ts: 0.000036,
+                name: "name_to_handle_at".to_owned(),
+                args: vec![
+                    Expression::Integer(IntegerExpression {
+                        value: IntegerExpressionValue::NamedConst("AT_FDCWD".to_owned()),
+                        metadata: Some("/".as_bytes().to_vec()),
+                    }),
+                    Expression::Buffer(BufferExpression {
+                        value: "/srv/data/file".as_bytes().to_vec(),
+                        type_: BufferType::Unknown,
+                    }),
+                    Expression::Struct(HashMap::new()),
+                    Expression::Buffer(BufferExpression {
+                        value: vec![],
+                        type_: BufferType::Unknown,
+                    }),
+                    Expression::Integer(IntegerExpression {
+                        value: IntegerExpressionValue::Literal(0),
+                        metadata: None,
+                    }),
+                ],
+                ret_val: 0,
+            }),
+            Ok(Syscall {
+                pid: 598056,
+                rel_ts: 0.000037,
+                name: "open_by_handle_at".to_owned(),
+                args: vec![
+                    Expression::Integer(IntegerExpression {
+                        value: IntegerExpressionValue::Literal(3),
+                        metadata: Some("/srv".as_bytes().to_vec()),
+                    }),
+                    Expression::Struct(HashMap::new()),
+                    Expression::Integer(IntegerExpression {
+                        value: IntegerExpressionValue::NamedConst("O_RDWR".to_owned()),
+                        metadata: None,
+                    }),
+                ],
+                ret_val: 4,
+            }),
+        ];
+        assert_eq!(
+            summarize(syscalls).unwrap(),
+            vec![
+                ProgramAction::Read("/srv/data/file".into()),
+                ProgramAction::Write("/srv".into()),
+                ProgramAction::Read("/srv".into()),
+                ProgramAction::Syscalls(
+                    [
+                        "name_to_handle_at".to_owned(),
+                        "open_by_handle_at".to_owned()
+                    ]
+                    .into()
+                ),
+                ProgramAction::OpenFiles(5),
+                ProgramAction::Tasks(1),
             ]
         );
     }
@@ -903,17 +3143,149 @@ mod tests {
                     ),
                 ])),
                 Expression::Integer(IntegerExpression {
-                    value: IntegerExpressionValue::Literal(33),
+                    value: IntegerExpressionValue::Literal(33),
+                    metadata: None,
+                }),
+            ],
+            ret_val: 0,
+        })];
+        assert_eq!(
+            summarize(syscalls).unwrap(),
+            vec![
+                ProgramAction::Read("/run/user/1000/systemd/private".into()),
+                ProgramAction::Syscalls(["connect".to_owned()].into()),
+                ProgramAction::Tasks(1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_connect_abstract_uds() {
+        let _ = simple_logger::SimpleLogger::new().init();
+
+        let syscalls = [Ok(Syscall {
+            pid: 598056,
+            rel_ts: 0.000036,
+            name: "connect".to_owned(),
+            args: vec![
+                Expression::Integer(IntegerExpression {
+                    value: IntegerExpressionValue::Literal(4),
+                    metadata: Some("socket:[4184522]".as_bytes().to_vec()),
+                }),
+                Expression::Struct(HashMap::from([
+                    (
+                        "sa_family".to_owned(),
+                        Expression::Integer(IntegerExpression {
+                            value: IntegerExpressionValue::NamedConst("AF_UNIX".to_owned()),
+                            metadata: None,
+                        }),
+                    ),
+                    (
+                        "sun_path".to_owned(),
+                        Expression::Buffer(BufferExpression {
+                            value: "/tmp/.X11-unix/X0\0\0".as_bytes().to_vec(),
+                            type_: BufferType::AbstractPath,
+                        }),
+                    ),
+                ])),
+                Expression::Integer(IntegerExpression {
+                    value: IntegerExpressionValue::Literal(110),
+                    metadata: None,
+                }),
+            ],
+            ret_val: 0,
+        })];
+        assert_eq!(
+            summarize(syscalls).unwrap(),
+            vec![
+                ProgramAction::AbstractSocket("/tmp/.X11-unix/X0".to_owned()),
+                ProgramAction::Syscalls(["connect".to_owned()].into()),
+                ProgramAction::Tasks(1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sendmsg_uds() {
+        let _ = simple_logger::SimpleLogger::new().init();
+
+        let syscalls = [Ok(Syscall {
+            pid: 598056,
+            rel_ts: 0.000036,
+            name: "sendmsg".to_owned(),
+            args: vec![
+                Expression::Integer(IntegerExpression {
+                    value: IntegerExpressionValue::Literal(3),
+                    metadata: None,
+                }),
+                Expression::Struct(HashMap::from([
+                    (
+                        "msg_name".to_owned(),
+                        Expression::Struct(HashMap::from([
+                            (
+                                "sa_family".to_owned(),
+                                Expression::Integer(IntegerExpression {
+                                    value: IntegerExpressionValue::NamedConst("AF_UNIX".to_owned()),
+                                    metadata: None,
+                                }),
+                            ),
+                            (
+                                "sun_path".to_owned(),
+                                Expression::Buffer(BufferExpression {
+                                    value: "/run/systemd/journal/socket".as_bytes().to_vec(),
+                                    type_: BufferType::Unknown,
+                                }),
+                            ),
+                        ])),
+                    ),
+                    (
+                        "msg_namelen".to_owned(),
+                        Expression::Integer(IntegerExpression {
+                            value: IntegerExpressionValue::Literal(29),
+                            metadata: None,
+                        }),
+                    ),
+                ])),
+                Expression::Integer(IntegerExpression {
+                    value: IntegerExpressionValue::NamedConst("MSG_NOSIGNAL".to_owned()),
                     metadata: None,
                 }),
             ],
-            ret_val: 0,
+            ret_val: 11,
         })];
         assert_eq!(
             summarize(syscalls).unwrap(),
             vec![
-                ProgramAction::Read("/run/user/1000/systemd/private".into()),
-                ProgramAction::Syscalls(["connect".to_owned()].into())
+                ProgramAction::Read("/run/systemd/journal/socket".into()),
+                ProgramAction::Syscalls(["sendmsg".to_owned()].into()),
+                ProgramAction::Tasks(1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_intermediate_actions() {
+        let _ = simple_logger::SimpleLogger::new().init();
+
+        let mut summarizer = Summarizer::default();
+        summarizer
+            .add_syscall(&syscall("getpid", vec![]).unwrap())
+            .unwrap();
+        assert_eq!(
+            summarizer.actions(),
+            vec![
+                ProgramAction::Syscalls(["getpid".to_owned()].into()),
+                ProgramAction::Tasks(1)
+            ]
+        );
+        summarizer
+            .add_syscall(&syscall("getuid", vec![]).unwrap())
+            .unwrap();
+        assert_eq!(
+            summarizer.finish(),
+            vec![
+                ProgramAction::Syscalls(["getpid".to_owned(), "getuid".to_owned()].into()),
+                ProgramAction::Tasks(1)
             ]
         );
     }
@@ -942,6 +3314,23 @@ mod tests {
             vec![port(1234)..=port(1234), port(5678)..=port(5678)]
         );
 
+        let set: CountableSetSpecifier<NetworkPort> = CountableSetSpecifier::Some(vec![
+            port(1234),
+            port(5000),
+            port(5001),
+            port(5002),
+            port(u16::MAX - 1),
+            port(u16::MAX),
+        ]);
+        assert_eq!(
+            set.ranges(),
+            vec![
+                port(1234)..=port(1234),
+                port(5000)..=port(5002),
+                port(u16::MAX - 1)..=port(u16::MAX)
+            ]
+        );
+
         let set: CountableSetSpecifier<NetworkPort> =
             CountableSetSpecifier::AllExcept(vec![port(1)]);
         assert_eq!(set.ranges(), vec![port(2)..=port(u16::MAX)]);
@@ -978,5 +3367,1067 @@ mod tests {
 
         let set: CountableSetSpecifier<NetworkPort> = CountableSetSpecifier::All;
         assert_eq!(set.ranges(), vec![port(1)..=port(u16::MAX)]);
+
+        let set: CountableSetSpecifier<NetworkPort> =
+            CountableSetSpecifier::AllExcept(vec![port(21), port(5000), port(5001), port(5002)]);
+        assert_eq!(
+            set.complement_ranges(),
+            vec![port(21)..=port(21), port(5000)..=port(5002)]
+        );
+        let set: CountableSetSpecifier<NetworkPort> = CountableSetSpecifier::One(port(1));
+        assert_eq!(set.complement_ranges(), vec![port(2)..=port(u16::MAX)]);
+        let set: CountableSetSpecifier<NetworkPort> = CountableSetSpecifier::All;
+        assert_eq!(set.complement_ranges(), vec![]);
+    }
+
+    #[test]
+    fn test_setuid_exec() {
+        use std::{fs, os::unix::fs::PermissionsExt as _};
+
+        let _ = simple_logger::SimpleLogger::new().init();
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let exe_paths = [
+            temp_dir.path().join("setuid"),
+            temp_dir.path().join("plain"),
+        ];
+        for (exe_path, mode) in exe_paths.iter().zip([0o4755, 0o755]) {
+            fs::write(exe_path, "").unwrap();
+            fs::set_permissions(exe_path, fs::Permissions::from_mode(mode)).unwrap();
+        }
+        // Each executable is run twice, but only reported once
+        let syscalls = exe_paths.iter().chain(&exe_paths).map(|exe_path| {
+            Ok(Syscall {
+                pid: 1234,
+                rel_ts: 0.000_010,
+                name: "execve".to_owned(),
+                args: vec![
+                    Expression::Buffer(BufferExpression {
+                        value: exe_path.as_os_str().as_bytes().to_vec(),
+                        type_: BufferType::Unknown,
+                    }),
+                    Expression::Collection {
+                        complement: false,
+                        values: vec![],
+                    },
+                    Expression::Collection {
+                        complement: false,
+                        values: vec![],
+                    },
+                ],
+                ret_val: 0,
+            })
+        });
+        assert_eq!(
+            summarize(syscalls).unwrap(),
+            vec![
+                ProgramAction::SetuidExec(exe_paths[0].clone()),
+                ProgramAction::Exec(exe_paths[0].clone()),
+                ProgramAction::Exec(exe_paths[1].clone()),
+                ProgramAction::Syscalls(["execve".to_owned()].into()),
+                ProgramAction::Tasks(1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_socket_protocols() {
+        let _ = simple_logger::SimpleLogger::new().init();
+
+        let sockets = [
+            ("AF_INET", "SOCK_STREAM", named("IPPROTO_TCP")),
+            ("AF_INET", "SOCK_SEQPACKET", named("IPPROTO_SCTP")),
+            (
+                "AF_INET6",
+                "SOCK_STREAM",
+                Expression::Integer(IntegerExpression {
+                    value: IntegerExpressionValue::Literal(IPPROTO_SCTP),
+                    metadata: None,
+                }),
+            ),
+            ("AF_INET", "SOCK_RAW", named("IPPROTO_RAW")),
+            (
+                "AF_UNIX",
+                "SOCK_SEQPACKET",
+                Expression::Integer(IntegerExpression {
+                    value: IntegerExpressionValue::Literal(0),
+                    metadata: None,
+                }),
+            ),
+        ];
+        let syscalls = sockets
+            .into_iter()
+            .enumerate()
+            .map(|(i, (af, type_, proto))| {
+                Ok(Syscall {
+                    pid: 1234,
+                    rel_ts: 0.000_010,
+                    name: "socket".to_owned(),
+                    args: vec![named(af), named(type_), proto],
+                    ret_val: i128::try_from(i).unwrap() + 3,
+                })
+            });
+        let action_protos: Vec<_> = summarize(syscalls)
+            .unwrap()
+            .into_iter()
+            .filter_map(|a| {
+                if let ProgramAction::NetworkActivity(NetworkActivity {
+                    proto: SetSpecifier::One(proto),
+                    ..
+                }) = a
+                {
+                    Some(proto)
+                } else {
+                    None
+                }
+            })
+            .collect();
+        assert_eq!(
+            action_protos,
+            vec![
+                SocketProtocol::Tcp,
+                SocketProtocol::Sctp,
+                SocketProtocol::Sctp,
+                SocketProtocol::Raw,
+                SocketProtocol::Other("SOCK_SEQPACKET".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_socket_families() {
+        use std::iter;
+
+        let _ = simple_logger::SimpleLogger::new().init();
+
+        let afs = [
+            "AF_NETLINK",
+            "AF_PACKET",
+            "AF_VSOCK",
+            "AF_BLUETOOTH",
+            "AF_ALG",
+        ];
+        let syscalls = afs
+            .iter()
+            .enumerate()
+            .map(|(i, af)| {
+                Ok(Syscall {
+                    pid: 1234,
+                    rel_ts: 0.000_010,
+                    name: "socket".to_owned(),
+                    args: vec![
+                        Expression::Integer(IntegerExpression {
+                            value: IntegerExpressionValue::NamedConst((*af).to_owned()),
+                            metadata: None,
+                        }),
+                        Expression::Integer(IntegerExpression {
+                            value: IntegerExpressionValue::NamedConst("SOCK_RAW".to_owned()),
+                            metadata: None,
+                        }),
+                        Expression::Integer(IntegerExpression {
+                            value: IntegerExpressionValue::Literal(0),
+                            metadata: None,
+                        }),
+                    ],
+                    ret_val: i128::try_from(i).unwrap() + 3,
+                })
+            })
+            .chain(iter::once(Ok(Syscall {
+                pid: 1234,
+                rel_ts: 0.000_010,
+                name: "socketpair".to_owned(),
+                args: vec![
+                    Expression::Integer(IntegerExpression {
+                        value: IntegerExpressionValue::NamedConst("AF_UNIX".to_owned()),
+                        metadata: None,
+                    }),
+                    Expression::Integer(IntegerExpression {
+                        value: IntegerExpressionValue::BinaryOr(vec![
+                            IntegerExpressionValue::NamedConst("SOCK_STREAM".to_owned()),
+                            IntegerExpressionValue::NamedConst("SOCK_CLOEXEC".to_owned()),
+                        ]),
+                        metadata: None,
+                    }),
+                    Expression::Integer(IntegerExpression {
+                        value: IntegerExpressionValue::Literal(0),
+                        metadata: None,
+                    }),
+                    Expression::Collection {
+                        complement: false,
+                        values: vec![],
+                    },
+                ],
+                ret_val: 0,
+            })));
+        let actions = summarize(syscalls).unwrap();
+        let action_afs: Vec<_> = actions
+            .iter()
+            .filter_map(|a| {
+                if let ProgramAction::NetworkActivity(NetworkActivity {
+                    af: SetSpecifier::One(af),
+                    kind: SetSpecifier::One(NetworkActivityKind::SocketCreation),
+                    ..
+                }) = a
+                {
+                    Some(af.clone())
+                } else {
+                    None
+                }
+            })
+            .collect();
+        assert_eq!(
+            action_afs,
+            afs.iter()
+                .chain(iter::once(&"AF_UNIX"))
+                .map(|af| SocketFamily::Other((*af).to_owned()))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_connect_inet() {
+        let _ = simple_logger::SimpleLogger::new().init();
+
+        let syscalls = [
+            Ok(Syscall {
+                pid: 1234,
+                rel_ts: 0.000_010,
+                name: "socket".to_owned(),
+                args: vec![
+                    named("AF_INET6"),
+                    named("SOCK_STREAM"),
+                    named("IPPROTO_TCP"),
+                ],
+                ret_val: 3,
+            }),
+            Ok(Syscall {
+                pid: 1234,
+                rel_ts: 0.000_020,
+                name: "connect".to_owned(),
+                args: vec![
+                    literal(3),
+                    Expression::Struct(HashMap::from([
+                        ("sa_family".to_owned(), named("AF_INET6")),
+                        (
+                            "sin6_port".to_owned(),
+                            Expression::Macro {
+                                name: "htons".to_owned(),
+                                args: vec![literal(443)],
+                            },
+                        ),
+                        (
+                            "sin6_addr".to_owned(),
+                            Expression::Macro {
+                                name: "inet_pton".to_owned(),
+                                args: vec![
+                                    named("AF_INET6"),
+                                    Expression::Buffer(BufferExpression {
+                                        value: "2001:db8::1".as_bytes().to_vec(),
+                                        type_: BufferType::Unknown,
+                                    }),
+                                    Expression::DestinationAddress("sin6_addr".to_owned()),
+                                ],
+                            },
+                        ),
+                    ])),
+                    literal(28),
+                ],
+                ret_val: 0,
+            }),
+        ];
+        let actions = summarize(syscalls).unwrap();
+        assert_eq!(
+            actions[1],
+            ProgramAction::NetworkActivity(NetworkActivity {
+                af: SetSpecifier::One(SocketFamily::Ipv6),
+                proto: SetSpecifier::One(SocketProtocol::Tcp),
+                kind: SetSpecifier::One(NetworkActivityKind::Connect),
+                local_port: CountableSetSpecifier::All,
+                remote_addr: Some("[2001:db8::1]:443".parse().unwrap()),
+            })
+        );
+        assert_eq!(
+            actions[1].to_string(),
+            "network connect: ipv6/tcp port any to [2001:db8::1]:443"
+        );
+    }
+
+    #[test]
+    fn test_set_credentials() {
+        let _ = simple_logger::SimpleLogger::new().init();
+
+        let syscalls = [
+            syscall("setgroups", vec![literal(0), literal(0)]),
+            syscall("setresgid", vec![literal(-1), literal(65534), literal(-1)]),
+            syscall("setuid", vec![literal(65534)]),
+        ];
+        let actions = summarize(syscalls).unwrap();
+        assert_eq!(
+            actions[..3],
+            [
+                ProgramAction::SetCredentials {
+                    uid: None,
+                    gid: None
+                },
+                ProgramAction::SetCredentials {
+                    uid: None,
+                    gid: Some(65534)
+                },
+                ProgramAction::SetCredentials {
+                    uid: Some(65534),
+                    gid: None
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_capset() {
+        let _ = simple_logger::SimpleLogger::new().init();
+
+        let integer = |value| {
+            Expression::Integer(IntegerExpression {
+                value,
+                metadata: None,
+            })
+        };
+        let shift = |cap: &str| IntegerExpressionValue::LeftBitShift {
+            bits: Box::new(IntegerExpressionValue::Literal(1)),
+            shift: Box::new(IntegerExpressionValue::NamedConst(cap.to_owned())),
+        };
+        let capset = |target, permitted| {
+            Ok(Syscall {
+                pid: 1234,
+                rel_ts: 0.000_010,
+                name: "capset".to_owned(),
+                args: vec![
+                    Expression::Struct(HashMap::from([
+                        (
+                            "version".to_owned(),
+                            integer(IntegerExpressionValue::NamedConst(
+                                "_LINUX_CAPABILITY_VERSION_3".to_owned(),
+                            )),
+                        ),
+                        (
+                            "pid".to_owned(),
+                            integer(IntegerExpressionValue::Literal(target)),
+                        ),
+                    ])),
+                    Expression::Struct(HashMap::from([
+                        (
+                            "effective".to_owned(),
+                            integer(IntegerExpressionValue::Literal(0)),
+                        ),
+                        ("permitted".to_owned(), integer(permitted)),
+                        (
+                            "inheritable".to_owned(),
+                            integer(IntegerExpressionValue::Literal(0)),
+                        ),
+                    ])),
+                ],
+                ret_val: 0,
+            })
+        };
+        let syscalls = [
+            capset(
+                0,
+                IntegerExpressionValue::BinaryOr(vec![
+                    shift("CAP_NET_BIND_SERVICE"),
+                    IntegerExpressionValue::LeftBitShift {
+                        bits: Box::new(IntegerExpressionValue::Literal(1)),
+                        shift: Box::new(IntegerExpressionValue::Literal(39)),
+                    },
+                ]),
+            ),
+            // Dropping all capabilities
+            capset(1234, IntegerExpressionValue::Literal(0)),
+            // Other process
+            capset(5678, shift("CAP_SYS_ADMIN")),
+        ];
+        let actions = summarize(syscalls).unwrap();
+        assert_eq!(
+            actions[..2],
+            [
+                ProgramAction::CapabilityUse("CAP_NET_BIND_SERVICE".to_owned()),
+                ProgramAction::CapabilityUse("CAP_BPF".to_owned()),
+            ]
+        );
+        assert!(matches!(actions[2], ProgramAction::Syscalls(_)));
+    }
+
+    #[test]
+    fn test_scheduling() {
+        let _ = simple_logger::SimpleLogger::new().init();
+
+        let syscalls = [
+            syscall(
+                "sched_setattr",
+                vec![
+                    literal(0),
+                    Expression::Struct(HashMap::from([
+                        ("sched_policy".to_owned(), named("SCHED_DEADLINE")),
+                        ("sched_nice".to_owned(), literal(0)),
+                    ])),
+                    literal(0),
+                ],
+            ),
+            syscall(
+                "setpriority",
+                vec![named("PRIO_PROCESS"), literal(1234), literal(-5)],
+            ),
+            // Other processes
+            syscall(
+                "setpriority",
+                vec![named("PRIO_PGRP"), literal(0), literal(10)],
+            ),
+            syscall(
+                "sched_setscheduler",
+                vec![literal(5678), named("SCHED_IDLE"), literal(0)],
+            ),
+        ];
+        let actions = summarize(syscalls).unwrap();
+        assert_eq!(
+            actions[..3],
+            [
+                ProgramAction::SetRealtimeScheduler,
+                ProgramAction::SetSchedulingPolicy("SCHED_DEADLINE".to_owned()),
+                ProgramAction::SetNice(-5),
+            ]
+        );
+        assert!(matches!(actions[3], ProgramAction::Syscalls(_)));
+    }
+
+    #[test]
+    fn test_locks() {
+        let _ = simple_logger::SimpleLogger::new().init();
+
+        let syscalls = [
+            syscall(
+                "flock",
+                vec![fd(3, "/var/lib/app/a.lock"), flags(&["LOCK_EX", "LOCK_NB"])],
+            ),
+            syscall(
+                "flock",
+                vec![fd(3, "/var/lib/app/a.lock"), flags(&["LOCK_UN"])],
+            ),
+            syscall(
+                "fcntl",
+                vec![
+                    fd(3, "/var/lib/app/b.db"),
+                    flags(&["F_SETLKW"]),
+                    Expression::Struct(HashMap::from([
+                        ("l_type".to_owned(), flags(&["F_RDLCK"])),
+                        ("l_whence".to_owned(), flags(&["SEEK_SET"])),
+                    ])),
+                ],
+            ),
+            syscall(
+                "fcntl",
+                vec![
+                    fd(3, "/var/lib/app/c.db"),
+                    flags(&["F_OFD_SETLK"]),
+                    Expression::Struct(HashMap::from([("l_type".to_owned(), flags(&["F_WRLCK"]))])),
+                ],
+            ),
+            syscall(
+                "fcntl",
+                vec![fd(3, "/var/lib/app/c.db"), flags(&["F_GETFL"])],
+            ),
+        ];
+        let actions = summarize(syscalls).unwrap();
+        assert_eq!(
+            actions[..3],
+            [
+                ProgramAction::Write("/var/lib/app/a.lock".into()),
+                ProgramAction::Read("/var/lib/app/b.db".into()),
+                ProgramAction::Write("/var/lib/app/c.db".into()),
+            ]
+        );
+        assert!(matches!(actions[3], ProgramAction::Syscalls(_)));
+    }
+
+    #[test]
+    fn test_mount_api() {
+        let _ = simple_logger::SimpleLogger::new().init();
+
+        let dir_fd = |path: Option<&str>| {
+            Expression::Integer(IntegerExpression {
+                value: path.map_or_else(
+                    || IntegerExpressionValue::NamedConst("AT_FDCWD".to_owned()),
+                    |_| IntegerExpressionValue::Literal(3),
+                ),
+                metadata: Some(path.unwrap_or("/").as_bytes().to_vec()),
+            })
+        };
+        let syscalls = [
+            syscall("fsopen", vec![buffer("ext4"), literal(0)]),
+            syscall(
+                "fsconfig",
+                vec![
+                    literal(3),
+                    literal(1),
+                    buffer("source"),
+                    buffer("/dev/sdb1"),
+                    literal(0),
+                ],
+            ),
+            syscall(
+                "fsconfig",
+                vec![literal(3), literal(6), literal(0), literal(0), literal(0)],
+            ),
+            syscall("fsmount", vec![literal(3), literal(0), literal(0)]),
+            // Detached tree from fsmount
+            syscall(
+                "move_mount",
+                vec![
+                    dir_fd(Some("/")),
+                    buffer(""),
+                    dir_fd(None),
+                    buffer("/mnt/data"),
+                    literal(4),
+                ],
+            ),
+            syscall(
+                "open_tree",
+                vec![dir_fd(None), buffer("/srv/www"), literal(1)],
+            ),
+            syscall(
+                "mount_setattr",
+                vec![
+                    dir_fd(Some("/var/lib")),
+                    buffer("app"),
+                    literal(0),
+                    literal(0),
+                    literal(32),
+                ],
+            ),
+        ];
+        let actions = summarize(syscalls).unwrap();
+        assert_eq!(
+            actions[..4],
+            [
+                ProgramAction::Read("/dev/sdb1".into()),
+                ProgramAction::Write("/mnt/data".into()),
+                ProgramAction::Read("/srv/www".into()),
+                ProgramAction::Write("/var/lib/app".into()),
+            ]
+        );
+        assert!(matches!(actions[4], ProgramAction::Syscalls(_)));
+    }
+
+    #[test]
+    fn test_namespaces() {
+        let _ = simple_logger::SimpleLogger::new().init();
+
+        let syscalls = [
+            // Plain fork
+            syscall(
+                "clone",
+                vec![Expression::Struct(HashMap::from([(
+                    "flags".to_owned(),
+                    flags(&["CLONE_CHILD_CLEARTID", "CLONE_CHILD_SETTID", "SIGCHLD"]),
+                )]))],
+            ),
+            syscall("unshare", vec![flags(&["CLONE_NEWNS", "CLONE_NEWUSER"])]),
+            syscall(
+                "clone3",
+                vec![
+                    Expression::Struct(HashMap::from([(
+                        "flags".to_owned(),
+                        flags(&["CLONE_NEWPID", "CLONE_PIDFD"]),
+                    )])),
+                    Expression::Integer(IntegerExpression {
+                        value: IntegerExpressionValue::Literal(88),
+                        metadata: None,
+                    }),
+                ],
+            ),
+            syscall(
+                "setns",
+                vec![
+                    Expression::Integer(IntegerExpression {
+                        value: IntegerExpressionValue::Literal(3),
+                        metadata: Some(b"net:[4026531840]".to_vec()),
+                    }),
+                    flags(&["CLONE_NEWNET"]),
+                ],
+            ),
+        ];
+        let actions = summarize(syscalls).unwrap();
+        assert_eq!(
+            actions[..4],
+            [
+                ProgramAction::NamespaceUse("mnt".to_owned()),
+                ProgramAction::NamespaceUse("user".to_owned()),
+                ProgramAction::NamespaceUse("pid".to_owned()),
+                ProgramAction::NamespaceUse("net".to_owned()),
+            ]
+        );
+        assert!(matches!(actions[4], ProgramAction::Syscalls(_)));
+
+        // Joining a namespace from its fd only may join any type
+        let actions = summarize([syscall(
+            "setns",
+            vec![
+                Expression::Integer(IntegerExpression {
+                    value: IntegerExpressionValue::Literal(3),
+                    metadata: Some(b"net:[4026531840]".to_vec()),
+                }),
+                Expression::Integer(IntegerExpression {
+                    value: IntegerExpressionValue::Literal(0),
+                    metadata: None,
+                }),
+            ],
+        )])
+        .unwrap();
+        assert_eq!(
+            actions
+                .iter()
+                .filter(|a| matches!(a, ProgramAction::NamespaceUse(_)))
+                .count(),
+            NAMESPACE_FLAGS.len()
+        );
+    }
+
+    #[test]
+    fn test_exec_mappings() {
+        let _ = simple_logger::SimpleLogger::new().init();
+
+        let mmap = |prot_names: &[&str], fd_value, fd_path: Option<&str>| {
+            syscall_returning(
+                "mmap",
+                vec![
+                    literal(0),
+                    literal(4096),
+                    flags(prot_names),
+                    named("MAP_SHARED"),
+                    fd_path.map_or_else(|| literal(fd_value), |p| fd(fd_value, p)),
+                    literal(0),
+                ],
+                0x7f00_0000_0000,
+            )
+        };
+        let is_wx =
+            |actions: &[ProgramAction]| actions.contains(&ProgramAction::WriteExecuteMemoryMapping);
+
+        // Executable mappings of files or of anonymous memory are fine
+        let actions = summarize([
+            mmap(&["PROT_READ", "PROT_EXEC"], 3, Some("/usr/lib/libc.so.6")),
+            mmap(&["PROT_READ", "PROT_EXEC"], -1, None),
+            mmap(
+                &["PROT_READ", "PROT_WRITE"],
+                4,
+                Some("/memfd:jit (deleted)"),
+            ),
+        ])
+        .unwrap();
+        assert!(!is_wx(&actions));
+
+        let actions = summarize([mmap(
+            &["PROT_READ", "PROT_EXEC"],
+            4,
+            Some("/memfd:jit (deleted)"),
+        )])
+        .unwrap();
+        assert!(is_wx(&actions));
+
+        let actions = summarize([syscall(
+            "mprotect",
+            vec![
+                literal(0x7f00_0000_0000),
+                literal(4096),
+                flags(&["PROT_READ", "PROT_EXEC"]),
+            ],
+        )])
+        .unwrap();
+        assert!(is_wx(&actions));
+    }
+
+    #[test]
+    fn test_io_uring() {
+        let _ = simple_logger::SimpleLogger::new().init();
+
+        let syscalls = [Ok(Syscall {
+            pid: 1234,
+            rel_ts: 0.000_010,
+            name: "io_uring_setup".to_owned(),
+            args: vec![
+                Expression::Integer(IntegerExpression {
+                    value: IntegerExpressionValue::Literal(256),
+                    metadata: None,
+                }),
+                Expression::Struct(HashMap::from([(
+                    "flags".to_owned(),
+                    Expression::Integer(IntegerExpression {
+                        value: IntegerExpressionValue::Literal(0),
+                        metadata: None,
+                    }),
+                )])),
+            ],
+            ret_val: 3,
+        })];
+        let actions = summarize(syscalls).unwrap();
+        assert_eq!(actions[0], ProgramAction::IoUring);
+        assert!(matches!(actions[1], ProgramAction::Syscalls(_)));
+    }
+
+    #[test]
+    fn test_keyring() {
+        let _ = simple_logger::SimpleLogger::new().init();
+
+        let syscalls = [
+            syscall(
+                "add_key",
+                vec![
+                    buffer("user"),
+                    buffer("foo"),
+                    buffer("bar"),
+                    Expression::Integer(IntegerExpression {
+                        value: IntegerExpressionValue::Literal(3),
+                        metadata: None,
+                    }),
+                    named("KEY_SPEC_SESSION_KEYRING"),
+                ],
+            ),
+            syscall(
+                "keyctl",
+                vec![
+                    named("KEYCTL_JOIN_SESSION_KEYRING"),
+                    Expression::Integer(IntegerExpression {
+                        value: IntegerExpressionValue::Literal(0),
+                        metadata: None,
+                    }),
+                ],
+            ),
+            syscall(
+                "request_key",
+                vec![
+                    buffer("user"),
+                    buffer("foo"),
+                    buffer(""),
+                    named("KEY_SPEC_USER_KEYRING"),
+                ],
+            ),
+        ];
+        let actions = summarize(syscalls).unwrap();
+        assert_eq!(
+            actions[..2],
+            [
+                ProgramAction::KeyringUse("session".to_owned()),
+                ProgramAction::KeyringUse("user".to_owned()),
+            ]
+        );
+        assert!(matches!(actions[2], ProgramAction::Syscalls(_)));
+    }
+
+    #[test]
+    fn test_ioctl() {
+        let _ = simple_logger::SimpleLogger::new().init();
+
+        let ioctl = |fd_path: Option<&str>, request| {
+            Ok(Syscall {
+                pid: 1234,
+                rel_ts: 0.000_010,
+                name: "ioctl".to_owned(),
+                args: vec![
+                    Expression::Integer(IntegerExpression {
+                        value: IntegerExpressionValue::Literal(3),
+                        metadata: fd_path.map(|p| p.as_bytes().to_vec()),
+                    }),
+                    Expression::Integer(IntegerExpression {
+                        value: request,
+                        metadata: None,
+                    }),
+                    Expression::Integer(IntegerExpression {
+                        value: IntegerExpressionValue::Literal(0x7ffd_5e1b_a4d0),
+                        metadata: None,
+                    }),
+                ],
+                ret_val: 0,
+            })
+        };
+        let syscalls = [
+            // TCGETS
+            ioctl(Some("/dev/ttyS0"), IntegerExpressionValue::Literal(0x5401)),
+            // VIDIOC_QUERYCAP
+            ioctl(
+                Some("/dev/video0"),
+                IntegerExpressionValue::Literal(0x8068_5600),
+            ),
+            ioctl(Some("/tmp/file"), IntegerExpressionValue::Literal(0x5401)),
+            ioctl(None, IntegerExpressionValue::Literal(0x5401)),
+            ioctl(
+                Some("/dev/ttyS0"),
+                IntegerExpressionValue::NamedConst("TCGETS".to_owned()),
+            ),
+        ];
+        let actions = summarize(syscalls).unwrap();
+        assert_eq!(
+            actions[..2],
+            [
+                ProgramAction::DeviceIoctl {
+                    path: "/dev/ttyS0".into(),
+                    class: b'T'
+                },
+                ProgramAction::DeviceIoctl {
+                    path: "/dev/video0".into(),
+                    class: b'V'
+                },
+            ]
+        );
+        assert!(matches!(actions[2], ProgramAction::Syscalls(_)));
+    }
+
+    #[test]
+    fn test_chown() {
+        let _ = simple_logger::SimpleLogger::new().init();
+
+        let chown = |path: &str, uid, gid| {
+            Ok(Syscall {
+                pid: 1234,
+                rel_ts: 0.000_010,
+                name: "chown".to_owned(),
+                args: vec![buffer(path), literal(uid), literal(gid)],
+                ret_val: 0,
+            })
+        };
+        let syscalls = [
+            chown("/var/lib/app/a", -1, -1),
+            // As decoded by the ptrace tracer
+            chown("/var/lib/app/b", 4_294_967_295, 100),
+            chown("/var/lib/app/c", 1000, -1),
+        ];
+        let actions = summarize(syscalls).unwrap();
+        assert_eq!(
+            actions[..5],
+            [
+                ProgramAction::Write("/var/lib/app/a".into()),
+                ProgramAction::Write("/var/lib/app/b".into()),
+                ProgramAction::ChangeOwner {
+                    path: "/var/lib/app/b".into(),
+                    uid: None,
+                    gid: Some(100),
+                },
+                ProgramAction::Write("/var/lib/app/c".into()),
+                ProgramAction::ChangeOwner {
+                    path: "/var/lib/app/c".into(),
+                    uid: Some(1000),
+                    gid: None,
+                },
+            ]
+        );
+        assert_eq!(
+            actions[2].to_string(),
+            "change owner of /var/lib/app/b to gid 100"
+        );
+    }
+
+    #[test]
+    fn test_listen_accept() {
+        let _ = simple_logger::SimpleLogger::new().init();
+
+        let syscalls = [
+            syscall_returning(
+                "socket",
+                vec![named("AF_INET"), named("SOCK_STREAM"), literal(0)],
+                3,
+            ),
+            syscall_returning("listen", vec![literal(3), literal(128)], 0),
+            syscall_returning(
+                "accept4",
+                vec![
+                    literal(3),
+                    Expression::Struct(HashMap::from([
+                        ("sa_family".to_owned(), named("AF_INET")),
+                        (
+                            "sin_port".to_owned(),
+                            Expression::Macro {
+                                name: "htons".to_owned(),
+                                args: vec![literal(51234)],
+                            },
+                        ),
+                        (
+                            "sin_addr".to_owned(),
+                            Expression::Macro {
+                                name: "inet_addr".to_owned(),
+                                args: vec![buffer("192.0.2.1")],
+                            },
+                        ),
+                    ])),
+                    literal(16),
+                    named("SOCK_CLOEXEC"),
+                ],
+                4,
+            ),
+            // Socket activation, the listening socket is inherited
+            syscall_returning("accept", vec![literal(5), literal(0), literal(0)], 6),
+        ];
+        let actions = summarize(syscalls).unwrap();
+        assert_eq!(
+            actions[1..3],
+            [
+                ProgramAction::NetworkActivity(NetworkActivity {
+                    af: SetSpecifier::One(SocketFamily::Ipv4),
+                    proto: SetSpecifier::One(SocketProtocol::Tcp),
+                    kind: SetSpecifier::One(NetworkActivityKind::Listen),
+                    local_port: CountableSetSpecifier::All,
+                    remote_addr: None,
+                }),
+                ProgramAction::NetworkActivity(NetworkActivity {
+                    af: SetSpecifier::One(SocketFamily::Ipv4),
+                    proto: SetSpecifier::One(SocketProtocol::Tcp),
+                    kind: SetSpecifier::One(NetworkActivityKind::Accept),
+                    local_port: CountableSetSpecifier::All,
+                    remote_addr: Some("192.0.2.1:51234".parse().unwrap()),
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sampling() {
+        let fstat = |rel_ts, fd, path: &str| Syscall {
+            pid: 1,
+            rel_ts,
+            name: "fstat".to_owned(),
+            args: vec![Expression::Integer(IntegerExpression {
+                value: IntegerExpressionValue::Literal(fd),
+                metadata: Some(path.as_bytes().to_vec()),
+            })],
+            ret_val: 0,
+        };
+        let mut sampler = Sampler::new(Duration::from_secs(1));
+        assert!(sampler.sample(&fstat(0.0, 3, "/a")));
+        // Same path through another fd
+        assert!(!sampler.sample(&fstat(0.1, 4, "/a")));
+        assert!(sampler.sample(&fstat(0.1, 3, "/b")));
+        assert!(!sampler.sample(&fstat(0.5, 3, "/a")));
+        assert!(sampler.sample(&fstat(0.5, 3, "/a")));
+        // Same syscall from another process
+        assert!(sampler.sample(&Syscall {
+            pid: 2,
+            ..fstat(0.1, 3, "/a")
+        }));
+
+        // Ids are not fds, and must not be ignored
+        let fchown = |uid| Syscall {
+            pid: 1,
+            rel_ts: 0.1,
+            name: "fchown".to_owned(),
+            args: vec![
+                Expression::Integer(IntegerExpression {
+                    value: IntegerExpressionValue::Literal(3),
+                    metadata: Some(b"/var/lib/app/a".to_vec()),
+                }),
+                Expression::Integer(IntegerExpression {
+                    value: IntegerExpressionValue::Literal(uid),
+                    metadata: None,
+                }),
+                Expression::Integer(IntegerExpression {
+                    value: IntegerExpressionValue::Literal(-1),
+                    metadata: None,
+                }),
+            ],
+            ret_val: 0,
+        };
+        let mut summarizer = Summarizer::with_sampling(Duration::from_secs(1));
+        for uid in [1000, 1000, 1001] {
+            summarizer.add_syscall(&fchown(uid)).unwrap();
+        }
+        assert_eq!(
+            summarizer
+                .actions()
+                .into_iter()
+                .filter(|a| matches!(a, ProgramAction::ChangeOwner { .. }))
+                .collect::<Vec<_>>(),
+            vec![
+                ProgramAction::ChangeOwner {
+                    path: "/var/lib/app/a".into(),
+                    uid: Some(1000),
+                    gid: None,
+                },
+                ProgramAction::ChangeOwner {
+                    path: "/var/lib/app/a".into(),
+                    uid: Some(1001),
+                    gid: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_path_resolver() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path().canonicalize().unwrap();
+        fs::create_dir(root.join("dir")).unwrap();
+        fs::write(root.join("dir/file"), "").unwrap();
+        std::os::unix::fs::symlink(root.join("dir"), root.join("dir_link")).unwrap();
+        std::os::unix::fs::symlink(root.join("dir/file"), root.join("file_link")).unwrap();
+
+        let mut resolver = PathResolver::default();
+        for _ in 0..2 {
+            for path in [
+                root.join("dir/file"),
+                root.join("dir_link/file"),
+                root.join("file_link"),
+                root.join("dir_link/../dir/file"),
+                root.join("dir_link/missing"),
+                root.join("missing/file"),
+                PathBuf::from("/"),
+            ] {
+                assert_eq!(
+                    resolver.canonicalize(path.clone()),
+                    path.canonicalize().unwrap_or(path)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_proc_self_path() {
+        assert_eq!(
+            proc_self_path(Path::new("/proc/self/status"), 42),
+            Some("/proc/self/status".into())
+        );
+        assert_eq!(
+            proc_self_path(Path::new("/proc/thread-self/attr/current"), 42),
+            Some("/proc/thread-self/attr/current".into())
+        );
+        assert_eq!(
+            proc_self_path(Path::new("/proc/42/fd"), 42),
+            Some("/proc/self/fd".into())
+        );
+        assert_eq!(
+            proc_self_path(Path::new("/proc/42"), 42),
+            Some("/proc/self".into())
+        );
+        assert_eq!(proc_self_path(Path::new("/proc/43/stat"), 42), None);
+        assert_eq!(proc_self_path(Path::new("/proc/meminfo"), 42), None);
+        assert_eq!(proc_self_path(Path::new("/etc/hosts"), 42), None);
+    }
+
+    #[test]
+    fn test_resource_usage() {
+        let task_syscall = |pid, name: &str, ret_val| Syscall {
+            pid,
+            ..syscall_returning(name, vec![], ret_val).unwrap()
+        };
+        let mut usage = ResourceUsage::default();
+        for s in [
+            task_syscall(1, "openat", 3),
+            task_syscall(1, "clone", 2),
+            task_syscall(2, "socket", 7),
+            task_syscall(2, "close", 0),
+            task_syscall(2, "exit", 0),
+            task_syscall(1, "clone3", 3),
+            task_syscall(1, "dup", 4),
+            task_syscall(3, "getpid", 3),
+        ] {
+            usage.add_syscall(&s);
+        }
+        assert_eq!(
+            usage.actions(),
+            vec![ProgramAction::OpenFiles(8), ProgramAction::Tasks(2)]
+        );
     }
 }
@@ -0,0 +1,135 @@
+//! Cross-checking of hardening configs with `systemd-analyze security`
+
+use std::process::{Command, Stdio};
+
+use crate::systemd::exposure;
+
+/// A setting checked by `systemd-analyze security`, in its JSON output
+#[derive(Debug, serde::Deserialize)]
+struct SecurityCheck {
+    /// Setting name and value, ie. `PrivateNetwork=` or `CapabilityBoundingSet=~CAP_SYS_ADMIN`
+    name: String,
+    /// Exposure of the setting, as a string ie. `"0.1"`, `null` if it is not exposing
+    exposure: Option<serde_json::Value>,
+}
+
+impl SecurityCheck {
+    /// Whether the setting increases exposure
+    fn is_flagged(&self) -> bool {
+        self.exposure
+            .as_ref()
+            .and_then(|e| e.as_f64().or_else(|| e.as_str()?.parse().ok()))
+            .is_some_and(|e| e > 0.0)
+    }
+}
+
+/// Result of `systemd-analyze security` for a unit
+#[derive(Debug)]
+pub(crate) struct SecurityAnalysis {
+    /// Overall exposure level, from 0 to 10 like ours
+    pub exposure: f64,
+    /// Flagged settings
+    flagged: Vec<String>,
+}
+
+impl SecurityAnalysis {
+    /// Flagged settings of options shh does not manage, that can only be set manually
+    pub(crate) fn unmanaged(&self) -> Vec<&str> {
+        self.flagged
+            .iter()
+            .map(String::as_str)
+            .filter(|n| {
+                // Names can be ie. `User=/DynamicUser=`
+                let option = n.split(['=', '/']).next().unwrap_or_default();
+                !exposure::is_managed(option)
+            })
+            .collect()
+    }
+}
+
+/// Run `systemd-analyze security` for a unit, and get its output
+fn run(unit_name: &str, json: bool) -> anyhow::Result<String> {
+    let output = Command::new("systemd-analyze")
+        .args(["security", "--no-pager"])
+        .args(json.then_some("--json=short"))
+        .arg(unit_name)
+        .stdin(Stdio::null())
+        .env("LANG", "C")
+        .output()?;
+    anyhow::ensure!(
+        output.status.success(),
+        "systemd-analyze failed with {}: {}",
+        output.status,
+        String::from_utf8_lossy(&output.stderr).trim()
+    );
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Parse overall exposure level from text output, ie. `→ Overall exposure level for foo.service: 4.2 OK :-)`
+fn parse_exposure(output: &str) -> Option<f64> {
+    output.lines().find_map(|l| {
+        l.split_once("Overall exposure level for")?
+            .1
+            .split_once(": ")?
+            .1
+            .split_whitespace()
+            .next()?
+            .parse()
+            .ok()
+    })
+}
+
+/// Parse flagged settings from JSON output
+fn parse_flagged(output: &str) -> anyhow::Result<Vec<String>> {
+    let checks: Vec<SecurityCheck> = serde_json::from_str(output)?;
+    Ok(checks
+        .into_iter()
+        .filter(SecurityCheck::is_flagged)
+        .map(|c| c.name)
+        .collect())
+}
+
+/// Analyze unit with `systemd-analyze security`
+pub(crate) fn analyze_security(unit_name: &str) -> anyhow::Result<SecurityAnalysis> {
+    let exposure = parse_exposure(&run(unit_name, false)?)
+        .ok_or_else(|| anyhow::anyhow!("Unable to parse overall exposure level"))?;
+    // JSON output needs systemd >= 250
+    let flagged = parse_flagged(&run(unit_name, true)?)?;
+    Ok(SecurityAnalysis { exposure, flagged })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse_exposure(
+                "  NAME  DESCRIPTION  EXPOSURE\n\u{2717} PrivateNetwork=  Service has access to the host's network  0.5\n\n\u{2192} Overall exposure level for foo.service: 4.2 OK \u{1f642}\n"
+            ),
+            Some(4.2)
+        );
+        assert_eq!(parse_exposure("garbage"), None);
+
+        let analysis = SecurityAnalysis {
+            exposure: 4.2,
+            flagged: parse_flagged(
+                r#"[{"set":false,"name":"PrivateNetwork=","json_field":"PrivateNetwork","description":"Service has access to the host's network","exposure":"0.5"},
+                {"set":true,"name":"PrivateTmp=","json_field":"PrivateTmp","description":"Service has no access to other software's temporary files","exposure":null},
+                {"set":false,"name":"User=/DynamicUser=","json_field":"UserOrDynamicUser","description":"Service runs as root user","exposure":"0.4"},
+                {"set":false,"name":"RestrictNamespaces=~user","json_field":"RestrictNamespaces_user","description":"Service may create user namespaces","exposure":0.3}]"#,
+            )
+            .unwrap(),
+        };
+        assert_eq!(
+            analysis.flagged,
+            vec![
+                "PrivateNetwork=",
+                "User=/DynamicUser=",
+                "RestrictNamespaces=~user"
+            ]
+        );
+        assert_eq!(analysis.unmanaged(), vec!["User=/DynamicUser="]);
+    }
+}
@@ -0,0 +1,87 @@
+//! D-Bus bus connections, which network isolation options do not restrict
+
+use std::{fmt, path::Path};
+
+use crate::summarize::ProgramAction;
+
+/// A D-Bus message bus
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub(crate) enum Bus {
+    System,
+    /// Session bus of a user
+    Session,
+}
+
+impl fmt::Display for Bus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::System => write!(f, "system"),
+            Self::Session => write!(f, "session"),
+        }
+    }
+}
+
+/// Socket paths of the system bus, `/var/run` being a symlink to `/run`
+const SYSTEM_BUS_SOCKETS: [&str; 2] = [
+    "/run/dbus/system_bus_socket",
+    "/var/run/dbus/system_bus_socket",
+];
+
+/// Get bus of a socket path, ie. `/run/dbus/system_bus_socket` or `/run/user/1000/bus`
+fn bus(path: &Path) -> Option<Bus> {
+    if SYSTEM_BUS_SOCKETS.iter().any(|s| path == Path::new(s)) {
+        return Some(Bus::System);
+    }
+    let mut components = path.strip_prefix("/run/user").ok()?.iter();
+    let uid = components.next()?.to_str()?;
+    (uid.parse::<u32>().is_ok() && components.next()? == "bus" && components.next().is_none())
+        .then_some(Bus::Session)
+}
+
+/// Get buses the program connected to
+pub(crate) fn connected_buses(actions: &[ProgramAction]) -> Vec<Bus> {
+    let mut buses = Vec::new();
+    for action in actions {
+        if let ProgramAction::Read(path) = action {
+            if let Some(bus) = bus(path).filter(|b| !buses.contains(b)) {
+                buses.push(bus);
+            }
+        }
+    }
+    buses
+}
+
+/// Get advice to restrict what the program can do on the buses it connected to
+pub(crate) fn bus_advice(actions: &[ProgramAction]) -> Vec<String> {
+    connected_buses(actions)
+        .into_iter()
+        .map(|bus| {
+            let policy = match bus {
+                Bus::System => "run it as a dedicated user (User= or DynamicUser=), and add a bus policy file in /etc/dbus-1/system.d/ only allowing it to send to the destinations and interfaces it needs",
+                Bus::Session => "check it needs the session bus at all, since the session bus policy allows everything to the session user",
+            };
+            format!(
+                "The program connects to the D-Bus {bus} bus, through a UNIX socket that PrivateNetwork= and IPAddressDeny= do not restrict, and the method calls it makes are not visible to profiling: to restrict them, {policy}, 'busctl monitor' shows the calls it makes"
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_connected_buses() {
+        let actions = vec![
+            ProgramAction::Read("/run/user/1000/bus".into()),
+            ProgramAction::Read("/run/user/1000/bus/foo".into()),
+            ProgramAction::Read("/run/dbus/system_bus_socket".into()),
+            ProgramAction::Read("/var/run/dbus/system_bus_socket".into()),
+            ProgramAction::Read("/run/dbus/foo".into()),
+        ];
+        assert_eq!(connected_buses(&actions), vec![Bus::Session, Bus::System]);
+        assert_eq!(bus_advice(&actions).len(), 2);
+        assert!(bus_advice(&actions[4..]).is_empty());
+    }
+}
@@ -0,0 +1,279 @@
+//! Complain mode, where denied syscalls are only logged before the filter is enforced
+
+use std::{collections::HashSet, fmt, iter, path::PathBuf, time::SystemTime};
+
+use crate::systemd::{
+    journal::Journal,
+    options::{ListMode, OptionValue, OptionWithValue},
+};
+
+/// Prefix of the commented out enforcing option, in a hardening fragment in complain mode
+pub(crate) const COMPLAIN_MODE_PREFIX: &str = "# Enforced by 'shh service enforce': ";
+
+/// Audit record type of seccomp actions, including `SECCOMP_RET_LOG`
+/// See <https://github.com/torvalds/linux/blob/v6.10/include/uapi/linux/audit.h>
+const AUDIT_SECCOMP_TYPE: &str = "type=1326";
+
+/// Audit architecture identifier of x86-64, the only one we can name syscalls for
+#[cfg(target_arch = "x86_64")]
+const AUDIT_ARCH_X86_64: &str = "c000003e";
+
+/// Build `SystemCallLog=` option logging the syscalls a `SystemCallFilter=` deny list would deny
+///
+/// `SystemCallLog=` does not support errno suffixes, and is an allow list (syscalls to log) when not negated.
+/// A non negated `SystemCallFilter=` following the deny list allows syscalls again, so it is translated to a negated
+/// `SystemCallLog=`, removing them from the logged syscalls.
+pub(crate) fn log_option(filter: &OptionWithValue) -> Option<OptionWithValue> {
+    // Options read back from the journal are plain strings
+    let (values, negation_prefix): (Vec<&str>, bool) = match &filter.value {
+        OptionValue::List {
+            values,
+            negation_prefix,
+            ..
+        } => (
+            values.iter().map(String::as_str).collect(),
+            !negation_prefix,
+        ),
+        OptionValue::String(value) => value.strip_prefix('~').map_or_else(
+            || (value.split_whitespace().collect(), true),
+            |v| (v.split_whitespace().collect(), false),
+        ),
+        OptionValue::Boolean(_) => return None,
+    };
+    Some(OptionWithValue {
+        name: "SystemCallLog".to_owned(),
+        value: OptionValue::List {
+            values: values
+                .into_iter()
+                .map(|v| v.split_once(':').map_or(v, |(c, _)| c).to_owned())
+                .collect(),
+            value_if_empty: None,
+            negation_prefix,
+            repeat_option: false,
+            mode: ListMode::BlackList,
+        },
+    })
+}
+
+/// A syscall that would have been denied, logged by the kernel
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub(crate) struct LoggedSyscall {
+    pub exe: PathBuf,
+    pub syscall: String,
+}
+
+impl fmt::Display for LoggedSyscall {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} called by {}", self.syscall, self.exe.display())
+    }
+}
+
+/// Parse seccomp audit record, and get the id of the calling process, ie.
+/// `audit: type=1326 audit(1700000000.123:45): ... pid=1234 comm="x" exe="/usr/bin/x" sig=0 arch=c000003e syscall=39 ...`
+fn parse_audit_line(line: &str) -> Option<(u32, LoggedSyscall)> {
+    if !line.contains(AUDIT_SECCOMP_TYPE) {
+        return None;
+    }
+    let field = |name: &str| {
+        line.split_whitespace()
+            .find_map(|f| f.strip_prefix(name)?.strip_prefix('='))
+    };
+    let pid: u32 = field("pid")?.parse().ok()?;
+    let exe = field("exe")?.trim_matches('"');
+    let nr: u64 = field("syscall")?.parse().ok()?;
+    let syscall =
+        syscall_name(field("arch"), nr).map_or_else(|| format!("syscall #{nr}"), ToOwned::to_owned);
+    Some((
+        pid,
+        LoggedSyscall {
+            exe: exe.into(),
+            syscall,
+        },
+    ))
+}
+
+/// Get name of a syscall from its audit architecture and number
+#[cfg(target_arch = "x86_64")]
+fn syscall_name(arch: Option<&str>, nr: u64) -> Option<&'static str> {
+    if arch == Some(AUDIT_ARCH_X86_64) {
+        crate::ptrace::syscall_name(nr)
+    } else {
+        None
+    }
+}
+
+/// Get name of a syscall from its audit architecture and number, we only have a syscall table for x86-64
+#[cfg(not(target_arch = "x86_64"))]
+fn syscall_name(_arch: Option<&str>, _nr: u64) -> Option<&'static str> {
+    None
+}
+
+/// Journal entry fields we need
+#[derive(Debug)]
+struct JournalEntry {
+    message: Option<String>,
+    pid: Option<String>,
+    unit: Option<String>,
+}
+
+impl JournalEntry {
+    /// Read fields of the current journal entry
+    fn read(journal: &Journal) -> anyhow::Result<Self> {
+        Ok(Self {
+            message: journal.field("MESSAGE")?,
+            pid: journal.field("_PID")?,
+            unit: journal.field("_SYSTEMD_UNIT")?,
+        })
+    }
+}
+
+/// Get syscalls logged by seccomp for processes of a unit, from its journal entries and kernel and audit records.
+/// Audit records have no unit, so their process must either be one of `unit_pids`, or have logged as part of the unit.
+// TODO APPROXIMATION processes that exited without logging anything can not be attributed to the unit
+fn unit_logged_syscalls<I>(
+    entries: I,
+    unit_name: &str,
+    unit_pids: &HashSet<u32>,
+) -> anyhow::Result<Vec<LoggedSyscall>>
+where
+    I: IntoIterator<Item = anyhow::Result<JournalEntry>>,
+{
+    let mut pids = unit_pids.clone();
+    let mut logged = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        if entry.unit.as_deref() == Some(unit_name) {
+            pids.extend(entry.pid.and_then(|p| p.parse::<u32>().ok()));
+        } else if let Some(message) = entry.message.as_deref() {
+            logged.extend(parse_audit_line(message));
+        }
+    }
+    Ok(logged
+        .into_iter()
+        .filter(|(pid, _)| pids.contains(pid))
+        .map(|(_, l)| l)
+        .collect())
+}
+
+/// Get syscalls logged by seccomp for processes of a unit since a given time, from the kernel and audit logs in the
+/// journal, including the journal namespace of the unit if it has one
+pub(crate) fn logged_syscalls(
+    since: SystemTime,
+    unit_name: &str,
+    unit_pids: &HashSet<u32>,
+    namespace: Option<&str>,
+) -> anyhow::Result<Vec<LoggedSyscall>> {
+    // Kernel and audit records are in the default namespace, so interleave it with the unit one
+    let mut journal = Journal::open(namespace, true)?;
+    journal.add_match("_TRANSPORT", "kernel")?;
+    journal.add_disjunction()?;
+    journal.add_match("_TRANSPORT", "audit")?;
+    journal.add_disjunction()?;
+    journal.add_match("_SYSTEMD_UNIT", unit_name)?;
+    journal.seek_time(since)?;
+    let entries = iter::from_fn(|| match journal.next_entry() {
+        Ok(true) => Some(JournalEntry::read(&journal)),
+        Ok(false) => None,
+        Err(err) => Some(Err(err)),
+    });
+    unit_logged_syscalls(entries, unit_name, unit_pids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_option() {
+        let filter: OptionWithValue = "SystemCallFilter=~@mount:EPERM @swap".parse().unwrap();
+        assert_eq!(
+            log_option(&filter).unwrap().to_string(),
+            "SystemCallLog=@mount @swap"
+        );
+        let exceptions: OptionWithValue = "SystemCallFilter=chown".parse().unwrap();
+        assert_eq!(
+            log_option(&exceptions).unwrap().to_string(),
+            "SystemCallLog=~chown"
+        );
+    }
+
+    #[test]
+    fn test_parse_audit_line() {
+        assert_eq!(
+            parse_audit_line(
+                r#"audit: type=1326 audit(1700000000.123:45): auid=4294967295 uid=0 gid=0 ses=4294967295 subj=unconfined pid=1234 comm="daemon" exe="/usr/bin/daemon" sig=0 arch=c000003e syscall=165 compat=0 ip=0x7f0000000000 code=0x7ffc0000"#
+            ),
+            Some((
+                1234,
+                LoggedSyscall {
+                    exe: "/usr/bin/daemon".into(),
+                    syscall: "mount".to_owned()
+                }
+            ))
+        );
+        assert_eq!(
+            parse_audit_line(r#"audit: type=1326 audit(1700000000.123:46): pid=1234 comm="daemon" exe="/usr/bin/daemon" sig=0 arch=c00000b7 syscall=40 compat=0"#)
+                .unwrap()
+                .1
+                .syscall,
+            "syscall #40"
+        );
+        assert_eq!(
+            parse_audit_line("audit: type=1400 apparmor=\"DENIED\""),
+            None
+        );
+    }
+
+    #[test]
+    fn test_unit_logged_syscalls() {
+        let entry = |message: Option<&str>, pid: Option<&str>, unit: Option<&str>| {
+            Ok(JournalEntry {
+                message: message.map(ToOwned::to_owned),
+                pid: pid.map(ToOwned::to_owned),
+                unit: unit.map(ToOwned::to_owned),
+            })
+        };
+        let entries = [
+            // Running process of the unit
+            entry(
+                Some(
+                    r#"audit: type=1326 audit(1700000000.123:45): pid=100 comm="daemon" exe="/usr/bin/daemon" sig=0 arch=c000003e syscall=165 compat=0"#,
+                ),
+                Some("100"),
+                None,
+            ),
+            // Exited child that logged as part of the unit
+            entry(Some("starting worker"), Some("200"), Some("foo.service")),
+            entry(
+                Some(
+                    r#"audit: type=1326 audit(1700000000.123:46): pid=200 comm="worker" exe="/usr/bin/worker" sig=0 arch=c000003e syscall=166 compat=0"#,
+                ),
+                Some("200"),
+                None,
+            ),
+            // Same executable, in another unit
+            entry(
+                Some(
+                    r#"audit: type=1326 audit(1700000000.123:47): pid=300 comm="daemon" exe="/usr/bin/daemon" sig=0 arch=c000003e syscall=165 compat=0"#,
+                ),
+                Some("300"),
+                None,
+            ),
+            entry(None, None, None),
+        ];
+        let logged = unit_logged_syscalls(entries, "foo.service", &HashSet::from([100])).unwrap();
+        assert_eq!(
+            logged,
+            vec![
+                LoggedSyscall {
+                    exe: "/usr/bin/daemon".into(),
+                    syscall: "mount".to_owned()
+                },
+                LoggedSyscall {
+                    exe: "/usr/bin/worker".into(),
+                    syscall: "umount2".to_owned()
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,60 @@
+//! Services managing cgroups or running containers, which options isolating mounts, namespaces and cgroups break
+
+use crate::summarize::ProgramAction;
+
+/// Executables of container runtimes and monitors
+const CONTAINER_RUNTIMES: [&str; 9] = [
+    "conmon",
+    "containerd-shim",
+    "containerd-shim-runc-v2",
+    "crun",
+    "lxc-start",
+    "runc",
+    "runsc",
+    "systemd-nspawn",
+    "youki",
+];
+
+/// Options breaking cgroup delegation (`Delegate=yes`), or the mount and namespace setup of containers
+pub(crate) const CONTAINER_BREAKING_OPTIONS: [&str; 10] = [
+    "PrivateDevices",
+    "PrivateMounts",
+    "PrivateTmp",
+    "ProtectControlGroups",
+    "ProtectHome",
+    "ProtectKernelModules",
+    "ProtectKernelTunables",
+    "ProtectProc",
+    "ProtectSystem",
+    "RestrictNamespaces",
+];
+
+/// Get the action showing the program runs containers, if any
+// TODO APPROXIMATION paths accessed in containers are seen from their own root, so they are not reliable for the
+// options we leave out anyway
+pub(crate) fn container_runtime_exec(actions: &[ProgramAction]) -> Option<&ProgramAction> {
+    actions.iter().find(|a| {
+        matches!(a, ProgramAction::Exec(path) if path
+            .file_name()
+            .is_some_and(|n| CONTAINER_RUNTIMES.iter().any(|r| n == *r)))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_container_runtime_exec() {
+        let actions = vec![
+            ProgramAction::Exec("/usr/bin/podman".into()),
+            ProgramAction::Read("/usr/bin/crun".into()),
+            ProgramAction::Exec("/usr/bin/crun".into()),
+        ];
+        assert_eq!(
+            container_runtime_exec(&actions),
+            Some(&ProgramAction::Exec("/usr/bin/crun".into()))
+        );
+        assert_eq!(container_runtime_exec(&actions[..2]), None);
+    }
+}
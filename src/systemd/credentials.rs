@@ -0,0 +1,112 @@
+//! Suggestions to pass secret files to services as credentials, instead of letting them read the files directly
+
+use std::{
+    collections::BTreeSet,
+    os::unix::fs::PermissionsExt as _,
+    path::{Path, PathBuf},
+};
+
+use crate::summarize::ProgramAction;
+
+/// Directory where services usually read their secrets from
+const SECRETS_DIR: &str = "/etc";
+
+/// Restrictive files read through system libraries (NSS, PAM, sudo...), that can not be passed as credentials
+const SYSTEM_FILES: [&str; 4] = [
+    "/etc/gshadow",
+    "/etc/shadow",
+    "/etc/sudoers",
+    "/etc/sudoers.d",
+];
+
+/// File name extensions of secret keys and certificate bundles
+const SECRET_EXTENSIONS: [&str; 6] = ["jks", "key", "keytab", "p12", "pem", "pfx"];
+
+/// File name parts of secret files
+const SECRET_NAME_PARTS: [&str; 5] = ["key", "passwd", "password", "secret", "token"];
+
+/// Whether a file read by the program is likely a secret, from its path and mode
+fn is_likely_secret(path: &Path, mode: u32) -> bool {
+    if !path.starts_with(SECRETS_DIR) || SYSTEM_FILES.iter().any(|f| path.starts_with(f)) {
+        return false;
+    }
+    // Not readable by others
+    let restrictive = (mode & 0o004) == 0;
+    let secret_name = path
+        .extension()
+        .is_some_and(|e| SECRET_EXTENSIONS.iter().any(|s| e.eq_ignore_ascii_case(s)))
+        || path.file_name().is_some_and(|n| {
+            let name = n.to_string_lossy().to_lowercase();
+            SECRET_NAME_PARTS.iter().any(|p| name.contains(p))
+        });
+    restrictive && secret_name
+}
+
+/// Build a suggestion to pass a secret file as a credential
+fn credential_warning(path: &Path) -> Option<String> {
+    let name = path.file_name()?.to_string_lossy();
+    Some(format!(
+        "{} looks like a secret, consider passing it with LoadCredential={name}:{}, the service then needs to read it from $CREDENTIALS_DIRECTORY/{name} (%d/{name} in its command line) instead",
+        path.display(),
+        path.display(),
+    ))
+}
+
+/// Get suggestions to pass secret files read by the program as credentials (`LoadCredential=`), so that the service
+/// does not need read access to them
+pub(crate) fn credential_warnings(actions: &[ProgramAction]) -> Vec<String> {
+    let written: BTreeSet<&Path> = actions
+        .iter()
+        .filter_map(|a| match a {
+            ProgramAction::Write(path) | ProgramAction::Create(path) => Some(path.as_path()),
+            _ => None,
+        })
+        .collect();
+    let read: BTreeSet<&PathBuf> = actions
+        .iter()
+        .filter_map(|a| match a {
+            ProgramAction::Read(path) if !written.contains(path.as_path()) => Some(path),
+            _ => None,
+        })
+        .collect();
+    read.into_iter()
+        // TODO APPROXIMATION
+        // This relies on the FS state when options are generated
+        .filter(|p| {
+            p.metadata()
+                .is_ok_and(|m| m.is_file() && is_likely_secret(p, m.permissions().mode()))
+        })
+        .filter_map(|p| credential_warning(p))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_likely_secret() {
+        assert!(is_likely_secret(
+            Path::new("/etc/foo/tls/server.key"),
+            0o100_600
+        ));
+        assert!(is_likely_secret(
+            Path::new("/etc/foo/db_password"),
+            0o100_640
+        ));
+        assert!(!is_likely_secret(
+            Path::new("/etc/foo/server.pem"),
+            0o100_644
+        ));
+        assert!(!is_likely_secret(Path::new("/etc/foo/foo.conf"), 0o100_600));
+        assert!(!is_likely_secret(Path::new("/etc/shadow"), 0o100_640));
+        assert!(!is_likely_secret(
+            Path::new("/var/lib/foo/id.key"),
+            0o100_600
+        ));
+        assert_eq!(
+            credential_warning(Path::new("/etc/foo/server.key")).unwrap(),
+            "/etc/foo/server.key looks like a secret, consider passing it with LoadCredential=server.key:/etc/foo/server.key, the service then needs to read it from $CREDENTIALS_DIRECTORY/server.key (%d/server.key in its command line) instead"
+        );
+    }
+}
@@ -0,0 +1,323 @@
+//! Systemd manager D-Bus interface
+
+use std::{collections::HashSet, path::PathBuf, time::Duration};
+
+use anyhow::Context as _;
+use nix::sys::signal::Signal;
+use zbus::{blocking::Connection, zvariant::OwnedObjectPath};
+
+use crate::error::ErrorKind;
+
+use proxy::{ManagerProxy, UnitProxy};
+
+/// Proxies generated from the systemd D-Bus interfaces
+// Generated proxy inherent methods have the same names as some `zbus::blocking::Proxy` trait methods
+#[expect(clippy::same_name_method)]
+mod proxy {
+    use zbus::zvariant::OwnedObjectPath;
+
+    #[zbus::proxy(
+        interface = "org.freedesktop.systemd1.Manager",
+        default_service = "org.freedesktop.systemd1",
+        default_path = "/org/freedesktop/systemd1",
+        gen_async = false
+    )]
+    pub(super) trait Manager {
+        fn reload(&self) -> zbus::Result<()>;
+
+        fn subscribe(&self) -> zbus::Result<()>;
+
+        fn load_unit(&self, name: &str) -> zbus::Result<OwnedObjectPath>;
+
+        fn kill_unit(&self, name: &str, whom: &str, signal: i32) -> zbus::Result<()>;
+
+        fn get_unit_processes(&self, name: &str) -> zbus::Result<Vec<(String, u32, String)>>;
+
+        #[expect(clippy::type_complexity)]
+        fn list_units(
+            &self,
+        ) -> zbus::Result<
+            Vec<(
+                String,
+                String,
+                String,
+                String,
+                String,
+                String,
+                OwnedObjectPath,
+                u32,
+                String,
+                OwnedObjectPath,
+            )>,
+        >;
+
+        #[expect(clippy::type_complexity)]
+        fn list_units_by_patterns(
+            &self,
+            states: &[&str],
+            patterns: &[&str],
+        ) -> zbus::Result<
+            Vec<(
+                String,
+                String,
+                String,
+                String,
+                String,
+                String,
+                OwnedObjectPath,
+                u32,
+                String,
+                OwnedObjectPath,
+            )>,
+        >;
+
+        fn list_unit_files(&self) -> zbus::Result<Vec<(String, String)>>;
+
+        #[zbus(property)]
+        fn version(&self) -> zbus::Result<String>;
+
+        #[zbus(signal)]
+        fn job_removed(
+            &self,
+            id: u32,
+            job: OwnedObjectPath,
+            unit: String,
+            result: String,
+        ) -> zbus::Result<()>;
+    }
+
+    #[zbus::proxy(
+        interface = "org.freedesktop.systemd1.Unit",
+        default_service = "org.freedesktop.systemd1",
+        gen_async = false
+    )]
+    pub(super) trait Unit {
+        #[zbus(property)]
+        fn active_state(&self) -> zbus::Result<String>;
+
+        #[zbus(property)]
+        fn fragment_path(&self) -> zbus::Result<String>;
+
+        #[zbus(property)]
+        fn drop_in_paths(&self) -> zbus::Result<Vec<String>>;
+
+        #[zbus(property)]
+        fn triggered_by(&self) -> zbus::Result<Vec<String>>;
+
+        #[zbus(property)]
+        fn active_enter_timestamp(&self) -> zbus::Result<u64>;
+
+        #[zbus(property)]
+        fn inactive_enter_timestamp(&self) -> zbus::Result<u64>;
+
+        #[zbus(property)]
+        fn inactive_exit_timestamp(&self) -> zbus::Result<u64>;
+    }
+}
+
+/// Action queuing a job for a unit
+#[derive(Debug, Clone, Copy, Eq, PartialEq, strum::Display)]
+#[strum(serialize_all = "kebab-case")]
+pub(crate) enum UnitAction {
+    Start,
+    Stop,
+    Restart,
+    TryRestart,
+}
+
+impl UnitAction {
+    /// Manager method queuing the job, and its arguments
+    fn job_call(self, unit_name: &str) -> (&'static str, (&str, &'static str)) {
+        let method = match self {
+            Self::Start => "StartUnit",
+            Self::Stop => "StopUnit",
+            Self::Restart => "RestartUnit",
+            Self::TryRestart => "TryRestartUnit",
+        };
+        // Replace conflicting queued jobs, like systemctl does
+        (method, (unit_name, "replace"))
+    }
+}
+
+/// Connection to a service manager
+pub(crate) struct Manager {
+    connection: Connection,
+    proxy: ManagerProxy<'static>,
+}
+
+impl Manager {
+    /// Connect to the system service manager
+    pub(crate) fn connect() -> anyhow::Result<Self> {
+        Self::new(Connection::system().context("Failed to connect to the system D-Bus bus")?)
+    }
+
+    /// Connect to the service manager of the calling user
+    pub(crate) fn connect_user() -> anyhow::Result<Self> {
+        Self::new(Connection::session().context("Failed to connect to the session D-Bus bus")?)
+    }
+
+    fn new(connection: Connection) -> anyhow::Result<Self> {
+        let proxy = ManagerProxy::new(&connection)?;
+        Ok(Self { connection, proxy })
+    }
+
+    /// Version of the service manager
+    pub(crate) fn version(&self) -> anyhow::Result<String> {
+        Ok(self.proxy.version()?)
+    }
+
+    /// Reload all unit files, like `systemctl daemon-reload`
+    pub(crate) fn reload(&self) -> anyhow::Result<()> {
+        Ok(self.proxy.reload()?)
+    }
+
+    fn unit(&self, unit_name: &str) -> anyhow::Result<UnitProxy<'_>> {
+        let path = self.proxy.load_unit(unit_name)?;
+        Ok(UnitProxy::builder(&self.connection).path(path)?.build()?)
+    }
+
+    /// Queue a job for a unit, and optionally wait for it to complete
+    pub(crate) fn unit_action(
+        &self,
+        unit_name: &str,
+        action: UnitAction,
+        block: bool,
+    ) -> anyhow::Result<()> {
+        let mut job_events = if block {
+            // Job completion signals are only sent to subscribed clients, and we must listen before the job starts
+            self.proxy.subscribe()?;
+            Some(self.proxy.receive_job_removed()?)
+        } else {
+            None
+        };
+        let (method, call_args) = action.job_call(unit_name);
+        let job: OwnedObjectPath = self.proxy.inner().call(method, &call_args)?;
+        if let Some(job_events) = job_events.as_mut() {
+            for event in job_events {
+                let args = event.args()?;
+                if args.job == job {
+                    if args.result != "done" {
+                        let err = anyhow::anyhow!(
+                            "Job {action} for {unit_name} failed with result {:?}",
+                            args.result
+                        );
+                        // A unit exceeding its start or stop timeout may succeed on retry
+                        return Err(if args.result == "timeout" {
+                            err.context(ErrorKind::Timeout)
+                        } else {
+                            err.context(ErrorKind::Systemd)
+                        });
+                    }
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether a unit is active, like `systemctl is-active`
+    pub(crate) fn is_active(&self, unit_name: &str) -> anyhow::Result<bool> {
+        let state = self.unit(unit_name)?.active_state()?;
+        Ok(matches!(state.as_str(), "active" | "reloading"))
+    }
+
+    /// Send a signal to the main process of a unit
+    pub(crate) fn kill_main_process(&self, unit_name: &str, signal: Signal) -> anyhow::Result<()> {
+        Ok(self.proxy.kill_unit(unit_name, "main", signal as i32)?)
+    }
+
+    /// Ids of the processes currently running in a unit
+    pub(crate) fn unit_pids(&self, unit_name: &str) -> anyhow::Result<HashSet<u32>> {
+        Ok(self
+            .proxy
+            .get_unit_processes(unit_name)?
+            .into_iter()
+            .map(|(_cgroup, pid, _cmdline)| pid)
+            .collect())
+    }
+
+    /// Main unit file, followed by drop-in files, in the order systemd applies them
+    pub(crate) fn unit_config_paths(&self, unit_name: &str) -> anyhow::Result<Vec<PathBuf>> {
+        let unit = self.unit(unit_name)?;
+        let fragment_path = unit.fragment_path()?;
+        anyhow::ensure!(
+            !fragment_path.is_empty(),
+            "Failed to locate main unit file of {unit_name}"
+        );
+        Ok(std::iter::once(fragment_path)
+            .chain(unit.drop_in_paths()?)
+            .map(PathBuf::from)
+            .collect())
+    }
+
+    /// Names of the units that can activate a unit, ie. sockets or timers
+    pub(crate) fn unit_triggered_by(&self, unit_name: &str) -> anyhow::Result<Vec<String>> {
+        Ok(self.unit(unit_name)?.triggered_by()?)
+    }
+
+    /// How long a stopped unit was active during its last activation, if it was
+    pub(crate) fn unit_last_runtime(&self, unit_name: &str) -> anyhow::Result<Option<Duration>> {
+        let unit = self.unit(unit_name)?;
+        // Timestamps are in microseconds, and zero if the state was never entered
+        let (start, end) = (
+            unit.active_enter_timestamp()?,
+            unit.inactive_enter_timestamp()?,
+        );
+        Ok(((start > 0) && (end > start)).then(|| Duration::from_micros(end - start)))
+    }
+
+    /// How long the current activation of a unit took to start, if it is started
+    pub(crate) fn unit_startup_time(&self, unit_name: &str) -> anyhow::Result<Option<Duration>> {
+        let unit = self.unit(unit_name)?;
+        // The active state timestamp is from the previous activation while the unit is starting
+        let (start, end) = (
+            unit.inactive_exit_timestamp()?,
+            unit.active_enter_timestamp()?,
+        );
+        Ok(((start > 0) && (end >= start)).then(|| Duration::from_micros(end - start)))
+    }
+
+    /// Names of loaded and installed units
+    pub(crate) fn unit_names(&self) -> anyhow::Result<Vec<String>> {
+        let loaded = self.proxy.list_units()?.into_iter().map(|u| u.0);
+        let installed = self
+            .proxy
+            .list_unit_files()?
+            .into_iter()
+            .filter_map(|(path, _state)| {
+                PathBuf::from(path)
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .map(ToOwned::to_owned)
+            });
+        Ok(loaded.chain(installed).collect())
+    }
+
+    /// Names of loaded units matching a shell style glob pattern
+    pub(crate) fn loaded_unit_names_matching(&self, pattern: &str) -> anyhow::Result<Vec<String>> {
+        Ok(self
+            .proxy
+            .list_units_by_patterns(&[], &[pattern])?
+            .into_iter()
+            .map(|u| u.0)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unit_action() {
+        assert_eq!(
+            UnitAction::Start.job_call("foo.service"),
+            ("StartUnit", ("foo.service", "replace"))
+        );
+        assert_eq!(
+            UnitAction::TryRestart.job_call("foo@bar.service"),
+            ("TryRestartUnit", ("foo@bar.service", "replace"))
+        );
+        assert_eq!(UnitAction::TryRestart.to_string(), "try-restart");
+    }
+}
@@ -0,0 +1,137 @@
+//! Device access, restricted with `DeviceAllow=` to the devices used when `PrivateDevices=` can not be applied
+
+use std::{
+    collections::BTreeMap,
+    os::unix::ffi::OsStrExt as _,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    summarize::ProgramAction,
+    systemd::options::{ListMode, OptionValue, OptionWithValue},
+};
+
+/// Devices always allowed by `DevicePolicy=closed`
+/// See <https://github.com/systemd/systemd/blob/v254/src/core/cgroup.c#L1318>
+const CLOSED_POLICY_DEVICES: [&str; 7] = [
+    "/dev/null",
+    "/dev/zero",
+    "/dev/full",
+    "/dev/random",
+    "/dev/urandom",
+    "/dev/tty",
+    "/dev/ptmx",
+];
+
+/// Paths under `/dev` that are not device nodes, or that are allowed by `DevicePolicy=closed` (pseudo TTYs)
+const NON_DEVICE_PATHS: [&str; 9] = [
+    "/dev/pts",
+    "/dev/shm",
+    "/dev/mqueue",
+    "/dev/hugepages",
+    "/dev/log",
+    "/dev/fd",
+    "/dev/stdin",
+    "/dev/stdout",
+    "/dev/stderr",
+];
+
+/// Device node used by an action, and whether it is written to
+fn device_access(action: &ProgramAction) -> Option<(&Path, bool)> {
+    let (path, write) = match action {
+        ProgramAction::Read(path) | ProgramAction::DeviceIoctl { path, .. } => (path, false),
+        ProgramAction::Write(path) | ProgramAction::Create(path) => (path, true),
+        _ => return None,
+    };
+    // TODO APPROXIMATION for services with a RootDirectory=, the path may not be checked in the right root
+    (path.starts_with("/dev")
+        && path.components().count() > 2
+        && !CLOSED_POLICY_DEVICES.iter().any(|d| path == Path::new(d))
+        && !NON_DEVICE_PATHS.iter().any(|d| path.starts_with(d))
+        && !path.is_dir())
+    .then_some((path.as_path(), write))
+}
+
+/// Get options only allowing access to the devices the program used, ie. opened or sent ioctl requests to
+pub(crate) fn device_allow_options(actions: &[ProgramAction]) -> Vec<OptionWithValue> {
+    let mut devices: BTreeMap<PathBuf, bool> = BTreeMap::new();
+    for (path, write) in actions.iter().filter_map(device_access) {
+        *devices.entry(path.to_owned()).or_default() |= write;
+    }
+    // Device specifiers are space separated
+    if devices
+        .keys()
+        .any(|p| p.as_os_str().as_bytes().contains(&b' '))
+    {
+        return vec![];
+    }
+    let mut opts = vec![OptionWithValue {
+        name: "DevicePolicy".to_owned(),
+        value: OptionValue::String("closed".to_owned()),
+    }];
+    if !devices.is_empty() {
+        opts.push(OptionWithValue {
+            name: "DeviceAllow".to_owned(),
+            value: OptionValue::List {
+                values: devices
+                    .into_iter()
+                    .map(|(path, write)| {
+                        format!(
+                            "{} {}",
+                            path.to_string_lossy(),
+                            if write { "rw" } else { "r" }
+                        )
+                    })
+                    .collect(),
+                value_if_empty: None,
+                negation_prefix: false,
+                repeat_option: true,
+                mode: ListMode::WhiteList,
+            },
+        });
+    }
+    opts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_device_allow_options() {
+        let actions = vec![
+            ProgramAction::Read("/dev/null".into()),
+            ProgramAction::Read("/dev/pts/0".into()),
+            ProgramAction::Read("/dev/shm/foo".into()),
+            ProgramAction::Read("/dev/nonexistent-video0".into()),
+            ProgramAction::DeviceIoctl {
+                path: "/dev/nonexistent-video0".into(),
+                class: b'V',
+            },
+            ProgramAction::DeviceIoctl {
+                path: "/dev/nonexistent-ttyUSB0".into(),
+                class: b'T',
+            },
+            ProgramAction::Write("/dev/nonexistent-ttyUSB0".into()),
+        ];
+        assert_eq!(
+            device_allow_options(&actions)
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec![
+                "DevicePolicy=closed",
+                "DeviceAllow=/dev/nonexistent-ttyUSB0 rw\nDeviceAllow=/dev/nonexistent-video0 r"
+            ]
+        );
+
+        // Only standard devices are used
+        assert_eq!(
+            device_allow_options(&actions[..3])
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec!["DevicePolicy=closed"]
+        );
+    }
+}
@@ -0,0 +1,173 @@
+//! Service exposure estimation
+
+use std::{collections::BTreeMap, fs, path::Path};
+
+use anyhow::Context as _;
+
+use crate::systemd::options::{OptionValue, OptionWithValue};
+
+/// Weight of each supported option in the exposure score, loosely modeled after `systemd-analyze security`
+/// See <https://github.com/systemd/systemd/blob/v257/src/analyze/analyze-security.c>
+const OPTION_WEIGHTS: [(&str, u32); 20] = [
+    ("CapabilityBoundingSet", 1500),
+    ("LockPersonality", 100),
+    ("MemoryDenyWriteExecute", 100),
+    ("PrivateDevices", 1000),
+    ("PrivateNetwork", 2500),
+    ("PrivateTmp", 1000),
+    ("ProtectClock", 1000),
+    ("ProtectControlGroups", 1000),
+    ("ProtectHome", 1000),
+    ("ProtectKernelLogs", 1000),
+    ("ProtectKernelModules", 1000),
+    ("ProtectKernelTunables", 1000),
+    ("ProtectProc", 1000),
+    ("ProtectSystem", 2000),
+    ("RestrictAddressFamilies", 1500),
+    ("RestrictNamespaces", 1500),
+    ("RestrictRealtime", 500),
+    ("SocketBindDeny", 1000),
+    ("SystemCallArchitectures", 1000),
+    ("SystemCallFilter", 3000),
+];
+
+/// Maximum exposure score, for a service without any hardening option
+pub(crate) const MAX_EXPOSURE: f64 = 10.0;
+
+/// Check if an option is one shh manages, from the ones `systemd-analyze security` checks
+pub(crate) fn is_managed(name: &str) -> bool {
+    // Not weighted, but also set by shh
+    OPTION_WEIGHTS.iter().any(|(n, _)| *n == name)
+        || [
+            "DeviceAllow",
+            "DevicePolicy",
+            "KeyringMode",
+            "ReadWritePaths",
+            "TemporaryFileSystem",
+        ]
+        .contains(&name)
+}
+
+/// Check if an option is set to a value that has some effect
+fn is_set(opts: &[OptionWithValue], name: &str) -> bool {
+    opts.iter()
+        .any(|o| (o.name == name) && !matches!(o.value, OptionValue::Boolean(false)))
+}
+
+/// Exposure estimation settings, to match an organization threat model instead of the defaults
+///
+/// Loaded from a TOML file, for example:
+/// ```toml
+/// # Change or add option weights, 0 to ignore an option
+/// weights = { PrivateNetwork = 5000, NoNewPrivileges = 500 }
+/// # Options that must be set, reported if they can not be
+/// mandatory = ["ProtectSystem", "SystemCallFilter"]
+/// ```
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ExposureModel {
+    /// Option weight overrides and additions
+    #[serde(default)]
+    weights: BTreeMap<String, u32>,
+    /// Options that must be set
+    #[serde(default)]
+    mandatory: Vec<String>,
+}
+
+impl ExposureModel {
+    pub(crate) fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read exposure model from {}", path.display()))?;
+        toml::from_str(&content)
+            .with_context(|| format!("Failed to parse exposure model from {}", path.display()))
+    }
+
+    /// Default weights, with overrides applied
+    fn weights(&self) -> BTreeMap<&str, u32> {
+        let mut weights: BTreeMap<&str, u32> = OPTION_WEIGHTS.into_iter().collect();
+        weights.extend(self.weights.iter().map(|(n, w)| (n.as_str(), *w)));
+        weights
+    }
+
+    /// Estimate exposure of a service with the given options, from 0 (fully hardened) to `MAX_EXPOSURE`
+    ///
+    /// This is a coarse approximation: an option counts as fully effective as soon as it is set,
+    /// regardless of its value.
+    pub(crate) fn exposure(&self, opts: &[OptionWithValue]) -> f64 {
+        let weights = self.weights();
+        let total: u32 = weights.values().sum();
+        if total == 0 {
+            return 0.0;
+        }
+        let hardened: u32 = weights
+            .iter()
+            .filter(|(name, _)| is_set(opts, name))
+            .map(|(_, w)| w)
+            .sum();
+        MAX_EXPOSURE * f64::from(total - hardened) / f64::from(total)
+    }
+
+    /// Get mandatory options that are not set
+    pub(crate) fn missing_mandatory(&self, opts: &[OptionWithValue]) -> Vec<&str> {
+        self.mandatory
+            .iter()
+            .map(String::as_str)
+            .filter(|name| !is_set(opts, name))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write as _;
+
+    use super::*;
+
+    #[test]
+    fn test_exposure() {
+        let model = ExposureModel::default();
+        let exposure = |opts: &[OptionWithValue]| model.exposure(opts);
+        assert!((exposure(&[]) - MAX_EXPOSURE).abs() < f64::EPSILON);
+
+        let opts: Vec<OptionWithValue> = OPTION_WEIGHTS
+            .iter()
+            .map(|(name, _)| format!("{name}=true").parse().unwrap())
+            .collect();
+        assert!(exposure(&opts).abs() < f64::EPSILON);
+
+        let partial = exposure(&opts[..opts.len() / 2]);
+        assert!((partial > 0.0) && (partial < MAX_EXPOSURE));
+        assert!(
+            (exposure(&["PrivateNetwork=false".parse().unwrap()]) - MAX_EXPOSURE).abs()
+                < f64::EPSILON
+        );
+    }
+
+    #[test]
+    fn test_exposure_model() {
+        let mut model_file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(
+            model_file,
+            "weights = {{ PrivateNetwork = 0, NoNewPrivileges = 1000 }}\nmandatory = [\"PrivateTmp\", \"NoNewPrivileges\"]"
+        )
+        .unwrap();
+        let model = ExposureModel::load(model_file.path()).unwrap();
+
+        let network_opts: Vec<OptionWithValue> = vec!["PrivateNetwork=true".parse().unwrap()];
+        assert!((model.exposure(&network_opts) - MAX_EXPOSURE).abs() < f64::EPSILON);
+        assert_eq!(
+            model.missing_mandatory(&network_opts),
+            vec!["PrivateTmp", "NoNewPrivileges"]
+        );
+
+        let opts: Vec<OptionWithValue> = vec![
+            "NoNewPrivileges=true".parse().unwrap(),
+            "PrivateTmp=true".parse().unwrap(),
+        ];
+        assert!(model.exposure(&opts) < ExposureModel::default().exposure(&opts));
+        assert!(model.missing_mandatory(&opts).is_empty());
+
+        writeln!(model_file, "unknown = 1").unwrap();
+        assert!(ExposureModel::load(model_file.path()).is_err());
+    }
+}
@@ -0,0 +1,197 @@
+//! Size estimation of the BPF programs systemd builds from options, to detect kernel and systemd limits before the
+//! service fails to start
+
+use std::collections::HashSet;
+
+use crate::systemd::options::{OptionValue, OptionWithValue, SYSCALL_CLASSES};
+
+/// Maximum number of instructions of a classic BPF program, as used by seccomp
+/// See <https://github.com/torvalds/linux/blob/v6.10/include/uapi/linux/bpf_common.h>
+const BPF_MAXINSNS: usize = 4096;
+
+/// Maximum number of instructions of all seccomp filters attached to a process
+/// See <https://github.com/torvalds/linux/blob/v6.10/kernel/seccomp.c>
+const SECCOMP_MAX_INSNS_PER_PATH: usize = 32768;
+
+/// Instructions the kernel counts for each seccomp filter, on top of its own instructions
+const SECCOMP_FILTER_PENALTY_INSNS: usize = 4;
+
+/// Instructions of a seccomp filter generated by libseccomp, independent of its rules (architecture check, syscall
+/// number load, default action)
+const SECCOMP_BASE_INSNS: usize = 8;
+
+/// Instructions for each syscall rule (syscall number comparison, action return)
+const SECCOMP_INSNS_PER_SYSCALL: usize = 2;
+
+/// Architectures systemd loads a separate filter for on x86-64 (native, x86, x32), unless
+/// `SystemCallArchitectures=native` is set
+const SECCOMP_ARCH_COUNT: usize = 3;
+
+/// Options systemd implements with their own seccomp filter, with an estimation of their syscall rule count
+const SECCOMP_OPTIONS: [(&str, usize); 7] = [
+    ("LockPersonality", 1),
+    ("MemoryDenyWriteExecute", 4),
+    ("PrivateDevices", 6),
+    ("ProtectClock", 8),
+    ("ProtectKernelLogs", 1),
+    ("ProtectKernelModules", 3),
+    ("RestrictRealtime", 1),
+];
+
+/// Maximum number of rules of each of `SocketBindAllow=` and `SocketBindDeny=`
+/// See <https://github.com/systemd/systemd/blob/v254/src/core/bpf/socket_bind/socket-bind-api.bpf.h>
+const SOCKET_BIND_MAX_RULES: usize = 128;
+
+/// Warn when an estimation exceeds this percentage of a limit, as estimations are approximate
+const WARN_LIMIT_PERCENT: usize = 75;
+
+fn values(opt: &OptionWithValue) -> Vec<&str> {
+    match &opt.value {
+        OptionValue::List { values, .. } => values.iter().map(String::as_str).collect(),
+        OptionValue::String(value) => value.trim_start_matches('~').split_whitespace().collect(),
+        OptionValue::Boolean(_) => vec![],
+    }
+}
+
+fn close_to_limit(estimation: usize, limit: usize) -> bool {
+    estimation * 100 > limit * WARN_LIMIT_PERCENT
+}
+
+/// Number of syscalls denied by `SystemCallFilter=` options, a deny list possibly followed by allowed exceptions
+fn denied_syscall_count(opts: &[OptionWithValue]) -> usize {
+    let mut denied: HashSet<&str> = HashSet::new();
+    let mut allowed: HashSet<&str> = HashSet::new();
+    for opt in opts.iter().filter(|o| o.name == "SystemCallFilter") {
+        let negated = match &opt.value {
+            OptionValue::List {
+                negation_prefix, ..
+            } => *negation_prefix,
+            OptionValue::String(value) => value.starts_with('~'),
+            OptionValue::Boolean(_) => continue,
+        };
+        let syscalls = if negated { &mut denied } else { &mut allowed };
+        let mut entries: Vec<&str> = values(opt)
+            .into_iter()
+            .map(|v| v.split_once(':').map_or(v, |(e, _)| e))
+            .collect();
+        while let Some(entry) = entries.pop() {
+            if let Some(class) = entry.strip_prefix('@') {
+                entries.extend(SYSCALL_CLASSES.get(class).into_iter().flatten());
+            } else {
+                syscalls.insert(entry);
+            }
+        }
+    }
+    denied.difference(&allowed).count()
+}
+
+/// Get warnings about BPF programs built from options that are estimated to be close to kernel or systemd limits
+pub(crate) fn filter_size_warnings(opts: &[OptionWithValue]) -> Vec<String> {
+    let mut warnings = Vec::new();
+
+    let denied_count = denied_syscall_count(opts);
+    let syscall_filter_insns = if denied_count > 0 {
+        SECCOMP_BASE_INSNS + denied_count * SECCOMP_INSNS_PER_SYSCALL
+    } else {
+        0
+    };
+    if close_to_limit(syscall_filter_insns, BPF_MAXINSNS) {
+        warnings.push(format!(
+            "SystemCallFilter= denies {denied_count} syscalls, its seccomp filter is estimated to {syscall_filter_insns} BPF instructions, close to the kernel limit of {BPF_MAXINSNS}: the service may fail to start, consider denying fewer syscalls"
+        ));
+    }
+
+    // Each seccomp based option gets its own filter, for each architecture
+    let filters_insns: Vec<_> = SECCOMP_OPTIONS
+        .iter()
+        .filter(|(name, _)| opts.iter().any(|o| o.name == *name))
+        .map(|(_, rules)| SECCOMP_BASE_INSNS + rules * SECCOMP_INSNS_PER_SYSCALL)
+        .chain((syscall_filter_insns > 0).then_some(syscall_filter_insns))
+        .collect();
+    let arch_count = if opts.iter().any(|o| {
+        o.name == "SystemCallArchitectures"
+            && matches!(&o.value, OptionValue::String(v) if v == "native")
+    }) {
+        1
+    } else {
+        SECCOMP_ARCH_COUNT
+    };
+    let total_insns = arch_count
+        * filters_insns
+            .iter()
+            .map(|i| i + SECCOMP_FILTER_PENALTY_INSNS)
+            .sum::<usize>();
+    if close_to_limit(total_insns, SECCOMP_MAX_INSNS_PER_PATH) {
+        warnings.push(format!(
+            "Seccomp filters are estimated to {total_insns} BPF instructions in total, close to the kernel limit of {SECCOMP_MAX_INSNS_PER_PATH}: the service may fail to start{}",
+            if arch_count > 1 {
+                ", consider setting SystemCallArchitectures=native"
+            } else {
+                ""
+            }
+        ));
+    }
+
+    for name in ["SocketBindAllow", "SocketBindDeny"] {
+        let rule_count: usize = opts
+            .iter()
+            .filter(|o| o.name == name)
+            .map(|o| values(o).len())
+            .sum();
+        if rule_count > SOCKET_BIND_MAX_RULES {
+            warnings.push(format!(
+                "{name}= has {rule_count} rules, more than the {SOCKET_BIND_MAX_RULES} supported by systemd: the service will fail to start"
+            ));
+        }
+    }
+
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::systemd::options::ListMode;
+
+    #[test]
+    fn test_filter_size_warnings() {
+        let opts: Vec<OptionWithValue> = [
+            "SystemCallFilter=~@mount:EPERM @swap",
+            "SystemCallFilter=mount",
+            "SocketBindDeny=ipv4:tcp",
+        ]
+        .into_iter()
+        .map(|o| o.parse().unwrap())
+        .collect();
+        assert_eq!(
+            denied_syscall_count(&opts),
+            SYSCALL_CLASSES["mount"].len() + SYSCALL_CLASSES["swap"].len() - 1
+        );
+        assert!(filter_size_warnings(&opts).is_empty());
+
+        let syscalls: Vec<_> = (0..2000).map(|i| format!("syscall{i}")).collect();
+        let big_opts = vec![
+            OptionWithValue {
+                name: "SystemCallFilter".to_owned(),
+                value: OptionValue::String(format!("~{}", syscalls.join(" "))),
+            },
+            OptionWithValue {
+                name: "SocketBindAllow".to_owned(),
+                value: OptionValue::List {
+                    values: (0..200).map(|p| format!("tcp:{p}")).collect(),
+                    value_if_empty: None,
+                    negation_prefix: false,
+                    repeat_option: true,
+                    mode: ListMode::WhiteList,
+                },
+            },
+        ];
+        assert_eq!(
+            filter_size_warnings(&big_opts),
+            vec![
+                "SystemCallFilter= denies 2000 syscalls, its seccomp filter is estimated to 4008 BPF instructions, close to the kernel limit of 4096: the service may fail to start, consider denying fewer syscalls",
+                "SocketBindAllow= has 200 rules, more than the 128 supported by systemd: the service will fail to start",
+            ]
+        );
+    }
+}
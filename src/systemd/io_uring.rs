@@ -0,0 +1,69 @@
+//! Programs using `io_uring`, whose syscalls must all stay allowed, and whose ring operations are not traced
+
+use crate::{summarize::ProgramAction, systemd::OptionWithValue};
+
+/// `io_uring` syscalls, all needed once a ring is set up: with a kernel side submission queue polling thread
+/// (`IORING_SETUP_SQPOLL`), `io_uring_enter` may not be called during profiling, and `io_uring_register` may only be
+/// called on some code paths
+pub(crate) const IO_URING_SYSCALLS: [&str; 3] =
+    ["io_uring_enter", "io_uring_register", "io_uring_setup"];
+
+/// Options built from file or network syscalls, which also apply to operations submitted through `io_uring` rings.
+/// Seccomp based options do not, since the kernel runs those operations without syscalls.
+const TRACED_OPERATION_OPTIONS: [&str; 8] = [
+    "ProtectSystem",
+    "ProtectHome",
+    "PrivateTmp",
+    "ReadOnlyPaths",
+    "BindReadOnlyPaths",
+    "PrivateDevices",
+    "PrivateNetwork",
+    "SocketBindDeny",
+];
+
+/// Whether the program set up an `io_uring` instance
+pub(crate) fn uses_io_uring(actions: &[ProgramAction]) -> bool {
+    actions.iter().any(|a| matches!(a, ProgramAction::IoUring))
+}
+
+/// Get warnings about programs using `io_uring`, if options may deny operations not seen during profiling
+pub(crate) fn io_uring_warnings(
+    actions: &[ProgramAction],
+    opts: &[OptionWithValue],
+) -> Vec<String> {
+    if !uses_io_uring(actions) {
+        return vec![];
+    }
+    let affected: Vec<_> = TRACED_OPERATION_OPTIONS
+        .iter()
+        .filter(|n| opts.iter().any(|o| o.name == **n))
+        .copied()
+        .collect();
+    if affected.is_empty() {
+        return vec![];
+    }
+    vec![format!(
+        "Program uses io_uring, file and network operations submitted through its rings are not traced, and {} may deny some of them, check its io_uring operations are also done with syscalls",
+        affected.join(", ")
+    )]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_io_uring_warnings() {
+        let opts: Vec<OptionWithValue> = vec![
+            "ProtectSystem=strict".parse().unwrap(),
+            "LockPersonality=true".parse().unwrap(),
+        ];
+        assert!(io_uring_warnings(&[], &opts).is_empty());
+
+        let actions = vec![ProgramAction::IoUring];
+        assert!(io_uring_warnings(&actions, &opts[1..]).is_empty());
+        let warnings = io_uring_warnings(&actions, &opts);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains(", and ProtectSystem may deny"));
+    }
+}
@@ -0,0 +1,318 @@
+//! Native systemd journal reading, through the sd-journal API of libsystemd, which is loaded at runtime so that
+//! neither journalctl nor the libsystemd development files are needed
+
+use std::{
+    ffi::{c_char, c_int, c_void, CStr, CString},
+    sync::OnceLock,
+    time::SystemTime,
+};
+
+use anyhow::Context as _;
+use nix::{errno::Errno, libc};
+
+use crate::error::ErrorKind;
+
+/// Only open journal files generated on the local machine, like journalctl does by default
+/// See <https://www.freedesktop.org/software/systemd/man/sd_journal_open.html>
+const SD_JOURNAL_LOCAL_ONLY: c_int = 1 << 0;
+/// Interleave the default namespace with the opened one
+const SD_JOURNAL_INCLUDE_DEFAULT_NAMESPACE: c_int = 1 << 6;
+
+type OpenNamespaceFn = unsafe extern "C" fn(*mut *mut c_void, *const c_char, c_int) -> c_int;
+type CloseFn = unsafe extern "C" fn(*mut c_void);
+type AddMatchFn = unsafe extern "C" fn(*mut c_void, *const c_void, usize) -> c_int;
+type JournalFn = unsafe extern "C" fn(*mut c_void) -> c_int;
+type SeekRealtimeFn = unsafe extern "C" fn(*mut c_void, u64) -> c_int;
+type GetDataFn =
+    unsafe extern "C" fn(*mut c_void, *const c_char, *mut *const c_void, *mut usize) -> c_int;
+type QueryUniqueFn = unsafe extern "C" fn(*mut c_void, *const c_char) -> c_int;
+type EnumerateUniqueFn = unsafe extern "C" fn(*mut c_void, *mut *const c_void, *mut usize) -> c_int;
+
+/// sd-journal functions we use
+struct Api {
+    open_namespace: OpenNamespaceFn,
+    close: CloseFn,
+    add_match: AddMatchFn,
+    add_disjunction: JournalFn,
+    seek_tail: JournalFn,
+    seek_realtime_usec: SeekRealtimeFn,
+    next: JournalFn,
+    previous: JournalFn,
+    get_data: GetDataFn,
+    query_unique: QueryUniqueFn,
+    enumerate_unique: EnumerateUniqueFn,
+}
+
+/// Library loaded once, and never unloaded
+static API: OnceLock<Result<Api, String>> = OnceLock::new();
+
+impl Api {
+    fn get() -> anyhow::Result<&'static Self> {
+        API.get_or_init(Self::load)
+            .as_ref()
+            .map_err(|e| anyhow::anyhow!("{e}"))
+            .context(ErrorKind::Environment)
+    }
+
+    fn load() -> Result<Self, String> {
+        // SAFETY: libsystemd has no initialization code with requirements on the caller
+        let handle = unsafe { libc::dlopen(c"libsystemd.so.0".as_ptr(), libc::RTLD_NOW) };
+        if handle.is_null() {
+            return Err(format!("Unable to load libsystemd: {}", Self::dl_error()));
+        }
+        let symbol = |name: &CStr| {
+            // SAFETY: the handle is valid, and never closed
+            let ptr = unsafe { libc::dlsym(handle, name.as_ptr()) };
+            if ptr.is_null() {
+                Err(format!(
+                    "Unable to find {} in libsystemd: {}",
+                    name.to_string_lossy(),
+                    Self::dl_error()
+                ))
+            } else {
+                Ok(ptr)
+            }
+        };
+        // SAFETY: the function types match the sd-journal.h declarations
+        unsafe {
+            Ok(Self {
+                open_namespace: std::mem::transmute::<*mut c_void, OpenNamespaceFn>(symbol(
+                    c"sd_journal_open_namespace",
+                )?),
+                close: std::mem::transmute::<*mut c_void, CloseFn>(symbol(c"sd_journal_close")?),
+                add_match: std::mem::transmute::<*mut c_void, AddMatchFn>(symbol(
+                    c"sd_journal_add_match",
+                )?),
+                add_disjunction: std::mem::transmute::<*mut c_void, JournalFn>(symbol(
+                    c"sd_journal_add_disjunction",
+                )?),
+                seek_tail: std::mem::transmute::<*mut c_void, JournalFn>(symbol(
+                    c"sd_journal_seek_tail",
+                )?),
+                seek_realtime_usec: std::mem::transmute::<*mut c_void, SeekRealtimeFn>(symbol(
+                    c"sd_journal_seek_realtime_usec",
+                )?),
+                next: std::mem::transmute::<*mut c_void, JournalFn>(symbol(c"sd_journal_next")?),
+                previous: std::mem::transmute::<*mut c_void, JournalFn>(symbol(
+                    c"sd_journal_previous",
+                )?),
+                get_data: std::mem::transmute::<*mut c_void, GetDataFn>(symbol(
+                    c"sd_journal_get_data",
+                )?),
+                query_unique: std::mem::transmute::<*mut c_void, QueryUniqueFn>(symbol(
+                    c"sd_journal_query_unique",
+                )?),
+                enumerate_unique: std::mem::transmute::<*mut c_void, EnumerateUniqueFn>(symbol(
+                    c"sd_journal_enumerate_unique",
+                )?),
+            })
+        }
+    }
+
+    fn dl_error() -> String {
+        // SAFETY: the returned string is copied before any other dl call
+        let err = unsafe { libc::dlerror() };
+        if err.is_null() {
+            String::new()
+        } else {
+            // SAFETY: dlerror returns a nul terminated string
+            unsafe { CStr::from_ptr(err) }
+                .to_string_lossy()
+                .into_owned()
+        }
+    }
+}
+
+/// Check the return value of a sd-journal function, which is a negative errno on error
+fn check(ret: c_int, function: &str) -> anyhow::Result<c_int> {
+    if ret < 0 {
+        Err(anyhow::Error::new(Errno::from_raw(-ret)).context(format!("{function} failed")))
+    } else {
+        Ok(ret)
+    }
+}
+
+/// Open journal, positioned before its first entry
+pub(crate) struct Journal {
+    api: &'static Api,
+    journal: *mut c_void,
+}
+
+impl Journal {
+    /// Open the local journal of a namespace, or of the default one, optionally interleaved with the default one
+    pub(crate) fn open(namespace: Option<&str>, include_default: bool) -> anyhow::Result<Self> {
+        let api = Api::get()?;
+        let namespace = namespace.map(CString::new).transpose()?;
+        let mut flags = SD_JOURNAL_LOCAL_ONLY;
+        if include_default {
+            flags |= SD_JOURNAL_INCLUDE_DEFAULT_NAMESPACE;
+        }
+        let mut journal = std::ptr::null_mut();
+        check(
+            // SAFETY: the namespace string outlives the call, and the journal pointer is only used if opening succeeded
+            unsafe {
+                (api.open_namespace)(
+                    &raw mut journal,
+                    namespace.as_ref().map_or(std::ptr::null(), |n| n.as_ptr()),
+                    flags,
+                )
+            },
+            "sd_journal_open_namespace",
+        )?;
+        Ok(Self { api, journal })
+    }
+
+    /// Only read entries with a field value, matches of the same field are alternatives, matches of different fields
+    /// must all apply, unless separated with a disjunction
+    pub(crate) fn add_match(&mut self, field: &str, value: &str) -> anyhow::Result<()> {
+        let data = format!("{field}={value}");
+        check(
+            // SAFETY: the journal is open, and the match data is copied
+            unsafe { (self.api.add_match)(self.journal, data.as_ptr().cast(), data.len()) },
+            "sd_journal_add_match",
+        )?;
+        Ok(())
+    }
+
+    /// Read entries matching either the previous matches, or the following ones
+    pub(crate) fn add_disjunction(&mut self) -> anyhow::Result<()> {
+        check(
+            // SAFETY: the journal is open
+            unsafe { (self.api.add_disjunction)(self.journal) },
+            "sd_journal_add_disjunction",
+        )?;
+        Ok(())
+    }
+
+    /// Move after the last entry, to read entries backwards
+    pub(crate) fn seek_tail(&mut self) -> anyhow::Result<()> {
+        check(
+            // SAFETY: the journal is open
+            unsafe { (self.api.seek_tail)(self.journal) },
+            "sd_journal_seek_tail",
+        )?;
+        Ok(())
+    }
+
+    /// Move before the first entry logged after a time
+    pub(crate) fn seek_time(&mut self, time: SystemTime) -> anyhow::Result<()> {
+        let usec = u64::try_from(time.duration_since(SystemTime::UNIX_EPOCH)?.as_micros())?;
+        check(
+            // SAFETY: the journal is open
+            unsafe { (self.api.seek_realtime_usec)(self.journal, usec) },
+            "sd_journal_seek_realtime_usec",
+        )?;
+        Ok(())
+    }
+
+    /// Move to the next entry, return false if there is none
+    pub(crate) fn next_entry(&mut self) -> anyhow::Result<bool> {
+        Ok(check(
+            // SAFETY: the journal is open
+            unsafe { (self.api.next)(self.journal) },
+            "sd_journal_next",
+        )? > 0)
+    }
+
+    /// Move to the previous entry, return false if there is none
+    pub(crate) fn previous_entry(&mut self) -> anyhow::Result<bool> {
+        Ok(check(
+            // SAFETY: the journal is open
+            unsafe { (self.api.previous)(self.journal) },
+            "sd_journal_previous",
+        )? > 0)
+    }
+
+    /// Get a field value of the current entry, invalid UTF-8 sequences are replaced
+    pub(crate) fn field(&self, field: &str) -> anyhow::Result<Option<String>> {
+        let field_c = CString::new(field)?;
+        let mut data = std::ptr::null();
+        let mut len = 0;
+        // SAFETY: the journal is open, and the returned data is copied before the journal is used again
+        let ret = unsafe {
+            (self.api.get_data)(self.journal, field_c.as_ptr(), &raw mut data, &raw mut len)
+        };
+        if ret == -(Errno::ENOENT as c_int) {
+            return Ok(None);
+        }
+        check(ret, "sd_journal_get_data")?;
+        // SAFETY: the data pointer and length were set by sd_journal_get_data
+        let data = unsafe { std::slice::from_raw_parts(data.cast::<u8>(), len) };
+        Ok(Self::field_value(data, field))
+    }
+
+    /// Get all values of a field in the journal, ie. to find unit names matching a pattern
+    pub(crate) fn unique_values(&mut self, field: &str) -> anyhow::Result<Vec<String>> {
+        let field_c = CString::new(field)?;
+        check(
+            // SAFETY: the journal is open
+            unsafe { (self.api.query_unique)(self.journal, field_c.as_ptr()) },
+            "sd_journal_query_unique",
+        )?;
+        let mut values = Vec::new();
+        loop {
+            let mut data = std::ptr::null();
+            let mut len = 0;
+            let ret = check(
+                // SAFETY: the journal is open, and the returned data is copied before the journal is used again
+                unsafe { (self.api.enumerate_unique)(self.journal, &raw mut data, &raw mut len) },
+                "sd_journal_enumerate_unique",
+            )?;
+            if ret == 0 {
+                break;
+            }
+            // SAFETY: the data pointer and length were set by sd_journal_enumerate_unique
+            let data = unsafe { std::slice::from_raw_parts(data.cast::<u8>(), len) };
+            values.extend(Self::field_value(data, field));
+        }
+        Ok(values)
+    }
+
+    /// Get value from field data, which is prefixed with the field name and '='
+    fn field_value(data: &[u8], field: &str) -> Option<String> {
+        data.strip_prefix(field.as_bytes())?
+            .strip_prefix(b"=")
+            .map(|v| String::from_utf8_lossy(v).into_owned())
+    }
+}
+
+impl Drop for Journal {
+    fn drop(&mut self) {
+        // SAFETY: the journal is open, and not used after this
+        unsafe {
+            (self.api.close)(self.journal);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_field_value() {
+        assert_eq!(
+            Journal::field_value(b"_SYSTEMD_UNIT=foo.service", "_SYSTEMD_UNIT"),
+            Some("foo.service".to_owned())
+        );
+        assert_eq!(
+            Journal::field_value(b"MESSAGE=a\xffb", "MESSAGE"),
+            Some("a\u{fffd}b".to_owned())
+        );
+        assert_eq!(Journal::field_value(b"_PID=1", "_SYSTEMD_UNIT"), None);
+    }
+
+    #[test]
+    fn test_journal() {
+        let mut journal = Journal::open(None, false).unwrap();
+        journal
+            .add_match("_SYSTEMD_UNIT", "shh-test-nonexistent.service")
+            .unwrap();
+        journal.seek_tail().unwrap();
+        assert!(!journal.previous_entry().unwrap());
+        assert!(!journal
+            .unique_values("_SYSTEMD_UNIT")
+            .unwrap()
+            .contains(&"shh-test-nonexistent.service".to_owned()));
+    }
+}
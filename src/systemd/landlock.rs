@@ -0,0 +1,72 @@
+//! Programs sandboxing themselves with Landlock, whose own filesystem restrictions overlap with generated options
+
+use crate::{summarize::ProgramAction, systemd::OptionWithValue};
+
+/// Syscall applying a Landlock ruleset to the calling thread, from then irrevocably
+const RESTRICT_SYSCALL: &str = "landlock_restrict_self";
+
+/// Options restricting filesystem access, which a Landlock ruleset may already restrict further
+const FILESYSTEM_OPTIONS: [&str; 5] = [
+    "ProtectSystem",
+    "ProtectHome",
+    "PrivateTmp",
+    "ReadOnlyPaths",
+    "BindReadOnlyPaths",
+];
+
+/// Get notes about programs restricting their own filesystem access with Landlock
+pub(crate) fn landlock_warnings(
+    actions: &[ProgramAction],
+    opts: &[OptionWithValue],
+) -> Vec<String> {
+    let self_sandboxed = actions.iter().any(
+        |a| matches!(a, ProgramAction::Syscalls(syscalls) if syscalls.contains(RESTRICT_SYSCALL)),
+    );
+    if !self_sandboxed {
+        return vec![];
+    }
+    let overlapping: Vec<_> = FILESYSTEM_OPTIONS
+        .iter()
+        .filter(|n| opts.iter().any(|o| o.name == **n))
+        .copied()
+        .collect();
+    vec![format!(
+        "Program sandboxes itself with Landlock ({RESTRICT_SYSCALL}), Landlock syscalls are kept allowed, and paths of its rules were seen during profiling so they are kept accessible{}",
+        if overlapping.is_empty() {
+            String::new()
+        } else {
+            format!(
+                ", {} partly duplicate its own restrictions, but still protect it until it sandboxes itself",
+                overlapping.join(", ")
+            )
+        }
+    )]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_landlock_warnings() {
+        let opts: Vec<OptionWithValue> = vec!["ProtectSystem=strict".parse().unwrap()];
+        let probing = vec![ProgramAction::Syscalls(
+            ["landlock_create_ruleset".to_owned()].into(),
+        )];
+        assert!(landlock_warnings(&probing, &opts).is_empty());
+
+        let restricting = vec![ProgramAction::Syscalls(
+            [
+                "landlock_add_rule".to_owned(),
+                "landlock_create_ruleset".to_owned(),
+                "landlock_restrict_self".to_owned(),
+            ]
+            .into(),
+        )];
+        let warnings = landlock_warnings(&restricting, &opts);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].ends_with(
+            ", ProtectSystem partly duplicate its own restrictions, but still protect it until it sandboxes itself"
+        ));
+    }
+}
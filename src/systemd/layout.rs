@@ -0,0 +1,194 @@
+//! Suggestions of a directory layout managed by systemd, to be compatible with `ProtectSystem=strict`
+
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    summarize::ProgramAction,
+    systemd::options::{OptionValue, OptionWithValue},
+};
+
+/// Directories systemd can create for a service and keep writable with `ProtectSystem=strict`, with the option doing so
+/// See <https://www.freedesktop.org/software/systemd/man/systemd.exec.html#RuntimeDirectory=>
+const MANAGED_DIRS: [(&str, &str); 4] = [
+    ("/var/lib", "StateDirectory"),
+    ("/var/cache", "CacheDirectory"),
+    ("/var/log", "LogsDirectory"),
+    ("/run", "RuntimeDirectory"),
+];
+
+/// Directory for configuration, which `ConfigurationDirectory=` does not make writable
+const CONFIG_DIR: &str = "/etc";
+
+/// Writes below these directories are not affected by `ProtectSystem=strict`, or are handled by other options
+/// (`PrivateTmp=`, `ProtectHome=`)
+const IGNORED_DIRS: [&str; 8] = [
+    "/dev",
+    "/proc",
+    "/sys",
+    "/tmp",
+    "/var/tmp",
+    "/home",
+    "/root",
+    "/run/user",
+];
+
+/// Layout of the directories a program writes to
+#[derive(Debug, Default, Eq, PartialEq)]
+struct Layout {
+    /// Options (name and value) to get the directories created by systemd
+    options: BTreeSet<(&'static str, String)>,
+    /// Written paths that need to be moved in a managed directory, with their new location
+    migrations: Vec<(PathBuf, PathBuf)>,
+}
+
+/// Split path as name of the first component below a base directory, and the rest
+fn split_below<'a>(path: &'a Path, base: &str) -> Option<(String, &'a Path)> {
+    let rel_path = path.strip_prefix(base).ok()?;
+    let mut components = rel_path.components();
+    let name = components.next()?.as_os_str().to_str()?.to_owned();
+    Some((name, components.as_path()))
+}
+
+impl Layout {
+    fn new(actions: &[ProgramAction]) -> Self {
+        let written: BTreeSet<&Path> = actions
+            .iter()
+            .filter_map(|a| match a {
+                ProgramAction::Write(path) | ProgramAction::Create(path) => Some(path.as_path()),
+                _ => None,
+            })
+            .filter(|p| !IGNORED_DIRS.iter().any(|d| p.starts_with(d)))
+            .collect();
+
+        let mut layout = Self::default();
+        let mut unmanaged = Vec::new();
+        for path in written {
+            if let Some((option, name)) = MANAGED_DIRS.iter().find_map(|(dir, option)| {
+                split_below(path, dir)
+                    .filter(|(_, rest)| !rest.as_os_str().is_empty())
+                    .map(|(name, _)| (*option, name))
+            }) {
+                layout.options.insert((option, name));
+            } else if let Some((name, rest)) =
+                split_below(path, CONFIG_DIR).filter(|(_, rest)| !rest.as_os_str().is_empty())
+            {
+                // State stored along configuration, it can move to the state directory of the same name
+                layout.migrations.push((
+                    path.to_owned(),
+                    Path::new(MANAGED_DIRS[0].0).join(&name).join(rest),
+                ));
+                layout.options.insert(("StateDirectory", name.clone()));
+                layout.options.insert(("ConfigurationDirectory", name));
+            } else {
+                unmanaged.push(path);
+            }
+        }
+
+        if !unmanaged.is_empty() {
+            // Name the state directory after an existing one, or the program
+            let name = layout
+                .options
+                .iter()
+                .find(|(option, _)| *option == "StateDirectory")
+                .map(|(_, name)| name.to_owned())
+                .or_else(|| {
+                    actions.iter().find_map(|a| match a {
+                        ProgramAction::Exec(path) => {
+                            Some(path.file_name()?.to_string_lossy().into_owned())
+                        }
+                        _ => None,
+                    })
+                })
+                .unwrap_or_else(|| "SERVICE".to_owned());
+            for path in unmanaged {
+                let Some(file_name) = path.file_name() else {
+                    continue;
+                };
+                layout.migrations.push((
+                    path.to_owned(),
+                    Path::new(MANAGED_DIRS[0].0).join(&name).join(file_name),
+                ));
+            }
+            layout.options.insert(("StateDirectory", name));
+        }
+
+        layout
+    }
+}
+
+/// Get suggestions to move written paths to directories managed by systemd, if they prevent using
+/// `ProtectSystem=strict`
+pub(crate) fn layout_warnings(actions: &[ProgramAction], opts: &[OptionWithValue]) -> Vec<String> {
+    if opts.iter().any(|o| {
+        o.name == "ProtectSystem" && matches!(&o.value, OptionValue::String(v) if v == "strict")
+    }) {
+        return vec![];
+    }
+    let layout = Layout::new(actions);
+    if layout.options.is_empty() {
+        return vec![];
+    }
+    let mut warnings = vec![format!(
+        "ProtectSystem=strict could be used with systemd managed directories: {}",
+        layout
+            .options
+            .iter()
+            .map(|(option, name)| format!("{option}={name}"))
+            .collect::<Vec<_>>()
+            .join(" ")
+    )];
+    warnings.extend(layout.migrations.iter().map(|(src, dst)| {
+        format!(
+            "Written path {} needs to be moved to {} for ProtectSystem=strict",
+            src.display(),
+            dst.display()
+        )
+    }));
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layout() {
+        let actions = vec![
+            ProgramAction::Exec("/usr/bin/foo".into()),
+            ProgramAction::Read("/etc/foo/foo.conf".into()),
+            ProgramAction::Write("/etc/foo/state.db".into()),
+            ProgramAction::Create("/var/lib/foo/cache".into()),
+            ProgramAction::Write("/var/log/foo/foo.log".into()),
+            ProgramAction::Write("/opt/foo/foo.pid".into()),
+            ProgramAction::Write("/tmp/foo.tmp".into()),
+            ProgramAction::Write("/dev/null".into()),
+        ];
+        assert_eq!(
+            Layout::new(&actions),
+            Layout {
+                options: BTreeSet::from([
+                    ("ConfigurationDirectory", "foo".to_owned()),
+                    ("LogsDirectory", "foo".to_owned()),
+                    ("StateDirectory", "foo".to_owned()),
+                ]),
+                migrations: vec![
+                    ("/etc/foo/state.db".into(), "/var/lib/foo/state.db".into()),
+                    ("/opt/foo/foo.pid".into(), "/var/lib/foo/foo.pid".into()),
+                ],
+            }
+        );
+
+        assert!(layout_warnings(&actions, &["ProtectSystem=strict".parse().unwrap()]).is_empty());
+        assert_eq!(
+            layout_warnings(&actions, &["ProtectSystem=full".parse().unwrap()]),
+            vec![
+                "ProtectSystem=strict could be used with systemd managed directories: ConfigurationDirectory=foo LogsDirectory=foo StateDirectory=foo",
+                "Written path /etc/foo/state.db needs to be moved to /var/lib/foo/state.db for ProtectSystem=strict",
+                "Written path /opt/foo/foo.pid needs to be moved to /var/lib/foo/foo.pid for ProtectSystem=strict",
+            ]
+        );
+    }
+}
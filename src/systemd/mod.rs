@@ -1,25 +1,250 @@
 //! Systemd code
 
+use std::{collections::HashMap, fmt::Write as _};
+
+use itertools::Itertools as _;
+
+use crate::coverage::Coverage;
+
+mod analyze;
+mod bus;
+mod complain;
+mod containers;
+mod credentials;
+mod dbus;
+mod devices;
+mod exposure;
+mod filter_size;
+mod io_uring;
+mod journal;
+mod landlock;
+mod layout;
+mod namespaces;
 mod options;
+mod plugins;
+mod pseudo_files;
+mod read_only_root;
 mod resolver;
+mod risk;
+mod scheduling;
 mod service;
+mod specifiers;
+mod syscall_filter;
+mod temp_dirs;
+mod transient;
+mod user;
 mod version;
 
+pub(crate) use analyze::analyze_security;
+pub(crate) use bus::bus_advice;
+pub(crate) use dbus::UnitAction;
+pub(crate) use exposure::{ExposureModel, MAX_EXPOSURE};
+pub(crate) use namespaces::related_unit_warnings;
+#[cfg(test)]
+pub(crate) use options::ListMode;
 pub(crate) use options::{
-    build_options, OptionDescription, OptionValue, SocketFamily, SocketProtocol,
+    build_options, DenySyscalls, OptionCategory, OptionDescription, OptionValue, OptionWithValue,
+    SocketFamily, SocketProtocol, SYSCALL_CLASSES, SYSTEM_SERVICE_ONLY_OPTIONS,
+};
+pub(crate) use plugins::{loaded_plugins, plugin_actions, Plugin};
+pub(crate) use read_only_root::read_only_root;
+pub(crate) use resolver::{
+    annotate, justify, resolve, translate_root_directory, warnings, OptionJustification,
 };
-pub(crate) use resolver::resolve;
+pub(crate) use risk::option_risks;
+pub(crate) use scheduling::scheduling_advice;
 pub(crate) use service::Service;
+pub(crate) use syscall_filter::group_coverage;
+pub(crate) use temp_dirs::{env_temp_dir, temp_dir_options};
+pub(crate) use transient::profile_transient_unit;
+pub(crate) use user::{dedicated_user, unprivileged_profiling_limits};
 pub(crate) use version::{KernelVersion, SystemdVersion};
 
 const START_OPTION_OUTPUT_SNIPPET: &str = "-------- Start of suggested service options --------";
 const END_OPTION_OUTPUT_SNIPPET: &str = "-------- End of suggested service options --------";
+const START_EXEC_START_PRE_OPTION_OUTPUT_SNIPPET: &str =
+    "-------- Start of suggested service setup options --------";
+const END_EXEC_START_PRE_OPTION_OUTPUT_SNIPPET: &str =
+    "-------- End of suggested service setup options --------";
+const START_SMOKE_TEST_OUTPUT_SNIPPET: &str = "-------- Start of smoke test script --------";
+const END_SMOKE_TEST_OUTPUT_SNIPPET: &str = "-------- End of smoke test script --------";
+const START_COVERAGE_OUTPUT_SNIPPET: &str = "-------- Start of profiling coverage --------";
+const END_COVERAGE_OUTPUT_SNIPPET: &str = "-------- End of profiling coverage --------";
+const START_TIMELINE_OUTPUT_SNIPPET: &str = "-------- Start of profiling timeline --------";
+const END_TIMELINE_OUTPUT_SNIPPET: &str = "-------- End of profiling timeline --------";
+
+/// Comment lines explaining option values, by option name
+pub(crate) type OptionComments = HashMap<String, Vec<String>>;
+
+/// Terminal escape sequences for colored output
+const STYLE_CATEGORY: &str = "\x1b[1;33m";
+const STYLE_COMMENT: &str = "\x1b[2m";
+const STYLE_OPTION_NAME: &str = "\x1b[1;36m";
+const STYLE_RESET: &str = "\x1b[0m";
+
+/// Format a comment line explaining an option value
+/// The option name is repeated, to be able to tell comments apart from category headers when parsing them back
+pub(crate) fn comment_line(name: &str, comment: &str) -> String {
+    format!("# {name}: {comment}")
+}
+
+/// Parse back option name and comment from a comment line, if it is one
+pub(crate) fn parse_comment_line(line: &str) -> Option<(&str, &str)> {
+    line.strip_prefix("# ")?
+        .split_once(": ")
+        .filter(|(name, _)| name.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+/// Format options grouped by category between snippet markers
+/// Category headers and option comments are comments, so the snippet can be pasted as is in a unit file, and the
+/// markers are never colored, so the snippet can still be parsed back
+fn format_snippet(
+    start: &str,
+    end: &str,
+    mut opts: Vec<OptionWithValue>,
+    comments: &OptionComments,
+    color: bool,
+) -> String {
+    let (style_category, style_comment, style_name, style_reset) = if color {
+        (
+            STYLE_CATEGORY,
+            STYLE_COMMENT,
+            STYLE_OPTION_NAME,
+            STYLE_RESET,
+        )
+    } else {
+        ("", "", "", "")
+    };
+    opts.sort_by_key(OptionWithValue::category);
+    let mut snippet = String::new();
+    let _ = writeln!(snippet, "{start}");
+    for (category, category_opts) in &opts.iter().chunk_by(|o| o.category()) {
+        let category_opts: Vec<_> = category_opts.collect();
+        // Options split on several lines are counted once
+        let opt_count = category_opts.iter().map(|o| &o.name).dedup().count();
+        let _ = writeln!(
+            snippet,
+            "{style_category}# {category} ({opt_count} option{}){style_reset}",
+            if opt_count > 1 { "s" } else { "" }
+        );
+        let mut prev_name = None;
+        for opt in category_opts {
+            // Options split on several lines (ie. allowed syscall exceptions) are commented once
+            let opt_comments = if prev_name == Some(&opt.name) {
+                None
+            } else {
+                comments.get(&opt.name)
+            };
+            prev_name = Some(&opt.name);
+            for comment in opt_comments.into_iter().flatten() {
+                let _ = writeln!(
+                    snippet,
+                    "{style_comment}{}{style_reset}",
+                    comment_line(&opt.name, comment)
+                );
+            }
+            // Options with repeated values are displayed on several lines
+            for line in opt.to_string().lines() {
+                let (name, value) = line.split_once('=').unwrap_or((line, ""));
+                let _ = writeln!(snippet, "{style_name}{name}{style_reset}={value}");
+            }
+        }
+    }
+    let _ = writeln!(snippet, "{end}");
+    snippet
+}
 
-pub(crate) fn report_options(opts: Vec<options::OptionWithValue>) {
+fn print_snippet(
+    start: &str,
+    end: &str,
+    opts: Vec<OptionWithValue>,
+    comments: &OptionComments,
+    color: bool,
+) {
     // Report (not through logging facility because we may need to parse it back from service logs)
-    println!("{START_OPTION_OUTPUT_SNIPPET}");
-    for opt in opts {
-        println!("{opt}");
+    print!("{}", format_snippet(start, end, opts, comments, color));
+}
+
+pub(crate) fn report_options(opts: Vec<OptionWithValue>, comments: &OptionComments, color: bool) {
+    print_snippet(
+        START_OPTION_OUTPUT_SNIPPET,
+        END_OPTION_OUTPUT_SNIPPET,
+        opts,
+        comments,
+        color,
+    );
+}
+
+/// Report options for the setup commands (`ExecStartPre`) of a service, when they are hardened separately
+pub(crate) fn report_exec_start_pre_options(
+    opts: Vec<OptionWithValue>,
+    comments: &OptionComments,
+    color: bool,
+) {
+    print_snippet(
+        START_EXEC_START_PRE_OPTION_OUTPUT_SNIPPET,
+        END_EXEC_START_PRE_OPTION_OUTPUT_SNIPPET,
+        opts,
+        comments,
+        color,
+    );
+}
+
+/// Report a smoke test script, to be retrieved from the service logs when profiling is finished
+pub(crate) fn report_smoke_test(script: &str) {
+    println!("{START_SMOKE_TEST_OUTPUT_SNIPPET}");
+    print!("{script}");
+    println!("{END_SMOKE_TEST_OUTPUT_SNIPPET}");
+}
+
+/// Report when each permission was first needed, to be retrieved from the service logs when profiling is finished
+pub(crate) fn report_timeline(timeline: &str) {
+    println!("{START_TIMELINE_OUTPUT_SNIPPET}");
+    print!("{timeline}");
+    println!("{END_TIMELINE_OUTPUT_SNIPPET}");
+}
+
+/// Report a summary of profiled actions, to be retrieved from the service logs when profiling is finished
+pub(crate) fn report_coverage(coverage: &Coverage) -> anyhow::Result<()> {
+    println!("{START_COVERAGE_OUTPUT_SNIPPET}");
+    println!("{}", serde_json::to_string(coverage)?);
+    println!("{END_COVERAGE_OUTPUT_SNIPPET}");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_snippet() {
+        let opts = || -> Vec<OptionWithValue> {
+            [
+                "SystemCallFilter=~@mount",
+                "ProtectSystem=strict",
+                "PrivateTmp=true",
+            ]
+            .into_iter()
+            .map(|o| o.parse().unwrap())
+            .collect()
+        };
+        assert_eq!(
+            format_snippet("start", "end", opts(), &OptionComments::new(), false),
+            "start\n# Filesystem (2 options)\nProtectSystem=strict\nPrivateTmp=true\n# System calls (1 option)\nSystemCallFilter=~@mount\nend\n"
+        );
+        let comments =
+            OptionComments::from([("PrivateTmp".to_owned(), vec!["No /tmp access".to_owned()])]);
+        let snippet = format_snippet("start", "end", opts(), &comments, false);
+        assert_eq!(
+            snippet,
+            "start\n# Filesystem (2 options)\nProtectSystem=strict\n# PrivateTmp: No /tmp access\nPrivateTmp=true\n# System calls (1 option)\nSystemCallFilter=~@mount\nend\n"
+        );
+        assert_eq!(
+            snippet
+                .lines()
+                .filter_map(parse_comment_line)
+                .collect::<Vec<_>>(),
+            vec![("PrivateTmp", "No /tmp access")]
+        );
     }
-    println!("{END_OPTION_OUTPUT_SNIPPET}");
 }
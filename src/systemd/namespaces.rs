@@ -0,0 +1,238 @@
+//! Detection of data exchanged with other programs through namespaces that isolating options would make private,
+//! with suggestions to share them between units
+
+use std::{collections::BTreeSet, path::Path};
+
+use crate::{
+    summarize::ProgramAction,
+    systemd::options::{OptionValue, OptionWithValue},
+};
+
+/// Temporary directories, made private to the service by `PrivateTmp=`
+const TMP_DIRS: [&str; 2] = ["/tmp", "/var/tmp"];
+
+/// Pseudo filesystems, whose paths accessed by several units are not data exchanged between them
+const PSEUDO_FS_DIRS: [&str; 3] = ["/dev", "/proc", "/sys"];
+
+/// Placeholder for the unit the service exchanges data with, which can not be known from profiling
+const OTHER_UNIT: &str = "OTHER.service";
+
+fn is_set(opts: &[OptionWithValue], name: &str) -> bool {
+    opts.iter()
+        .any(|o| o.name == name && matches!(o.value, OptionValue::Boolean(true)))
+}
+
+fn join_paths(paths: &BTreeSet<&Path>) -> String {
+    paths
+        .iter()
+        .map(|p| p.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Get warnings about temporary files and abstract sockets shared with other programs, which isolating options
+/// (`PrivateTmp=`, `PrivateNetwork=`) would break, unless the units share their namespaces with `JoinsNamespaceOf=`
+pub(crate) fn shared_namespace_warnings(
+    actions: &[ProgramAction],
+    opts: &[OptionWithValue],
+) -> Vec<String> {
+    let created: BTreeSet<&Path> = actions
+        .iter()
+        .filter_map(|a| match a {
+            ProgramAction::Create(path) => Some(path.as_path()),
+            _ => None,
+        })
+        .collect();
+    let mut read_shared = BTreeSet::new();
+    let mut written_shared = BTreeSet::new();
+    let mut abstract_sockets = BTreeSet::new();
+    for action in actions {
+        match action {
+            ProgramAction::Read(path) | ProgramAction::Write(path)
+                if TMP_DIRS
+                    .iter()
+                    .any(|d| path.starts_with(d) && (path != Path::new(d)))
+                    && !created.contains(path.as_path()) =>
+            {
+                if matches!(action, ProgramAction::Write(_)) {
+                    written_shared.insert(path.as_path());
+                } else {
+                    read_shared.insert(path.as_path());
+                }
+            }
+            ProgramAction::AbstractSocket(name) => {
+                abstract_sockets.insert(format!("@{name}"));
+            }
+            _ => {}
+        }
+    }
+
+    let mut warnings = Vec::new();
+    if is_set(opts, "PrivateTmp") {
+        // Reading files not created by the program already prevents PrivateTmp=, but writing to them does not
+        if !written_shared.is_empty() {
+            warnings.push(format!(
+                "PrivateTmp=true makes {} private to the service, which breaks exchanging data through them with other programs, unless they run in a unit sharing its temporary directories with JoinsNamespaceOf={OTHER_UNIT}, and PrivateTmp=true in both units",
+                join_paths(&written_shared)
+            ));
+        }
+    } else if !read_shared.is_empty() || !written_shared.is_empty() {
+        warnings.push(format!(
+            "Temporary files {} are shared with other programs, if they run in a unit, PrivateTmp=true could still be used with JoinsNamespaceOf={OTHER_UNIT}, and PrivateTmp=true in both units",
+            join_paths(&read_shared.union(&written_shared).copied().collect())
+        ));
+    }
+    if !abstract_sockets.is_empty() && !is_set(opts, "PrivateNetwork") {
+        warnings.push(format!(
+            "Abstract UNIX sockets {} are shared with other programs in the same network namespace, if they run in a unit, PrivateNetwork=true could still be used with JoinsNamespaceOf={OTHER_UNIT}, and PrivateNetwork=true in both units",
+            abstract_sockets.into_iter().collect::<Vec<_>>().join(", ")
+        ));
+    }
+    warnings
+}
+
+/// Paths written or created by a unit
+fn written_paths(actions: &[ProgramAction]) -> BTreeSet<&Path> {
+    actions
+        .iter()
+        .filter_map(|a| match a {
+            ProgramAction::Write(path) | ProgramAction::Create(path) => Some(path.as_path()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Paths accessed by a unit
+fn accessed_paths(actions: &[ProgramAction]) -> BTreeSet<&Path> {
+    actions
+        .iter()
+        .filter_map(|a| match a {
+            ProgramAction::Read(path)
+            | ProgramAction::Write(path)
+            | ProgramAction::Create(path) => Some(path.as_path()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn abstract_sockets(actions: &[ProgramAction]) -> BTreeSet<&str> {
+    actions
+        .iter()
+        .filter_map(|a| match a {
+            ProgramAction::AbstractSocket(name) => Some(name.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Get warnings about data exchanged between units profiled together, through paths one writes and another
+/// accesses, or abstract sockets both use, with the options that keep these exchanges working
+pub(crate) fn related_unit_warnings(units: &[(String, Vec<ProgramAction>)]) -> Vec<String> {
+    let mut warnings = Vec::new();
+    for (i, (unit, actions)) in units.iter().enumerate() {
+        for (other_unit, other_actions) in &units[i + 1..] {
+            let exchanged: BTreeSet<&Path> = written_paths(actions)
+                .intersection(&accessed_paths(other_actions))
+                .chain(written_paths(other_actions).intersection(&accessed_paths(actions)))
+                .copied()
+                .filter(|p| !PSEUDO_FS_DIRS.iter().any(|d| p.starts_with(d)))
+                .collect();
+            let (tmp_paths, other_paths): (BTreeSet<&Path>, BTreeSet<&Path>) = exchanged
+                .into_iter()
+                .partition(|p| TMP_DIRS.iter().any(|d| p.starts_with(d)));
+            if !tmp_paths.is_empty() {
+                warnings.push(format!(
+                    "{unit} and {other_unit} exchange data through temporary files {}, PrivateTmp=true can only be used with JoinsNamespaceOf={other_unit} in {unit}, and PrivateTmp=true in both units",
+                    join_paths(&tmp_paths)
+                ));
+            }
+            if !other_paths.is_empty() {
+                warnings.push(format!(
+                    "{unit} and {other_unit} exchange data through {}, which must stay accessible to both units if their hardening config is edited",
+                    join_paths(&other_paths)
+                ));
+            }
+            let sockets: Vec<_> = abstract_sockets(actions)
+                .intersection(&abstract_sockets(other_actions))
+                .map(|s| format!("@{s}"))
+                .collect();
+            if !sockets.is_empty() {
+                warnings.push(format!(
+                    "{unit} and {other_unit} exchange data through abstract UNIX sockets {}, PrivateNetwork=true can only be used with JoinsNamespaceOf={other_unit} in {unit}, and PrivateNetwork=true in both units",
+                    sockets.join(", ")
+                ));
+            }
+        }
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shared_namespace_warnings() {
+        let actions = vec![
+            ProgramAction::Create("/tmp/own".into()),
+            ProgramAction::Write("/tmp/own".into()),
+            ProgramAction::Read("/tmp".into()),
+            ProgramAction::Write("/tmp/shared.fifo".into()),
+            ProgramAction::AbstractSocket("foo".to_owned()),
+        ];
+        let private_tmp: Vec<OptionWithValue> = vec!["PrivateTmp=true".parse().unwrap()];
+        assert_eq!(
+            shared_namespace_warnings(&actions, &private_tmp),
+            vec![
+                "PrivateTmp=true makes /tmp/shared.fifo private to the service, which breaks exchanging data through them with other programs, unless they run in a unit sharing its temporary directories with JoinsNamespaceOf=OTHER.service, and PrivateTmp=true in both units",
+                "Abstract UNIX sockets @foo are shared with other programs in the same network namespace, if they run in a unit, PrivateNetwork=true could still be used with JoinsNamespaceOf=OTHER.service, and PrivateNetwork=true in both units",
+            ]
+        );
+
+        let read_actions = vec![ProgramAction::Read("/var/tmp/foo.lock".into())];
+        assert_eq!(
+            shared_namespace_warnings(&read_actions, &[]),
+            vec![
+                "Temporary files /var/tmp/foo.lock are shared with other programs, if they run in a unit, PrivateTmp=true could still be used with JoinsNamespaceOf=OTHER.service, and PrivateTmp=true in both units",
+            ]
+        );
+        assert!(shared_namespace_warnings(&[], &private_tmp).is_empty());
+    }
+
+    #[test]
+    fn test_related_unit_warnings() {
+        let units = vec![
+            (
+                "foo.service".to_owned(),
+                vec![
+                    ProgramAction::Create("/run/foo/foo.sock".into()),
+                    ProgramAction::Write("/tmp/foo.queue".into()),
+                    ProgramAction::Write("/dev/null".into()),
+                    ProgramAction::AbstractSocket("foo".to_owned()),
+                ],
+            ),
+            (
+                "foo-worker@1.service".to_owned(),
+                vec![
+                    ProgramAction::Write("/run/foo/foo.sock".into()),
+                    ProgramAction::Read("/tmp/foo.queue".into()),
+                    ProgramAction::Write("/dev/null".into()),
+                    ProgramAction::AbstractSocket("foo".to_owned()),
+                ],
+            ),
+            (
+                "bar.service".to_owned(),
+                vec![ProgramAction::Read("/etc/bar.conf".into())],
+            ),
+        ];
+        assert_eq!(
+            related_unit_warnings(&units),
+            vec![
+                "foo.service and foo-worker@1.service exchange data through temporary files /tmp/foo.queue, PrivateTmp=true can only be used with JoinsNamespaceOf=foo-worker@1.service in foo.service, and PrivateTmp=true in both units",
+                "foo.service and foo-worker@1.service exchange data through /run/foo/foo.sock, which must stay accessible to both units if their hardening config is edited",
+                "foo.service and foo-worker@1.service exchange data through abstract UNIX sockets @foo, PrivateNetwork=true can only be used with JoinsNamespaceOf=foo-worker@1.service in foo.service, and PrivateNetwork=true in both units",
+            ]
+        );
+        assert!(related_unit_warnings(&units[2..]).is_empty());
+    }
+}
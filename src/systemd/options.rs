@@ -15,11 +15,16 @@ use itertools::Itertools;
 use strum::IntoEnumIterator;
 
 use crate::{
-    cl::{HardeningMode, HardeningOptions},
+    capabilities::CAPABILITIES,
+    cl::{CapabilitySource, HardeningMode, HardeningOptions, HardeningPreset, SyscallDenyAction},
     summarize::{
         CountableSetSpecifier, NetworkActivity, NetworkActivityKind, ProgramAction, SetSpecifier,
+        NAMESPACE_FLAGS,
+    },
+    systemd::{
+        containers::CONTAINER_BREAKING_OPTIONS, pseudo_files::OPTIONAL_PROC_FILES, KernelVersion,
+        SystemdVersion,
     },
-    systemd::{KernelVersion, SystemdVersion},
 };
 
 /// Callbacks to dynamically update an option to make it compatible with an action
@@ -29,6 +34,8 @@ pub(crate) struct OptionUpdater {
     pub effect: fn(&OptionValueEffect, &ProgramAction) -> Option<OptionValueEffect>,
     /// Generate the option value from the new effect
     pub value: fn(&OptionValueEffect) -> OptionValue,
+    /// Generate other options the new effect relies on
+    pub extra_options: fn(&OptionValueEffect) -> Vec<OptionWithValue>,
 }
 
 /// Systemd option with its possibles values, and their effect
@@ -102,16 +109,27 @@ pub(crate) enum PathDescription {
         exceptions: Vec<PathBuf>,
     },
     Pattern(regex::bytes::Regex),
+    PatternExcept {
+        pattern: regex::bytes::Regex,
+        exceptions: Vec<PathBuf>,
+    },
 }
 
 impl PathDescription {
     pub(crate) fn matches(&self, path: &Path) -> bool {
-        assert!(path.is_absolute(), "{path:?}");
+        assert!(path.is_absolute(), "{}", path.display());
         match self {
             PathDescription::Base { base, exceptions } => {
                 path.starts_with(base) && !exceptions.iter().any(|e| path.starts_with(e))
             }
             PathDescription::Pattern(r) => r.is_match(path.as_os_str().as_bytes()),
+            PathDescription::PatternExcept {
+                pattern,
+                exceptions,
+            } => {
+                pattern.is_match(path.as_os_str().as_bytes())
+                    && !exceptions.iter().any(|e| path.starts_with(e))
+            }
         }
     }
 }
@@ -181,6 +199,10 @@ impl FromStr for SocketFamily {
 pub(crate) enum SocketProtocol {
     Tcp,
     Udp,
+    /// Stream or sequential packet socket with `IPPROTO_SCTP`
+    Sctp,
+    /// `SOCK_RAW` socket, including `IPPROTO_RAW`
+    Raw,
     Other(String),
 }
 
@@ -191,6 +213,7 @@ impl FromStr for SocketProtocol {
         match s {
             "SOCK_STREAM" => Ok(Self::Tcp),
             "SOCK_DGRAM" => Ok(Self::Udp),
+            "SOCK_RAW" => Ok(Self::Raw),
             _ => Ok(Self::Other(s.to_owned())),
         }
     }
@@ -224,11 +247,75 @@ impl DenySyscalls {
 }
 
 /// A systemd option with a value, as would be present in a config file
+#[derive(Debug)]
 pub(crate) struct OptionWithValue {
     pub name: String,
     pub value: OptionValue,
 }
 
+/// Kind of restriction an option applies, to group options in output
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, strum::Display)]
+pub(crate) enum OptionCategory {
+    #[strum(serialize = "Filesystem")]
+    Filesystem,
+    #[strum(serialize = "Network")]
+    Network,
+    #[strum(serialize = "Kernel")]
+    Kernel,
+    #[strum(serialize = "System calls")]
+    SystemCalls,
+    #[strum(serialize = "Capabilities")]
+    Capabilities,
+    #[strum(serialize = "Resources")]
+    Resources,
+    #[strum(serialize = "Other")]
+    Other,
+}
+
+impl OptionCategory {
+    /// Category of an option from its name
+    pub(crate) fn of(name: &str) -> Self {
+        match name {
+            "BindReadOnlyPaths"
+            | "DeviceAllow"
+            | "DevicePolicy"
+            | "PrivateDevices"
+            | "PrivateTmp"
+            | "ProtectHome"
+            | "ProcSubset"
+            | "ProtectProc"
+            | "ProtectSystem"
+            | "ReadWritePaths"
+            | "TemporaryFileSystem"
+            | "WorkingDirectory" => Self::Filesystem,
+            "PrivateNetwork" | "RestrictAddressFamilies" | "SocketBindAllow" | "SocketBindDeny" => {
+                Self::Network
+            }
+            "KeyringMode"
+            | "LockPersonality"
+            | "ProtectClock"
+            | "ProtectControlGroups"
+            | "ProtectKernelLogs"
+            | "ProtectKernelModules"
+            | "ProtectKernelTunables"
+            | "RestrictNamespaces"
+            | "RestrictRealtime" => Self::Kernel,
+            "MemoryDenyWriteExecute" | "SystemCallArchitectures" | "SystemCallFilter" => {
+                Self::SystemCalls
+            }
+            "CapabilityBoundingSet" => Self::Capabilities,
+            "LimitNOFILE" | "TasksMax" => Self::Resources,
+            _ => Self::Other,
+        }
+    }
+}
+
+impl OptionWithValue {
+    pub(crate) fn category(&self) -> OptionCategory {
+        OptionCategory::of(&self.name)
+    }
+}
+
 impl FromStr for OptionWithValue {
     type Err = anyhow::Error;
 
@@ -298,7 +385,7 @@ impl fmt::Display for OptionWithValue {
     }
 }
 
-static SYSCALL_CLASSES: LazyLock<HashMap<&'static str, HashSet<&'static str>>> =
+pub(crate) static SYSCALL_CLASSES: LazyLock<HashMap<&'static str, HashSet<&'static str>>> =
     LazyLock::new(|| {
         HashMap::from([
             (
@@ -816,6 +903,79 @@ static SYSCALL_CLASSES: LazyLock<HashMap<&'static str, HashSet<&'static str>>> =
         ])
     });
 
+/// Paths hidden by `ProtectHome`, with the depth of user home directories below them
+const HOME_PATHS: [(&str, usize); 3] = [("/home/", 1), ("/root/", 0), ("/run/user/", 1)];
+
+/// Maximum number of directories to bind mount with `ProtectHome=tmpfs`, before giving up on it
+pub(crate) const PROTECT_HOME_MAX_BIND_PATHS: usize = 8;
+
+/// Sysfs mount point, made read only by `ProtectKernelTunables`
+const SYSFS_PATH: &str = "/sys/";
+
+/// Maximum number of sysfs paths to make writable with `ProtectKernelTunables`, before giving up on it
+pub(crate) const PROTECT_KERNEL_TUNABLES_MAX_SYSFS_WRITE_PATHS: usize = 8;
+
+/// Options not supported by services of the per-user service manager
+/// See <https://www.freedesktop.org/software/systemd/man/systemd.exec.html>
+pub(crate) const SYSTEM_SERVICE_ONLY_OPTIONS: [&str; 15] = [
+    // Only available for system services
+    "ProcSubset",
+    "ProtectControlGroups",
+    "ProtectProc",
+    // Need file system namespacing or capabilities, only available to user services with `PrivateUsers=`, which
+    // changes the identities the service sees and is not generated
+    "BindReadOnlyPaths",
+    "CapabilityBoundingSet",
+    "PrivateDevices",
+    "PrivateNetwork",
+    "PrivateTmp",
+    "ProtectClock",
+    "ProtectHome",
+    "ProtectKernelLogs",
+    "ProtectKernelModules",
+    "ProtectKernelTunables",
+    "ProtectSystem",
+    "ReadWritePaths",
+];
+
+/// Syscalls commonly used to escape containers and sandboxes, denied individually when not used, even if their
+/// group can not be denied
+/// `open_by_handle_at` can open any file of a filesystem from a forged handle, bypassing mount namespaces
+pub(crate) const SENSITIVE_SYSCALLS: [&str; 2] = ["name_to_handle_at", "open_by_handle_at"];
+
+/// Lowest generated `LimitNOFILE` value, which is also the default soft limit
+const MIN_LIMIT_NOFILE: u64 = 1024;
+/// Lowest generated `TasksMax` value
+const MIN_TASKS_MAX: u64 = 64;
+
+/// Minimum ratio between a generated resource limit and the observed peak usage, since profiling may not have seen
+/// the heaviest load
+pub(crate) const RESOURCE_LIMIT_MARGIN: u64 = 2;
+
+/// Resource limit for an observed peak usage, rounded up to a power of two
+fn resource_limit(peak: u64, min: u64) -> u64 {
+    peak.saturating_mul(RESOURCE_LIMIT_MARGIN)
+        .next_power_of_two()
+        .max(min)
+}
+
+/// Options relying on BPF programs attached to the service cgroup (`DevicePolicy=`, `DeviceAllow=`,
+/// `SocketBindDeny=`)
+const CGROUP_BPF_OPTIONS: [&str; 3] = ["PrivateDevices", "ProtectClock", "SocketBindDeny"];
+
+/// Get options a hardening preset leaves out
+fn preset_left_out_options(preset: HardeningPreset) -> Vec<&'static str> {
+    match preset {
+        HardeningPreset::Server => vec![],
+        HardeningPreset::Desktop => vec!["PrivateNetwork", "PrivateTmp", "ProtectHome"],
+        HardeningPreset::Container => vec!["SocketBindDeny"],
+        HardeningPreset::Embedded => CGROUP_BPF_OPTIONS
+            .into_iter()
+            .chain(["ProcSubset", "ProtectProc"])
+            .collect(),
+    }
+}
+
 #[expect(clippy::too_many_lines)]
 pub(crate) fn build_options(
     systemd_version: &SystemdVersion,
@@ -878,7 +1038,10 @@ pub(crate) fn build_options(
     });
 
     // https://www.freedesktop.org/software/systemd/man/systemd.exec.html#ProtectHome=
-    let home_paths = ["/home/", "/root/", "/run/user/"];
+    //
+    // If the program only reads a few specific directories in home dirs, we can still use the tmpfs value,
+    // and bind mount those directories read only on top of it.
+    let home_paths = HOME_PATHS.map(|(p, _)| p);
     options.push(OptionDescription {
         name: "ProtectHome",
         possible_values: vec![
@@ -931,7 +1094,89 @@ pub(crate) fn build_options(
                 )),
             },
         ],
-        updater: None,
+        updater: Some(OptionUpdater {
+            effect: |e, a| {
+                let OptionValueEffect::Multiple(effects) = e else {
+                    unreachable!();
+                };
+                let ProgramAction::Read(path) = a else {
+                    return None;
+                };
+                // Only the tmpfs value keeps home dirs read only, which bind mounts need
+                if !effects
+                    .iter()
+                    .any(|sub_eff| matches!(sub_eff, OptionValueEffect::DenyWrite(_)))
+                {
+                    return None;
+                }
+                // TODO APPROXIMATION for services with a RootDirectory=, the path may not be checked in the right root
+                let dir = if path.is_dir() {
+                    path.as_path()
+                } else {
+                    path.parent()?
+                };
+                // Bind mount specifiers are space separated
+                if dir.as_os_str().as_bytes().contains(&b' ') {
+                    return None;
+                }
+                let mut added = false;
+                let mut bind_count = 0;
+                let mut new_effects = effects.clone();
+                for effect in &mut new_effects {
+                    if let OptionValueEffect::Hide(PathDescription::Base { base, exceptions }) =
+                        effect
+                    {
+                        if let Some((_, user_home_depth)) =
+                            HOME_PATHS.iter().find(|(p, _)| Path::new(p) == base)
+                        {
+                            if dir
+                                .strip_prefix(&base)
+                                .is_ok_and(|r| r.components().count() > *user_home_depth)
+                            {
+                                exceptions.push(dir.to_owned());
+                                added = true;
+                            }
+                        }
+                        bind_count += exceptions.len();
+                    }
+                }
+                (added && (bind_count <= PROTECT_HOME_MAX_BIND_PATHS))
+                    .then_some(OptionValueEffect::Multiple(new_effects))
+            },
+            value: |_| OptionValue::String("tmpfs".to_owned()),
+            extra_options: |e| {
+                let OptionValueEffect::Multiple(effects) = e else {
+                    unreachable!();
+                };
+                let bind_paths: Vec<_> = effects
+                    .iter()
+                    .filter_map(|sub_eff| {
+                        if let OptionValueEffect::Hide(PathDescription::Base {
+                            exceptions, ..
+                        }) = sub_eff
+                        {
+                            Some(exceptions)
+                        } else {
+                            None
+                        }
+                    })
+                    .flatten()
+                    // Don't fail if the directory is missing when the service starts
+                    .map(|p| format!("-{}", p.to_string_lossy()))
+                    .sorted_unstable()
+                    .collect();
+                vec![OptionWithValue {
+                    name: "BindReadOnlyPaths".to_owned(),
+                    value: OptionValue::List {
+                        values: bind_paths,
+                        value_if_empty: None,
+                        negation_prefix: false,
+                        repeat_option: false,
+                        mode: ListMode::WhiteList,
+                    },
+                }]
+            },
+        }),
     });
 
     // https://www.freedesktop.org/software/systemd/man/systemd.exec.html#PrivateTmp=
@@ -1027,14 +1272,75 @@ pub(crate) fn build_options(
                 .chain(
                     // https://github.com/systemd/systemd/blob/v254/src/core/namespace.c#L130
                     iter::once(OptionValueEffect::DenyWrite(PathDescription::Base {
-                        base: "/sys/".into(),
+                        base: SYSFS_PATH.into(),
                         exceptions: vec![],
                     })),
                 )
                 .collect(),
             )),
         }],
-        updater: None,
+        updater: Some(OptionUpdater {
+            effect: |e, a| {
+                let OptionValueEffect::Multiple(effects) = e else {
+                    unreachable!();
+                };
+                // Hardware touching daemons write to a few device attributes (LEDs, GPIOs...), which can be made
+                // writable again, leaving the rest of /sys read only
+                let ProgramAction::Write(path) = a else {
+                    return None;
+                };
+                // Path specifiers are space separated
+                if !path.starts_with(SYSFS_PATH) || path.as_os_str().as_bytes().contains(&b' ') {
+                    return None;
+                }
+                let mut new_effects = effects.clone();
+                let exceptions = new_effects.iter_mut().find_map(|sub_eff| match sub_eff {
+                    OptionValueEffect::DenyWrite(PathDescription::Base { base, exceptions })
+                        if base == Path::new(SYSFS_PATH) =>
+                    {
+                        Some(exceptions)
+                    }
+                    _ => None,
+                })?;
+                exceptions.push(path.to_owned());
+                (exceptions.len() <= PROTECT_KERNEL_TUNABLES_MAX_SYSFS_WRITE_PATHS)
+                    .then_some(OptionValueEffect::Multiple(new_effects))
+            },
+            value: |_| OptionValue::Boolean(true),
+            extra_options: |e| {
+                let OptionValueEffect::Multiple(effects) = e else {
+                    unreachable!();
+                };
+                let write_paths: Vec<_> = effects
+                    .iter()
+                    .filter_map(|sub_eff| {
+                        if let OptionValueEffect::DenyWrite(PathDescription::Base {
+                            exceptions,
+                            ..
+                        }) = sub_eff
+                        {
+                            Some(exceptions)
+                        } else {
+                            None
+                        }
+                    })
+                    .flatten()
+                    // Device nodes may be missing when the service starts
+                    .map(|p| format!("-{}", p.to_string_lossy()))
+                    .sorted_unstable()
+                    .collect();
+                vec![OptionWithValue {
+                    name: "ReadWritePaths".to_owned(),
+                    value: OptionValue::List {
+                        values: write_paths,
+                        value_if_empty: None,
+                        negation_prefix: false,
+                        repeat_option: false,
+                        mode: ListMode::WhiteList,
+                    },
+                }]
+            },
+        }),
     });
 
     // https://www.freedesktop.org/software/systemd/man/systemd.exec.html#ProtectKernelModules=
@@ -1059,24 +1365,27 @@ pub(crate) fn build_options(
     });
 
     // https://www.freedesktop.org/software/systemd/man/systemd.exec.html#ProtectKernelLogs=
-    options.push(OptionDescription {
-        name: "ProtectKernelLogs",
-        possible_values: vec![OptionValueDescription {
-            value: OptionValue::Boolean(true),
-            desc: OptionEffect::Simple(OptionValueEffect::Multiple(vec![
-                // https://github.com/systemd/systemd/blob/v254/src/core/namespace.c#L140
-                OptionValueEffect::Hide(PathDescription::Base {
-                    base: "/proc/kmsg".into(),
-                    exceptions: vec![],
-                }),
-                OptionValueEffect::Hide(PathDescription::Base {
-                    base: "/dev/kmsg".into(),
-                    exceptions: vec![],
-                }),
-            ])),
-        }],
-        updater: None,
-    });
+    // Supported since systemd v244
+    if systemd_version >= &SystemdVersion::new(244, 0) {
+        options.push(OptionDescription {
+            name: "ProtectKernelLogs",
+            possible_values: vec![OptionValueDescription {
+                value: OptionValue::Boolean(true),
+                desc: OptionEffect::Simple(OptionValueEffect::Multiple(vec![
+                    // https://github.com/systemd/systemd/blob/v254/src/core/namespace.c#L140
+                    OptionValueEffect::Hide(PathDescription::Base {
+                        base: "/proc/kmsg".into(),
+                        exceptions: vec![],
+                    }),
+                    OptionValueEffect::Hide(PathDescription::Base {
+                        base: "/dev/kmsg".into(),
+                        exceptions: vec![],
+                    }),
+                ])),
+            }],
+            updater: None,
+        });
+    }
 
     // https://www.freedesktop.org/software/systemd/man/systemd.exec.html#ProtectControlGroups=
     // TODO private/strip
@@ -1112,6 +1421,27 @@ pub(crate) fn build_options(
             }],
             updater: None,
         });
+
+        // https://www.freedesktop.org/software/systemd/man/systemd.exec.html#ProcSubset=
+        options.push(OptionDescription {
+            name: "ProcSubset",
+            possible_values: vec![OptionValueDescription {
+                value: OptionValue::String("pid".to_owned()),
+                desc: OptionEffect::Simple(OptionValueEffect::Hide(
+                    PathDescription::PatternExcept {
+                        // Everything in /proc, except process directories
+                        #[expect(clippy::unwrap_used)]
+                        pattern: regex::bytes::Regex::new("^/proc/[^/]*[^/0-9]").unwrap(),
+                        exceptions: ["/proc/self", "/proc/thread-self"]
+                            .into_iter()
+                            .chain(OPTIONAL_PROC_FILES)
+                            .map(PathBuf::from)
+                            .collect(),
+                    },
+                )),
+            }],
+            updater: None,
+        });
     }
 
     // https://www.freedesktop.org/software/systemd/man/systemd.exec.html#MemoryDenyWriteExecute=
@@ -1192,8 +1522,10 @@ pub(crate) fn build_options(
                                 #[expect(clippy::unwrap_used)]
                                 af: SetSpecifier::One(af.parse().unwrap()),
                                 proto: SetSpecifier::All,
-                                kind: SetSpecifier::All,
+                                // Only socket creation is filtered, not accepting on inherited sockets
+                                kind: SetSpecifier::One(NetworkActivityKind::SocketCreation),
                                 local_port: CountableSetSpecifier::All,
+                                remote_addr: None,
                             },
                         ))
                     })
@@ -1219,6 +1551,7 @@ pub(crate) fn build_options(
                         proto: SetSpecifier::All,
                         kind: SetSpecifier::All,
                         local_port: CountableSetSpecifier::All,
+                        remote_addr: None,
                     }),
                 )),
             }],
@@ -1228,95 +1561,132 @@ pub(crate) fn build_options(
 
     // https://www.freedesktop.org/software/systemd/man/systemd.resource-control.html#SocketBindAllow=bind-rule
     //
-    // We don't go as far as allowing/denying individual ports, as that would easily break for example if a port is changed
-    // in a server configuration
+    // We deny binding to all ports for each address family and protocol, and allow the ports bound during profiling
+    // again with SocketBindAllow, which takes precedence. Adjacent ports are coalesced into ranges to keep the rules
+    // readable.
+    // This breaks if a port is changed in a server configuration, so the allowed ports must be updated with it.
     let deny_binds: Vec<_> = SocketFamily::iter()
         .take(2)
         .cartesian_product(SocketProtocol::iter().take(2))
         .collect();
-    options.push(OptionDescription {
-        name: "SocketBindDeny",
-        possible_values: vec![OptionValueDescription {
-            value: OptionValue::List {
-                values: deny_binds
-                    .iter()
-                    .map(|(af, proto)| format!("{af}:{proto}"))
-                    .collect(),
-                value_if_empty: None,
-                negation_prefix: false,
-                repeat_option: true,
-                mode: ListMode::BlackList,
-            },
-            desc: OptionEffect::Cumulative(
-                deny_binds
-                    .into_iter()
-                    .map(|(af, proto)| {
-                        OptionValueEffect::DenyAction(ProgramAction::NetworkActivity(
-                            NetworkActivity {
-                                af: SetSpecifier::One(af),
-                                proto: SetSpecifier::One(proto),
-                                kind: SetSpecifier::One(NetworkActivityKind::Bind),
-                                local_port: CountableSetSpecifier::All,
-                            },
-                        ))
-                    })
-                    .collect(),
-            ),
-        }],
-        updater: hardening_opts.network_firewalling.then_some(OptionUpdater {
-            effect: |e, a| {
-                let OptionValueEffect::DenyAction(ProgramAction::NetworkActivity(effect_na)) = e
-                else {
-                    unreachable!();
-                };
-                let ProgramAction::NetworkActivity(NetworkActivity {
-                    local_port: CountableSetSpecifier::One(local_port),
-                    ..
-                }) = a
-                else {
-                    unreachable!();
-                };
-                let mut new_eff_local_port = effect_na.local_port.clone();
-                new_eff_local_port.remove(local_port);
-                Some(OptionValueEffect::DenyAction(
-                    ProgramAction::NetworkActivity(NetworkActivity {
-                        af: effect_na.af.clone(),
-                        proto: effect_na.proto.clone(),
-                        kind: effect_na.kind.clone(),
-                        local_port: new_eff_local_port,
-                    }),
-                ))
-            },
-            value: |e| {
-                let OptionValueEffect::DenyAction(ProgramAction::NetworkActivity(denied_na)) = e
-                else {
-                    unreachable!();
-                };
-                OptionValue::List {
-                    values: denied_na
-                        .af
-                        .elements()
+    // Supported since systemd v249
+    if systemd_version >= &SystemdVersion::new(249, 0) {
+        options.push(OptionDescription {
+            name: "SocketBindDeny",
+            possible_values: vec![OptionValueDescription {
+                value: OptionValue::List {
+                    values: deny_binds
                         .iter()
-                        .cartesian_product(denied_na.proto.elements())
-                        .cartesian_product(denied_na.local_port.ranges())
-                        .map(|((af, proto), port_range)| {
-                            format!(
-                                "{}:{}:{}-{}",
-                                af,
-                                proto,
-                                port_range.start(),
-                                port_range.end()
-                            )
-                        })
+                        .map(|(af, proto)| format!("{af}:{proto}"))
                         .collect(),
                     value_if_empty: None,
                     negation_prefix: false,
                     repeat_option: true,
                     mode: ListMode::BlackList,
-                }
-            },
-        }),
-    });
+                },
+                desc: OptionEffect::Cumulative(
+                    deny_binds
+                        .into_iter()
+                        .map(|(af, proto)| {
+                            OptionValueEffect::DenyAction(ProgramAction::NetworkActivity(
+                                NetworkActivity {
+                                    af: SetSpecifier::One(af),
+                                    proto: SetSpecifier::One(proto),
+                                    kind: SetSpecifier::One(NetworkActivityKind::Bind),
+                                    local_port: CountableSetSpecifier::All,
+                                    remote_addr: None,
+                                },
+                            ))
+                        })
+                        .collect(),
+                ),
+            }],
+            updater: hardening_opts.network_firewalling.then_some(OptionUpdater {
+                effect: |e, a| {
+                    let OptionValueEffect::DenyAction(ProgramAction::NetworkActivity(effect_na)) =
+                        e
+                    else {
+                        unreachable!();
+                    };
+                    let ProgramAction::NetworkActivity(NetworkActivity {
+                        local_port: CountableSetSpecifier::One(local_port),
+                        ..
+                    }) = a
+                    else {
+                        // Port is not known, so we can not allow it specifically
+                        return None;
+                    };
+                    let mut new_eff_local_port = effect_na.local_port.clone();
+                    new_eff_local_port.remove(local_port);
+                    Some(OptionValueEffect::DenyAction(
+                        ProgramAction::NetworkActivity(NetworkActivity {
+                            af: effect_na.af.clone(),
+                            proto: effect_na.proto.clone(),
+                            kind: effect_na.kind.clone(),
+                            local_port: new_eff_local_port,
+                            remote_addr: None,
+                        }),
+                    ))
+                },
+                value: |e| {
+                    let OptionValueEffect::DenyAction(ProgramAction::NetworkActivity(denied_na)) =
+                        e
+                    else {
+                        unreachable!();
+                    };
+                    OptionValue::List {
+                        values: denied_na
+                            .af
+                            .elements()
+                            .iter()
+                            .cartesian_product(denied_na.proto.elements())
+                            .map(|(af, proto)| format!("{af}:{proto}"))
+                            .collect(),
+                        value_if_empty: None,
+                        negation_prefix: false,
+                        repeat_option: true,
+                        mode: ListMode::BlackList,
+                    }
+                },
+                extra_options: |e| {
+                    let OptionValueEffect::DenyAction(ProgramAction::NetworkActivity(denied_na)) =
+                        e
+                    else {
+                        unreachable!();
+                    };
+                    vec![OptionWithValue {
+                        name: "SocketBindAllow".to_owned(),
+                        value: OptionValue::List {
+                            values: denied_na
+                                .af
+                                .elements()
+                                .iter()
+                                .cartesian_product(denied_na.proto.elements())
+                                .cartesian_product(denied_na.local_port.complement_ranges())
+                                .map(|((af, proto), port_range)| {
+                                    if port_range.start() == port_range.end() {
+                                        format!("{af}:{proto}:{}", port_range.start())
+                                    } else {
+                                        format!(
+                                            "{}:{}:{}-{}",
+                                            af,
+                                            proto,
+                                            port_range.start(),
+                                            port_range.end()
+                                        )
+                                    }
+                                })
+                                .collect(),
+                            value_if_empty: None,
+                            negation_prefix: false,
+                            repeat_option: true,
+                            mode: ListMode::WhiteList,
+                        },
+                    }]
+                },
+            }),
+        });
+    }
 
     // https://www.freedesktop.org/software/systemd/man/systemd.exec.html#LockPersonality=
     options.push(OptionDescription {
@@ -1345,19 +1715,76 @@ pub(crate) fn build_options(
         updater: None,
     });
 
-    // https://www.freedesktop.org/software/systemd/man/systemd.exec.html#ProtectClock=
+    // https://www.freedesktop.org/software/systemd/man/systemd.exec.html#KeyringMode=
+    // A private session keyring is not linked to the user keyring, so keys added to it by other processes
+    // of the same user are not found, programs using it get a shared session keyring instead
+    options.push(OptionDescription {
+        name: "KeyringMode",
+        possible_values: vec![
+            OptionValueDescription {
+                value: OptionValue::String("shared".to_owned()),
+                desc: OptionEffect::None,
+            },
+            OptionValueDescription {
+                value: OptionValue::String("private".to_owned()),
+                desc: OptionEffect::Simple(OptionValueEffect::Multiple(
+                    ["user", "user-session"]
+                        .into_iter()
+                        .map(|keyring| {
+                            OptionValueEffect::DenyAction(ProgramAction::KeyringUse(
+                                keyring.to_owned(),
+                            ))
+                        })
+                        .collect(),
+                )),
+            },
+        ],
+        updater: None,
+    });
+
+    // https://www.freedesktop.org/software/systemd/man/systemd.exec.html#RestrictNamespaces=
+    // Setting a namespace type allows it, and no type at all is set with 'true'
     options.push(OptionDescription {
-        name: "ProtectClock",
+        name: "RestrictNamespaces",
         possible_values: vec![OptionValueDescription {
-            value: OptionValue::Boolean(true),
-            // This option essentially does the same thing as deny @clock
-            desc: OptionEffect::Simple(OptionValueEffect::DenySyscalls(DenySyscalls::Class(
-                "clock",
-            ))),
+            value: OptionValue::List {
+                values: NAMESPACE_FLAGS
+                    .iter()
+                    .map(|(_, ns)| (*ns).to_owned())
+                    .collect(),
+                value_if_empty: Some("true".to_owned()),
+                negation_prefix: false,
+                repeat_option: false,
+                mode: ListMode::WhiteList,
+            },
+            desc: OptionEffect::Cumulative(
+                NAMESPACE_FLAGS
+                    .iter()
+                    .map(|(_, ns)| {
+                        OptionValueEffect::DenyAction(ProgramAction::NamespaceUse((*ns).to_owned()))
+                    })
+                    .collect(),
+            ),
         }],
         updater: None,
     });
 
+    // https://www.freedesktop.org/software/systemd/man/systemd.exec.html#ProtectClock=
+    // Supported since systemd v245
+    if systemd_version >= &SystemdVersion::new(245, 0) {
+        options.push(OptionDescription {
+            name: "ProtectClock",
+            possible_values: vec![OptionValueDescription {
+                value: OptionValue::Boolean(true),
+                // This option essentially does the same thing as deny @clock
+                desc: OptionEffect::Simple(OptionValueEffect::DenySyscalls(DenySyscalls::Class(
+                    "clock",
+                ))),
+            }],
+            updater: None,
+        });
+    }
+
     // https://www.freedesktop.org/software/systemd/man/latest/systemd.exec.html#CapabilityBoundingSet=
     // Note: we don't want to duplicate the kernel permission checking logic here, which would be
     // a maintenance nightmare, so in most case we over (never under!) simplify the capability's effect
@@ -1379,9 +1806,14 @@ pub(crate) fn build_options(
             OptionValueEffect::DenySyscalls(DenySyscalls::Single("bpf")),
         ),
         // CAP_CHECKPOINT_RESTORE: too complex?
+        // Changing the owner to unchanged ids (-1) does not need the capability
         (
             "CAP_CHOWN",
-            OptionValueEffect::DenySyscalls(DenySyscalls::Class("chown")),
+            OptionValueEffect::DenyAction(ProgramAction::ChangeOwner {
+                path: "/".into(),
+                uid: None,
+                gid: None,
+            }),
         ),
         // CAP_DAC_OVERRIDE: too complex?
         // CAP_DAC_READ_SEARCH: too complex?
@@ -1412,6 +1844,7 @@ pub(crate) fn build_options(
                         proto: SetSpecifier::All,
                         kind: SetSpecifier::All,
                         local_port: CountableSetSpecifier::All,
+                        remote_addr: None,
                     }),
                 ))
                 .chain(
@@ -1421,9 +1854,10 @@ pub(crate) fn build_options(
                             NetworkActivity {
                                 #[expect(clippy::unwrap_used)]
                                 af: SetSpecifier::One(af.parse().unwrap()),
-                                proto: SetSpecifier::One(SocketProtocol::Other("SOCK_RAW".into())),
+                                proto: SetSpecifier::One(SocketProtocol::Raw),
                                 kind: SetSpecifier::All,
                                 local_port: CountableSetSpecifier::All,
+                                remote_addr: None,
                             },
                         ))
                     }),
@@ -1503,6 +1937,21 @@ pub(crate) fn build_options(
             OptionValueEffect::DenyAction(ProgramAction::SetAlarm),
         ),
     ];
+    // With kernel capability checks traced during profiling, needs are exact, so all capabilities can be removed
+    // if never checked, instead of only those we can infer from syscalls
+    let cap_effects: Vec<_> = if hardening_opts.capability_source == CapabilitySource::Kprobe {
+        CAPABILITIES
+            .iter()
+            .map(|c| {
+                (
+                    *c,
+                    OptionValueEffect::DenyAction(ProgramAction::Capability((*c).to_owned())),
+                )
+            })
+            .collect()
+    } else {
+        cap_effects.into_iter().collect()
+    };
     options.push(OptionDescription {
         name: "CapabilityBoundingSet",
         possible_values: vec![OptionValueDescription {
@@ -1527,6 +1976,10 @@ pub(crate) fn build_options(
     // fails, we don't monitor it, but if we deny the chown syscall, the program gets killed with SIGSYS
     // signal when it makes the call, so change the default to just return EPERM.
     // Real world example: https://github.com/tjko/jpegoptim/blob/v1.5.5/jpegoptim.c#L1097-L1099
+    // The action can be changed per syscall group, because some runtimes probe for syscalls and only fall
+    // back gracefully on ENOSYS.
+    //
+    // Sensitive syscalls are also denied individually, in case their group is needed for other syscalls.
     //
     let mut syscall_classes: Vec<_> = SYSCALL_CLASSES.keys().copied().collect();
     syscall_classes.sort_unstable();
@@ -1536,7 +1989,19 @@ pub(crate) fn build_options(
             value: OptionValue::List {
                 values: syscall_classes
                     .iter()
-                    .map(|c| format!("@{c}:EPERM"))
+                    .map(|c| (format!("@{c}"), hardening_opts.syscall_deny_action(c)))
+                    .chain(
+                        SENSITIVE_SYSCALLS
+                            .iter()
+                            .map(|s| ((*s).to_owned(), hardening_opts.syscall_deny_action(s))),
+                    )
+                    // Always explicit, a bare entry would use the action of `SystemCallErrorNumber=` if the unit
+                    // sets it
+                    .map(|(v, action)| match action {
+                        SyscallDenyAction::Eperm => format!("{v}:EPERM"),
+                        SyscallDenyAction::Enosys => format!("{v}:ENOSYS"),
+                        SyscallDenyAction::Kill => format!("{v}:kill"),
+                    })
                     .collect(),
                 value_if_empty: None,
                 negation_prefix: true,
@@ -1547,6 +2012,11 @@ pub(crate) fn build_options(
                 syscall_classes
                     .into_iter()
                     .map(|class| OptionValueEffect::DenySyscalls(DenySyscalls::Class(class)))
+                    .chain(
+                        SENSITIVE_SYSCALLS
+                            .into_iter()
+                            .map(|sc| OptionValueEffect::DenySyscalls(DenySyscalls::Single(sc))),
+                    )
                     .collect(),
             ),
         }],
@@ -1568,6 +2038,114 @@ pub(crate) fn build_options(
         });
     }
 
+    // https://www.freedesktop.org/software/systemd/man/systemd.exec.html#WorkingDirectory=
+    //
+    // Programs that resolve relative paths from their initial working directory get it set explicitly, others
+    // run from the root directory.
+    // The root directory is the initial value, so it can only be changed once, if several working directories were
+    // seen the option is not set at all.
+    options.push(OptionDescription {
+        name: "WorkingDirectory",
+        possible_values: vec![OptionValueDescription {
+            value: OptionValue::String("/".to_owned()),
+            desc: OptionEffect::Simple(OptionValueEffect::DenyAction(
+                ProgramAction::WorkingDirectory("/".into()),
+            )),
+        }],
+        updater: Some(OptionUpdater {
+            effect: |e, a| {
+                let OptionValueEffect::DenyAction(ProgramAction::WorkingDirectory(cur_dir)) = e
+                else {
+                    unreachable!();
+                };
+                let ProgramAction::WorkingDirectory(dir) = a else {
+                    return None;
+                };
+                (cur_dir == Path::new("/")).then(|| {
+                    OptionValueEffect::DenyAction(ProgramAction::WorkingDirectory(dir.to_owned()))
+                })
+            },
+            value: |e| {
+                let OptionValueEffect::DenyAction(ProgramAction::WorkingDirectory(dir)) = e else {
+                    unreachable!();
+                };
+                OptionValue::String(dir.to_string_lossy().into_owned())
+            },
+            extra_options: |_| vec![],
+        }),
+    });
+
+    if hardening_opts.resource_limits {
+        // https://www.freedesktop.org/software/systemd/man/systemd.exec.html#LimitNOFILE=
+        //
+        // The limit applies to file descriptor numbers, so the highest one seen gives the peak usage.
+        // LimitNPROC is not supported because it counts all processes of the user, not only the service ones.
+        options.push(OptionDescription {
+            name: "LimitNOFILE",
+            possible_values: vec![OptionValueDescription {
+                value: OptionValue::String(MIN_LIMIT_NOFILE.to_string()),
+                desc: OptionEffect::Simple(OptionValueEffect::DenyAction(
+                    ProgramAction::OpenFiles(MIN_LIMIT_NOFILE),
+                )),
+            }],
+            updater: Some(OptionUpdater {
+                effect: |_, a| {
+                    let ProgramAction::OpenFiles(count) = a else {
+                        return None;
+                    };
+                    Some(OptionValueEffect::DenyAction(ProgramAction::OpenFiles(
+                        resource_limit(*count, MIN_LIMIT_NOFILE),
+                    )))
+                },
+                value: |e| {
+                    let OptionValueEffect::DenyAction(ProgramAction::OpenFiles(limit)) = e else {
+                        unreachable!();
+                    };
+                    OptionValue::String(limit.to_string())
+                },
+                extra_options: |_| vec![],
+            }),
+        });
+
+        // https://www.freedesktop.org/software/systemd/man/systemd.resource-control.html#TasksMax=N
+        options.push(OptionDescription {
+            name: "TasksMax",
+            possible_values: vec![OptionValueDescription {
+                value: OptionValue::String(MIN_TASKS_MAX.to_string()),
+                desc: OptionEffect::Simple(OptionValueEffect::DenyAction(ProgramAction::Tasks(
+                    MIN_TASKS_MAX,
+                ))),
+            }],
+            updater: Some(OptionUpdater {
+                effect: |_, a| {
+                    let ProgramAction::Tasks(count) = a else {
+                        return None;
+                    };
+                    Some(OptionValueEffect::DenyAction(ProgramAction::Tasks(
+                        resource_limit(*count, MIN_TASKS_MAX),
+                    )))
+                },
+                value: |e| {
+                    let OptionValueEffect::DenyAction(ProgramAction::Tasks(limit)) = e else {
+                        unreachable!();
+                    };
+                    OptionValue::String(limit.to_string())
+                },
+                extra_options: |_| vec![],
+            }),
+        });
+    }
+
+    if hardening_opts.container_manager {
+        // Delegated cgroups and container setup need a writable cgroup tree, host mounts and namespace creation
+        options.retain(|o| !CONTAINER_BREAKING_OPTIONS.contains(&o.name));
+    }
+
+    if let Some(preset) = hardening_opts.preset {
+        let left_out = preset_left_out_options(preset);
+        options.retain(|o| !left_out.contains(&o.name));
+    }
+
     log::debug!("{options:#?}");
     options
 }
@@ -0,0 +1,257 @@
+//! NSS and PAM modules loaded by the program, whose needs depend on the system configuration and on which lookups
+//! or authentications happened during profiling
+
+use std::{fmt, path::Path};
+
+use crate::{
+    summarize::{
+        CountableSetSpecifier, NetworkActivity, NetworkActivityKind, ProgramAction, SetSpecifier,
+    },
+    systemd::options::{SocketFamily, SocketProtocol},
+};
+
+/// Plugin interface
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
+pub(crate) enum PluginKind {
+    /// Name Service Switch module, ie. `libnss_systemd.so.2`
+    Nss,
+    /// Pluggable Authentication Module, ie. `pam_unix.so`
+    Pam,
+}
+
+impl fmt::Display for PluginKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Nss => write!(f, "NSS"),
+            Self::Pam => write!(f, "PAM"),
+        }
+    }
+}
+
+/// A loaded plugin
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd)]
+pub(crate) struct Plugin {
+    pub kind: PluginKind,
+    /// Module name, ie. `systemd` for `libnss_systemd.so.2`
+    pub name: String,
+}
+
+impl fmt::Display for Plugin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.kind, self.name)
+    }
+}
+
+/// Sockets a plugin uses
+#[derive(Clone, Copy)]
+enum PluginSockets {
+    None,
+    /// UNIX sockets, to a local daemon
+    Unix,
+    /// IP sockets, to remote servers
+    Inet,
+}
+
+/// Resources known plugins need, that a profiling run does not necessarily exercise, ie. because all lookups were
+/// answered by a previous module
+struct PluginNeeds {
+    kind: PluginKind,
+    name: &'static str,
+    /// Read paths, including UNIX sockets connected to
+    read_paths: &'static [&'static str],
+    sockets: PluginSockets,
+}
+
+const PLUGIN_NEEDS: [PluginNeeds; 10] = [
+    PluginNeeds {
+        kind: PluginKind::Nss,
+        name: "dns",
+        read_paths: &["/etc/hosts", "/etc/resolv.conf"],
+        sockets: PluginSockets::Inet,
+    },
+    PluginNeeds {
+        kind: PluginKind::Nss,
+        name: "files",
+        read_paths: &["/etc/group", "/etc/hosts", "/etc/passwd", "/etc/services"],
+        sockets: PluginSockets::None,
+    },
+    PluginNeeds {
+        kind: PluginKind::Nss,
+        name: "resolve",
+        read_paths: &["/run/systemd/resolve/io.systemd.Resolve"],
+        sockets: PluginSockets::Unix,
+    },
+    PluginNeeds {
+        kind: PluginKind::Nss,
+        name: "sss",
+        read_paths: &["/var/lib/sss/pipes/nss"],
+        sockets: PluginSockets::Unix,
+    },
+    PluginNeeds {
+        kind: PluginKind::Nss,
+        name: "systemd",
+        read_paths: &["/run/systemd/userdb"],
+        sockets: PluginSockets::Unix,
+    },
+    PluginNeeds {
+        kind: PluginKind::Pam,
+        name: "env",
+        read_paths: &["/etc/environment", "/etc/security/pam_env.conf"],
+        sockets: PluginSockets::None,
+    },
+    PluginNeeds {
+        kind: PluginKind::Pam,
+        name: "limits",
+        read_paths: &["/etc/security/limits.conf", "/etc/security/limits.d"],
+        sockets: PluginSockets::None,
+    },
+    PluginNeeds {
+        kind: PluginKind::Pam,
+        name: "sss",
+        read_paths: &["/var/lib/sss/pipes/pam"],
+        sockets: PluginSockets::Unix,
+    },
+    PluginNeeds {
+        kind: PluginKind::Pam,
+        name: "systemd",
+        read_paths: &["/run/dbus/system_bus_socket"],
+        sockets: PluginSockets::Unix,
+    },
+    PluginNeeds {
+        kind: PluginKind::Pam,
+        name: "unix",
+        read_paths: &["/etc/group", "/etc/passwd", "/etc/shadow"],
+        sockets: PluginSockets::None,
+    },
+];
+
+/// Get plugin from the path of a loaded library, ie. `/lib/x86_64-linux-gnu/libnss_systemd.so.2` or
+/// `/usr/lib/security/pam_unix.so`
+fn plugin(path: &Path) -> Option<Plugin> {
+    let file_name = path.file_name()?.to_str()?;
+    if let Some(name) = file_name
+        .strip_prefix("libnss_")
+        .and_then(|n| n.split_once(".so"))
+        .map(|(n, _)| n)
+    {
+        Some(Plugin {
+            kind: PluginKind::Nss,
+            name: name.to_owned(),
+        })
+    } else if path.parent()?.file_name()? == "security" {
+        Some(Plugin {
+            kind: PluginKind::Pam,
+            name: file_name
+                .strip_prefix("pam_")?
+                .strip_suffix(".so")?
+                .to_owned(),
+        })
+    } else {
+        None
+    }
+}
+
+/// Get NSS and PAM plugins the program loaded
+pub(crate) fn loaded_plugins(actions: &[ProgramAction]) -> Vec<Plugin> {
+    let mut plugins: Vec<_> = actions
+        .iter()
+        .filter_map(|a| match a {
+            ProgramAction::Read(path) => plugin(path),
+            _ => None,
+        })
+        .collect();
+    plugins.sort_unstable();
+    plugins.dedup();
+    plugins
+}
+
+/// Get actions for the known needs of loaded plugins, to resolve options keeping the plugin set working
+pub(crate) fn plugin_actions(actions: &[ProgramAction]) -> Vec<ProgramAction> {
+    let mut plugin_actions = Vec::new();
+    for plugin in loaded_plugins(actions) {
+        let Some(needs) = PLUGIN_NEEDS
+            .iter()
+            .find(|n| n.kind == plugin.kind && n.name == plugin.name)
+        else {
+            continue;
+        };
+        plugin_actions.extend(
+            needs
+                .read_paths
+                .iter()
+                .map(|p| ProgramAction::Read(p.into())),
+        );
+        let (af, proto, syscalls) = match needs.sockets {
+            PluginSockets::None => continue,
+            PluginSockets::Unix => (
+                SetSpecifier::One(SocketFamily::Other("AF_UNIX".to_owned())),
+                SetSpecifier::One(SocketProtocol::Tcp),
+                ["socket", "connect"].as_slice(),
+            ),
+            PluginSockets::Inet => (
+                SetSpecifier::Some(vec![SocketFamily::Ipv4, SocketFamily::Ipv6]),
+                SetSpecifier::Some(vec![SocketProtocol::Tcp, SocketProtocol::Udp]),
+                ["socket", "connect", "sendto", "recvfrom"].as_slice(),
+            ),
+        };
+        plugin_actions.push(ProgramAction::NetworkActivity(NetworkActivity {
+            af,
+            proto,
+            kind: SetSpecifier::One(NetworkActivityKind::SocketCreation),
+            local_port: CountableSetSpecifier::All,
+            remote_addr: None,
+        }));
+        plugin_actions.push(ProgramAction::Syscalls(
+            syscalls.iter().map(|s| (*s).to_owned()).collect(),
+        ));
+    }
+    plugin_actions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plugins() {
+        let actions = vec![
+            ProgramAction::Read("/etc/nsswitch.conf".into()),
+            ProgramAction::Read("/lib/x86_64-linux-gnu/libnss_systemd.so.2".into()),
+            ProgramAction::Read("/usr/lib/x86_64-linux-gnu/security/pam_unix.so".into()),
+            ProgramAction::Read("/usr/lib/x86_64-linux-gnu/security/pam_foo.so".into()),
+            ProgramAction::Read("/lib/x86_64-linux-gnu/libnss_systemd.so.2".into()),
+            ProgramAction::Read("/usr/lib/x86_64-linux-gnu/libpam.so.0".into()),
+        ];
+        assert_eq!(
+            loaded_plugins(&actions)
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec!["NSS systemd", "PAM foo", "PAM unix"]
+        );
+        let extra_actions = plugin_actions(&actions);
+        assert_eq!(
+            extra_actions[0],
+            ProgramAction::Read("/run/systemd/userdb".into())
+        );
+        assert!(matches!(
+            &extra_actions[1],
+            ProgramAction::NetworkActivity(NetworkActivity {
+                af: SetSpecifier::One(SocketFamily::Other(af)),
+                ..
+            }) if af == "AF_UNIX"
+        ));
+        assert_eq!(
+            extra_actions[2],
+            ProgramAction::Syscalls(["connect".to_owned(), "socket".to_owned()].into())
+        );
+        assert_eq!(
+            extra_actions[3..],
+            [
+                ProgramAction::Read("/etc/group".into()),
+                ProgramAction::Read("/etc/passwd".into()),
+                ProgramAction::Read("/etc/shadow".into()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,65 @@
+//! Reads of `/proc` pseudo files outside of process directories, which `ProcSubset=pid` hides
+
+use std::path::Path;
+
+use crate::{
+    summarize::ProgramAction,
+    systemd::options::{OptionValue, OptionWithValue},
+};
+
+/// Pseudo files common libraries only probe for, and work without (libselinux, crypto libraries checking for FIPS
+/// mode, glibc malloc), so reading them does not prevent `ProcSubset=pid`
+pub(crate) const OPTIONAL_PROC_FILES: [&str; 3] = [
+    "/proc/filesystems",
+    "/proc/sys/crypto/fips_enabled",
+    "/proc/sys/vm/overcommit_memory",
+];
+
+/// Get warnings about optional pseudo files the program read, that `ProcSubset=pid` hides
+pub(crate) fn pseudo_file_warnings(
+    actions: &[ProgramAction],
+    opts: &[OptionWithValue],
+) -> Vec<String> {
+    if !opts
+        .iter()
+        .any(|o| o.name == "ProcSubset" && matches!(&o.value, OptionValue::String(v) if v == "pid"))
+    {
+        return vec![];
+    }
+    let read: Vec<_> = OPTIONAL_PROC_FILES
+        .iter()
+        .filter(|f| {
+            actions
+                .iter()
+                .any(|a| matches!(a, ProgramAction::Read(path) if path == Path::new(f)))
+        })
+        .copied()
+        .collect();
+    if read.is_empty() {
+        return vec![];
+    }
+    vec![format!(
+        "ProcSubset=pid hides {}, read by the program, common libraries only probe for them and work without them, but check the program does not rely on their content",
+        read.join(", ")
+    )]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pseudo_file_warnings() {
+        let actions = vec![
+            ProgramAction::Read("/proc/self/status".into()),
+            ProgramAction::Read("/proc/filesystems".into()),
+        ];
+        let opts: Vec<OptionWithValue> = vec!["ProcSubset=pid".parse().unwrap()];
+        assert_eq!(
+            pseudo_file_warnings(&actions, &opts),
+            vec!["ProcSubset=pid hides /proc/filesystems, read by the program, common libraries only probe for them and work without them, but check the program does not rely on their content"]
+        );
+        assert!(pseudo_file_warnings(&actions, &[]).is_empty());
+        assert!(pseudo_file_warnings(&actions[..1], &opts).is_empty());
+    }
+}
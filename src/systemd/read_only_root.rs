@@ -0,0 +1,164 @@
+//! Feasibility of an effectively read only root filesystem: `ProtectSystem=strict`, empty `/etc` and `/var`, and only
+//! explicitly bound paths in them
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use crate::summarize::ProgramAction;
+
+/// Directories replaced by an empty temporary file system, with the depth of the paths bound in them
+const EMPTY_DIRS: [(&str, usize); 2] = [("/etc", 1), ("/var", 2)];
+
+/// Writes below these directories are not affected by `ProtectSystem=strict`, or are handled by other options
+/// (`PrivateTmp=`, `ProtectHome=`, `RuntimeDirectory=`)
+const WRITABLE_DIRS: [&str; 8] = [
+    "/dev", "/proc", "/sys", "/tmp", "/var/tmp", "/home", "/root", "/run",
+];
+
+/// Paths to bind in the empty directories, or what prevents a read only root
+#[derive(Debug, Default, Eq, PartialEq)]
+struct ReadOnlyRoot {
+    /// Bound paths, and whether they need to be writable
+    binds: BTreeMap<PathBuf, bool>,
+    /// Reasons a read only root is not possible
+    blockers: Vec<String>,
+}
+
+impl ReadOnlyRoot {
+    fn new(actions: &[ProgramAction]) -> Self {
+        let mut root = Self::default();
+        for action in actions {
+            let (path, write) = match action {
+                ProgramAction::Read(path)
+                | ProgramAction::Exec(path)
+                | ProgramAction::SetuidExec(path) => (path, false),
+                ProgramAction::Write(path) | ProgramAction::Create(path) => (path, true),
+                _ => continue,
+            };
+            if WRITABLE_DIRS.iter().any(|d| path.starts_with(d)) {
+                continue;
+            }
+            let Some((dir, depth)) = EMPTY_DIRS.iter().find(|(d, _)| path.starts_with(d)) else {
+                if write {
+                    root.blockers.push(format!(
+                        "{} is written to, on the read only root",
+                        path.display()
+                    ));
+                }
+                continue;
+            };
+            if let Some(parent) = path.parent().filter(|p| {
+                matches!(action, ProgramAction::Create(_)) && Self::depth_below(p, dir) < *depth
+            }) {
+                root.blockers.push(format!(
+                    "{} is created in {}, which would be empty and temporary",
+                    path.display(),
+                    parent.display()
+                ));
+                continue;
+            }
+            let Some(bind) = Self::bind_path(path, dir, *depth) else {
+                continue;
+            };
+            *root.binds.entry(bind).or_default() |= write;
+        }
+        root.blockers.sort_unstable();
+        root.blockers.dedup();
+        root
+    }
+
+    /// Number of path components below a base directory
+    fn depth_below(path: &Path, base: &str) -> usize {
+        path.strip_prefix(base)
+            .map_or(0, |r| r.components().count())
+    }
+
+    /// Get path to bind for an accessed path, ie. its parent at the given depth below a base directory
+    fn bind_path(path: &Path, base: &str, depth: usize) -> Option<PathBuf> {
+        let rel_path = path.strip_prefix(base).ok()?;
+        // Directories above bound paths are only listed
+        (rel_path.components().count() >= depth)
+            .then(|| Path::new(base).join(rel_path.components().take(depth).collect::<PathBuf>()))
+    }
+}
+
+/// Get options to run with an effectively read only root filesystem, or the reasons it is not possible
+pub(crate) fn read_only_root(actions: &[ProgramAction]) -> Result<Vec<String>, Vec<String>> {
+    let root = ReadOnlyRoot::new(actions);
+    if !root.blockers.is_empty() {
+        return Err(root.blockers);
+    }
+    let mut opts = vec![
+        "ProtectSystem=strict".to_owned(),
+        format!(
+            "TemporaryFileSystem={}",
+            EMPTY_DIRS
+                .iter()
+                .map(|(d, _)| *d)
+                .collect::<Vec<_>>()
+                .join(" ")
+        ),
+    ];
+    for (name, writable) in [("BindReadOnlyPaths", false), ("BindPaths", true)] {
+        // Don't fail if the path is missing when the service starts
+        let paths: Vec<_> = root
+            .binds
+            .iter()
+            .filter(|(_, w)| **w == writable)
+            .map(|(p, _)| format!("-{}", p.display()))
+            .collect();
+        if !paths.is_empty() {
+            opts.push(format!("{name}={}", paths.join(" ")));
+        }
+    }
+    Ok(opts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_only_root() {
+        let actions = vec![
+            ProgramAction::Exec("/usr/bin/foo".into()),
+            ProgramAction::Read("/etc".into()),
+            ProgramAction::Read("/etc/foo/foo.conf".into()),
+            ProgramAction::Read("/etc/hosts".into()),
+            ProgramAction::Read("/var/lib/foo/db".into()),
+            ProgramAction::Write("/var/lib/foo/db".into()),
+            ProgramAction::Read("/var/lib".into()),
+            ProgramAction::Write("/var/log/foo/foo.log".into()),
+            ProgramAction::Write("/run/foo/foo.pid".into()),
+            ProgramAction::Write("/tmp/foo.tmp".into()),
+        ];
+        assert_eq!(
+            read_only_root(&actions),
+            Ok(vec![
+                "ProtectSystem=strict".to_owned(),
+                "TemporaryFileSystem=/etc /var".to_owned(),
+                "BindReadOnlyPaths=-/etc/foo -/etc/hosts".to_owned(),
+                "BindPaths=-/var/lib/foo -/var/log/foo".to_owned(),
+            ])
+        );
+
+        let blocked_actions = vec![
+            ProgramAction::Read("/etc/foo/foo.conf".into()),
+            ProgramAction::Create("/etc/foo.conf.tmp".into()),
+            ProgramAction::Create("/var/lib/foo".into()),
+            ProgramAction::Write("/opt/foo/state".into()),
+        ];
+        assert_eq!(
+            read_only_root(&blocked_actions),
+            Err(vec![
+                "/etc/foo.conf.tmp is created in /etc, which would be empty and temporary"
+                    .to_owned(),
+                "/opt/foo/state is written to, on the read only root".to_owned(),
+                "/var/lib/foo is created in /var/lib, which would be empty and temporary"
+                    .to_owned(),
+            ])
+        );
+    }
+}
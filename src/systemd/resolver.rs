@@ -1,67 +1,110 @@
 //! Resolver code that finds options compatible with program actions
 
+use std::path::{Path, PathBuf};
+
+use itertools::Itertools as _;
+
 use crate::{
-    summarize::{NetworkActivity, ProgramAction},
-    systemd::options::{
-        ListMode, OptionDescription, OptionEffect, OptionValue, OptionValueEffect, OptionWithValue,
+    path_tree::PathTree,
+    summarize::{NetworkActivity, ProgramAction, SetSpecifier},
+    systemd::{
+        containers::{container_runtime_exec, CONTAINER_BREAKING_OPTIONS},
+        credentials::credential_warnings,
+        devices,
+        filter_size::filter_size_warnings,
+        io_uring::io_uring_warnings,
+        landlock::landlock_warnings,
+        layout::layout_warnings,
+        namespaces::shared_namespace_warnings,
+        options::{
+            ListMode, OptionDescription, OptionEffect, OptionValue, OptionValueEffect,
+            OptionWithValue, RESOURCE_LIMIT_MARGIN,
+        },
+        pseudo_files::pseudo_file_warnings,
+        syscall_filter, OptionComments, SocketFamily,
     },
 };
 
 use super::options::OptionUpdater;
 
 impl OptionValueEffect {
+    /// Check compatibility with the action at index `action_idx`, `paths` being built from all actions
     fn compatible(
         &self,
         action: &ProgramAction,
-        prev_actions: &[ProgramAction],
+        action_idx: usize,
+        paths: &PathTree,
         updater: Option<&OptionUpdater>,
     ) -> ActionOptionEffectCompatibility {
         match self {
             OptionValueEffect::DenyAction(denied) => {
                 let compatible = match denied {
-                    ProgramAction::NetworkActivity(denied) => {
-                        if let ProgramAction::NetworkActivity(NetworkActivity {
+                    ProgramAction::NetworkActivity(denied) => match action {
+                        ProgramAction::NetworkActivity(NetworkActivity {
                             af,
                             proto,
                             kind,
                             local_port,
-                        }) = action
-                        {
+                            ..
+                        }) => {
                             let af_match = denied.af.intersects(af);
                             let proto_match = denied.proto.intersects(proto);
                             let kind_match = denied.kind.intersects(kind);
                             let local_port_match = denied.local_port.intersects(local_port);
                             !af_match || !proto_match || !kind_match || !local_port_match
-                        } else {
-                            true
                         }
-                    }
+                        // Abstract sockets need AF_UNIX, and live in the network namespace
+                        ProgramAction::AbstractSocket(_) => !denied.af.intersects(
+                            &SetSpecifier::One(SocketFamily::Other("AF_UNIX".to_owned())),
+                        ),
+                        _ => true,
+                    },
                     ProgramAction::WriteExecuteMemoryMapping
                     | ProgramAction::SetRealtimeScheduler
+                    | ProgramAction::NamespaceUse(_)
+                    | ProgramAction::KeyringUse(_)
                     | ProgramAction::Wakeup
                     | ProgramAction::MknodSpecial
                     | ProgramAction::SetAlarm => action != denied,
-                    ProgramAction::Syscalls(_)
+                    // A capability kept with capset must be in the bounding set, or capset fails
+                    ProgramAction::Capability(cap) => !matches!(
+                        action,
+                        ProgramAction::Capability(c) | ProgramAction::CapabilityUse(c) if c == cap
+                    ),
+                    // The denied action holds the only working directory allowed
+                    // Any owner change is denied, even to ids the program could set without privileges
+                    ProgramAction::ChangeOwner { .. } => {
+                        !matches!(action, ProgramAction::ChangeOwner { .. })
+                    }
+                    ProgramAction::WorkingDirectory(dir) => !matches!(
+                        action,
+                        ProgramAction::WorkingDirectory(action_dir) if action_dir != dir
+                    ),
+                    // For resource usage, the denied action holds the limit, and usage too close to it is denied
+                    ProgramAction::OpenFiles(limit) => !matches!(
+                        action,
+                        ProgramAction::OpenFiles(count) if count.saturating_mul(RESOURCE_LIMIT_MARGIN) > *limit
+                    ),
+                    ProgramAction::Tasks(limit) => !matches!(
+                        action,
+                        ProgramAction::Tasks(count) if count.saturating_mul(RESOURCE_LIMIT_MARGIN) > *limit
+                    ),
+                    ProgramAction::Exec(_)
+                    | ProgramAction::SetuidExec(_)
+                    | ProgramAction::AbstractSocket(_)
+                    | ProgramAction::Syscalls(_)
+                    | ProgramAction::TempDirectory(_)
+                    | ProgramAction::SetSchedulingPolicy(_)
+                    | ProgramAction::SetNice(_)
+                    | ProgramAction::SetCredentials { .. }
+                    | ProgramAction::CapabilityUse(_)
+                    | ProgramAction::DeviceIoctl { .. }
+                    | ProgramAction::IoUring
                     | ProgramAction::Read(_)
                     | ProgramAction::Write(_)
                     | ProgramAction::Create(_) => unreachable!(),
                 };
-                if compatible {
-                    ActionOptionEffectCompatibility::Compatible
-                } else if let Some(updater) = updater {
-                    if let Some(new_eff) = (updater.effect)(self, action) {
-                        ActionOptionEffectCompatibility::CompatibleIfChanged(
-                            ChangedOptionValueDescription {
-                                value: (updater.value)(&new_eff),
-                                effect: new_eff,
-                            },
-                        )
-                    } else {
-                        ActionOptionEffectCompatibility::Incompatible
-                    }
-                } else {
-                    ActionOptionEffectCompatibility::Incompatible
-                }
+                self.update_if_incompatible(compatible, action, updater)
             }
             OptionValueEffect::DenyWrite(ro_paths) => match action {
                 ProgramAction::Write(path_action) | ProgramAction::Create(path_action) => {
@@ -72,7 +115,7 @@ impl OptionValueEffect {
             OptionValueEffect::Hide(hidden_paths) => {
                 if let ProgramAction::Read(path_action) = action {
                     (!hidden_paths.matches(path_action)
-                        || prev_actions.contains(&ProgramAction::Create(path_action.clone())))
+                        || paths.created_before(path_action, action_idx))
                     .into()
                 } else {
                     ActionOptionEffectCompatibility::Compatible
@@ -91,14 +134,43 @@ impl OptionValueEffect {
                     ActionOptionEffectCompatibility::Compatible
                 }
             }
-            OptionValueEffect::Multiple(effects) => effects
-                .iter()
-                .all(|e| match e.compatible(action, prev_actions, None) {
-                    ActionOptionEffectCompatibility::Compatible => true,
-                    ActionOptionEffectCompatibility::CompatibleIfChanged(_) => todo!(),
-                    ActionOptionEffectCompatibility::Incompatible => false,
-                })
-                .into(),
+            OptionValueEffect::Multiple(effects) => {
+                let compatible =
+                    effects
+                        .iter()
+                        .all(|e| match e.compatible(action, action_idx, paths, None) {
+                            ActionOptionEffectCompatibility::Compatible => true,
+                            ActionOptionEffectCompatibility::CompatibleIfChanged(_) => todo!(),
+                            ActionOptionEffectCompatibility::Incompatible => false,
+                        });
+                self.update_if_incompatible(compatible, action, updater)
+            }
+        }
+    }
+
+    /// Try to change effect with updater, if it is incompatible with action
+    fn update_if_incompatible(
+        &self,
+        compatible: bool,
+        action: &ProgramAction,
+        updater: Option<&OptionUpdater>,
+    ) -> ActionOptionEffectCompatibility {
+        if compatible {
+            ActionOptionEffectCompatibility::Compatible
+        } else if let Some(updater) = updater {
+            if let Some(new_eff) = (updater.effect)(self, action) {
+                ActionOptionEffectCompatibility::CompatibleIfChanged(Box::new(
+                    ChangedOptionValueDescription {
+                        value: (updater.value)(&new_eff),
+                        extra_options: (updater.extra_options)(&new_eff),
+                        effect: new_eff,
+                    },
+                ))
+            } else {
+                ActionOptionEffectCompatibility::Incompatible
+            }
+        } else {
+            ActionOptionEffectCompatibility::Incompatible
         }
     }
 }
@@ -107,13 +179,14 @@ impl OptionValueEffect {
 #[derive(Debug)]
 pub(crate) struct ChangedOptionValueDescription {
     pub value: OptionValue,
+    pub extra_options: Vec<OptionWithValue>,
     pub effect: OptionValueEffect,
 }
 
 /// How compatible is an action with an option effect?
 pub(crate) enum ActionOptionEffectCompatibility {
     Compatible,
-    CompatibleIfChanged(ChangedOptionValueDescription),
+    CompatibleIfChanged(Box<ChangedOptionValueDescription>),
     Incompatible,
 }
 
@@ -130,46 +203,77 @@ impl From<bool> for ActionOptionEffectCompatibility {
 pub(crate) fn actions_compatible(
     eff: &OptionValueEffect,
     actions: &[ProgramAction],
+    paths: &PathTree,
     updater: Option<&OptionUpdater>,
 ) -> ActionOptionEffectCompatibility {
+    match check_actions(eff, actions, paths, updater) {
+        Ok(Some(new_desc)) => {
+            ActionOptionEffectCompatibility::CompatibleIfChanged(Box::new(new_desc))
+        }
+        Ok(None) => ActionOptionEffectCompatibility::Compatible,
+        Err(_) => ActionOptionEffectCompatibility::Incompatible,
+    }
+}
+
+/// Check if effect is compatible with all actions, possibly after being changed,
+/// or return the first incompatible action
+fn check_actions<'a>(
+    eff: &OptionValueEffect,
+    actions: &'a [ProgramAction],
+    paths: &PathTree,
+    updater: Option<&OptionUpdater>,
+) -> Result<Option<ChangedOptionValueDescription>, &'a ProgramAction> {
     let mut changed_desc: Option<ChangedOptionValueDescription> = None;
-    for i in 0..actions.len() {
+    for (i, action) in actions.iter().enumerate() {
         let cur_eff = changed_desc.as_ref().map_or(eff, |d| &d.effect);
-        match cur_eff.compatible(&actions[i], &actions[..i], updater) {
+        match cur_eff.compatible(action, i, paths, updater) {
             ActionOptionEffectCompatibility::Compatible => {}
             ActionOptionEffectCompatibility::CompatibleIfChanged(new_desc) => {
                 log::debug!(
                     "Option effect {:?} is incompatible with {:?}, changing effect to {:?}",
                     cur_eff,
-                    actions[i],
+                    action,
                     new_desc.effect
                 );
-                changed_desc = Some(new_desc);
+                changed_desc = Some(*new_desc);
             }
             ActionOptionEffectCompatibility::Incompatible => {
                 log::debug!(
                     "Option effect {:?} is incompatible with {:?}",
                     cur_eff,
-                    actions[i]
+                    action
                 );
-                return ActionOptionEffectCompatibility::Incompatible;
+                return Err(action);
             }
         }
     }
-
-    if let Some(new_desc) = changed_desc {
-        ActionOptionEffectCompatibility::CompatibleIfChanged(new_desc)
-    } else {
-        ActionOptionEffectCompatibility::Compatible
-    }
+    Ok(changed_desc)
 }
 
 pub(crate) fn resolve(
-    opts: &Vec<OptionDescription>,
+    opts: &[OptionDescription],
     actions: &[ProgramAction],
 ) -> Vec<OptionWithValue> {
+    let paths = PathTree::new(actions);
+    let container_exec = container_runtime_exec(actions);
+    if let Some(action) = container_exec {
+        log::warn!(
+            "Program runs containers ({action}), options isolating mounts, namespaces and cgroups are not applied: {}",
+            CONTAINER_BREAKING_OPTIONS
+                .iter()
+                .filter(|n| opts.iter().any(|o| o.name == **n))
+                .join(", ")
+        );
+    }
     let mut candidates = Vec::new();
     for opt in opts {
+        if container_exec.is_some() && CONTAINER_BREAKING_OPTIONS.contains(&opt.name) {
+            continue;
+        }
+        if opt.name == "SystemCallFilter" {
+            candidates.extend(syscall_filter::resolve(opt, actions));
+            continue;
+        }
         // Options are in the less to most restrictive order,
         // so for non cumulative options, iterate from the end
         for opt_value_desc in opt.possible_values.iter().rev() {
@@ -182,7 +286,7 @@ pub(crate) fn resolve(
                     break;
                 }
                 OptionEffect::Simple(effect) => {
-                    match actions_compatible(effect, actions, opt.updater.as_ref()) {
+                    match actions_compatible(effect, actions, &paths, opt.updater.as_ref()) {
                         ActionOptionEffectCompatibility::Compatible => {
                             candidates.push(OptionWithValue {
                                 name: opt.name.to_owned(),
@@ -193,8 +297,9 @@ pub(crate) fn resolve(
                         ActionOptionEffectCompatibility::CompatibleIfChanged(opt_new_desc) => {
                             candidates.push(OptionWithValue {
                                 name: opt.name.to_owned(),
-                                value: opt_new_desc.value.clone(),
+                                value: opt_new_desc.value,
                             });
+                            candidates.extend(opt_new_desc.extra_options);
                             break;
                         }
                         ActionOptionEffectCompatibility::Incompatible => {}
@@ -210,11 +315,12 @@ pub(crate) fn resolve(
                             mode,
                         } => {
                             let mut compatible_opts = Vec::new();
+                            let mut extra_candidates = Vec::new();
                             debug_assert_eq!(values.len(), effects.len());
                             let mut cur_effects = effects.clone();
                             for (optv, opte) in values.iter().zip(&mut cur_effects) {
                                 let compatible =
-                                    actions_compatible(opte, actions, opt.updater.as_ref());
+                                    actions_compatible(opte, actions, &paths, opt.updater.as_ref());
                                 let mut cur_opt_vals = vec![optv.to_owned()];
                                 let enable_opt = match mode {
                                     ListMode::WhiteList => matches!(
@@ -227,8 +333,9 @@ pub(crate) fn resolve(
                                             nd,
                                         ) => {
                                             *opte = nd.effect;
-                                            match actions_compatible(opte, actions, None) {
+                                            match actions_compatible(opte, actions, &paths, None) {
                                                 ActionOptionEffectCompatibility::Compatible => {
+                                                    extra_candidates.extend(nd.extra_options);
                                                     cur_opt_vals = if let OptionValue::List {
                                                         values: new_vals, ..
                                                     } = nd.value {
@@ -249,6 +356,9 @@ pub(crate) fn resolve(
                                     compatible_opts.append(&mut cur_opt_vals);
                                 }
                             }
+                            // Order does not matter to systemd, so sort to get a stable output
+                            compatible_opts.sort_unstable();
+                            compatible_opts.dedup();
                             if !compatible_opts.is_empty() || value_if_empty.is_some() {
                                 candidates.push(OptionWithValue {
                                     name: opt.name.to_owned(),
@@ -261,6 +371,7 @@ pub(crate) fn resolve(
                                     },
                                 });
                             }
+                            candidates.append(&mut extra_candidates);
                             break;
                         }
                         _ => unreachable!(),
@@ -269,28 +380,426 @@ pub(crate) fn resolve(
             }
         }
     }
+    // Devices can still be restricted to the ones used, if the program can not have a private /dev
+    if container_exec.is_none()
+        && opts.iter().any(|o| o.name == "PrivateDevices")
+        && !candidates.iter().any(|c| c.name == "PrivateDevices")
+    {
+        candidates.extend(devices::device_allow_options(actions));
+    }
+    for warning in warnings(actions, &candidates) {
+        log::warn!("{warning}");
+    }
+
     candidates
 }
 
+/// Why an option value was not used, or was needed
+#[derive(Debug)]
+pub(crate) enum OptionJustification {
+    /// Restrictive value was rejected because it would break an action
+    Rejected {
+        option: &'static str,
+        value: String,
+        action: ProgramAction,
+    },
+    /// Allow list value was added because an action needs it
+    Allowed {
+        option: &'static str,
+        value: String,
+        action: ProgramAction,
+    },
+}
+
+/// Explain option values that were relaxed or left out by `resolve`, with the first action that motivated it
+pub(crate) fn justify(
+    opts: &[OptionDescription],
+    actions: &[ProgramAction],
+) -> Vec<OptionJustification> {
+    let paths = PathTree::new(actions);
+    let container_exec = container_runtime_exec(actions);
+    let mut justifications = Vec::new();
+    for opt in opts {
+        if let Some(action) =
+            container_exec.filter(|_| CONTAINER_BREAKING_OPTIONS.contains(&opt.name))
+        {
+            // Container runtimes set up their own mounts, namespaces and cgroups
+            if let Some(most_restrictive) = opt.possible_values.last() {
+                justifications.push(OptionJustification::Rejected {
+                    option: opt.name,
+                    value: value_string(&most_restrictive.value),
+                    action: action.to_owned(),
+                });
+            }
+            continue;
+        }
+        if opt.name == "SystemCallFilter" {
+            justifications.extend(syscall_filter::justify(opt, actions));
+            continue;
+        }
+        for opt_value_desc in opt.possible_values.iter().rev() {
+            match &opt_value_desc.desc {
+                OptionEffect::None => break,
+                OptionEffect::Simple(effect) => {
+                    match check_actions(effect, actions, &paths, opt.updater.as_ref()) {
+                        Ok(_) => break,
+                        Err(action) => justifications.push(OptionJustification::Rejected {
+                            option: opt.name,
+                            value: value_string(&opt_value_desc.value),
+                            action: action.to_owned(),
+                        }),
+                    }
+                }
+                OptionEffect::Cumulative(effects) => {
+                    let OptionValue::List { values, mode, .. } = &opt_value_desc.value else {
+                        unreachable!();
+                    };
+                    for (optv, opte) in values.iter().zip(effects) {
+                        if let Err(action) =
+                            check_actions(opte, actions, &paths, opt.updater.as_ref())
+                        {
+                            let (option, value, action) =
+                                (opt.name, optv.to_owned(), action.to_owned());
+                            justifications.push(match mode {
+                                ListMode::WhiteList => OptionJustification::Allowed {
+                                    option,
+                                    value,
+                                    action,
+                                },
+                                ListMode::BlackList => OptionJustification::Rejected {
+                                    option,
+                                    value,
+                                    action,
+                                },
+                            });
+                        }
+                    }
+                    break;
+                }
+            }
+        }
+    }
+    justifications
+}
+
+/// Explain each resolved option, from the values that were relaxed or allowed for observed actions
+pub(crate) fn annotate(
+    opts: &[OptionWithValue],
+    justifications: &[OptionJustification],
+) -> OptionComments {
+    opts.iter()
+        .map(|opt| {
+            let mut comments: Vec<_> = justifications
+                .iter()
+                .filter_map(|j| match j {
+                    OptionJustification::Rejected {
+                        option,
+                        value,
+                        action,
+                    } if *option == opt.name => {
+                        Some(format!("{value} not applied, would break: {action}"))
+                    }
+                    OptionJustification::Allowed {
+                        option,
+                        value,
+                        action,
+                    } if *option == opt.name => {
+                        Some(format!("{value} allowed, needed for: {action}"))
+                    }
+                    OptionJustification::Rejected { .. } | OptionJustification::Allowed { .. } => {
+                        None
+                    }
+                })
+                .collect();
+            if comments.is_empty() {
+                comments.push("Compatible with all profiled actions".to_owned());
+            }
+            (opt.name.clone(), comments)
+        })
+        .collect()
+}
+
+/// Options with paths to mount from the host, into the service file system namespace
+const BIND_OPTIONS: [&str; 2] = ["BindPaths", "BindReadOnlyPaths"];
+
+/// Options with paths relative to the host root directory, unless prefixed with '+'
+/// See <https://www.freedesktop.org/software/systemd/man/systemd.exec.html#ReadWritePaths=>
+const HOST_PATH_OPTIONS: [&str; 5] = [
+    "ReadWritePaths",
+    "ReadOnlyPaths",
+    "InaccessiblePaths",
+    "ExecPaths",
+    "NoExecPaths",
+];
+
+/// Translate paths observed from a service running in a `RootDirectory=` to host paths, where options expect them.
+/// Path options and bind mount sources are relative to the host, only bind mount destinations are relative to the
+/// root directory.
+/// See <https://www.freedesktop.org/software/systemd/man/systemd.exec.html#BindPaths=>
+pub(crate) fn translate_root_directory(opts: &mut [OptionWithValue], root_directory: &Path) {
+    for opt in opts.iter_mut() {
+        let is_bind = BIND_OPTIONS.contains(&opt.name.as_str());
+        if !is_bind && !HOST_PATH_OPTIONS.contains(&opt.name.as_str()) {
+            continue;
+        }
+        let OptionValue::List { values, .. } = &mut opt.value else {
+            continue;
+        };
+        for value in values.iter_mut() {
+            let (prefix, path) = value
+                .strip_prefix('-')
+                .map_or(("", value.as_str()), |p| ("-", p));
+            if path.contains(':') || path.starts_with('+') {
+                // Source and destination are already set, or path is already relative to the root directory
+                continue;
+            }
+            // Collect components to drop the trailing '/' of the root path
+            let host_path: PathBuf = root_directory
+                .join(path.trim_start_matches('/'))
+                .components()
+                .collect();
+            *value = if is_bind {
+                format!("{prefix}{}:{path}", host_path.to_string_lossy())
+            } else {
+                format!("{prefix}{}", host_path.to_string_lossy())
+            };
+        }
+    }
+}
+
+/// Format option value like in a config file
+fn value_string(value: &OptionValue) -> String {
+    match value {
+        OptionValue::Boolean(v) => if *v { "true" } else { "false" }.to_owned(),
+        OptionValue::String(v) => v.to_owned(),
+        OptionValue::List { values, .. } => values.join(" "),
+    }
+}
+
+/// Get warnings about observed actions that resolved options may still affect
+pub(crate) fn warnings(actions: &[ProgramAction], opts: &[OptionWithValue]) -> Vec<String> {
+    let mut warnings = setuid_exec_warnings(actions, opts);
+    warnings.extend(layout_warnings(actions, opts));
+    warnings.extend(shared_namespace_warnings(actions, opts));
+    warnings.extend(credential_warnings(actions));
+    warnings.extend(filter_size_warnings(opts));
+    warnings.extend(landlock_warnings(actions, opts));
+    warnings.extend(io_uring_warnings(actions, opts));
+    warnings.extend(pseudo_file_warnings(actions, opts));
+    warnings
+}
+
+/// Options implying `NoNewPrivileges=true` (if the service does not run as root), which prevents setuid and setgid
+/// executables from gaining privileges
+/// See <https://www.freedesktop.org/software/systemd/man/systemd.exec.html#NoNewPrivileges=>
+const NO_NEW_PRIVILEGES_OPTIONS: [&str; 12] = [
+    "NoNewPrivileges",
+    "SystemCallFilter",
+    "SystemCallArchitectures",
+    "RestrictAddressFamilies",
+    "PrivateDevices",
+    "ProtectKernelTunables",
+    "ProtectKernelModules",
+    "ProtectKernelLogs",
+    "ProtectClock",
+    "MemoryDenyWriteExecute",
+    "RestrictRealtime",
+    "LockPersonality",
+];
+
+/// Capabilities needed by common setuid executables, to run them without the setuid bit
+const SETUID_EXEC_CAPABILITIES: [(&str, &[&str]); 10] = [
+    ("fusermount", &["CAP_SYS_ADMIN"]),
+    ("fusermount3", &["CAP_SYS_ADMIN"]),
+    ("mount", &["CAP_SYS_ADMIN"]),
+    ("newgidmap", &["CAP_SETGID"]),
+    ("newuidmap", &["CAP_SETUID"]),
+    ("ping", &["CAP_NET_RAW"]),
+    ("su", &["CAP_SETGID", "CAP_SETUID"]),
+    ("sudo", &["CAP_SETGID", "CAP_SETUID"]),
+    ("traceroute", &["CAP_NET_RAW"]),
+    ("umount", &["CAP_SYS_ADMIN"]),
+];
+
+/// Get warnings about setuid or setgid executables that will run without privileges with the given options
+fn setuid_exec_warnings(actions: &[ProgramAction], opts: &[OptionWithValue]) -> Vec<String> {
+    let nnp_opts: Vec<_> = opts
+        .iter()
+        .map(|o| o.name.as_str())
+        .filter(|n| NO_NEW_PRIVILEGES_OPTIONS.contains(n))
+        .collect();
+    actions
+        .iter()
+        .filter_map(|a| {
+            if let ProgramAction::SetuidExec(path) = a {
+                Some(path)
+            } else {
+                None
+            }
+        })
+        .unique()
+        .filter_map(|path| {
+            let caps = path.file_name().and_then(|n| {
+                SETUID_EXEC_CAPABILITIES
+                    .iter()
+                    .find(|(exe, _)| n == *exe)
+                    .map(|(_, caps)| caps)
+            });
+            // Removing capabilities from the bounding set also neuters setuid executables
+            let bounded_caps: Vec<_> = caps
+                .into_iter()
+                .flat_map(|caps| caps.iter())
+                .filter(|c| {
+                    opts.iter().any(|o| {
+                        matches!(&o.value, OptionValue::List { values, .. }
+                            if (o.name == "CapabilityBoundingSet") && values.iter().any(|v| v == *c))
+                    })
+                })
+                .collect();
+            let mut neutering_opts = nnp_opts.clone();
+            if !bounded_caps.is_empty() {
+                neutering_opts.push("CapabilityBoundingSet");
+            }
+            if neutering_opts.is_empty() {
+                return None;
+            }
+            let advice = if let Some(caps) = caps {
+                format!(
+                    "consider removing the setuid bit, and running the service with AmbientCapabilities={} instead",
+                    caps.join(" ")
+                )
+            } else {
+                "consider granting the capabilities it needs with AmbientCapabilities= instead"
+                    .to_owned()
+            };
+            Some(format!(
+                "Setuid/setgid executable {} was run, it will not gain privileges with options {}: {}",
+                path.display(),
+                neutering_opts.join(", "),
+                advice
+            ))
+        })
+        .collect()
+}
+
 #[expect(clippy::shadow_unrelated)]
 #[cfg(test)]
 mod tests {
+    use std::num::NonZeroU16;
+
     use super::*;
 
     use crate::{
-        cl::HardeningOptions,
-        systemd::{build_options, KernelVersion, SystemdVersion},
+        capabilities::CAPABILITIES,
+        cl::{
+            CapabilitySource, HardeningOptions, HardeningPreset, SyscallDenyAction,
+            SyscallGroupDenyAction,
+        },
+        summarize::{CountableSetSpecifier, NetworkActivityKind, NetworkPort, SetSpecifier},
+        systemd::{
+            build_options,
+            options::{PROTECT_HOME_MAX_BIND_PATHS, PROTECT_KERNEL_TUNABLES_MAX_SYSFS_WRITE_PATHS},
+            KernelVersion, SocketFamily, SocketProtocol, SystemdVersion,
+            SYSTEM_SERVICE_ONLY_OPTIONS,
+        },
     };
 
-    fn test_options(names: &[&str]) -> Vec<OptionDescription> {
+    fn test_options_with(
+        names: &[&str],
+        hardening_opts: &HardeningOptions,
+    ) -> Vec<OptionDescription> {
         let sd_version = SystemdVersion::new(254, 0);
         let kernel_version = KernelVersion::new(6, 4, 0);
-        build_options(&sd_version, &kernel_version, &HardeningOptions::safe())
+        build_options(&sd_version, &kernel_version, hardening_opts)
             .into_iter()
             .filter(|o| names.contains(&o.name))
             .collect()
     }
 
+    fn test_options(names: &[&str]) -> Vec<OptionDescription> {
+        test_options_with(names, &HardeningOptions::safe())
+    }
+
+    #[test]
+    fn test_old_systemd_version() {
+        let names: Vec<_> = build_options(
+            &SystemdVersion::new(243, 0),
+            &KernelVersion::new(6, 4, 0),
+            &HardeningOptions::strict(),
+        )
+        .into_iter()
+        .map(|o| o.name)
+        .collect();
+        assert!(names.contains(&"ProtectKernelTunables"));
+        for name in [
+            "ProtectKernelLogs",
+            "ProtectClock",
+            "ProtectProc",
+            "ProcSubset",
+            "SocketBindDeny",
+        ] {
+            assert!(!names.contains(&name), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_system_service_only_options() {
+        let names: Vec<_> = build_options(
+            &SystemdVersion::new(254, 0),
+            &KernelVersion::new(6, 4, 0),
+            &HardeningOptions::strict(),
+        )
+        .into_iter()
+        .map(|o| o.name)
+        .collect();
+        for name in [
+            "ProtectSystem",
+            "ProtectHome",
+            "ProtectProc",
+            "CapabilityBoundingSet",
+        ] {
+            assert!(names.contains(&name), "{name}");
+            assert!(SYSTEM_SERVICE_ONLY_OPTIONS.contains(&name), "{name}");
+        }
+        // Seccomp and cgroup based options work in user services
+        for name in [
+            "SystemCallFilter",
+            "MemoryDenyWriteExecute",
+            "RestrictAddressFamilies",
+            "SocketBindDeny",
+        ] {
+            assert!(names.contains(&name), "{name}");
+            assert!(!SYSTEM_SERVICE_ONLY_OPTIONS.contains(&name), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_resolve_proc_subset() {
+        let _ = simple_logger::SimpleLogger::new().init();
+
+        let opts = test_options(&["ProtectProc", "ProcSubset"]);
+
+        let actions = vec![
+            ProgramAction::Read("/proc/self/status".into()),
+            ProgramAction::Read("/proc/filesystems".into()),
+        ];
+        let candidates = resolve(&opts, &actions);
+        assert_eq!(
+            candidates
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec!["ProtectProc=ptraceable", "ProcSubset=pid"]
+        );
+
+        let actions = vec![
+            ProgramAction::Read("/proc/meminfo".into()),
+            ProgramAction::Read("/proc/1/cgroup".into()),
+        ];
+        let candidates = resolve(&opts, &actions);
+        assert!(candidates.is_empty());
+    }
+
     #[test]
     fn test_resolve_protect_system() {
         let _ = simple_logger::SimpleLogger::new().init();
@@ -350,6 +859,157 @@ mod tests {
         let candidates = resolve(&opts, &actions);
         assert_eq!(candidates.len(), 1);
         assert_eq!(format!("{}", candidates[0]), "ProtectHome=true");
+        let actions = vec![
+            ProgramAction::Read("/home/user/.config/app/app.conf".into()),
+            ProgramAction::Read("/root/.cache/app/data".into()),
+            ProgramAction::Read("/home/user/.config/app/other.conf".into()),
+        ];
+        let candidates = resolve(&opts, &actions);
+        assert_eq!(candidates.len(), 2);
+        assert_eq!(format!("{}", candidates[0]), "ProtectHome=tmpfs");
+        assert_eq!(
+            format!("{}", candidates[1]),
+            "BindReadOnlyPaths=-/home/user/.config/app -/root/.cache/app"
+        );
+
+        let actions = vec![
+            ProgramAction::Read("/home/user/.config/app/app.conf".into()),
+            ProgramAction::Write("/home/user/.config/app/app.conf".into()),
+        ];
+        let candidates = resolve(&opts, &actions);
+        assert_eq!(candidates.len(), 0);
+
+        let actions: Vec<_> = (0..=PROTECT_HOME_MAX_BIND_PATHS)
+            .map(|i| ProgramAction::Read(format!("/home/user/dir{i}/file").into()))
+            .collect();
+        let candidates = resolve(&opts, &actions);
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(format!("{}", candidates[0]), "ProtectHome=read-only");
+    }
+
+    #[test]
+    fn test_resolve_protect_kernel_tunables() {
+        let _ = simple_logger::SimpleLogger::new().init();
+
+        let opts = test_options(&["ProtectKernelTunables"]);
+
+        let actions = vec![
+            ProgramAction::Write("/sys/class/leds/led0/brightness".into()),
+            ProgramAction::Read("/sys/class/gpio/gpio17/value".into()),
+            ProgramAction::Write("/sys/class/gpio/gpio17/value".into()),
+            ProgramAction::Write("/sys/class/leds/led0/brightness".into()),
+        ];
+        let candidates = resolve(&opts, &actions);
+        assert_eq!(candidates.len(), 2);
+        assert_eq!(format!("{}", candidates[0]), "ProtectKernelTunables=true");
+        assert_eq!(
+            format!("{}", candidates[1]),
+            "ReadWritePaths=-/sys/class/gpio/gpio17/value -/sys/class/leds/led0/brightness"
+        );
+
+        let actions = vec![
+            ProgramAction::Write("/sys/class/leds/led0/brightness".into()),
+            ProgramAction::Write("/proc/sys/net/ipv4/ip_forward".into()),
+        ];
+        let candidates = resolve(&opts, &actions);
+        assert_eq!(candidates.len(), 0);
+
+        let actions: Vec<_> = (0..=PROTECT_KERNEL_TUNABLES_MAX_SYSFS_WRITE_PATHS)
+            .map(|i| ProgramAction::Write(format!("/sys/class/leds/led{i}/brightness").into()))
+            .collect();
+        let candidates = resolve(&opts, &actions);
+        assert_eq!(candidates.len(), 0);
+    }
+
+    #[test]
+    fn test_resolve_container_manager() {
+        let _ = simple_logger::SimpleLogger::new().init();
+
+        let names = ["ProtectControlGroups", "ProtectSystem", "LockPersonality"];
+        let opts = test_options(&names);
+
+        let actions = vec![ProgramAction::Exec("/usr/bin/runc".into())];
+        let candidates = resolve(&opts, &actions);
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(format!("{}", candidates[0]), "LockPersonality=true");
+        let justifications = justify(&opts, &actions);
+        assert!(justifications.iter().any(|j| matches!(
+            j,
+            OptionJustification::Rejected { option: "ProtectControlGroups", value, .. } if value == "true"
+        )));
+
+        let opts = test_options_with(
+            &names,
+            &HardeningOptions {
+                container_manager: true,
+                ..HardeningOptions::safe()
+            },
+        );
+        assert_eq!(
+            opts.iter().map(|o| o.name).collect::<Vec<_>>(),
+            vec!["LockPersonality"]
+        );
+    }
+
+    #[test]
+    fn test_hardening_presets() {
+        let names = [
+            "PrivateDevices",
+            "PrivateTmp",
+            "ProtectHome",
+            "ProtectProc",
+            "SocketBindDeny",
+        ];
+        let preset_names = |preset| {
+            test_options_with(
+                &names,
+                &HardeningOptions {
+                    preset: Some(preset),
+                    ..HardeningOptions::strict()
+                },
+            )
+            .into_iter()
+            .map(|o| o.name)
+            .collect::<Vec<_>>()
+        };
+        assert_eq!(preset_names(HardeningPreset::Server).len(), names.len());
+        assert_eq!(
+            preset_names(HardeningPreset::Desktop),
+            vec!["PrivateDevices", "ProtectProc", "SocketBindDeny"]
+        );
+        assert_eq!(
+            preset_names(HardeningPreset::Container),
+            vec!["ProtectHome", "PrivateTmp", "PrivateDevices", "ProtectProc"]
+        );
+        assert_eq!(
+            preset_names(HardeningPreset::Embedded),
+            vec!["ProtectHome", "PrivateTmp"]
+        );
+    }
+
+    #[test]
+    fn test_resolve_traced_capabilities() {
+        let _ = simple_logger::SimpleLogger::new().init();
+
+        let opts = test_options_with(
+            &["CapabilityBoundingSet"],
+            &HardeningOptions {
+                capability_source: CapabilitySource::Kprobe,
+                ..HardeningOptions::safe()
+            },
+        );
+        let actions = vec![
+            ProgramAction::Capability("CAP_NET_BIND_SERVICE".to_owned()),
+            ProgramAction::Capability("CAP_SETUID".to_owned()),
+        ];
+        let candidates = resolve(&opts, &actions);
+        assert_eq!(candidates.len(), 1);
+        let OptionValue::List { values, .. } = &candidates[0].value else {
+            panic!();
+        };
+        assert_eq!(values.len(), CAPABILITIES.len() - 2);
+        assert!(values.iter().any(|v| v == "CAP_SYS_ADMIN"));
+        assert!(!values.iter().any(|v| v == "CAP_SETUID"));
     }
 
     #[test]
@@ -380,4 +1040,337 @@ mod tests {
         assert_eq!(candidates.len(), 1);
         assert_eq!(format!("{}", candidates[0]), "PrivateTmp=true");
     }
+
+    #[test]
+    fn test_resolve_system_call_filter_deny_action() {
+        let _ = simple_logger::SimpleLogger::new().init();
+
+        let mut hardening_opts = HardeningOptions::safe();
+        hardening_opts.syscall_deny_action = SyscallDenyAction::Enosys;
+        hardening_opts.syscall_group_deny_actions = vec!["@clock=kill".parse().unwrap()];
+        let opts = test_options_with(&["SystemCallFilter"], &hardening_opts);
+
+        let actions = vec![];
+        let candidates = resolve(&opts, &actions);
+        assert_eq!(candidates.len(), 1);
+        let candidate = format!("{}", candidates[0]);
+        assert!(candidate.starts_with("SystemCallFilter=~"));
+        assert!(candidate.contains("@aio:ENOSYS"));
+        assert!(candidate.contains("@clock:kill "));
+        assert!(!candidate.contains("EPERM"));
+
+        assert!("clock=ignore".parse::<SyscallGroupDenyAction>().is_err());
+        assert!("nonexistent=kill"
+            .parse::<SyscallGroupDenyAction>()
+            .is_err());
+    }
+
+    #[test]
+    fn test_resolve_system_call_filter_io_uring() {
+        let _ = simple_logger::SimpleLogger::new().init();
+
+        let opts = test_options(&["SystemCallFilter"]);
+
+        let mut actions = vec![ProgramAction::Syscalls(
+            ["io_uring_enter".to_owned(), "io_uring_setup".to_owned()].into(),
+        )];
+        let candidates = resolve(&opts, &actions);
+        assert_eq!(candidates.len(), 2);
+        assert!(candidates[0].to_string().contains("@aio:EPERM"));
+        assert_eq!(
+            candidates[1].to_string(),
+            "SystemCallFilter=io_uring_enter io_uring_setup"
+        );
+
+        // All io_uring syscalls are kept allowed, even those not seen during profiling
+        actions.push(ProgramAction::IoUring);
+        let candidates = resolve(&opts, &actions);
+        assert_eq!(candidates.len(), 1);
+        assert!(!candidates[0].to_string().contains("@aio"));
+    }
+
+    #[test]
+    fn test_resolve_stable_output() {
+        let _ = simple_logger::SimpleLogger::new().init();
+
+        let opts = test_options(&["SystemCallFilter", "ProtectSystem", "PrivateTmp"]);
+
+        let mut actions = vec![
+            ProgramAction::Write("/var/cache/whatever".into()),
+            ProgramAction::Read("/tmp/data".into()),
+            ProgramAction::Syscalls(["read".to_owned(), "chown".to_owned()].into()),
+        ];
+        let candidates = resolve(&opts, &actions);
+        actions.reverse();
+        let reversed_candidates = resolve(&opts, &actions);
+        assert_eq!(
+            candidates
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            reversed_candidates
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_setuid_exec_warnings() {
+        let _ = simple_logger::SimpleLogger::new().init();
+
+        let opts = test_options(&["LockPersonality", "ProtectSystem"]);
+
+        let actions = vec![
+            ProgramAction::SetuidExec("/usr/bin/ping".into()),
+            ProgramAction::SetuidExec("/usr/bin/ping".into()),
+            ProgramAction::SetuidExec("/usr/bin/unknown".into()),
+        ];
+        let candidates = resolve(&opts, &actions);
+        let warnings = setuid_exec_warnings(&actions, &candidates);
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].contains(" /usr/bin/ping "));
+        assert!(warnings[0].contains("LockPersonality"));
+        assert!(!warnings[0].contains("ProtectSystem"));
+        assert!(warnings[0].contains("AmbientCapabilities=CAP_NET_RAW"));
+        assert!(warnings[1].contains(" /usr/bin/unknown "));
+
+        let opts = test_options(&["ProtectSystem"]);
+        let candidates = resolve(&opts, &actions);
+        assert!(setuid_exec_warnings(&actions, &candidates).is_empty());
+    }
+
+    #[test]
+    fn test_resolve_working_directory() {
+        let _ = simple_logger::SimpleLogger::new().init();
+
+        let opts = test_options(&["WorkingDirectory"]);
+
+        let candidates = resolve(&opts, &[]);
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(format!("{}", candidates[0]), "WorkingDirectory=/");
+
+        let actions = vec![
+            ProgramAction::WorkingDirectory("/srv/app".into()),
+            ProgramAction::WorkingDirectory("/srv/app".into()),
+        ];
+        let candidates = resolve(&opts, &actions);
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(format!("{}", candidates[0]), "WorkingDirectory=/srv/app");
+
+        let actions = vec![
+            ProgramAction::WorkingDirectory("/srv/app".into()),
+            ProgramAction::WorkingDirectory("/".into()),
+        ];
+        assert!(resolve(&opts, &actions).is_empty());
+    }
+
+    #[test]
+    fn test_resolve_restrict_address_families() {
+        let _ = simple_logger::SimpleLogger::new().init();
+
+        let opts = test_options(&["RestrictAddressFamilies"]);
+
+        let actions = vec![];
+        let candidates = resolve(&opts, &actions);
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(format!("{}", candidates[0]), "RestrictAddressFamilies=none");
+
+        let actions: Vec<_> = ["AF_VSOCK", "AF_NETLINK", "AF_PACKET", "AF_INET6"]
+            .into_iter()
+            .map(|af| {
+                ProgramAction::NetworkActivity(NetworkActivity {
+                    af: SetSpecifier::One(af.parse().unwrap()),
+                    proto: SetSpecifier::One(SocketProtocol::Raw),
+                    kind: SetSpecifier::One(NetworkActivityKind::SocketCreation),
+                    local_port: CountableSetSpecifier::All,
+                    remote_addr: None,
+                })
+            })
+            .collect();
+        let candidates = resolve(&opts, &actions);
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(
+            format!("{}", candidates[0]),
+            "RestrictAddressFamilies=AF_INET6 AF_NETLINK AF_PACKET AF_VSOCK"
+        );
+
+        let actions = vec![ProgramAction::AbstractSocket(
+            "/tmp/.X11-unix/X0".to_owned(),
+        )];
+        let candidates = resolve(&opts, &actions);
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(
+            format!("{}", candidates[0]),
+            "RestrictAddressFamilies=AF_UNIX"
+        );
+    }
+
+    #[test]
+    fn test_resolve_socket_bind_port_ranges() {
+        let _ = simple_logger::SimpleLogger::new().init();
+
+        let mut hardening_opts = HardeningOptions::safe();
+        hardening_opts.network_firewalling = true;
+        let opts = test_options_with(&["SocketBindDeny"], &hardening_opts);
+
+        let actions: Vec<_> = [5001, 21, 5000, 5002]
+            .into_iter()
+            .map(|port: u16| {
+                ProgramAction::NetworkActivity(NetworkActivity {
+                    af: SetSpecifier::One(SocketFamily::Ipv4),
+                    proto: SetSpecifier::One(SocketProtocol::Tcp),
+                    kind: SetSpecifier::One(NetworkActivityKind::Bind),
+                    local_port: CountableSetSpecifier::One(NetworkPort::from(
+                        NonZeroU16::new(port).unwrap(),
+                    )),
+                    remote_addr: None,
+                })
+            })
+            .collect();
+        let candidates = resolve(&opts, &actions);
+        assert_eq!(candidates.len(), 2);
+        assert_eq!(
+            format!("{}", candidates[0]),
+            "SocketBindDeny=ipv4:tcp\nSocketBindDeny=ipv4:udp\nSocketBindDeny=ipv6:tcp\nSocketBindDeny=ipv6:udp"
+        );
+        assert_eq!(
+            format!("{}", candidates[1]),
+            "SocketBindAllow=ipv4:tcp:21\nSocketBindAllow=ipv4:tcp:5000-5002"
+        );
+
+        let actions = vec![ProgramAction::NetworkActivity(NetworkActivity {
+            af: SetSpecifier::One(SocketFamily::Ipv4),
+            proto: SetSpecifier::One(SocketProtocol::Tcp),
+            kind: SetSpecifier::One(NetworkActivityKind::Bind),
+            local_port: CountableSetSpecifier::All,
+            remote_addr: None,
+        })];
+        let candidates = resolve(&opts, &actions);
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(
+            format!("{}", candidates[0]),
+            "SocketBindDeny=ipv4:udp\nSocketBindDeny=ipv6:tcp\nSocketBindDeny=ipv6:udp"
+        );
+    }
+
+    #[test]
+    fn test_resolve_extra_actions() {
+        let _ = simple_logger::SimpleLogger::new().init();
+
+        let mut hardening_opts = HardeningOptions::safe();
+        hardening_opts.network_firewalling = true;
+        hardening_opts.extra_write_paths = vec!["/etc/plop.conf".into()];
+        hardening_opts.extra_bind_ports = vec!["tcp:8080".parse().unwrap()];
+        hardening_opts.extra_syscalls = vec!["mount".to_owned()];
+        let opts = test_options_with(
+            &["ProtectSystem", "SocketBindDeny", "SystemCallFilter"],
+            &hardening_opts,
+        );
+
+        let candidates: Vec<_> = resolve(&opts, &hardening_opts.extra_actions())
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            candidates[..4],
+            [
+                "ProtectSystem=true",
+                "SocketBindDeny=ipv4:tcp\nSocketBindDeny=ipv4:udp\nSocketBindDeny=ipv6:tcp\nSocketBindDeny=ipv6:udp",
+                "SocketBindAllow=ipv4:tcp:8080",
+                "SocketBindAllow=ipv6:tcp:8080",
+            ]
+        );
+        assert!(!candidates[4].contains("@network-io"));
+        assert_eq!(candidates[5], "SystemCallFilter=mount");
+    }
+
+    #[test]
+    fn test_translate_root_directory() {
+        let paths = |name: &str, values: &[&str]| OptionWithValue {
+            name: name.to_owned(),
+            value: OptionValue::List {
+                values: values.iter().map(|v| (*v).to_owned()).collect(),
+                value_if_empty: None,
+                negation_prefix: false,
+                repeat_option: false,
+                mode: ListMode::WhiteList,
+            },
+        };
+        let mut opts: Vec<OptionWithValue> = vec![
+            "ProtectHome=tmpfs".parse().unwrap(),
+            paths("BindReadOnlyPaths", &["-/home/user/.config", "/a:/b"]),
+            paths("ReadWritePaths", &["/var/lib/foo", "-/run/foo"]),
+            paths("ReadOnlyPaths", &["+/etc"]),
+            paths("InaccessiblePaths", &["/home"]),
+            paths("ExecPaths", &["/usr/bin"]),
+            paths("NoExecPaths", &["/"]),
+        ];
+        translate_root_directory(&mut opts, Path::new("/srv/chroot"));
+        assert_eq!(
+            opts.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec![
+                "ProtectHome=tmpfs",
+                "BindReadOnlyPaths=-/srv/chroot/home/user/.config:/home/user/.config /a:/b",
+                "ReadWritePaths=/srv/chroot/var/lib/foo -/srv/chroot/run/foo",
+                "ReadOnlyPaths=+/etc",
+                "InaccessiblePaths=/srv/chroot/home",
+                "ExecPaths=/srv/chroot/usr/bin",
+                "NoExecPaths=/srv/chroot",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_annotate() {
+        let _ = simple_logger::SimpleLogger::new().init();
+
+        let opts = test_options(&["ProtectSystem", "PrivateTmp"]);
+        let actions = vec![ProgramAction::Write("/var/lib/data".into())];
+        let resolved = resolve(&opts, &actions);
+        let annotations = annotate(&resolved, &justify(&opts, &actions));
+        assert_eq!(
+            annotations["ProtectSystem"],
+            vec!["strict not applied, would break: write /var/lib/data"]
+        );
+        assert_eq!(
+            annotations["PrivateTmp"],
+            vec!["Compatible with all profiled actions"]
+        );
+    }
+
+    #[test]
+    fn test_resolve_resource_limits() {
+        let _ = simple_logger::SimpleLogger::new().init();
+
+        let mut hardening_opts = HardeningOptions::safe();
+        hardening_opts.resource_limits = true;
+        let opts = test_options_with(&["LimitNOFILE", "TasksMax"], &hardening_opts);
+
+        let candidates = resolve(&opts, &[ProgramAction::OpenFiles(12)]);
+        assert_eq!(
+            candidates
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec!["LimitNOFILE=1024", "TasksMax=64"]
+        );
+
+        let candidates = resolve(
+            &opts,
+            &[
+                ProgramAction::OpenFiles(1500),
+                ProgramAction::Tasks(40),
+                ProgramAction::OpenFiles(3000),
+                ProgramAction::Tasks(100),
+            ],
+        );
+        assert_eq!(
+            candidates
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec!["LimitNOFILE=8192", "TasksMax=256"]
+        );
+    }
 }
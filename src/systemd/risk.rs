@@ -0,0 +1,103 @@
+//! Breakage risk of options
+
+use crate::{
+    cl::RiskLevel,
+    systemd::{options::OptionWithValue, resolver::OptionJustification},
+};
+
+/// Base breakage risk of options, from how often they break programs in ways profiling can miss
+const OPTION_RISKS: [(&str, RiskLevel); 21] = [
+    ("CapabilityBoundingSet", RiskLevel::Medium),
+    ("LimitNOFILE", RiskLevel::High),
+    ("LockPersonality", RiskLevel::Low),
+    ("MemoryDenyWriteExecute", RiskLevel::Medium),
+    ("PrivateDevices", RiskLevel::Medium),
+    ("PrivateNetwork", RiskLevel::High),
+    ("PrivateTmp", RiskLevel::Medium),
+    ("ProtectClock", RiskLevel::Low),
+    ("ProtectControlGroups", RiskLevel::Low),
+    ("ProtectHome", RiskLevel::Medium),
+    ("ProtectKernelLogs", RiskLevel::Low),
+    ("ProtectKernelModules", RiskLevel::Low),
+    ("ProtectKernelTunables", RiskLevel::Low),
+    ("ProtectProc", RiskLevel::Medium),
+    ("ProtectSystem", RiskLevel::Medium),
+    ("RestrictAddressFamilies", RiskLevel::Medium),
+    ("RestrictRealtime", RiskLevel::Low),
+    ("SocketBindDeny", RiskLevel::Medium),
+    ("SystemCallArchitectures", RiskLevel::Low),
+    ("SystemCallFilter", RiskLevel::High),
+    ("TasksMax", RiskLevel::High),
+];
+
+/// Options only generated to support another one, which must be applied together
+const DEPENDENT_OPTIONS: [(&str, &str); 2] = [
+    ("BindReadOnlyPaths", "ProtectHome"),
+    ("SocketBindAllow", "SocketBindDeny"),
+];
+
+/// Get breakage risk of an option, raised if the program was observed close to its limits
+fn option_risk(name: &str, justifications: &[OptionJustification]) -> RiskLevel {
+    let name = DEPENDENT_OPTIONS
+        .iter()
+        .find(|(dependent, _)| *dependent == name)
+        .map_or(name, |(_, parent)| parent);
+    let base = OPTION_RISKS
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map_or(RiskLevel::Medium, |(_, r)| *r);
+    let borderline = justifications.iter().any(|j| match j {
+        OptionJustification::Rejected { option, .. }
+        | OptionJustification::Allowed { option, .. } => *option == name,
+    });
+    if borderline {
+        base.raised()
+    } else {
+        base
+    }
+}
+
+/// Get breakage risk of each option
+pub(crate) fn option_risks(
+    opts: &[OptionWithValue],
+    justifications: &[OptionJustification],
+) -> Vec<RiskLevel> {
+    opts.iter()
+        .map(|o| option_risk(&o.name, justifications))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::summarize::ProgramAction;
+
+    #[test]
+    fn test_option_risks() {
+        let opts: Vec<OptionWithValue> = [
+            "ProtectClock=true",
+            "ProtectSystem=full",
+            "ProtectHome=tmpfs",
+            "BindReadOnlyPaths=-/home/user/.config",
+            "Unknown=true",
+        ]
+        .iter()
+        .map(|o| o.parse().unwrap())
+        .collect();
+        let justifications = vec![OptionJustification::Rejected {
+            option: "ProtectSystem",
+            value: "strict".to_owned(),
+            action: ProgramAction::Write("/var/lib/app".into()),
+        }];
+        assert_eq!(
+            option_risks(&opts, &justifications),
+            vec![
+                RiskLevel::Low,
+                RiskLevel::High,
+                RiskLevel::Medium,
+                RiskLevel::Medium,
+                RiskLevel::Medium
+            ]
+        );
+    }
+}
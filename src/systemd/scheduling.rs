@@ -0,0 +1,91 @@
+//! CPU scheduling settings the program applies to itself, which systemd can apply from the start instead
+
+use itertools::Itertools as _;
+
+use crate::summarize::ProgramAction;
+
+/// `CPUSchedulingPolicy=` values of scheduling policies
+const POLICIES: [(&str, &str); 5] = [
+    ("SCHED_OTHER", "other"),
+    ("SCHED_BATCH", "batch"),
+    ("SCHED_IDLE", "idle"),
+    ("SCHED_FIFO", "fifo"),
+    ("SCHED_RR", "rr"),
+];
+
+/// Get advice to set the scheduling policy and nice value with unit options, only if the program always sets the
+/// same ones
+pub(crate) fn scheduling_advice(actions: &[ProgramAction]) -> Vec<String> {
+    let mut advice = Vec::new();
+
+    let policies: Vec<_> = actions
+        .iter()
+        .filter_map(|a| match a {
+            ProgramAction::SetSchedulingPolicy(policy) => Some(policy.as_str()),
+            _ => None,
+        })
+        .unique()
+        .collect();
+    if let [policy] = policies.as_slice() {
+        if let Some((_, value)) = POLICIES.iter().find(|(p, _)| p == policy) {
+            let realtime = if matches!(*value, "fifo" | "rr") {
+                ", with CPUSchedulingPriority= set to the priority it uses, RestrictRealtime=true still can not be used since the program sets it itself"
+            } else {
+                ""
+            };
+            advice.push(format!(
+                "The program sets its own scheduling policy to {policy}, CPUSchedulingPolicy={value} sets it from the start{realtime}"
+            ));
+        }
+    }
+
+    let nice_values: Vec<_> = actions
+        .iter()
+        .filter_map(|a| match a {
+            ProgramAction::SetNice(nice) => Some(*nice),
+            _ => None,
+        })
+        .unique()
+        .collect();
+    if let [nice] = nice_values.as_slice() {
+        let privileges = if *nice < 0 {
+            ", so that the program does not need CAP_SYS_NICE to raise its priority"
+        } else {
+            ""
+        };
+        advice.push(format!(
+            "The program sets its own nice value to {nice}, Nice={nice} sets it from the start{privileges}"
+        ));
+    }
+
+    advice
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scheduling_advice() {
+        let actions = vec![
+            ProgramAction::SetSchedulingPolicy("SCHED_BATCH".to_owned()),
+            ProgramAction::SetNice(-5),
+            ProgramAction::SetNice(-5),
+        ];
+        assert_eq!(
+            scheduling_advice(&actions),
+            vec![
+                "The program sets its own scheduling policy to SCHED_BATCH, CPUSchedulingPolicy=batch sets it from the start",
+                "The program sets its own nice value to -5, Nice=-5 sets it from the start, so that the program does not need CAP_SYS_NICE to raise its priority",
+            ]
+        );
+
+        // Values changing over time can not be set from the start
+        let changing_actions = vec![
+            ProgramAction::SetSchedulingPolicy("SCHED_DEADLINE".to_owned()),
+            ProgramAction::SetNice(5),
+            ProgramAction::SetNice(10),
+        ];
+        assert!(scheduling_advice(&changing_actions).is_empty());
+    }
+}
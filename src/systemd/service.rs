@@ -1,19 +1,40 @@
 //! Systemd service actions
 
 use std::{
+    collections::BTreeSet,
     env,
+    fmt::Write as _,
     fs::{self, File},
-    io::{BufRead, BufReader, BufWriter, Write},
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    iter,
     path::{Path, PathBuf},
-    process::{Command, Stdio},
+    time::{Duration, SystemTime},
 };
 
+use anyhow::Context as _;
 use itertools::Itertools;
+use nix::sys::signal::Signal;
 use rand::Rng;
 
+use clap::Parser as _;
+
 use crate::{
-    cl::HardeningOptions,
-    systemd::{options::OptionWithValue, END_OPTION_OUTPUT_SNIPPET, START_OPTION_OUTPUT_SNIPPET},
+    cl::{self, HardeningOptions, RiskLevel, SamplingOptions, StraceLogOptions, StraceOptions},
+    coverage::{self, Coverage, ServiceContext},
+    systemd::{
+        comment_line,
+        complain::{self, LoggedSyscall, COMPLAIN_MODE_PREFIX},
+        dbus::{Manager, UnitAction},
+        journal::Journal,
+        options::OptionWithValue,
+        parse_comment_line,
+        specifiers::Specifiers,
+        OptionComments, END_COVERAGE_OUTPUT_SNIPPET, END_EXEC_START_PRE_OPTION_OUTPUT_SNIPPET,
+        END_OPTION_OUTPUT_SNIPPET, END_SMOKE_TEST_OUTPUT_SNIPPET, END_TIMELINE_OUTPUT_SNIPPET,
+        START_COVERAGE_OUTPUT_SNIPPET, START_EXEC_START_PRE_OPTION_OUTPUT_SNIPPET,
+        START_OPTION_OUTPUT_SNIPPET, START_SMOKE_TEST_OUTPUT_SNIPPET,
+        START_TIMELINE_OUTPUT_SNIPPET,
+    },
 };
 
 pub(crate) struct Service {
@@ -21,11 +42,85 @@ pub(crate) struct Service {
     arg: Option<String>,
 }
 
+/// Directories of runtime (profiling) and persistent (hardening) unit config fragments
+const FRAGMENT_BASE_DIRS: [&str; 2] = ["/run/systemd/system", "/etc/systemd/system"];
 const PROFILING_FRAGMENT_NAME: &str = "profile";
 const HARDENING_FRAGMENT_NAME: &str = "harden";
 /// Command line prefix for `ExecStartXxx`= that bypasses all hardening options
 /// See <https://www.freedesktop.org/software/systemd/man/255/systemd.service.html#Command%20lines>
 const PRIVILEGED_PREFIX: &str = "+";
+/// Options copied from the service to the helper unit running its setup commands, so they run in the same context
+// TODO APPROXIMATION some other options (DynamicUser, RuntimeDirectory...) can not be shared between units this simply
+const EXEC_START_PRE_INHERITED_OPTIONS: [&str; 9] = [
+    "RootDirectory",
+    "RootImage",
+    "User",
+    "Group",
+    "SupplementaryGroups",
+    "Environment",
+    "EnvironmentFile",
+    "WorkingDirectory",
+    "UMask",
+];
+
+/// Arguments of the profile data merge command of a profiling fragment
+pub(crate) struct ProfilingMergeArgs {
+    pub hardening_opts: HardeningOptions,
+    pub paths: Vec<PathBuf>,
+    pub exec_start_pre_paths: Vec<PathBuf>,
+    pub root_directory: Option<PathBuf>,
+    pub accumulate_dir: Option<PathBuf>,
+    pub activations: Option<usize>,
+}
+
+/// Quote an argument of a unit command line (`ExecStart=`...), so that systemd passes it unchanged to the program:
+/// specifiers and environment variable references are escaped, and arguments with whitespace or quotes are quoted
+/// See <https://www.freedesktop.org/software/systemd/man/255/systemd.service.html#Command%20lines>
+fn quote_exec_arg(arg: &str) -> String {
+    let escaped = arg.replace('%', "%%").replace('$', "$$");
+    if escaped.is_empty()
+        || (escaped == ";")
+        || escaped.contains(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | '\\'))
+    {
+        format!("\"{}\"", escaped.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        escaped
+    }
+}
+
+/// Build a unit command line from arguments
+fn exec_cmdline<S: AsRef<str>>(args: &[S]) -> String {
+    args.iter().map(|a| quote_exec_arg(a.as_ref())).join(" ")
+}
+
+/// Split a unit command line into arguments, undoing the quoting and escaping of `quote_exec_arg`
+fn split_exec_cmdline(cmdline: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut chars = cmdline.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        if chars.peek().is_none() {
+            break;
+        }
+        let mut arg = String::new();
+        let mut quote = None;
+        while let Some(c) = chars.next() {
+            match (c, quote) {
+                ('\\', Some(_)) => arg.extend(chars.next()),
+                ('"' | '\'', None) => quote = Some(c),
+                (_, Some(q)) if c == q => quote = None,
+                (_, None) if c.is_whitespace() => break,
+                ('%' | '$', _) if chars.peek() == Some(&c) => {
+                    chars.next();
+                    arg.push(c);
+                }
+                _ => arg.push(c),
+            }
+        }
+        args.push(arg);
+    }
+    args
+}
 
 impl Service {
     pub(crate) fn new(unit: &str) -> Self {
@@ -42,7 +137,132 @@ impl Service {
         }
     }
 
-    fn unit_name(&self) -> String {
+    /// List names of known service units (loaded or installed) starting with a prefix, as accepted by `new`
+    pub(crate) fn list_names(prefix: &str) -> anyhow::Result<Vec<String>> {
+        let mut names: Vec<_> = Self::service_names(Manager::connect()?.unit_names()?)
+            .filter(|n| n.starts_with(prefix))
+            .collect();
+        names.sort_unstable();
+        names.dedup();
+        Ok(names)
+    }
+
+    /// Get unit glob pattern of a service name given with wildcards (`*`, `?` or `[`), ie. `php-fpm*`
+    fn unit_pattern(name: &str) -> Option<String> {
+        name.contains(['*', '?', '[']).then(|| {
+            if name.ends_with(".service") {
+                name.to_owned()
+            } else {
+                format!("{name}.service")
+            }
+        })
+    }
+
+    /// Expand a service name given with wildcards to the names of the loaded services matching it, as accepted by
+    /// `new`, other names being returned as is
+    pub(crate) fn expand_name(name: &str) -> anyhow::Result<Vec<String>> {
+        let Some(pattern) = Self::unit_pattern(name) else {
+            return Ok(vec![name.to_owned()]);
+        };
+        let mut names: Vec<_> =
+            Self::service_names(Manager::connect()?.loaded_unit_names_matching(&pattern)?)
+                .collect();
+        anyhow::ensure!(!names.is_empty(), "No loaded service matches {name:?}");
+        names.sort_unstable();
+        Ok(names)
+    }
+
+    /// List services with profiling or hardening config fragments, templates being returned without instance
+    pub(crate) fn list_managed() -> anyhow::Result<Vec<Self>> {
+        Self::list_managed_in(&FRAGMENT_BASE_DIRS.map(Path::new))
+    }
+
+    fn list_managed_in(base_dirs: &[&Path]) -> anyhow::Result<Vec<Self>> {
+        let fragment_prefix = format!("zz_{}-", env!("CARGO_PKG_NAME"));
+        let mut names = BTreeSet::new();
+        for base_dir in base_dirs {
+            let Ok(entries) = fs::read_dir(base_dir) else {
+                continue;
+            };
+            for entry in entries {
+                let entry = entry?;
+                let Some(name) = entry
+                    .file_name()
+                    .to_str()
+                    .and_then(|n| n.strip_suffix(".service.d"))
+                    .map(ToOwned::to_owned)
+                else {
+                    continue;
+                };
+                if fs::read_dir(entry.path())?.filter_map(Result::ok).any(|f| {
+                    f.file_name()
+                        .to_string_lossy()
+                        .starts_with(&fragment_prefix)
+                }) {
+                    names.insert(name);
+                }
+            }
+        }
+        Ok(names.iter().map(|n| Self::new(n)).collect())
+    }
+
+    /// Services to restart for this one: itself, or the instances of a template
+    pub(crate) fn instances(&self) -> anyhow::Result<Vec<Self>> {
+        if self.arg.as_deref() == Some("") {
+            Ok(Self::list_names(&format!("{}@", self.name))?
+                .iter()
+                .map(|n| Self::new(n))
+                .collect())
+        } else {
+            Ok(vec![Self {
+                name: self.name.clone(),
+                arg: self.arg.clone(),
+            }])
+        }
+    }
+
+    /// Remove profile data directories left by interrupted or accumulated profiling, of all services
+    pub(crate) fn remove_profile_data_dirs() -> anyhow::Result<()> {
+        let dir_prefix = format!("{}-profile-data_", env!("CARGO_PKG_NAME"));
+        for entry in fs::read_dir("/run")? {
+            let path = entry?.path();
+            if path.is_dir()
+                && path
+                    .file_name()
+                    .is_some_and(|n| n.to_string_lossy().starts_with(&dir_prefix))
+            {
+                fs::remove_dir_all(&path)?;
+                log::info!("{path:?} removed");
+            }
+        }
+        Ok(())
+    }
+
+    /// Get service names from unit names
+    fn service_names(unit_names: Vec<String>) -> impl Iterator<Item = String> {
+        unit_names
+            .into_iter()
+            .filter_map(|u| u.strip_suffix(".service").map(ToOwned::to_owned))
+            // Templates can not be profiled without an instance
+            .filter(|n| !n.ends_with('@'))
+    }
+
+    /// Unit name to match in the journal, matching all instances of a template
+    /// Names of the units whose journal entries are from the service, all instances for a template
+    fn journal_unit_names(&self, journal: &mut Journal) -> anyhow::Result<Vec<String>> {
+        if self.arg.as_deref() == Some("") {
+            let prefix = format!("{}@", self.name);
+            Ok(journal
+                .unique_values("_SYSTEMD_UNIT")?
+                .into_iter()
+                .filter(|n| n.starts_with(&prefix) && n.ends_with(".service"))
+                .collect())
+        } else {
+            Ok(vec![self.unit_name()])
+        }
+    }
+
+    pub(crate) fn unit_name(&self) -> String {
         format!(
             "{}{}.service",
             &self.name,
@@ -54,9 +274,16 @@ impl Service {
         )
     }
 
+    #[expect(clippy::too_many_arguments)]
     pub(crate) fn add_profile_fragment(
         &self,
         hardening_opts: &HardeningOptions,
+        split_exec_start_pre: bool,
+        strace_opts: &StraceOptions,
+        sampling_opts: &SamplingOptions,
+        strace_log_opts: &StraceLogOptions,
+        activations: Option<usize>,
+        annotate: bool,
     ) -> anyhow::Result<()> {
         // Check first if our fragment does not yet exist
         let fragment_path = self.fragment_path(PROFILING_FRAGMENT_NAME, false);
@@ -81,93 +308,230 @@ impl Service {
         #[expect(clippy::unwrap_used)] // fragment_path guarantees by construction we have a parent
         fs::create_dir_all(fragment_path.parent().unwrap())?;
         let mut fragment_file = BufWriter::new(File::create(&fragment_path)?);
+
+        // Profile data dir
+        let mut rng = rand::thread_rng();
+        let profile_data_dir = PathBuf::from(format!(
+            "/run/{}-profile-data_{:08x}",
+            env!("CARGO_PKG_NAME"),
+            rng.gen::<u32>()
+        ));
+        let shh_bin = env::current_exe()?
+            .to_str()
+            .ok_or_else(|| anyhow::anyhow!("Unable to decode current executable path"))?
+            .to_owned();
+
+        self.write_profile_fragment(
+            &mut fragment_file,
+            &config_paths,
+            &profile_data_dir,
+            &shh_bin,
+            hardening_opts,
+            split_exec_start_pre,
+            strace_opts,
+            sampling_opts,
+            strace_log_opts,
+            activations,
+            annotate,
+        )?;
+        fragment_file.flush()?;
+
+        log::info!("Config fragment written in {fragment_path:?}");
+        Ok(())
+    }
+
+    /// Write the profiling fragment content, wrapping commands of the unit with the given config files
+    #[expect(clippy::too_many_arguments)]
+    fn write_profile_fragment<W: Write>(
+        &self,
+        writer: &mut W,
+        config_paths: &[&Path],
+        profile_data_dir: &Path,
+        shh_bin: &str,
+        hardening_opts: &HardeningOptions,
+        split_exec_start_pre: bool,
+        strace_opts: &StraceOptions,
+        sampling_opts: &SamplingOptions,
+        strace_log_opts: &StraceLogOptions,
+        activations: Option<usize>,
+        annotate: bool,
+    ) -> anyhow::Result<()> {
         writeln!(
-            fragment_file,
+            writer,
             "# This file has been autogenerated by {}",
             env!("CARGO_PKG_NAME")
         )?;
-        writeln!(fragment_file, "[Service]")?;
-        // writeln!(fragment_file, "AmbientCapabilities=CAP_SYS_PTRACE")?;
+        writeln!(writer, "[Service]")?;
+        // writeln!(writer, "AmbientCapabilities=CAP_SYS_PTRACE")?;
         // needed because strace becomes the main process
-        writeln!(fragment_file, "NotifyAccess=all")?;
-        writeln!(fragment_file, "Environment=RUST_BACKTRACE=1")?;
-        if !Self::config_vals("SystemCallFilter", &config_paths)?.is_empty() {
+        writeln!(writer, "NotifyAccess=all")?;
+        writeln!(writer, "Environment=RUST_BACKTRACE=1")?;
+        if !Self::config_vals("SystemCallFilter", config_paths)?.is_empty() {
             // Allow ptracing, only if a syscall filter is already in place, otherwise it becomes a whitelist
-            writeln!(fragment_file, "SystemCallFilter=@debug")?;
+            writeln!(writer, "SystemCallFilter=@debug")?;
         }
         // strace may slow down enough to risk reaching some service timeouts
-        writeln!(fragment_file, "TimeoutStartSec=infinity")?;
-        writeln!(fragment_file, "KillMode=control-group")?;
-        writeln!(fragment_file, "StandardOutput=journal")?;
+        writeln!(writer, "TimeoutStartSec=infinity")?;
+        writeln!(writer, "StandardOutput=journal")?;
+
+        // Forking services detach from the process systemd starts, but the profiling wrapper runs until all traced
+        // processes exit, so it must be the main process instead, and systemd must not wait for the PID file
+        let forking = Self::config_vals("Type", config_paths)?.pop().as_deref() == Some("forking");
+        let pid_file = if forking {
+            writeln!(writer, "Type=simple")?;
+            writeln!(writer, "PIDFile=")?;
+            // Stop signals, from systemd or from ExecStop= commands signaling $MAINPID, reach the wrapper, which
+            // forwards them to the daemon, so they must not also be sent to the daemon directly
+            writeln!(writer, "KillMode=mixed")?;
+            log::info!(
+                "Service forks, the profiling wrapper will be its main process during profiling"
+            );
+            // Relative PID file paths are relative to the runtime directory
+            Self::config_vals("PIDFile", config_paths)?
+                .pop()
+                .map(|p| Path::new("/run").join(p))
+        } else {
+            writeln!(writer, "KillMode=control-group")?;
+            None
+        };
+
+        // Services with delegated cgroups (ie. container managers) create cgroups and containers themselves, which
+        // options isolating mounts, namespaces and cgroups break
+        let mut hardening_args = hardening_opts.to_args();
+        let delegate = Self::config_vals("Delegate", config_paths)?
+            .pop()
+            .is_some_and(|v| !["no", "false", "0", "off", ""].contains(&v.as_str()));
+        if delegate && !hardening_opts.container_manager {
+            log::info!(
+                "Service has Delegate= set, options breaking cgroup delegation will not be applied"
+            );
+            hardening_args.push("--container-manager".to_owned());
+        }
 
-        // Profile data dir
-        let mut rng = rand::thread_rng();
-        let profile_data_dir = PathBuf::from(format!(
-            "/run/{}-profile-data_{:08x}",
-            env!("CARGO_PKG_NAME"),
-            rng.gen::<u32>()
-        ));
         #[expect(clippy::unwrap_used)]
         writeln!(
-            fragment_file,
+            writer,
             "RuntimeDirectory={}",
             profile_data_dir.file_name().unwrap().to_str().unwrap()
         )?;
 
-        let shh_bin = env::current_exe()?
-            .to_str()
-            .ok_or_else(|| anyhow::anyhow!("Unable to decode current executable path"))?
-            .to_owned();
-
         // Wrap ExecStartXxx directives
         let mut exec_start_idx = 1;
         let mut profile_data_paths = Vec::new();
+        let mut exec_start_pre_profile_data_paths = Vec::new();
         for exec_start_opt in ["ExecStartPre", "ExecStart", "ExecStartPost"] {
-            let exec_start_cmds = Self::config_vals(exec_start_opt, &config_paths)?;
+            let exec_start_cmds = Self::config_vals(exec_start_opt, config_paths)?;
             if !exec_start_cmds.is_empty() {
-                writeln!(fragment_file, "{exec_start_opt}=")?;
+                writeln!(writer, "{exec_start_opt}=")?;
             }
             for cmd in exec_start_cmds {
                 if cmd.starts_with(PRIVILEGED_PREFIX) {
                     // TODO handle other special prefixes?
                     // Write command unchanged
-                    writeln!(fragment_file, "{exec_start_opt}={cmd}")?;
+                    writeln!(writer, "{exec_start_opt}={cmd}")?;
                 } else {
                     let profile_data_path = profile_data_dir.join(format!("{exec_start_idx:03}"));
+                    let strace_log_path = strace_log_opts.path.as_ref().map(|p| {
+                        let mut path = p.clone().into_os_string();
+                        path.push(format!(".{}.{exec_start_idx:03}", self.unit_name()));
+                        PathBuf::from(path)
+                    });
                     exec_start_idx += 1;
-                    #[expect(clippy::unwrap_used)]
+                    let mut run_args = hardening_args.clone();
+                    run_args.extend(strace_opts.to_args());
+                    run_args.extend(sampling_opts.to_args());
+                    if let Some(strace_log_path) = strace_log_path.as_ref() {
+                        run_args.extend(strace_log_opts.to_args(strace_log_path));
+                    }
+                    if let Some(pid_file) =
+                        pid_file.as_ref().filter(|_| exec_start_opt == "ExecStart")
+                    {
+                        run_args.extend([
+                            "--pid-file".to_owned(),
+                            pid_file.to_string_lossy().into_owned(),
+                        ]);
+                    }
+                    run_args.extend([
+                        "-p".to_owned(),
+                        profile_data_path.to_string_lossy().into_owned(),
+                    ]);
                     writeln!(
-                        fragment_file,
-                        "{}={} run {} -p {} -- {}",
+                        writer,
+                        "{}={} run {} -- {}",
                         exec_start_opt,
-                        shh_bin,
-                        hardening_opts.to_cmdline(),
-                        profile_data_path.to_str().unwrap(),
+                        quote_exec_arg(shh_bin),
+                        exec_cmdline(&run_args),
                         cmd
                     )?;
-                    profile_data_paths.push(profile_data_path);
+                    if split_exec_start_pre && (exec_start_opt == "ExecStartPre") {
+                        exec_start_pre_profile_data_paths.push(profile_data_path);
+                    } else {
+                        profile_data_paths.push(profile_data_path);
+                    }
                 }
             }
         }
 
+        // Paths seen by the service are relative to its root directory
+        let root_directory = Self::config_vals("RootDirectory", config_paths)?.pop();
+        if !Self::config_vals("RootImage", config_paths)?.is_empty() {
+            log::warn!("Service uses RootImage=, paths seen by the service can not be translated to host paths, bind mount options may need manual review");
+        }
+
+        // Profile data of each activation is moved out of the runtime directory, which is removed when the service stops
+        let accumulate_dir = activations.map(|n| {
+            let mut dir = profile_data_dir.as_os_str().to_owned();
+            dir.push("_accumulated");
+            (PathBuf::from(dir), n)
+        });
+
         // Add invocation that merges previous profiles
-        #[expect(clippy::unwrap_used)]
-        writeln!(
-            fragment_file,
-            "ExecStopPost={} merge-profile-data {} {}",
-            shh_bin,
-            hardening_opts.to_cmdline(),
+        let mut merge_args = vec![shh_bin.to_owned(), "merge-profile-data".to_owned()];
+        merge_args.extend(hardening_args);
+        merge_args.extend(["--unit".to_owned(), self.unit_name()]);
+        if let Some(root_directory) = root_directory {
+            merge_args.extend(["--root-directory".to_owned(), root_directory]);
+        }
+        if annotate {
+            merge_args.push("--annotate".to_owned());
+        }
+        merge_args.extend([
+            "--smoke-test".to_owned(),
+            "--coverage".to_owned(),
+            "--timeline".to_owned(),
+        ]);
+        if let Some((dir, n)) = accumulate_dir {
+            merge_args.extend([
+                "--accumulate-dir".to_owned(),
+                dir.to_string_lossy().into_owned(),
+                "--activations".to_owned(),
+                n.to_string(),
+            ]);
+        }
+        for path in &exec_start_pre_profile_data_paths {
+            merge_args.extend([
+                "--exec-start-pre-profile-data".to_owned(),
+                path.to_string_lossy().into_owned(),
+            ]);
+        }
+        merge_args.extend(
             profile_data_paths
                 .iter()
-                .map(|p| p.to_str().unwrap())
-                .join(" ")
-        )?;
-
-        log::info!("Config fragment written in {fragment_path:?}");
+                .map(|p| p.to_string_lossy().into_owned()),
+        );
+        writeln!(writer, "ExecStopPost={}", exec_cmdline(&merge_args))?;
         Ok(())
     }
 
     pub(crate) fn remove_profile_fragment(&self) -> anyhow::Result<()> {
+        let _ = fs::remove_file(self.baseline_startup_time_path());
+        let _ = fs::remove_file(self.related_units_path());
+        let paused_fragment_path = self.paused_profile_fragment_path();
+        if paused_fragment_path.is_file() {
+            fs::remove_file(&paused_fragment_path)?;
+            log::info!("{paused_fragment_path:?} removed");
+            return Ok(());
+        }
         let fragment_path = self.fragment_path(PROFILING_FRAGMENT_NAME, false);
         fs::remove_file(&fragment_path)?;
         log::info!("{fragment_path:?} removed");
@@ -183,113 +547,571 @@ impl Service {
     }
 
     pub(crate) fn remove_hardening_fragment(&self) -> anyhow::Result<()> {
+        let exec_start_pre_unit_path = self.exec_start_pre_unit_path();
+        if exec_start_pre_unit_path.is_file() {
+            fs::remove_file(&exec_start_pre_unit_path)?;
+            log::info!("{exec_start_pre_unit_path:?} removed");
+        }
         let fragment_path = self.fragment_path(HARDENING_FRAGMENT_NAME, true);
         fs::remove_file(&fragment_path)?;
         log::info!("{fragment_path:?} removed");
         Ok(())
     }
 
-    pub(crate) fn add_hardening_fragment(&self, opts: Vec<OptionWithValue>) -> anyhow::Result<()> {
+    /// Write hardening fragment, and if setup commands have their own options, the helper unit running them
+    /// In complain mode, the syscall filter is written commented out, and syscalls it would deny are only logged
+    pub(crate) fn add_hardening_fragment(
+        &self,
+        opts: Vec<OptionWithValue>,
+        comments: &OptionComments,
+        exec_start_pre_opts: Option<Vec<OptionWithValue>>,
+        complain: bool,
+    ) -> anyhow::Result<()> {
         let fragment_path = self.fragment_path(HARDENING_FRAGMENT_NAME, true);
         #[expect(clippy::unwrap_used)]
         fs::create_dir_all(fragment_path.parent().unwrap())?;
 
-        let mut fragment_file = BufWriter::new(File::create(&fragment_path)?);
-        writeln!(
-            fragment_file,
-            "# This file has been autogenerated by {}",
+        let mut fragment = format!(
+            "# This file has been autogenerated by {}\n[Service]\n",
             env!("CARGO_PKG_NAME")
-        )?;
-        writeln!(fragment_file, "[Service]")?;
+        );
+        let specifiers = self.specifiers()?;
+        let mut prev_name = None;
         for opt in opts {
-            writeln!(fragment_file, "{opt}")?;
+            let opt = specifiers.apply(opt);
+            if prev_name.as_ref() != Some(&opt.name) {
+                for comment in comments.get(&opt.name).into_iter().flatten() {
+                    writeln!(fragment, "{}", comment_line(&opt.name, comment))?;
+                }
+            }
+            prev_name = Some(opt.name.clone());
+            if complain && (opt.name == "SystemCallFilter") {
+                if let Some(log_opt) = complain::log_option(&opt) {
+                    writeln!(fragment, "{COMPLAIN_MODE_PREFIX}{opt}")?;
+                    writeln!(fragment, "{log_opt}")?;
+                    continue;
+                }
+            }
+            fragment.push_str(&opt.to_string());
+            fragment.push('\n');
+        }
+
+        if let Some(exec_start_pre_opts) = exec_start_pre_opts {
+            let config_paths_bufs: Vec<_> = self
+                .config_paths()?
+                .into_iter()
+                .filter(|p| p.is_file() && (*p != fragment_path))
+                .collect();
+            let config_paths = config_paths_bufs
+                .iter()
+                .map(PathBuf::as_path)
+                .collect::<Vec<_>>();
+            let exec_start_pre_cmds = Self::config_vals("ExecStartPre", &config_paths)?;
+            let (privileged_cmds, cmds): (Vec<_>, Vec<_>) = exec_start_pre_cmds
+                .into_iter()
+                .partition(|c| c.starts_with(PRIVILEGED_PREFIX));
+            if !cmds.is_empty() {
+                let unit = self.exec_start_pre_unit(&config_paths, &cmds, exec_start_pre_opts)?;
+                let unit_path = self.exec_start_pre_unit_path();
+                fs::write(&unit_path, unit)?;
+                log::info!("Setup commands helper unit written in {unit_path:?}");
+
+                // Replace unprivileged setup commands by a privileged one starting the helper unit
+                // TODO APPROXIMATION privileged setup commands now all run before the unprivileged ones
+                fragment.push_str("ExecStartPre=\n");
+                for cmd in privileged_cmds {
+                    writeln!(fragment, "ExecStartPre={cmd}")?;
+                }
+                writeln!(
+                    fragment,
+                    "ExecStartPre={PRIVILEGED_PREFIX}systemctl start {}",
+                    self.exec_start_pre_unit_name()
+                )?;
+            }
+        }
+
+        Self::write_fragment(&fragment_path, &fragment)
+    }
+
+    fn write_fragment(fragment_path: &Path, fragment: &str) -> anyhow::Result<()> {
+        // Leave identical file untouched, to keep it friendly to configuration management tools
+        if fs::read_to_string(fragment_path).is_ok_and(|c| c == fragment) {
+            log::info!("Config fragment {fragment_path:?} is unchanged");
+            return Ok(());
         }
+        fs::write(fragment_path, fragment)?;
 
         log::info!("Config fragment written in {fragment_path:?}");
         Ok(())
     }
 
-    #[expect(clippy::unused_self)]
-    pub(crate) fn reload_unit_config(&self) -> anyhow::Result<()> {
-        let status = Command::new("systemctl").arg("daemon-reload").status()?;
-        if !status.success() {
-            anyhow::bail!("systemctl failed: {status}");
+    /// Get applied hardening fragment, and helper unit running setup commands if any
+    pub(crate) fn hardening_fragment(&self) -> anyhow::Result<(String, Option<String>)> {
+        let fragment_path = self.fragment_path(HARDENING_FRAGMENT_NAME, true);
+        let fragment = fs::read_to_string(&fragment_path).with_context(|| {
+            format!(
+                "No hardening config applied to {} ({} can not be read)",
+                self.unit_name(),
+                fragment_path.display()
+            )
+        })?;
+        let exec_start_pre_unit_path = self.exec_start_pre_unit_path();
+        let exec_start_pre_unit = exec_start_pre_unit_path
+            .is_file()
+            .then(|| fs::read_to_string(&exec_start_pre_unit_path))
+            .transpose()?;
+        Ok((fragment, exec_start_pre_unit))
+    }
+
+    /// Path of the hardening fragment, where options are written
+    pub(crate) fn hardening_fragment_path(&self) -> PathBuf {
+        self.fragment_path(HARDENING_FRAGMENT_NAME, true)
+    }
+
+    /// Paths of the hardening fragment and helper unit files, if they exist
+    pub(crate) fn hardening_paths(&self) -> Vec<PathBuf> {
+        [
+            self.hardening_fragment_path(),
+            self.exec_start_pre_unit_path(),
+        ]
+        .into_iter()
+        .filter(|p| p.is_file())
+        .collect()
+    }
+
+    /// Write hardening fragment and helper unit as they were applied to the same service elsewhere
+    pub(crate) fn import_hardening_fragment(
+        &self,
+        fragment: &str,
+        exec_start_pre_unit: Option<&str>,
+    ) -> anyhow::Result<()> {
+        let fragment_path = self.fragment_path(HARDENING_FRAGMENT_NAME, true);
+        #[expect(clippy::unwrap_used)]
+        fs::create_dir_all(fragment_path.parent().unwrap())?;
+        let exec_start_pre_unit_path = self.exec_start_pre_unit_path();
+        if let Some(exec_start_pre_unit) = exec_start_pre_unit {
+            fs::write(&exec_start_pre_unit_path, exec_start_pre_unit)?;
+            log::info!("Setup commands helper unit written in {exec_start_pre_unit_path:?}");
+        } else if exec_start_pre_unit_path.is_file() {
+            fs::remove_file(&exec_start_pre_unit_path)?;
+            log::info!("{exec_start_pre_unit_path:?} removed");
         }
-        Ok(())
+        Self::write_fragment(&fragment_path, fragment)
     }
 
-    pub(crate) fn action(&self, verb: &str, block: bool) -> anyhow::Result<()> {
+    /// Get the time hardening config was applied in complain mode, if it currently is
+    pub(crate) fn complain_mode_start_time(&self) -> anyhow::Result<Option<SystemTime>> {
+        let fragment_path = self.fragment_path(HARDENING_FRAGMENT_NAME, true);
+        if !fs::read_to_string(&fragment_path)
+            .is_ok_and(|c| c.lines().any(|l| l.starts_with(COMPLAIN_MODE_PREFIX)))
+        {
+            return Ok(None);
+        }
+        Ok(Some(fs::metadata(fragment_path)?.modified()?))
+    }
+
+    /// Get syscalls the service made since complain mode started, that the syscall filter would deny
+    pub(crate) fn complain_mode_logged_syscalls(
+        &self,
+        since: SystemTime,
+    ) -> anyhow::Result<Vec<LoggedSyscall>> {
         let unit_name = self.unit_name();
-        log::info!("{} {}", verb, unit_name);
-        let mut cmd = vec![verb];
-        if !block {
-            cmd.push("--no-block");
+        let unit_pids = Manager::connect()?.unit_pids(&unit_name)?;
+        let mut logged = complain::logged_syscalls(
+            since,
+            &unit_name,
+            &unit_pids,
+            self.log_namespace()?.as_deref(),
+        )?;
+        logged.sort_unstable();
+        Ok(logged)
+    }
+
+    /// Switch hardening config from complain mode to enforcing the syscall filter
+    pub(crate) fn enforce_hardening_fragment(&self) -> anyhow::Result<()> {
+        let fragment_path = self.fragment_path(HARDENING_FRAGMENT_NAME, true);
+        let fragment = fs::read_to_string(&fragment_path)?;
+        let mut enforced = String::new();
+        for line in fragment.lines() {
+            if line.starts_with("SystemCallLog=") {
+                continue;
+            }
+            writeln!(
+                enforced,
+                "{}",
+                line.strip_prefix(COMPLAIN_MODE_PREFIX).unwrap_or(line)
+            )?;
+        }
+        fs::write(&fragment_path, enforced)?;
+        log::info!("Syscall filter enforced in {fragment_path:?}");
+        Ok(())
+    }
+
+    /// Path of the profiling fragment while profiling is paused, which systemd ignores since it lacks the .conf
+    /// extension
+    fn paused_profile_fragment_path(&self) -> PathBuf {
+        let mut path = self
+            .fragment_path(PROFILING_FRAGMENT_NAME, false)
+            .into_os_string();
+        path.push(".paused");
+        PathBuf::from(path)
+    }
+
+    /// Path of the startup time of the service before profiling, which systemd ignores since it lacks the .conf
+    /// extension
+    fn baseline_startup_time_path(&self) -> PathBuf {
+        let mut path = self
+            .fragment_path(PROFILING_FRAGMENT_NAME, false)
+            .into_os_string();
+        path.push(".baseline");
+        PathBuf::from(path)
+    }
+
+    /// Record how long the service took to start before profiling, if it is running, to compare with profiled
+    /// activations
+    pub(crate) fn record_baseline_startup_time(&self) -> anyhow::Result<()> {
+        if !self.is_active()? {
+            return Ok(());
         }
-        cmd.push(&unit_name);
-        let status = Command::new("systemctl").args(cmd).status()?;
-        if !status.success() {
-            anyhow::bail!("systemctl failed: {status}");
+        if let Some(startup_time) = Manager::connect()?.unit_startup_time(&self.unit_name())? {
+            fs::write(
+                self.baseline_startup_time_path(),
+                startup_time.as_micros().to_string(),
+            )?;
         }
         Ok(())
     }
 
-    pub(crate) fn profiling_result(&self) -> anyhow::Result<Vec<OptionWithValue>> {
-        // Start journalctl process
-        let mut child = Command::new("journalctl")
-            .args([
-                "-r",
-                "-o",
-                "cat",
-                "--output-fields=MESSAGE",
-                "--no-tail",
-                "-u",
-                &self.unit_name(),
-            ])
-            .stdin(Stdio::null())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::null())
-            .env("LANG", "C")
-            .spawn()?;
+    /// Get how long the service took to start before profiling, and during the current profiled activation
+    pub(crate) fn startup_times(&self) -> anyhow::Result<Option<(Duration, Duration)>> {
+        let Ok(baseline) = fs::read_to_string(self.baseline_startup_time_path()) else {
+            return Ok(None);
+        };
+        let baseline = Duration::from_micros(baseline.trim().parse()?);
+        Ok(Manager::connect()?
+            .unit_startup_time(&self.unit_name())?
+            .map(|profiled| (baseline, profiled)))
+    }
 
-        // Parse its output
-        #[expect(clippy::unwrap_used)]
-        let reader = BufReader::new(child.stdout.take().unwrap());
-        let snippet_lines: Vec<_> = reader
-            .lines()
+    /// Path of the names of units profiled along with the service, which systemd ignores since it lacks the .conf
+    /// extension
+    fn related_units_path(&self) -> PathBuf {
+        let mut path = self
+            .fragment_path(PROFILING_FRAGMENT_NAME, false)
+            .into_os_string();
+        path.push(".related");
+        PathBuf::from(path)
+    }
+
+    /// Record units profiled along with the service, so that profiling of all of them is finished together
+    pub(crate) fn set_related_units(&self, units: &[Self]) -> anyhow::Result<()> {
+        let path = self.related_units_path();
+        let names: Vec<_> = units.iter().map(Self::unit_name).collect();
+        fs::write(&path, names.join("\n"))
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Get units profiled along with the service
+    pub(crate) fn related_units(&self) -> anyhow::Result<Vec<Self>> {
+        match fs::read_to_string(self.related_units_path()) {
+            Ok(names) => Ok(names
+                .lines()
+                .filter_map(|n| n.strip_suffix(".service"))
+                .map(Self::new)
+                .collect()),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub(crate) fn is_profile_paused(&self) -> bool {
+        self.paused_profile_fragment_path().is_file()
+    }
+
+    /// Pause profiling: the service is stopped while its profiling config is still loaded, so its profile data is moved
+    /// to an accumulation directory, and the profiling fragment is disabled until profiling is resumed
+    pub(crate) fn pause_profile(&self) -> anyhow::Result<()> {
+        let fragment_path = self.fragment_path(PROFILING_FRAGMENT_NAME, false);
+        anyhow::ensure!(fragment_path.is_file(), "Service is not being profiled");
+
+        // Profile data is removed once merged, unless it is accumulated
+        if self.profiling_merge_args()?.accumulate_dir.is_none() {
+            let runtime_dir = Self::config_vals("RuntimeDirectory", &[&fragment_path])?
+                .pop()
+                .ok_or_else(|| {
+                    anyhow::anyhow!("Unable to find profile data directory in {fragment_path:?}")
+                })?;
+            let fragment =
+                Self::accumulating_fragment(&fs::read_to_string(&fragment_path)?, &runtime_dir);
+            fs::write(&fragment_path, fragment)?;
+            self.reload_unit_config()?;
+        }
+        self.action(UnitAction::Stop, true)?;
+
+        fs::rename(&fragment_path, self.paused_profile_fragment_path())?;
+        log::info!("Profiling paused, profile data so far will be merged when it is finished");
+        Ok(())
+    }
+
+    /// Rewrite profiling fragment, so that its merge command accumulates profile data instead of removing it
+    fn accumulating_fragment(fragment: &str, runtime_dir: &str) -> String {
+        fragment.replacen(
+            " merge-profile-data ",
+            &format!(" merge-profile-data --accumulate-dir /run/{runtime_dir}_accumulated "),
+            1,
+        )
+    }
+
+    /// Resume profiling paused by `pause_profile`
+    pub(crate) fn resume_profile(&self) -> anyhow::Result<()> {
+        let paused_fragment_path = self.paused_profile_fragment_path();
+        anyhow::ensure!(paused_fragment_path.is_file(), "Profiling is not paused");
+        fs::rename(
+            &paused_fragment_path,
+            self.fragment_path(PROFILING_FRAGMENT_NAME, false),
+        )?;
+        log::info!("Profiling resumed");
+        Ok(())
+    }
+
+    /// Whether profiling is set up, and not paused
+    pub(crate) fn is_profiled(&self) -> bool {
+        self.fragment_path(PROFILING_FRAGMENT_NAME, false).is_file()
+    }
+
+    /// Get hardening options and profile data paths from the merge command of the profiling fragment
+    pub(crate) fn profiling_merge_args(&self) -> anyhow::Result<ProfilingMergeArgs> {
+        Self::fragment_merge_args(&self.profiling_fragment_path()?)
+    }
+
+    /// Get path of the profiling fragment, whether profiling is paused or not
+    fn profiling_fragment_path(&self) -> anyhow::Result<PathBuf> {
+        let fragment_path = if self.is_profile_paused() {
+            self.paused_profile_fragment_path()
+        } else {
+            self.fragment_path(PROFILING_FRAGMENT_NAME, false)
+        };
+        anyhow::ensure!(
+            fragment_path.is_file(),
+            "Profiling fragment config does not exist at {fragment_path:?}"
+        );
+        Ok(fragment_path)
+    }
+
+    /// Only output options within breakage risk bounds when the profiling fragment merge command runs
+    pub(crate) fn set_profiling_risk_bounds(
+        &self,
+        min_risk: Option<RiskLevel>,
+        max_risk: Option<RiskLevel>,
+    ) -> anyhow::Result<()> {
+        let fragment_path = self.profiling_fragment_path()?;
+        let fragment =
+            Self::risk_bounded_fragment(&fs::read_to_string(&fragment_path)?, min_risk, max_risk);
+        fs::write(&fragment_path, fragment)?;
+        Ok(())
+    }
+
+    /// Rewrite profiling fragment, so that its merge command only outputs options within breakage risk bounds
+    fn risk_bounded_fragment(
+        fragment: &str,
+        min_risk: Option<RiskLevel>,
+        max_risk: Option<RiskLevel>,
+    ) -> String {
+        let risk_args: String = min_risk
+            .map(|r| format!("--min-risk {r} "))
+            .into_iter()
+            .chain(max_risk.map(|r| format!("--max-risk {r} ")))
+            .collect();
+        fragment.replacen(
+            " merge-profile-data ",
+            &format!(" merge-profile-data {risk_args}"),
+            1,
+        )
+    }
+
+    /// Get merge command arguments from a profiling fragment file
+    fn fragment_merge_args(fragment_path: &Path) -> anyhow::Result<ProfilingMergeArgs> {
+        let merge_cmd = Self::config_vals("ExecStopPost", &[fragment_path])?
+            .pop()
+            .ok_or_else(|| anyhow::anyhow!("Unable to find merge command in {fragment_path:?}"))?;
+        match cl::Args::try_parse_from(split_exec_cmdline(&merge_cmd))?.action {
+            cl::Action::MergeProfileData {
+                hardening_opts,
+                paths,
+                exec_start_pre_paths,
+                root_directory,
+                accumulate_dir,
+                activations,
+                ..
+            } => Ok(ProfilingMergeArgs {
+                hardening_opts,
+                paths,
+                exec_start_pre_paths,
+                root_directory,
+                accumulate_dir,
+                activations,
+            }),
+            _ => anyhow::bail!("Unexpected merge command {merge_cmd:?}"),
+        }
+    }
+
+    #[expect(clippy::unused_self)]
+    pub(crate) fn reload_unit_config(&self) -> anyhow::Result<()> {
+        Manager::connect()?.reload()
+    }
+
+    pub(crate) fn action(&self, action: UnitAction, block: bool) -> anyhow::Result<()> {
+        let unit_name = self.unit_name();
+        log::info!("{action} {unit_name}");
+        Manager::connect()?.unit_action(&unit_name, action, block)
+    }
+
+    pub(crate) fn is_active(&self) -> anyhow::Result<bool> {
+        Manager::connect()?.is_active(&self.unit_name())
+    }
+
+    /// Send a signal to the main process of the service
+    pub(crate) fn signal_main_process(&self, signal: Signal) -> anyhow::Result<()> {
+        let unit_name = self.unit_name();
+        log::info!("Sending {signal} to main process of {unit_name}");
+        Manager::connect()?.kill_main_process(&unit_name, signal)
+    }
+
+    /// Get profiling result options, and their comments if they were annotated
+    pub(crate) fn profiling_result(
+        &self,
+    ) -> anyhow::Result<(Vec<OptionWithValue>, OptionComments)> {
+        self.journal_options(START_OPTION_OUTPUT_SNIPPET, END_OPTION_OUTPUT_SNIPPET)
+    }
+
+    /// Get profiling result of setup commands, when they are profiled separately from the main process
+    pub(crate) fn exec_start_pre_profiling_result(&self) -> anyhow::Result<Vec<OptionWithValue>> {
+        self.journal_options(
+            START_EXEC_START_PRE_OPTION_OUTPUT_SNIPPET,
+            END_EXEC_START_PRE_OPTION_OUTPUT_SNIPPET,
+        )
+        .map(|(opts, _comments)| opts)
+    }
+
+    /// Get the smoke test script output when profiling ended, from the service logs
+    pub(crate) fn smoke_test_script(&self) -> anyhow::Result<String> {
+        let lines = self.journal_snippet(
+            START_SMOKE_TEST_OUTPUT_SNIPPET,
+            END_SMOKE_TEST_OUTPUT_SNIPPET,
+        )?;
+        let mut script = lines.join("\n");
+        script.push('\n');
+        Ok(script)
+    }
+
+    /// Get when each permission was first needed while profiling, from the service logs
+    pub(crate) fn profiling_timeline(&self) -> anyhow::Result<String> {
+        let lines =
+            self.journal_snippet(START_TIMELINE_OUTPUT_SNIPPET, END_TIMELINE_OUTPUT_SNIPPET)?;
+        let mut timeline = lines.join("\n");
+        timeline.push('\n');
+        Ok(timeline)
+    }
+
+    /// Get warnings about poor profiling coverage, from the summary of profiled actions in the service logs, and the
+    /// state of the stopped service, while its profiling config is still loaded
+    pub(crate) fn profiling_coverage_warnings(&self) -> anyhow::Result<Vec<String>> {
+        let coverage: Coverage = serde_json::from_str(
+            &self
+                .journal_snippet(START_COVERAGE_OUTPUT_SNIPPET, END_COVERAGE_OUTPUT_SNIPPET)?
+                .concat(),
+        )?;
+        let config_paths_bufs = self.config_paths()?;
+        let config_paths = config_paths_bufs
+            .iter()
+            .map(PathBuf::as_path)
+            .collect::<Vec<_>>();
+        let exec_start_pre_cmds = Self::config_vals("ExecStartPre", &config_paths)?
+            .into_iter()
+            // Only commands wrapped by the profiling config are profiled
+            .filter_map(|c| c.split_once(" -- ").map(|(_, cmd)| cmd.to_owned()))
+            .collect();
+        let manager = Manager::connect()?;
+        let unit_name = self.unit_name();
+        let context = ServiceContext {
+            runtime: manager.unit_last_runtime(&unit_name)?,
+            socket_units: manager
+                .unit_triggered_by(&unit_name)?
+                .into_iter()
+                .filter(|u| u.ends_with(".socket"))
+                .collect(),
+            exec_start_pre_cmds,
+        };
+        Ok(coverage::warnings(&coverage, &context))
+    }
+
+    /// Parse the last options snippet delimited by the given markers from the service logs
+    fn journal_options(
+        &self,
+        start_snippet: &str,
+        end_snippet: &str,
+    ) -> anyhow::Result<(Vec<OptionWithValue>, OptionComments)> {
+        let mut opts = Vec::new();
+        let mut comments = OptionComments::new();
+        for line in self.journal_snippet(start_snippet, end_snippet)? {
+            if let Some((name, comment)) = parse_comment_line(&line) {
+                comments
+                    .entry(name.to_owned())
+                    .or_default()
+                    .push(comment.to_owned());
+            } else if !line.starts_with('#') {
+                // Not a category header
+                opts.push(line.parse::<OptionWithValue>()?);
+            }
+        }
+        Ok((opts, comments))
+    }
+
+    /// Get the lines of the last snippet delimited by the given markers from the service logs, without the markers
+    fn journal_snippet(
+        &self,
+        start_snippet: &str,
+        end_snippet: &str,
+    ) -> anyhow::Result<Vec<String>> {
+        // Output of services logging to a journal namespace is not in the default one
+        let mut journal = Journal::open(self.log_namespace()?.as_deref(), false)?;
+        let unit_names = self.journal_unit_names(&mut journal)?;
+        anyhow::ensure!(
+            !unit_names.is_empty(),
+            "No journal entries for {}",
+            self.unit_name()
+        );
+        for unit_name in &unit_names {
+            journal.add_match("_SYSTEMD_UNIT", unit_name)?;
+        }
+
+        // Read messages in reverse chronological order, to get the end as fast as possible
+        journal.seek_tail()?;
+        let messages = iter::from_fn(|| match journal.previous_entry() {
+            Ok(true) => Some(journal.field("MESSAGE").map(Option::unwrap_or_default)),
+            Ok(false) => None,
+            Err(err) => Some(Err(err)),
+        });
+        let snippet_lines: Vec<_> = messages
             // Stream lines but bubble up errors
-            .skip_while(|r| {
-                r.as_ref()
-                    .map(|l| l != END_OPTION_OUTPUT_SNIPPET)
-                    .unwrap_or(false)
-            })
-            .take_while_inclusive(|r| {
-                r.as_ref()
-                    .map(|l| l != START_OPTION_OUTPUT_SNIPPET)
-                    .unwrap_or(true)
-            })
+            .skip_while(|r| r.as_ref().is_ok_and(|l| l != end_snippet))
+            .take_while_inclusive(|r| r.as_ref().map_or(true, |l| l != start_snippet))
             .collect::<Result<_, _>>()?;
         if (snippet_lines.len() < 2)
             || (snippet_lines
                 .last()
                 .ok_or_else(|| anyhow::anyhow!("Unable to get profiling result lines"))?
-                != START_OPTION_OUTPUT_SNIPPET)
+                != start_snippet)
         {
             anyhow::bail!("Unable to get profiling result snippet");
         }
-        // The output with '-r' flag is in reverse chronological order
-        // (to get the end as fast as possible), so reverse it, after we have
-        // removed marker lines
-        let opts = snippet_lines[1..snippet_lines.len() - 1]
+        // Restore chronological order, after we have removed marker lines
+        let lines = snippet_lines[1..snippet_lines.len() - 1]
             .iter()
             .rev()
-            .map(|l| l.parse::<OptionWithValue>())
-            .collect::<anyhow::Result<_>>()?;
-
-        // Stop journalctl
-        child.kill()?;
-        child.wait()?;
-
-        Ok(opts)
+            .cloned()
+            .collect();
+        Ok(lines)
     }
 
     fn config_vals(key: &str, config_paths: &[&Path]) -> anyhow::Result<Vec<String>> {
@@ -346,64 +1168,106 @@ impl Service {
         Ok(vals)
     }
 
+    /// Get specifier values of the service, to use them in its hardening fragment
+    fn specifiers(&self) -> anyhow::Result<Specifiers> {
+        let config_paths_bufs: Vec<_> = self
+            .config_paths()?
+            .into_iter()
+            .filter(|p| p.is_file())
+            .collect();
+        let config_paths = config_paths_bufs
+            .iter()
+            .map(PathBuf::as_path)
+            .collect::<Vec<_>>();
+        let dynamic_user = Self::config_vals("DynamicUser", &config_paths)?
+            .pop()
+            .is_some_and(|v| ["1", "yes", "true", "on"].contains(&v.as_str()));
+        // %h is the home of the service manager, not influenced by User=, so only use it if the service runs as root
+        let runs_as_root = !dynamic_user
+            && Self::config_vals("User", &config_paths)?
+                .pop()
+                .is_none_or(|u| ["", "root", "0"].contains(&u.as_str()));
+        let home = runs_as_root.then(|| PathBuf::from("/root"));
+        Ok(Specifiers::new(self.arg.as_deref(), home))
+    }
+
     fn config_paths(&self) -> anyhow::Result<Vec<PathBuf>> {
-        let output = Command::new("systemctl")
-            .args(["status", "-n", "0", &self.unit_name()])
-            .env("LANG", "C")
-            .output()?;
-        let mut paths = Vec::new();
-        let mut drop_in_dir = None;
-        for line in output.stdout.lines() {
-            let line = line?;
-            let line = line.trim_start();
-            if line.starts_with("Loaded:") {
-                // Main unit file
-                anyhow::ensure!(paths.is_empty());
-                let path = line
-                    .split_once('(')
-                    .ok_or_else(|| anyhow::anyhow!("Failed to locate main unit file"))?
-                    .1
-                    .split_once(';')
-                    .ok_or_else(|| anyhow::anyhow!("Failed to locate main unit file"))?
-                    .0;
-                paths.push(PathBuf::from(path));
-            } else if line.starts_with("Drop-In:") {
-                // Drop in base dir
-                anyhow::ensure!(paths.len() == 1);
-                anyhow::ensure!(drop_in_dir.is_none());
-                let dir = line
-                    .split_once(':')
-                    .ok_or_else(|| anyhow::anyhow!("Failed to locate unit config fragment dir"))?
-                    .1
-                    .trim_start();
-                drop_in_dir = Some(PathBuf::from(dir));
-            } else if let Some(dir) = drop_in_dir.as_ref() {
-                if line.contains(':') {
-                    // Not a path, next key: val line
-                    break;
-                } else if line.starts_with('/') {
-                    // New base dir
-                    drop_in_dir = Some(PathBuf::from(line));
-                } else {
-                    for filename in line.trim().chars().skip(2).collect::<String>().split(", ") {
-                        let path = dir.join(filename);
-                        paths.push(path);
-                    }
-                }
+        Manager::connect()?.unit_config_paths(&self.unit_name())
+    }
+
+    /// Get the journal namespace the service logs to (`LogNamespace=`), if not the default one
+    fn log_namespace(&self) -> anyhow::Result<Option<String>> {
+        let config_paths_bufs = self.config_paths()?;
+        let config_paths = config_paths_bufs
+            .iter()
+            .map(PathBuf::as_path)
+            .collect::<Vec<_>>();
+        Self::log_namespace_in(&config_paths)
+    }
+
+    fn log_namespace_in(config_paths: &[&Path]) -> anyhow::Result<Option<String>> {
+        Ok(Self::config_vals("LogNamespace", config_paths)?.pop())
+    }
+
+    /// Name of the helper unit running setup commands, from the service unit or any of its instances
+    fn exec_start_pre_unit_name(&self) -> String {
+        format!(
+            "{}-{}-pre{}.service",
+            self.name,
+            env!("CARGO_PKG_NAME"),
+            if self.arg.is_some() { "@%i" } else { "" }
+        )
+    }
+
+    pub(crate) fn exec_start_pre_unit_path(&self) -> PathBuf {
+        PathBuf::from(format!(
+            "/etc/systemd/system/{}-{}-pre{}.service",
+            self.name,
+            env!("CARGO_PKG_NAME"),
+            if self.arg.is_some() { "@" } else { "" }
+        ))
+    }
+
+    /// Build helper unit running setup commands with their own hardening options
+    fn exec_start_pre_unit(
+        &self,
+        config_paths: &[&Path],
+        cmds: &[String],
+        opts: Vec<OptionWithValue>,
+    ) -> anyhow::Result<String> {
+        let mut unit = format!(
+            "# This file has been autogenerated by {}\n[Unit]\nDescription=Setup commands of {}\n[Service]\nType=oneshot\n",
+            env!("CARGO_PKG_NAME"),
+            self.unit_name()
+        );
+        for inherited_opt in EXEC_START_PRE_INHERITED_OPTIONS {
+            for val in Self::config_vals(inherited_opt, config_paths)? {
+                writeln!(unit, "{inherited_opt}={val}")?;
             }
         }
-        Ok(paths)
+        for cmd in cmds {
+            writeln!(unit, "ExecStart={cmd}")?;
+        }
+        for opt in opts {
+            writeln!(unit, "{opt}")?;
+        }
+        Ok(unit)
+    }
+
+    /// Name of the unit drop-in directory, shared by all instances of a template
+    pub(crate) fn drop_in_dir_name(&self) -> String {
+        format!(
+            "{}{}.service.d",
+            self.name,
+            if self.arg.is_some() { "@" } else { "" }
+        )
     }
 
     fn fragment_path(&self, name: &str, persistent: bool) -> PathBuf {
         [
             if persistent { "/etc" } else { "/run" },
             "systemd/system/",
-            &format!(
-                "{}{}.service.d",
-                self.name,
-                if self.arg.is_some() { "@" } else { "" }
-            ),
+            &self.drop_in_dir_name(),
             &format!("zz_{}-{}.conf", env!("CARGO_PKG_NAME"), name),
         ]
         .iter()
@@ -415,6 +1279,287 @@ impl Service {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_exec_cmdline() {
+        let args = ["-m", "/srv/my data/100%", "", "a\"b\\c", "$HOME", ";"];
+        let cmdline = exec_cmdline(&args);
+        assert_eq!(
+            cmdline,
+            r#"-m "/srv/my data/100%%" "" "a\"b\\c" $$HOME ";""#
+        );
+        assert_eq!(split_exec_cmdline(&cmdline), args);
+
+        let mut hardening_opts = HardeningOptions::safe();
+        hardening_opts.extra_read_paths = vec!["/srv/my data/100%".into()];
+        let hardening_cmdline = exec_cmdline(&hardening_opts.to_args());
+        let parsed = HardeningOptions::try_parse_from(
+            iter::once("shh".to_owned()).chain(split_exec_cmdline(&hardening_cmdline)),
+        )
+        .unwrap();
+        assert_eq!(parsed.extra_read_paths, hardening_opts.extra_read_paths);
+    }
+
+    #[test]
+    fn test_write_profile_fragment_quoting() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let config_path = tmp_dir.path().join("foo.service");
+        fs::write(
+            &config_path,
+            "[Service]\nRootDirectory=/srv/my root%\nExecStart=/usr/bin/foo --bar\n",
+        )
+        .unwrap();
+        let mut hardening_opts = HardeningOptions::safe();
+        hardening_opts.extra_read_paths = vec!["/srv/my data/100%".into()];
+        let strace_log_opts = StraceLogOptions {
+            path: Some("/var/log/my logs/strace%".into()),
+            max_size_mb: None,
+            keep: 3,
+            compress: false,
+            redact: false,
+        };
+
+        let mut fragment = Vec::new();
+        Service::new("foo")
+            .write_profile_fragment(
+                &mut fragment,
+                &[&config_path],
+                Path::new("/run/shh data%"),
+                "/usr/bin/shh",
+                &hardening_opts,
+                false,
+                &StraceOptions { path: None },
+                &SamplingOptions { interval_ms: None },
+                &strace_log_opts,
+                Some(2),
+                false,
+            )
+            .unwrap();
+        let fragment = String::from_utf8(fragment).unwrap();
+        assert!(fragment.contains(
+            r#"ExecStart=/usr/bin/shh run -m safe --syscall-deny-action eperm --extra-read-path "/srv/my data/100%%" --strace-log-path "/var/log/my logs/strace%%.foo.service.001" -p "/run/shh data%%/001" -- /usr/bin/foo --bar"#
+        ), "{fragment}");
+        assert!(fragment.contains(
+            r#"--root-directory "/srv/my root%%" --smoke-test --coverage --timeline --accumulate-dir "/run/shh data%%_accumulated" --activations 2 "/run/shh data%%/001""#
+        ), "{fragment}");
+
+        let fragment_path = tmp_dir.path().join("fragment.conf");
+        fs::write(&fragment_path, &fragment).unwrap();
+        let merge_args = Service::fragment_merge_args(&fragment_path).unwrap();
+        assert_eq!(
+            merge_args.hardening_opts.extra_read_paths,
+            hardening_opts.extra_read_paths
+        );
+        assert_eq!(
+            merge_args.root_directory,
+            Some(PathBuf::from("/srv/my root%"))
+        );
+        assert_eq!(
+            merge_args.accumulate_dir,
+            Some(PathBuf::from("/run/shh data%_accumulated"))
+        );
+        assert_eq!(merge_args.paths, vec![PathBuf::from("/run/shh data%/001")]);
+    }
+
+    #[test]
+    fn test_write_profile_fragment_forking() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let config_path = tmp_dir.path().join("foo.service");
+        let write_fragment = |unit: &str| {
+            fs::write(&config_path, unit).unwrap();
+            let mut fragment = Vec::new();
+            Service::new("foo")
+                .write_profile_fragment(
+                    &mut fragment,
+                    &[&config_path],
+                    Path::new("/run/shh-foo"),
+                    "/usr/bin/shh",
+                    &HardeningOptions::safe(),
+                    false,
+                    &StraceOptions { path: None },
+                    &SamplingOptions { interval_ms: None },
+                    &StraceLogOptions {
+                        path: None,
+                        max_size_mb: None,
+                        keep: 3,
+                        compress: false,
+                        redact: false,
+                    },
+                    None,
+                    false,
+                )
+                .unwrap();
+            String::from_utf8(fragment).unwrap()
+        };
+
+        let fragment = write_fragment(
+            "[Service]\nType=forking\nPIDFile=foo/foo.pid\nExecStartPre=/usr/bin/foo --check\nExecStart=/usr/bin/foo --daemon\nExecStop=/bin/kill $MAINPID\n",
+        );
+        let lines: Vec<_> = fragment.lines().collect();
+        for line in ["Type=simple", "PIDFile=", "KillMode=mixed"] {
+            assert!(lines.contains(&line), "{fragment}");
+        }
+        assert!(!lines.contains(&"KillMode=control-group"), "{fragment}");
+        let exec_start_pre = lines
+            .iter()
+            .find(|l| l.starts_with("ExecStartPre=/usr/bin/shh "))
+            .unwrap();
+        assert!(!exec_start_pre.contains("--pid-file"), "{fragment}");
+        let exec_start = lines
+            .iter()
+            .find(|l| l.starts_with("ExecStart=/usr/bin/shh "))
+            .unwrap();
+        assert!(
+            exec_start.contains(
+                " --pid-file /run/foo/foo.pid -p /run/shh-foo/002 -- /usr/bin/foo --daemon"
+            ),
+            "{fragment}"
+        );
+
+        let simple_fragment = write_fragment("[Service]\nExecStart=/usr/bin/foo\n");
+        let simple_lines: Vec<_> = simple_fragment.lines().collect();
+        assert!(
+            simple_lines.contains(&"KillMode=control-group"),
+            "{simple_fragment}"
+        );
+        assert!(!simple_lines.contains(&"Type=simple"), "{simple_fragment}");
+        assert!(!simple_fragment.contains("--pid-file"), "{simple_fragment}");
+    }
+
+    #[test]
+    fn test_risk_bounded_fragment() {
+        let fragment =
+            "[Service]\nExecStopPost=/usr/bin/shh merge-profile-data /run/shh-foo/profile.bin\n";
+        let fragment = Service::risk_bounded_fragment(fragment, None, Some(RiskLevel::Medium));
+        assert_eq!(
+            fragment,
+            "[Service]\nExecStopPost=/usr/bin/shh merge-profile-data --max-risk medium /run/shh-foo/profile.bin\n"
+        );
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let fragment_path = tmp_dir.path().join("fragment.conf");
+        fs::write(&fragment_path, &fragment).unwrap();
+        let merge_cmd = Service::config_vals("ExecStopPost", &[&fragment_path])
+            .unwrap()
+            .pop()
+            .unwrap();
+        let cl::Action::MergeProfileData { report_opts, .. } =
+            cl::Args::try_parse_from(split_exec_cmdline(&merge_cmd))
+                .unwrap()
+                .action
+        else {
+            panic!("Unexpected merge command {merge_cmd:?}");
+        };
+        assert_eq!(report_opts.min_risk, None);
+        assert_eq!(report_opts.max_risk, Some(RiskLevel::Medium));
+    }
+
+    #[test]
+    fn test_pause_profile_fragment() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let config_path = tmp_dir.path().join("foo.service");
+        fs::write(&config_path, "[Service]\nExecStart=/usr/bin/foo\n").unwrap();
+        let mut fragment = Vec::new();
+        Service::new("foo")
+            .write_profile_fragment(
+                &mut fragment,
+                &[&config_path],
+                Path::new("/run/shh-foo"),
+                "/usr/bin/shh",
+                &HardeningOptions::safe(),
+                false,
+                &StraceOptions { path: None },
+                &SamplingOptions { interval_ms: None },
+                &StraceLogOptions {
+                    path: None,
+                    max_size_mb: None,
+                    keep: 3,
+                    compress: false,
+                    redact: false,
+                },
+                None,
+                false,
+            )
+            .unwrap();
+        let fragment = String::from_utf8(fragment).unwrap();
+        let fragment_path = tmp_dir.path().join("fragment.conf");
+        fs::write(&fragment_path, &fragment).unwrap();
+        let merge_args = Service::fragment_merge_args(&fragment_path).unwrap();
+        assert_eq!(merge_args.accumulate_dir, None);
+
+        // Profile data of the service run before pausing is kept
+        fs::write(
+            &fragment_path,
+            Service::accumulating_fragment(&fragment, "shh-foo"),
+        )
+        .unwrap();
+        let paused_merge_args = Service::fragment_merge_args(&fragment_path).unwrap();
+        assert_eq!(
+            paused_merge_args.accumulate_dir,
+            Some(PathBuf::from("/run/shh-foo_accumulated"))
+        );
+        assert_eq!(paused_merge_args.paths, merge_args.paths);
+
+        // While paused, systemd does not load the fragment, so the service runs without being profiled
+        let paused_fragment_path = Service::new("foo").paused_profile_fragment_path();
+        assert_eq!(
+            paused_fragment_path,
+            Path::new("/run/systemd/system/foo.service.d/zz_shh-profile.conf.paused")
+        );
+        assert_ne!(
+            paused_fragment_path.extension(),
+            Some(std::ffi::OsStr::new("conf"))
+        );
+    }
+
+    #[test]
+    fn test_list_managed() {
+        let run_dir = tempfile::tempdir().unwrap();
+        let etc_dir = tempfile::tempdir().unwrap();
+        for (base_dir, unit, fragment) in [
+            (&run_dir, "foo", "zz_shh-profile.conf.paused"),
+            (&etc_dir, "bar@", "zz_shh-harden.conf"),
+            (&etc_dir, "baz", "override.conf"),
+        ] {
+            let dir = base_dir.path().join(format!("{unit}.service.d"));
+            fs::create_dir(&dir).unwrap();
+            File::create(dir.join(fragment)).unwrap();
+        }
+        let services =
+            Service::list_managed_in(&[run_dir.path(), etc_dir.path(), Path::new("/nonexistent")])
+                .unwrap();
+        assert_eq!(
+            services.iter().map(Service::unit_name).collect::<Vec<_>>(),
+            vec!["bar@.service", "foo.service"]
+        );
+    }
+
+    #[test]
+    fn test_service_names() {
+        assert_eq!(
+            Service::service_names(vec![
+                "getty@.service".to_owned(),
+                "getty@tty1.service".to_owned(),
+                "nginx.service".to_owned(),
+                "sshd.socket".to_owned(),
+            ])
+            .collect::<Vec<_>>(),
+            vec!["getty@tty1", "nginx"]
+        );
+    }
+
+    #[test]
+    fn test_unit_pattern() {
+        assert_eq!(
+            Service::unit_pattern("php-fpm*"),
+            Some("php-fpm*.service".to_owned())
+        );
+        assert_eq!(
+            Service::unit_pattern("myapp-worker@[0-9].service"),
+            Some("myapp-worker@[0-9].service".to_owned())
+        );
+        assert_eq!(Service::unit_pattern("nginx"), None);
+        assert_eq!(Service::unit_pattern("getty@tty1"), None);
+    }
+
     #[test]
     fn test_config_vals() {
         let _ = simple_logger::SimpleLogger::new().init();
@@ -441,6 +1586,25 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_log_namespace() {
+        let _ = simple_logger::SimpleLogger::new().init();
+
+        let mut cfg_file1 = tempfile::NamedTempFile::new().unwrap();
+        let mut cfg_file2 = tempfile::NamedTempFile::new().unwrap();
+
+        writeln!(cfg_file1, "LogNamespace=foo").unwrap();
+        assert_eq!(
+            Service::log_namespace_in(&[cfg_file1.path()]).unwrap(),
+            Some("foo".to_owned())
+        );
+        writeln!(cfg_file2, "LogNamespace=").unwrap();
+        assert_eq!(
+            Service::log_namespace_in(&[cfg_file1.path(), cfg_file2.path()]).unwrap(),
+            None
+        );
+    }
+
     #[test]
     fn test_config_val_multiline() {
         let _ = simple_logger::SimpleLogger::new().init();
@@ -462,4 +1626,38 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn test_exec_start_pre_unit() {
+        let mut cfg_file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(cfg_file, "[Service]").unwrap();
+        writeln!(cfg_file, "User=foo").unwrap();
+        writeln!(cfg_file, "ExecStartPre=/usr/bin/setup").unwrap();
+        writeln!(cfg_file, "ExecStart=/usr/bin/daemon").unwrap();
+
+        let service = Service::new("foo@bar");
+        assert_eq!(service.exec_start_pre_unit_name(), "foo-shh-pre@%i.service");
+        assert_eq!(
+            service.exec_start_pre_unit_path(),
+            Path::new("/etc/systemd/system/foo-shh-pre@.service")
+        );
+        assert_eq!(
+            service
+                .exec_start_pre_unit(
+                    &[cfg_file.path()],
+                    &["/usr/bin/setup".to_owned()],
+                    vec!["ProtectSystem=full".parse().unwrap()]
+                )
+                .unwrap(),
+            "# This file has been autogenerated by shh
+[Unit]
+Description=Setup commands of foo@bar.service
+[Service]
+Type=oneshot
+User=foo
+ExecStart=/usr/bin/setup
+ProtectSystem=full
+"
+        );
+    }
 }
@@ -0,0 +1,133 @@
+//! Systemd specifiers in generated option values, so that a fragment shared by all instances of a template unit, or
+//! used with `DynamicUser=`, stays correct
+
+use std::path::{Path, PathBuf};
+
+use itertools::Itertools as _;
+
+use crate::systemd::options::{OptionValue, OptionWithValue};
+
+/// Directories of the system service manager, with their specifier
+/// See <https://www.freedesktop.org/software/systemd/man/systemd.unit.html#Specifiers>
+const DIR_SPECIFIERS: [(&str, &str); 4] = [
+    ("/run", "%t"),
+    ("/var/lib", "%S"),
+    ("/var/cache", "%C"),
+    ("/var/log", "%L"),
+];
+
+/// Specifier values of a unit
+#[derive(Debug)]
+pub(crate) struct Specifiers {
+    /// Instance name, if unit is an instance of a template, and it does not need escaping
+    instance: Option<String>,
+    /// Home directory of the service manager (`%h`), if the service runs as the user of the manager
+    home: Option<PathBuf>,
+}
+
+impl Specifiers {
+    pub(crate) fn new(instance: Option<&str>, home: Option<PathBuf>) -> Self {
+        Self {
+            // Escaped instance names (%i) differ from the names seen in paths (%I)
+            instance: instance
+                .filter(|i| !i.is_empty() && !i.contains('\\'))
+                .map(ToOwned::to_owned),
+            home,
+        }
+    }
+
+    /// Replace directory prefix and instance name with specifiers in a path, and escape '%' characters
+    fn path(&self, path: &str) -> String {
+        let path = Path::new(path);
+        let (specifier, rel_path) = self
+            .home
+            .iter()
+            .map(|h| (h.as_path(), "%h"))
+            .chain(DIR_SPECIFIERS.iter().map(|(d, s)| (Path::new(d), *s)))
+            .filter_map(|(dir, specifier)| Some((specifier, path.strip_prefix(dir).ok()?)))
+            // Prefer the most specific directory
+            .min_by_key(|(_, rel_path)| rel_path.components().count())
+            .unwrap_or(("", path));
+        let rel_path = rel_path
+            .to_string_lossy()
+            .split('/')
+            .map(|c| {
+                if self.instance.as_deref() == Some(c) {
+                    "%i".to_owned()
+                } else {
+                    c.replace('%', "%%")
+                }
+            })
+            .join("/");
+        match (specifier, rel_path.as_str()) {
+            ("", _) => rel_path,
+            (_, "") => specifier.to_owned(),
+            _ => format!("{specifier}/{rel_path}"),
+        }
+    }
+
+    /// Replace paths in a value, possibly with several space or colon separated parts (ie. `BindPaths=`), and prefixed
+    /// with '-' or '+'
+    fn value(&self, value: &str) -> String {
+        value
+            .split(' ')
+            .map(|word| {
+                word.split(':')
+                    .map(|token| {
+                        let (prefix, path) = token
+                            .split_at(token.len() - token.trim_start_matches(['-', '+']).len());
+                        if path.starts_with('/') {
+                            format!("{prefix}{}", self.path(path))
+                        } else {
+                            token.to_owned()
+                        }
+                    })
+                    .join(":")
+            })
+            .join(" ")
+    }
+
+    /// Use specifiers in all paths of an option value
+    pub(crate) fn apply(&self, mut opt: OptionWithValue) -> OptionWithValue {
+        match &mut opt.value {
+            OptionValue::Boolean(_) => {}
+            OptionValue::String(value) => *value = self.value(value),
+            OptionValue::List { values, .. } => {
+                for value in values {
+                    *value = self.value(value);
+                }
+            }
+        }
+        opt
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply() {
+        let specifiers = Specifiers::new(Some("inst"), Some("/root".into()));
+        let apply = |opt: &str| specifiers.apply(opt.parse().unwrap()).to_string();
+        assert_eq!(apply("ProtectSystem=strict"), "ProtectSystem=strict");
+        assert_eq!(
+            apply("ReadWritePaths=/run/foo/inst -/var/log/foo/inst.log /var/cache"),
+            "ReadWritePaths=%t/foo/%i -%L/foo/inst.log %C"
+        );
+        assert_eq!(
+            apply("BindReadOnlyPaths=/root/conf:/etc/foo/100%"),
+            "BindReadOnlyPaths=%h/conf:/etc/foo/100%%"
+        );
+        // Not the home of the service manager
+        assert_eq!(
+            Specifiers::new(None, None).path("/var/lib/foo/db"),
+            "%S/foo/db"
+        );
+        assert_eq!(apply("WorkingDirectory=/inst"), "WorkingDirectory=/%i");
+        assert_eq!(
+            Specifiers::new(Some("a\\x2db"), None).path("/srv/a\\x2db"),
+            "/srv/a\\x2db"
+        );
+    }
+}
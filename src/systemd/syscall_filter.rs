@@ -0,0 +1,339 @@
+//! `SystemCallFilter=` minimization, combining denied syscall groups with allowed syscall exceptions
+
+use std::collections::{BTreeSet, HashSet};
+
+use crate::{
+    summarize::ProgramAction,
+    systemd::{
+        io_uring::{uses_io_uring, IO_URING_SYSCALLS},
+        options::{
+            DenySyscalls, ListMode, OptionDescription, OptionEffect, OptionValue,
+            OptionValueEffect, OptionWithValue, SENSITIVE_SYSCALLS, SYSCALL_CLASSES,
+        },
+        OptionJustification,
+    },
+};
+
+/// Minimum number of syscalls a group must newly deny, for each syscall exception it needs
+const MIN_DENIED_PER_EXCEPTION: usize = 4;
+
+/// Maximum number of syscall exceptions, to keep the filter readable
+const MAX_EXCEPTIONS: usize = 8;
+
+/// Syscall groups to deny, and used syscalls of those groups to allow again
+#[derive(Debug, Default, Eq, PartialEq)]
+struct SyscallFilter {
+    /// Indexes of denied groups
+    groups: Vec<usize>,
+    exceptions: BTreeSet<&'static str>,
+}
+
+/// Choose syscall groups to deny, to deny as many syscalls as possible with few exceptions for used syscalls.
+///
+/// This is a greedy weighted set cover: groups are picked by decreasing ratio of newly denied syscalls per new
+/// exception, so groups that need no new exception always come first, and groups that would need too many
+/// exceptions for what they deny are never picked.
+fn minimize(groups: &[HashSet<&'static str>], used: &HashSet<&str>) -> SyscallFilter {
+    let mut filter = SyscallFilter::default();
+    let mut denied: HashSet<&str> = HashSet::new();
+    let mut remaining: Vec<usize> = (0..groups.len()).collect();
+    loop {
+        let mut best: Option<(usize, usize, usize)> = None;
+        for (pos, &group_idx) in remaining.iter().enumerate() {
+            let group = &groups[group_idx];
+            let gain = group
+                .iter()
+                .filter(|s| !used.contains(*s) && !denied.contains(*s))
+                .count();
+            let cost = group
+                .iter()
+                .filter(|s| used.contains(*s) && !filter.exceptions.contains(*s))
+                .count();
+            let worth = if cost == 0 {
+                // Groups not used at all are always denied, even if other groups already deny their syscalls
+                (gain > 0) || !group.iter().any(|s| used.contains(s))
+            } else {
+                (gain >= MIN_DENIED_PER_EXCEPTION * cost)
+                    && (filter.exceptions.len() + cost <= MAX_EXCEPTIONS)
+            };
+            // Compare gain/cost ratios without dividing, a zero cost being an infinite ratio
+            if worth
+                && best.is_none_or(|(_, best_gain, best_cost)| {
+                    (best_cost != 0) && ((cost == 0) || (gain * best_cost > best_gain * cost))
+                })
+            {
+                best = Some((pos, gain, cost));
+            }
+        }
+        let Some((pos, ..)) = best else {
+            break;
+        };
+        let group_idx = remaining.swap_remove(pos);
+        for &syscall in &groups[group_idx] {
+            if used.contains(syscall) {
+                filter.exceptions.insert(syscall);
+            } else {
+                denied.insert(syscall);
+            }
+        }
+        filter.groups.push(group_idx);
+    }
+    // Single syscalls are redundant if a denied group also denies them
+    let redundant: Vec<usize> = filter
+        .groups
+        .iter()
+        .copied()
+        .filter(|&i| {
+            (groups[i].len() == 1)
+                && filter
+                    .groups
+                    .iter()
+                    .any(|&j| (groups[j].len() > 1) && groups[j].is_superset(&groups[i]))
+        })
+        .collect();
+    filter.groups.retain(|i| !redundant.contains(i));
+    filter.groups.sort_unstable();
+    filter
+}
+
+/// Syscalls used by the program, including those it may need later but that were not seen during profiling
+fn used_syscalls(actions: &[ProgramAction]) -> HashSet<&str> {
+    let mut used: HashSet<&str> = actions
+        .iter()
+        .filter_map(|a| match a {
+            ProgramAction::Syscalls(syscalls) => Some(syscalls),
+            _ => None,
+        })
+        .flatten()
+        .map(String::as_str)
+        .collect();
+    if uses_io_uring(actions) {
+        used.extend(IO_URING_SYSCALLS);
+    }
+    used
+}
+
+/// Minimize the syscall groups of the `SystemCallFilter=` option description, for the syscalls used by the program
+fn minimize_option<'a>(
+    opt: &'a OptionDescription,
+    actions: &[ProgramAction],
+) -> (&'a OptionValue, Vec<HashSet<&'static str>>, SyscallFilter) {
+    let [opt_value_desc] = opt.possible_values.as_slice() else {
+        unreachable!();
+    };
+    let OptionEffect::Cumulative(effects) = &opt_value_desc.desc else {
+        unreachable!();
+    };
+    let groups: Vec<_> = effects
+        .iter()
+        .map(|e| {
+            let OptionValueEffect::DenySyscalls(denied) = e else {
+                unreachable!();
+            };
+            denied.syscalls()
+        })
+        .collect();
+    let filter = minimize(&groups, &used_syscalls(actions));
+    (&opt_value_desc.value, groups, filter)
+}
+
+/// Syscalls of a group used by the program, and whether `SystemCallFilter=` denies the group
+#[derive(Debug, Eq, PartialEq)]
+pub(crate) struct GroupCoverage {
+    pub name: &'static str,
+    pub used: BTreeSet<&'static str>,
+    pub size: usize,
+    /// If denied, used syscalls are allowed again as exceptions, otherwise the group is left out because denying it
+    /// would need too many exceptions for what it denies
+    pub denied: bool,
+}
+
+/// Get the coverage of each syscall group by the syscalls used by the program, with the groups built like for the
+/// `SystemCallFilter=` option
+pub(crate) fn group_coverage(actions: &[ProgramAction]) -> Vec<GroupCoverage> {
+    let mut names: Vec<_> = SYSCALL_CLASSES.keys().copied().collect();
+    names.sort_unstable();
+    let groups: Vec<_> = names
+        .iter()
+        .map(|c| DenySyscalls::Class(c).syscalls())
+        .chain(
+            SENSITIVE_SYSCALLS
+                .iter()
+                .map(|s| DenySyscalls::Single(s).syscalls()),
+        )
+        .collect();
+    let used = used_syscalls(actions);
+    let filter = minimize(&groups, &used);
+    names
+        .into_iter()
+        .zip(&groups)
+        .enumerate()
+        .map(|(i, (name, group))| GroupCoverage {
+            name,
+            used: group
+                .iter()
+                .filter(|s| used.contains(*s))
+                .copied()
+                .collect(),
+            size: group.len(),
+            denied: filter.groups.contains(&i),
+        })
+        .collect()
+}
+
+/// Resolve `SystemCallFilter=` options from the syscalls used by the program: a deny list of syscall groups,
+/// followed if needed by an allow list of exceptions, which systemd removes from the denied syscalls
+pub(crate) fn resolve(opt: &OptionDescription, actions: &[ProgramAction]) -> Vec<OptionWithValue> {
+    let (
+        OptionValue::List {
+            values,
+            value_if_empty,
+            negation_prefix,
+            repeat_option,
+            mode,
+        },
+        _,
+        filter,
+    ) = minimize_option(opt, actions)
+    else {
+        unreachable!();
+    };
+    if filter.groups.is_empty() {
+        return vec![];
+    }
+    let mut denied_values: Vec<_> = filter.groups.iter().map(|&i| values[i].clone()).collect();
+    denied_values.sort_unstable();
+    let mut opts = vec![OptionWithValue {
+        name: opt.name.to_owned(),
+        value: OptionValue::List {
+            values: denied_values,
+            value_if_empty: value_if_empty.clone(),
+            negation_prefix: *negation_prefix,
+            repeat_option: *repeat_option,
+            mode: mode.clone(),
+        },
+    }];
+    if !filter.exceptions.is_empty() {
+        opts.push(OptionWithValue {
+            name: opt.name.to_owned(),
+            value: OptionValue::List {
+                values: filter
+                    .exceptions
+                    .into_iter()
+                    .map(ToOwned::to_owned)
+                    .collect(),
+                value_if_empty: None,
+                negation_prefix: false,
+                repeat_option: false,
+                mode: ListMode::WhiteList,
+            },
+        });
+    }
+    opts
+}
+
+/// Explain the syscall groups left out of `SystemCallFilter=`, and the syscalls allowed as exceptions
+pub(crate) fn justify(
+    opt: &OptionDescription,
+    actions: &[ProgramAction],
+) -> Vec<OptionJustification> {
+    let (OptionValue::List { values, .. }, groups, filter) = minimize_option(opt, actions) else {
+        unreachable!();
+    };
+    let first_action_using = |syscall: &str| {
+        actions.iter().find(|a| match a {
+            ProgramAction::Syscalls(syscalls) => syscalls.contains(syscall),
+            ProgramAction::IoUring => IO_URING_SYSCALLS.contains(&syscall),
+            _ => false,
+        })
+    };
+    let rejected = values
+        .iter()
+        .zip(&groups)
+        .enumerate()
+        .filter(|(i, _)| !filter.groups.contains(i))
+        .filter_map(|(_, (value, group))| {
+            let action = group.iter().find_map(|s| first_action_using(s))?;
+            Some(OptionJustification::Rejected {
+                option: opt.name,
+                value: value.clone(),
+                action: action.to_owned(),
+            })
+        });
+    let allowed = filter.exceptions.iter().filter_map(|syscall| {
+        Some(OptionJustification::Allowed {
+            option: opt.name,
+            value: (*syscall).to_owned(),
+            action: first_action_using(syscall)?.to_owned(),
+        })
+    });
+    rejected.chain(allowed).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_minimize() {
+        let groups = vec![
+            HashSet::from(["a1", "a2", "a3"]),
+            HashSet::from(["b1", "b2", "b3", "b4", "b5", "b6", "b7", "b8", "used1"]),
+            HashSet::from(["c1", "c2", "used2"]),
+            HashSet::from(["d1", "d2", "d3", "d4", "used1"]),
+            HashSet::from(["used2"]),
+        ];
+        let used = HashSet::from(["used1", "used2", "other"]);
+        assert_eq!(
+            minimize(&groups, &used),
+            SyscallFilter {
+                // The group with too few syscalls per exception is left out, and the one needing an exception
+                // already allowed for a previous group is added
+                groups: vec![0, 1, 3],
+                exceptions: BTreeSet::from(["used1"]),
+            }
+        );
+    }
+
+    #[test]
+    fn test_group_coverage() {
+        let actions = vec![ProgramAction::Syscalls(
+            ["chown".to_owned(), "fchown".to_owned(), "read".to_owned()].into(),
+        )];
+        let coverage = group_coverage(&actions);
+        assert_eq!(coverage.len(), SYSCALL_CLASSES.len());
+        assert_eq!(
+            coverage.iter().find(|g| g.name == "chown"),
+            Some(&GroupCoverage {
+                name: "chown",
+                used: BTreeSet::from(["chown", "fchown"]),
+                size: 7,
+                // Denying the 5 other syscalls is not worth 2 exceptions
+                denied: false,
+            })
+        );
+        assert!(coverage
+            .iter()
+            .find(|g| g.name == "aio")
+            .is_some_and(|g| g.used.is_empty() && g.denied));
+    }
+
+    #[test]
+    fn test_minimize_single_syscalls() {
+        let groups = vec![
+            HashSet::from(["a1", "a2", "single1"]),
+            HashSet::from(["single1"]),
+            HashSet::from(["c1", "used", "single2"]),
+            HashSet::from(["single2"]),
+        ];
+        let used = HashSet::from(["used"]);
+        assert_eq!(
+            minimize(&groups, &used),
+            SyscallFilter {
+                // Single syscalls are only denied if their group is not
+                groups: vec![0, 3],
+                exceptions: BTreeSet::new(),
+            }
+        );
+    }
+}
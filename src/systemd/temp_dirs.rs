@@ -0,0 +1,163 @@
+//! Temporary directories set with `$TMPDIR`, outside of the ones made private by `PrivateTmp=`
+
+use std::{
+    collections::HashSet,
+    env,
+    os::unix::ffi::OsStrExt as _,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    summarize::ProgramAction,
+    systemd::options::{ListMode, OptionValue, OptionWithValue},
+};
+
+/// Directories made private by `PrivateTmp=`, or handled by `ProtectHome=`
+const HANDLED_DIRS: [&str; 5] = ["/tmp", "/var/tmp", "/home", "/root", "/run/user"];
+
+/// Get the temporary directory set in the environment of the profiled program, if it is not handled by other options
+pub(crate) fn env_temp_dir() -> Option<ProgramAction> {
+    let dir = PathBuf::from(env::var_os("TMPDIR")?);
+    (dir.is_absolute() && !HANDLED_DIRS.iter().any(|d| dir.starts_with(d)))
+        .then_some(ProgramAction::TempDirectory(dir))
+}
+
+/// Path accessed by an action, and whether it is a write
+fn action_path(action: &ProgramAction) -> Option<(&Path, bool)> {
+    match action {
+        ProgramAction::Read(path) | ProgramAction::Exec(path) | ProgramAction::SetuidExec(path) => {
+            Some((path, false))
+        }
+        ProgramAction::Write(path) | ProgramAction::Create(path) => Some((path, true)),
+        _ => None,
+    }
+}
+
+/// Whether all files read in a directory were created by the program before, ie. the directory can be empty
+fn only_created_files_read(actions: &[ProgramAction], dir: &Path) -> bool {
+    let mut created: HashSet<&Path> = HashSet::new();
+    for action in actions {
+        match action {
+            ProgramAction::Create(path) if path.starts_with(dir) => {
+                created.insert(path);
+            }
+            ProgramAction::Read(path)
+            | ProgramAction::Exec(path)
+            | ProgramAction::SetuidExec(path)
+                if path.starts_with(dir)
+                    && path != dir
+                    && !path.ancestors().any(|a| created.contains(a)) =>
+            {
+                return false;
+            }
+            _ => {}
+        }
+    }
+    true
+}
+
+/// Make the temporary directories written to writable with their own option, and remove the actions in them, so
+/// that they do not prevent `ProtectSystem=strict`.
+/// A directory only holding files created by the program gets an empty private file system, otherwise it is kept
+/// shared.
+pub(crate) fn temp_dir_options(actions: &mut Vec<ProgramAction>) -> Vec<OptionWithValue> {
+    let mut dirs: Vec<PathBuf> = actions
+        .iter()
+        .filter_map(|a| match a {
+            ProgramAction::TempDirectory(dir) => Some(dir.to_owned()),
+            _ => None,
+        })
+        .collect();
+    dirs.sort_unstable();
+    dirs.dedup();
+
+    let mut opts = Vec::new();
+    for dir in dirs {
+        // Path specifiers are space separated
+        if dir.as_os_str().as_bytes().contains(&b' ')
+            || !actions
+                .iter()
+                .filter_map(action_path)
+                .any(|(p, write)| write && p.starts_with(&dir))
+        {
+            continue;
+        }
+        let (name, value, private) = if only_created_files_read(actions, &dir) {
+            (
+                "TemporaryFileSystem",
+                // Writable by the service user
+                format!("{}:mode=1777", dir.to_string_lossy()),
+                true,
+            )
+        } else {
+            ("ReadWritePaths", dir.to_string_lossy().into_owned(), false)
+        };
+        // Reads of pre-existing files in a shared directory are still resolved like others
+        actions.retain(|a| {
+            action_path(a).is_none_or(|(p, write)| !(p.starts_with(&dir) && (write || private)))
+        });
+        opts.push(OptionWithValue {
+            name: name.to_owned(),
+            value: OptionValue::List {
+                values: vec![value],
+                value_if_empty: None,
+                negation_prefix: false,
+                repeat_option: false,
+                mode: ListMode::WhiteList,
+            },
+        });
+    }
+    opts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_temp_dir_options() {
+        let mut actions = vec![
+            ProgramAction::TempDirectory("/srv/tmp".into()),
+            ProgramAction::Create("/srv/tmp/a".into()),
+            ProgramAction::Read("/srv/tmp/a".into()),
+            ProgramAction::Write("/srv/tmp/a".into()),
+            ProgramAction::Read("/srv/data".into()),
+        ];
+        assert_eq!(
+            temp_dir_options(&mut actions)
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec!["TemporaryFileSystem=/srv/tmp:mode=1777"]
+        );
+        assert_eq!(
+            actions,
+            vec![
+                ProgramAction::TempDirectory("/srv/tmp".into()),
+                ProgramAction::Read("/srv/data".into()),
+            ]
+        );
+
+        // Pre-existing files are read, the directory is kept
+        let mut shared_actions = vec![
+            ProgramAction::TempDirectory("/srv/tmp".into()),
+            ProgramAction::Read("/srv/tmp/cache".into()),
+            ProgramAction::Write("/srv/tmp/cache".into()),
+        ];
+        assert_eq!(
+            temp_dir_options(&mut shared_actions)
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec!["ReadWritePaths=/srv/tmp"]
+        );
+        assert_eq!(shared_actions.len(), 2);
+
+        // Not written to
+        let mut unused_actions = vec![
+            ProgramAction::TempDirectory("/srv/tmp".into()),
+            ProgramAction::Read("/srv/tmp/cache".into()),
+        ];
+        assert!(temp_dir_options(&mut unused_actions).is_empty());
+    }
+}
@@ -0,0 +1,175 @@
+//! Transient systemd unit profiling
+
+use std::{
+    env, fs,
+    path::PathBuf,
+    process::{Command, Stdio},
+};
+
+use anyhow::Context as _;
+use rand::Rng;
+
+use crate::{
+    cl::{
+        HardeningOptions, RunIdentityOptions, SamplingOptions, StraceOptions, Tracer,
+        TransientUnitOptions,
+    },
+    error::ErrorKind,
+    profile::ProfileData,
+    systemd::dbus::Manager,
+};
+
+/// Run command wrapped by the profiling code in a transient service unit (or scope), wait for it to exit,
+/// and get its profile data
+pub(crate) fn profile_transient_unit(
+    command: &[String],
+    hardening_opts: &HardeningOptions,
+    identity_opts: &RunIdentityOptions,
+    tracer: Tracer,
+    strace_opts: &StraceOptions,
+    sampling_opts: &SamplingOptions,
+    unit_opts: &TransientUnitOptions,
+) -> anyhow::Result<ProfileData> {
+    let shh_bin = env::current_exe()?
+        .to_str()
+        .ok_or_else(|| anyhow::anyhow!("Unable to decode current executable path"))?
+        .to_owned();
+
+    let mut unit_properties = Vec::new();
+    let mut cmd = Command::new("systemd-run");
+    cmd.arg("--quiet");
+    if unit_opts.user {
+        // Fail early if there is no user service manager to talk to, ie. without a login session or lingering
+        let version = Manager::connect_user()
+            .and_then(|m| m.version())
+            .context("Unable to reach the user service manager")
+            .context(ErrorKind::Systemd)?;
+        log::debug!("User service manager version: {version}");
+        cmd.arg("--user");
+    }
+    // Scopes run the command as a child of systemd-run, so the profile data can be written in a temporary directory.
+    // For services, let systemd create the profile data dir, so that it has the right owner if the unit runs as
+    // another user, and keep it after the unit stops so we can read it
+    let tmp_dir = unit_opts.scope.then(tempfile::tempdir).transpose()?;
+    let profile_data_dir = if let Some(tmp_dir) = &tmp_dir {
+        cmd.arg("--scope");
+        tmp_dir.path().to_owned()
+    } else {
+        cmd.args(["--wait", "--collect", "--pipe"]);
+        let mut rng = rand::thread_rng();
+        let profile_data_dir_name = format!(
+            "{}-profile-data_{:08x}",
+            env!("CARGO_PKG_NAME"),
+            rng.gen::<u32>()
+        );
+        unit_properties.push(format!("RuntimeDirectory={profile_data_dir_name}"));
+        unit_properties.push("RuntimeDirectoryPreserve=yes".to_owned());
+        // Runtime directories of the user service manager are relative to $XDG_RUNTIME_DIR
+        let runtime_dir = if unit_opts.user {
+            env::var_os("XDG_RUNTIME_DIR")
+                .map(PathBuf::from)
+                .ok_or_else(|| anyhow::anyhow!("XDG_RUNTIME_DIR is not set"))?
+        } else {
+            PathBuf::from("/run")
+        };
+        runtime_dir.join(&profile_data_dir_name)
+    };
+    let profile_data_path = profile_data_dir.join("001");
+
+    unit_properties.extend(identity_properties(identity_opts, unit_opts.scope));
+    unit_properties.extend(unit_opts.properties.iter().cloned());
+
+    for property in &unit_properties {
+        cmd.args(["-p", property]);
+    }
+    #[expect(clippy::unwrap_used)]
+    cmd.arg("--")
+        .arg(shh_bin)
+        .arg("run")
+        .args(scope_identity_args(identity_opts, unit_opts.scope))
+        .args(hardening_opts.to_args())
+        .arg(format!("--tracer={tracer}"))
+        .args(strace_opts.to_args())
+        .args(sampling_opts.to_args())
+        .args(["-p", profile_data_path.to_str().unwrap(), "--"])
+        .args(command)
+        .stdin(Stdio::inherit());
+    log::info!("Starting transient unit with {cmd:?}");
+    let status = cmd.status()?;
+
+    let profile_data = ProfileData::read(&profile_data_path);
+    if tmp_dir.is_none() {
+        let _ = fs::remove_dir_all(&profile_data_dir);
+    }
+    if !status.success() {
+        log::warn!("Transient unit exited with {status}");
+    }
+    if unit_opts.user {
+        log::info!("Options can be applied to the application by running it as a user service, ie. with 'systemd-run --user -p OPTION=VALUE ...', or in a drop-in of its user service unit");
+    }
+    profile_data
+}
+
+/// Unit properties to run the command as another user, scopes do not support them since they run as a child of
+/// `systemd-run`
+fn identity_properties(identity_opts: &RunIdentityOptions, scope: bool) -> Vec<String> {
+    if scope {
+        return vec![];
+    }
+    identity_opts
+        .user
+        .iter()
+        .map(|u| format!("User={u}"))
+        .chain(identity_opts.group.iter().map(|g| format!("Group={g}")))
+        .collect()
+}
+
+/// Profiling command arguments to run the command as another user in a scope, where the tracer switches identity
+fn scope_identity_args(identity_opts: &RunIdentityOptions, scope: bool) -> Vec<String> {
+    if !scope {
+        return vec![];
+    }
+    identity_opts
+        .user
+        .iter()
+        .flat_map(|u| ["--user".to_owned(), u.clone()])
+        .chain(
+            identity_opts
+                .group
+                .iter()
+                .flat_map(|g| ["--group".to_owned(), g.clone()]),
+        )
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identity() {
+        let identity_opts = RunIdentityOptions {
+            user: Some("app".to_owned()),
+            group: Some("users".to_owned()),
+        };
+        assert_eq!(
+            identity_properties(&identity_opts, false),
+            vec!["User=app", "Group=users"]
+        );
+        assert!(scope_identity_args(&identity_opts, false).is_empty());
+
+        // systemd-run --scope rejects User= and Group=
+        assert!(identity_properties(&identity_opts, true).is_empty());
+        assert_eq!(
+            scope_identity_args(&identity_opts, true),
+            vec!["--user", "app", "--group", "users"]
+        );
+
+        let no_identity_opts = RunIdentityOptions {
+            user: None,
+            group: None,
+        };
+        assert!(identity_properties(&no_identity_opts, false).is_empty());
+        assert!(scope_identity_args(&no_identity_opts, true).is_empty());
+    }
+}
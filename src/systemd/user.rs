@@ -0,0 +1,234 @@
+//! Feasibility of running as a dedicated unprivileged user (`User=`, `DynamicUser=`), instead of root
+
+use std::collections::BTreeSet;
+
+use itertools::Itertools as _;
+use nix::unistd::Uid;
+
+use crate::{
+    summarize::{
+        CountableSetSpecifier, NetworkActivity, NetworkActivityKind, ProgramAction, SetSpecifier,
+    },
+    systemd::options::{DenySyscalls, OptionDescription, SocketFamily, SocketProtocol},
+};
+
+/// Privileged syscalls that are also used without privileges
+const UNPRIVILEGED_SYSCALLS: [&str; 3] = [
+    // Reading the clock adjustment parameters is allowed
+    "adjtimex",
+    "clock_adjtime",
+    // Dropping capabilities is allowed
+    "capset",
+];
+
+/// First port that can be bound without `CAP_NET_BIND_SERVICE`, unless changed with the
+/// `net.ipv4.ip_unprivileged_port_start` sysctl
+const UNPRIVILEGED_PORT_START: u16 = 1024;
+
+/// Options that can only be partly evaluated when the program is profiled without root privileges, with what
+/// profiling misses then
+const UNPRIVILEGED_PROFILING_LIMITS: [(&str, &str); 6] = [
+    (
+        "CapabilityBoundingSet",
+        "privileged operations fail, so the capabilities they need are not seen",
+    ),
+    (
+        "SystemCallFilter",
+        "programs may skip privileged syscalls when not running as root",
+    ),
+    (
+        "ProtectSystem",
+        "writes to paths only root can write fail, and may stop the program before later accesses",
+    ),
+    (
+        "ProtectHome",
+        "the home directory of the profiling user is accessed, instead of the one of the service user",
+    ),
+    (
+        "PrivateDevices",
+        "devices only root can open are not used",
+    ),
+    (
+        "SocketBindDeny",
+        "ports below 1024 can not be bound",
+    ),
+];
+
+/// Get supported options that can not be fully evaluated when profiling without root privileges, with why
+pub(crate) fn unprivileged_profiling_limits(sd_opts: &[OptionDescription]) -> Vec<String> {
+    UNPRIVILEGED_PROFILING_LIMITS
+        .iter()
+        .filter(|(name, _)| sd_opts.iter().any(|o| o.name == *name))
+        .map(|(name, limit)| format!("{name}: {limit}"))
+        .collect()
+}
+
+/// What needs root privileges in an action, if anything
+fn privileged_need(action: &ProgramAction) -> Option<String> {
+    match action {
+        ProgramAction::MknodSpecial => Some("special files are created (CAP_MKNOD)".to_owned()),
+        ProgramAction::SetAlarm => {
+            Some("privileged timer alarms are set (CAP_WAKE_ALARM)".to_owned())
+        }
+        ProgramAction::Wakeup => Some("suspend is inhibited (CAP_BLOCK_SUSPEND)".to_owned()),
+        ProgramAction::SetRealtimeScheduler => Some(
+            "a real time scheduling policy is set (CAP_SYS_NICE, unless allowed with LimitRTPRIO=)"
+                .to_owned(),
+        ),
+        ProgramAction::SetNice(nice) if *nice < 0 => Some(format!(
+            "nice value {nice} is set (CAP_SYS_NICE, unless allowed with LimitNICE=)"
+        )),
+        ProgramAction::ChangeOwner { path, .. } => Some(format!(
+            "owner of {} is changed (CAP_CHOWN)",
+            path.display()
+        )),
+        ProgramAction::NetworkActivity(NetworkActivity {
+            af: SetSpecifier::One(SocketFamily::Ipv4 | SocketFamily::Ipv6),
+            kind: SetSpecifier::One(NetworkActivityKind::Bind),
+            local_port: CountableSetSpecifier::One(port),
+            ..
+        }) if port.get() < UNPRIVILEGED_PORT_START => Some(format!(
+            "port {port} is bound (CAP_NET_BIND_SERVICE, or bind it with a socket unit)"
+        )),
+        ProgramAction::NetworkActivity(NetworkActivity {
+            af: SetSpecifier::One(af),
+            proto: SetSpecifier::One(proto),
+            kind: SetSpecifier::One(NetworkActivityKind::SocketCreation),
+            ..
+        }) if matches!(af, SocketFamily::Other(af) if af == "AF_PACKET")
+            || (*proto == SocketProtocol::Raw
+                && !matches!(af, SocketFamily::Other(af) if af == "AF_NETLINK")) =>
+        {
+            Some("raw sockets are created (CAP_NET_RAW)".to_owned())
+        }
+        ProgramAction::Capability(cap) => Some(format!("capability {cap} is checked")),
+        ProgramAction::CapabilityUse(cap) => Some(format!("capability {cap} is kept with capset")),
+        ProgramAction::Syscalls(syscalls) => {
+            let privileged = DenySyscalls::Class("privileged").syscalls();
+            // Owner and credential changes are checked from their actions
+            let covered: Vec<_> = DenySyscalls::Class("chown")
+                .syscalls()
+                .into_iter()
+                .chain(DenySyscalls::Class("setuid").syscalls())
+                .collect();
+            let used: Vec<_> = syscalls
+                .iter()
+                .map(String::as_str)
+                .filter(|s| {
+                    privileged.contains(s)
+                        && !UNPRIVILEGED_SYSCALLS.contains(s)
+                        && !covered.contains(s)
+                        && !s.starts_with("setfs")
+                })
+                .collect();
+            (!used.is_empty()).then(|| {
+                format!(
+                    "privileged syscalls are called: {}",
+                    used.into_iter().join(", ")
+                )
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Check if the program could run as a dedicated unprivileged user, or get what prevents it
+pub(crate) fn dedicated_user(actions: &[ProgramAction]) -> Result<(), Vec<String>> {
+    let mut blockers: BTreeSet<String> = actions.iter().filter_map(privileged_need).collect();
+
+    if actions
+        .iter()
+        .any(|a| matches!(a, ProgramAction::SetCredentials { .. }))
+    {
+        let uids: Vec<_> = actions
+            .iter()
+            .filter_map(|a| match a {
+                ProgramAction::SetCredentials { uid: Some(uid), .. } => Some(*uid),
+                _ => None,
+            })
+            .unique()
+            .collect();
+        let blocker = match uids.as_slice() {
+            // The program drops privileges itself
+            [uid] if *uid != 0 => {
+                let user = nix::unistd::User::from_uid(Uid::from_raw(*uid))
+                    .ok()
+                    .flatten()
+                    .map_or_else(|| uid.to_string(), |u| u.name);
+                format!(
+                    "credentials are changed to uid {uid} (CAP_SETUID, CAP_SETGID), User={user} lets systemd do it instead, if the program supports not switching user itself"
+                )
+            }
+            _ => "credentials are changed (CAP_SETUID, CAP_SETGID)".to_owned(),
+        };
+        blockers.insert(blocker);
+    }
+
+    if blockers.is_empty() {
+        Ok(())
+    } else {
+        Err(blockers.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dedicated_user() {
+        let actions = vec![
+            ProgramAction::Read("/etc/app.conf".into()),
+            ProgramAction::Syscalls(["read".to_owned(), "capset".to_owned()].into()),
+        ];
+        assert_eq!(dedicated_user(&actions), Ok(()));
+
+        let privileged_actions = vec![
+            ProgramAction::Syscalls(
+                [
+                    "setresuid".to_owned(),
+                    "chroot".to_owned(),
+                    "chown".to_owned(),
+                ]
+                .into(),
+            ),
+            ProgramAction::ChangeOwner {
+                path: "/run/app".into(),
+                uid: Some(4_000_000),
+                gid: None,
+            },
+            ProgramAction::SetCredentials {
+                uid: None,
+                gid: Some(4_000_000),
+            },
+            ProgramAction::SetCredentials {
+                uid: Some(4_000_000),
+                gid: None,
+            },
+        ];
+        assert_eq!(
+            dedicated_user(&privileged_actions),
+            Err(vec![
+                "credentials are changed to uid 4000000 (CAP_SETUID, CAP_SETGID), User=4000000 lets systemd do it instead, if the program supports not switching user itself".to_owned(),
+                "owner of /run/app is changed (CAP_CHOWN)".to_owned(),
+                "privileged syscalls are called: chroot".to_owned(),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_unprivileged_profiling_limits() {
+        let sd_opts: Vec<_> = ["ProtectHome", "LockPersonality"]
+            .into_iter()
+            .map(|name| OptionDescription {
+                name,
+                possible_values: vec![],
+                updater: None,
+            })
+            .collect();
+        assert_eq!(
+            unprivileged_profiling_limits(&sd_opts),
+            vec!["ProtectHome: the home directory of the profiling user is accessed, instead of the one of the service user"]
+        );
+    }
+}
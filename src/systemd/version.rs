@@ -1,8 +1,9 @@
 //! Systemd & kernel version
 
-use std::{fmt, io::BufRead, process::Command, str};
+use std::{fmt, io::BufRead, process::Command, str, str::FromStr};
 
-#[derive(Debug, Ord, PartialOrd, Eq, PartialEq)]
+#[derive(Debug, Clone, Ord, PartialOrd, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(try_from = "String", into = "String")]
 pub(crate) struct SystemdVersion {
     pub major: u16,
     pub minor: u16,
@@ -13,6 +14,11 @@ impl SystemdVersion {
         Self { major, minor }
     }
 
+    /// Version assumed when there is no systemd, with support for all options
+    pub(crate) fn latest() -> Self {
+        Self::new(u16::MAX, u16::MAX)
+    }
+
     pub(crate) fn local_system() -> anyhow::Result<Self> {
         let output = Command::new("systemctl").arg("--version").output()?;
         if !output.status.success() {
@@ -34,15 +40,25 @@ impl SystemdVersion {
             .split_once(')')
             .ok_or_else(|| anyhow::anyhow!("Unable to parse systemd version"))?
             .0;
-        let major_str = version
+        version.parse()
+    }
+}
+
+impl FromStr for SystemdVersion {
+    type Err = anyhow::Error;
+
+    /// Parse version like '254', '254.1' or '255~rc3-2'
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let major_str = s
             .chars()
             .take_while(char::is_ascii_digit)
             .collect::<String>();
-        let major = major_str.parse()?;
-        let minor = if let Some('.') = version.chars().nth(major_str.len()) {
+        let major = major_str
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid systemd version {s:?}"))?;
+        let minor = if let Some('.') = s.chars().nth(major_str.len()) {
             // Actual minor version
-            version
-                .chars()
+            s.chars()
                 .skip(major_str.len() + 1)
                 .take_while(char::is_ascii_digit)
                 .collect::<String>()
@@ -61,7 +77,22 @@ impl fmt::Display for SystemdVersion {
     }
 }
 
-#[derive(Ord, PartialOrd, Eq, PartialEq)]
+impl TryFrom<String> for SystemdVersion {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<SystemdVersion> for String {
+    fn from(v: SystemdVersion) -> Self {
+        v.to_string()
+    }
+}
+
+#[derive(Debug, Clone, Ord, PartialOrd, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(try_from = "String", into = "String")]
 pub(crate) struct KernelVersion {
     major: u16,
     minor: u16,
@@ -82,7 +113,16 @@ impl KernelVersion {
         if !output.status.success() {
             anyhow::bail!("uname invocation failed with code {:?}", output.status);
         }
-        let tokens: Vec<_> = str::from_utf8(&output.stdout)?.splitn(3, '.').collect();
+        str::from_utf8(&output.stdout)?.trim().parse()
+    }
+}
+
+impl FromStr for KernelVersion {
+    type Err = anyhow::Error;
+
+    /// Parse release like '6.4.12-arch1-1'
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tokens: Vec<_> = s.splitn(3, '.').collect();
         let release = tokens
             .get(2)
             .ok_or_else(|| anyhow::anyhow!("Unable to get kernel release version"))?
@@ -109,9 +149,23 @@ impl fmt::Display for KernelVersion {
     }
 }
 
+impl TryFrom<String> for KernelVersion {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<KernelVersion> for String {
+    fn from(v: KernelVersion) -> Self {
+        v.to_string()
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::systemd::SystemdVersion;
+    use crate::systemd::{KernelVersion, SystemdVersion};
 
     #[test]
     fn test_parse_version() {
@@ -123,5 +177,19 @@ mod tests {
             SystemdVersion::parse_version_line("systemd 255 (255~rc3-2)").unwrap(),
             SystemdVersion::new(255, 0)
         );
+        assert_eq!(
+            "250".parse::<SystemdVersion>().unwrap(),
+            SystemdVersion::new(250, 0)
+        );
+        assert!("v250".parse::<SystemdVersion>().is_err());
+    }
+
+    #[test]
+    fn test_parse_kernel_version() {
+        assert_eq!(
+            "6.4.12-arch1-1".parse::<KernelVersion>().unwrap(),
+            KernelVersion::new(6, 4, 12)
+        );
+        assert!("6.4".parse::<KernelVersion>().is_err());
     }
 }
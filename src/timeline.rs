@@ -0,0 +1,169 @@
+//! Timeline of when each permission was first needed while profiling, to tell startup needs from steady state ones
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write as _,
+    time::Duration,
+};
+
+use crate::{
+    summarize::{ProgramAction, Summarizer},
+    watch::syscall_groups,
+};
+
+/// Permissions first needed after this time since the start of profiling are steady state needs
+const STARTUP_DURATION: Duration = Duration::from_secs(10);
+
+/// Time since the start of profiling a permission was first needed, and its description
+pub(crate) type TimelineEntry = (Duration, String);
+
+/// Get descriptions of actions and syscall groups, with the time they were first needed, in chronological order
+fn first_needed<'a>(
+    actions: impl Iterator<Item = (f64, &'a ProgramAction)>,
+    syscalls: impl Iterator<Item = (f64, &'a str)>,
+) -> Vec<TimelineEntry> {
+    let mut seen = HashSet::new();
+    let mut entries = Vec::new();
+    for (time, action) in actions {
+        if matches!(
+            action,
+            ProgramAction::OpenFiles(_) | ProgramAction::Tasks(_) | ProgramAction::Syscalls(_)
+        ) {
+            continue;
+        }
+        let desc = action.to_string();
+        if seen.insert(desc.clone()) {
+            entries.push((time, desc));
+        }
+    }
+
+    let syscall_groups = syscall_groups();
+    let mut group_times: HashMap<&str, f64> = HashMap::new();
+    for (time, syscall) in syscalls {
+        for group in syscall_groups.get(syscall).into_iter().flatten() {
+            group_times
+                .entry(group)
+                .and_modify(|t| *t = t.min(time))
+                .or_insert(time);
+        }
+    }
+    let mut group_entries: Vec<_> = group_times
+        .into_iter()
+        .map(|(group, time)| (time, format!("syscall group @{group}")))
+        .collect();
+    group_entries.sort_unstable_by(|a, b| a.1.cmp(&b.1));
+    entries.extend(group_entries);
+
+    // Stable sort keeps the order actions were added in, for identical times
+    entries.sort_by(|a, b| a.0.total_cmp(&b.0));
+    entries
+        .into_iter()
+        .map(|(time, desc)| (Duration::try_from_secs_f64(time).unwrap_or_default(), desc))
+        .collect()
+}
+
+/// Get when each permission was first needed so far
+pub(crate) fn entries(summarizer: &Summarizer) -> Vec<TimelineEntry> {
+    first_needed(summarizer.timed_actions(), summarizer.timed_syscalls())
+}
+
+/// Merge timelines of several profiled runs, keeping the earliest time each permission was first needed
+pub(crate) fn merge(timelines: &[&[TimelineEntry]]) -> Vec<TimelineEntry> {
+    let mut first_times: HashMap<&str, Duration> = HashMap::new();
+    let mut descs = Vec::new();
+    for (time, desc) in timelines.iter().copied().flatten() {
+        first_times
+            .entry(desc.as_str())
+            .and_modify(|t| *t = (*t).min(*time))
+            .or_insert_with(|| {
+                descs.push(desc.as_str());
+                *time
+            });
+    }
+    let mut entries: Vec<_> = descs
+        .into_iter()
+        .map(|d| (first_times[d], d.to_owned()))
+        .collect();
+    // Stable sort keeps the order permissions were seen in, for identical times
+    entries.sort_by_key(|e| e.0);
+    entries
+}
+
+/// Format the timeline of permissions, split between startup and steady state
+pub(crate) fn format(entries: &[TimelineEntry]) -> String {
+    let (startup, steady_state) = entries.split_at(
+        entries
+            .iter()
+            .position(|(t, _)| *t > STARTUP_DURATION)
+            .unwrap_or(entries.len()),
+    );
+    let mut timeline = String::new();
+    for (title, section) in [
+        (
+            format!("Startup (first {}s)", STARTUP_DURATION.as_secs()),
+            startup,
+        ),
+        ("Steady state".to_owned(), steady_state),
+    ] {
+        let _ = writeln!(timeline, "{title}:");
+        if section.is_empty() {
+            let _ = writeln!(timeline, "  (none)");
+        }
+        for (time, desc) in section {
+            let _ = writeln!(timeline, "  [{:8.2}s] {desc}", time.as_secs_f64());
+        }
+    }
+    timeline
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timeline() {
+        let actions = [
+            (0.1, ProgramAction::Read("/etc/foo.conf".into())),
+            (0.2, ProgramAction::Tasks(4)),
+            (5.0, ProgramAction::Read("/etc/foo.conf".into())),
+            (60.0, ProgramAction::Write("/var/lib/foo/db".into())),
+        ];
+        let syscalls = [(0.1, "openat"), (30.5, "mount"), (31.0, "umount2")];
+        let entries = first_needed(
+            actions.iter().map(|(t, a)| (*t, a)),
+            syscalls.iter().copied(),
+        );
+        assert_eq!(
+            format(&entries),
+            "Startup (first 10s):
+  [    0.10s] read /etc/foo.conf
+  [    0.10s] syscall group @file-system
+Steady state:
+  [   30.50s] syscall group @mount
+  [   60.00s] write /var/lib/foo/db
+"
+        );
+    }
+
+    #[test]
+    fn test_merge() {
+        let secs = Duration::from_secs;
+        let first = [
+            (secs(1), "read /etc/foo.conf".to_owned()),
+            (secs(40), "syscall group @mount".to_owned()),
+        ];
+        let second = [
+            (secs(2), "read /etc/foo.conf".to_owned()),
+            (secs(5), "syscall group @mount".to_owned()),
+            (secs(20), "write /var/lib/foo/db".to_owned()),
+        ];
+        assert_eq!(
+            merge(&[&first, &second]),
+            vec![
+                (secs(1), "read /etc/foo.conf".to_owned()),
+                (secs(5), "syscall group @mount".to_owned()),
+                (secs(20), "write /var/lib/foo/db".to_owned()),
+            ]
+        );
+    }
+}
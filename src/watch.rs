@@ -0,0 +1,158 @@
+//! Live view of actions discovered while profiling
+
+use std::{
+    collections::{HashMap, HashSet},
+    time::Instant,
+};
+
+use crate::{
+    summarize::{ProgramAction, Summarizer},
+    systemd::{DenySyscalls, SYSCALL_CLASSES},
+};
+
+/// Track summarizer progress to report actions never seen before
+pub(crate) struct Watcher {
+    start: Instant,
+    /// Number of summarizer actions already checked
+    action_count: usize,
+    /// Number of distinct syscalls already checked
+    syscall_count: usize,
+    seen: HashSet<String>,
+    /// Syscall groups each syscall belongs to
+    syscall_groups: HashMap<&'static str, Vec<&'static str>>,
+    seen_groups: HashSet<&'static str>,
+}
+
+/// Get the syscall groups each syscall belongs to
+pub(crate) fn syscall_groups() -> HashMap<&'static str, Vec<&'static str>> {
+    let mut syscall_groups: HashMap<_, Vec<_>> = HashMap::new();
+    for &class in SYSCALL_CLASSES.keys() {
+        for syscall in DenySyscalls::Class(class).syscalls() {
+            syscall_groups.entry(syscall).or_default().push(class);
+        }
+    }
+    syscall_groups
+}
+
+impl Watcher {
+    pub(crate) fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            action_count: 0,
+            syscall_count: 0,
+            seen: HashSet::new(),
+            syscall_groups: syscall_groups(),
+            seen_groups: HashSet::new(),
+        }
+    }
+
+    /// Get descriptions of actions and syscall groups discovered since the last call
+    pub(crate) fn discovered(&mut self, summarizer: &Summarizer) -> Vec<String> {
+        let mut discovered = Vec::new();
+
+        let actions = summarizer.raw_actions();
+        for action in &actions[self.action_count..] {
+            if matches!(
+                action,
+                ProgramAction::OpenFiles(_) | ProgramAction::Tasks(_) | ProgramAction::Syscalls(_)
+            ) {
+                continue;
+            }
+            let desc = action.to_string();
+            if self.seen.insert(desc.clone()) {
+                discovered.push(desc);
+            }
+        }
+        self.action_count = actions.len();
+
+        // Syscalls are only added, so only look for new groups when there are new ones
+        let syscall_count = summarizer.syscall_count();
+        if syscall_count != self.syscall_count {
+            self.syscall_count = syscall_count;
+            let mut new_groups: Vec<_> = summarizer
+                .syscall_names()
+                .filter_map(|s| self.syscall_groups.get(s))
+                .flatten()
+                .copied()
+                .filter(|g| !self.seen_groups.contains(g))
+                .collect();
+            new_groups.sort_unstable();
+            new_groups.dedup();
+            for group in new_groups {
+                self.seen_groups.insert(group);
+                discovered.push(format!("syscall group @{group}"));
+            }
+        }
+
+        discovered
+    }
+
+    /// Print actions discovered since the last call, with the elapsed time, to see when coverage plateaus
+    pub(crate) fn print_discovered(&mut self, summarizer: &Summarizer) {
+        let discovered = self.discovered(summarizer);
+        if discovered.is_empty() {
+            return;
+        }
+        let elapsed = self.start.elapsed().as_secs_f64();
+        for desc in discovered {
+            println!("[{elapsed:8.2}s] {desc}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strace::{
+        BufferExpression, BufferType, Expression, IntegerExpression, IntegerExpressionValue,
+        Syscall,
+    };
+
+    fn openat(path: &str) -> Syscall {
+        Syscall {
+            pid: 1234,
+            rel_ts: 0.000_010,
+            name: "openat".to_owned(),
+            args: vec![
+                Expression::Integer(IntegerExpression {
+                    value: IntegerExpressionValue::NamedConst("AT_FDCWD".to_owned()),
+                    metadata: Some(b"/".to_vec()),
+                }),
+                Expression::Buffer(BufferExpression {
+                    value: path.as_bytes().to_vec(),
+                    type_: BufferType::Unknown,
+                }),
+                Expression::Integer(IntegerExpression {
+                    value: IntegerExpressionValue::NamedConst("O_RDONLY".to_owned()),
+                    metadata: None,
+                }),
+            ],
+            ret_val: 3,
+        }
+    }
+
+    #[test]
+    fn test_discovered() {
+        let mut summarizer = Summarizer::default();
+        let mut watcher = Watcher::new();
+
+        summarizer.add_syscall(&openat("/etc/foo.conf")).unwrap();
+        let first = watcher.discovered(&summarizer);
+        assert!(
+            first.contains(&"read /etc/foo.conf".to_owned()),
+            "{first:?}"
+        );
+        assert!(
+            first.iter().any(|d| d.starts_with("syscall group @")),
+            "{first:?}"
+        );
+
+        // Only actions of the second batch are reported
+        summarizer.add_syscall(&openat("/etc/foo.conf")).unwrap();
+        summarizer.add_syscall(&openat("/etc/bar.conf")).unwrap();
+        let second = watcher.discovered(&summarizer);
+        assert_eq!(second, vec!["read /etc/bar.conf".to_owned()]);
+
+        assert!(watcher.discovered(&summarizer).is_empty());
+    }
+}